@@ -0,0 +1,491 @@
+//! 更新包解压
+//!
+//! 使用 `zip` / `tar` + `flate2` 流式解压 zip 和 tar.gz（不依赖设备上的 `unzip` / `tar` 命令）：
+//! 拒绝绝对路径、`..` 路径穿越以及符号链接、硬链接等非普通文件条目，
+//! 按实际写出的字节数限制解压总大小和条目数，并在写出时同时计算每个文件的 MD5 和 SHA-256。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use ring::digest;
+
+/// 解压限制
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    /// 解压后文件总大小上限（字节）
+    pub max_total_bytes: u64,
+    /// 条目数上限
+    pub max_entries: usize,
+}
+
+/// 解压出的普通文件
+#[derive(Debug, Clone)]
+pub struct ExtractedFile {
+    /// 相对解压目录的路径（以 `/` 分隔）
+    pub path: String,
+    pub md5: String,
+    pub sha256: String,
+}
+
+/// 解压 zip 或 tar.gz 到 `dest`，返回解压出的文件（同名条目只保留最后一个）
+pub fn extract(archive: &Path, dest: &Path, limits: &ExtractLimits) -> Result<Vec<ExtractedFile>, String> {
+    let mut file = fs::File::open(archive)
+        .map_err(|e| format!("Failed to open archive {}: {}", archive.display(), e))?;
+    let mut magic = [0u8; 4];
+    let read = file
+        .read(&mut magic)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let mut extractor = Extractor {
+        dest,
+        limits,
+        total_bytes: 0,
+        entries: 0,
+        files: BTreeMap::new(),
+    };
+    match &magic[..read] {
+        [0x50, 0x4B, 0x03, 0x04] => extract_zip(file, &mut extractor)?,
+        [0x1F, 0x8B, ..] => extract_tar_gz(file, &mut extractor)?,
+        _ => return Err("Unsupported archive format (expected zip or tar.gz)".to_string()),
+    }
+    Ok(extractor.files.into_values().collect())
+}
+
+/// 校验条目路径，返回相对路径；`./` 之类的根目录条目返回 None
+fn sanitize_entry_path(name: &str) -> Result<Option<PathBuf>, String> {
+    if name.contains('\0') || name.contains('\\') {
+        return Err(format!("Invalid entry path: {:?}", name));
+    }
+
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("Unsafe entry path: {}", name));
+            }
+        }
+    }
+    Ok((!path.as_os_str().is_empty()).then_some(path))
+}
+
+struct Extractor<'a> {
+    dest: &'a Path,
+    limits: &'a ExtractLimits,
+    total_bytes: u64,
+    entries: usize,
+    files: BTreeMap<String, ExtractedFile>,
+}
+
+impl Extractor<'_> {
+    fn count_entry(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(format!("Archive has more than {} entries", self.limits.max_entries));
+        }
+        Ok(())
+    }
+
+    fn create_dir(&mut self, name: &str) -> Result<(), String> {
+        self.count_entry()?;
+        if let Some(relative) = sanitize_entry_path(name)? {
+            let path = self.dest.join(relative);
+            fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create dir {}: {}", path.display(), e))?;
+            set_mode(&path, 0o755)?;
+        }
+        Ok(())
+    }
+
+    /// 写出普通文件
+    fn write_file(&mut self, name: &str, reader: &mut dyn Read, executable: bool) -> Result<(), String> {
+        self.count_entry()?;
+        let relative = sanitize_entry_path(name)?
+            .ok_or_else(|| format!("Invalid file entry: {:?}", name))?;
+        let path = self.dest.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create dir {}: {}", parent.display(), e))?;
+        }
+
+        let mut file = fs::File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut md5 = md5::Context::new();
+        let mut sha256 = digest::Context::new(&digest::SHA256);
+        let mut buffer = vec![0u8; 64 * 1024];
+
+        loop {
+            let n = reader
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+            if n == 0 {
+                break;
+            }
+            self.total_bytes += n as u64;
+            if self.total_bytes > self.limits.max_total_bytes {
+                return Err(format!(
+                    "Archive exceeds extracted size limit of {} bytes",
+                    self.limits.max_total_bytes
+                ));
+            }
+            let chunk = &buffer[..n];
+            md5.consume(chunk);
+            sha256.update(chunk);
+            file.write_all(chunk)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        drop(file);
+        set_mode(&path, if executable { 0o755 } else { 0o644 })?;
+
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.files.insert(
+            relative.clone(),
+            ExtractedFile {
+                path: relative,
+                md5: format!("{:x}", md5.compute()),
+                sha256: digest_hex(sha256.finish().as_ref()),
+            },
+        );
+        Ok(())
+    }
+}
+
+fn digest_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .map_err(|e| format!("Failed to set permissions for {}: {}", path.display(), e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+fn extract_tar_gz(file: fs::File, extractor: &mut Extractor) -> Result<(), String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read tar archive: {}", e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("Invalid tar entry path: {}", e))?
+            .to_string_lossy()
+            .into_owned();
+        let executable = entry.header().mode().map(|mode| mode & 0o111 != 0).unwrap_or(false);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => extractor.create_dir(&name)?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                extractor.write_file(&name, &mut entry, executable)?;
+            }
+            // pax 扩展头，由 tar 自行处理
+            tar::EntryType::XGlobalHeader | tar::EntryType::XHeader => {}
+            other => return Err(format!("Unsupported tar entry type {:?}: {}", other, name)),
+        }
+    }
+    Ok(())
+}
+
+fn extract_zip(file: fs::File, extractor: &mut Extractor) -> Result<(), String> {
+    const S_IFMT: u32 = 0o170000;
+    const S_IFREG: u32 = 0o100000;
+    const S_IFDIR: u32 = 0o040000;
+
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Invalid zip archive: {}", e))?;
+    if archive.len() > extractor.limits.max_entries {
+        return Err(format!("Archive has more than {} entries", extractor.limits.max_entries));
+    }
+
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+        let name = entry.name().to_string();
+        let file_type = entry.unix_mode().map(|mode| mode & S_IFMT).filter(|t| *t != 0);
+        let is_dir = entry.is_dir();
+        match file_type {
+            None => {}
+            Some(S_IFDIR) if is_dir => {}
+            Some(S_IFREG) if !is_dir => {}
+            Some(_) => return Err(format!("Unsupported zip entry type: {}", name)),
+        }
+        if is_dir {
+            extractor.create_dir(&name)?;
+            continue;
+        }
+        if entry.encrypted() {
+            return Err(format!("Encrypted zip entry: {}", name));
+        }
+
+        // 读取器按中央目录中的压缩大小读取，并在读完时校验 CRC32
+        let executable = entry.unix_mode().is_some_and(|mode| mode & 0o111 != 0);
+        let expected_size = entry.size();
+        let before = extractor.total_bytes;
+        extractor.write_file(&name, &mut entry, executable)?;
+        if extractor.total_bytes - before != expected_size {
+            return Err(format!("Zip entry is corrupt (size mismatch): {}", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+
+    const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+    const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+    const ZIP_END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+
+    const LIMITS: ExtractLimits = ExtractLimits {
+        max_total_bytes: 1024 * 1024,
+        max_entries: 100,
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 构造 tar 头（直接写入名称字段，以便测试 `..` 之类 tar::Builder 会拒绝的路径）
+    fn tar_header(name: &str, size: u64, mode: u32, entry_type: tar::EntryType) -> tar::Header {
+        let mut header = tar::Header::new_ustar();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_size(size);
+        header.set_mode(mode);
+        header.set_entry_type(entry_type);
+        header.set_cksum();
+        header
+    }
+
+    /// (名称, 内容, 权限)，内容为 None 表示目录
+    fn tar_gz(entries: &[(&str, Option<&[u8]>, u32)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, data, mode) in entries {
+            match data {
+                Some(data) => {
+                    let header = tar_header(name, data.len() as u64, *mode, tar::EntryType::Regular);
+                    builder.append(&header, *data).unwrap();
+                }
+                None => {
+                    let header = tar_header(name, 0, *mode, tar::EntryType::Directory);
+                    builder.append(&header, std::io::empty()).unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// 最小 zip 写入器：(名称, 内容, Unix 文件类型和权限)，内容较长时使用 deflate 压缩
+    fn zip(entries: &[(&str, &[u8], u32)]) -> Vec<u8> {
+        let (out, central) = zip_parts(entries);
+        zip_finish(out, &central, entries.len())
+    }
+
+    /// 返回 (本地文件头和数据, 中央目录)，便于测试构造损坏的归档
+    fn zip_parts(entries: &[(&str, &[u8], u32)]) -> (Vec<u8>, Vec<u8>) {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data, mode) in entries {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let (method, payload) = if data.len() > 16 {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).unwrap();
+                (8u16, encoder.finish().unwrap())
+            } else {
+                (0u16, data.to_vec())
+            };
+            let offset = out.len() as u32;
+
+            out.extend_from_slice(&ZIP_LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 4]);
+            out.extend_from_slice(&crc.sum().to_le_bytes());
+            out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&payload);
+
+            central.extend_from_slice(&ZIP_CENTRAL_HEADER.to_le_bytes());
+            central.extend_from_slice(&[20, 3, 20, 0, 0, 0]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 4]);
+            central.extend_from_slice(&crc.sum().to_le_bytes());
+            central.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&(mode << 16).to_le_bytes());
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        (out, central)
+    }
+
+    fn zip_finish(mut out: Vec<u8>, central: &[u8], count: usize) -> Vec<u8> {
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(central);
+        out.extend_from_slice(&ZIP_END_OF_CENTRAL_DIR.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(count as u16).to_le_bytes());
+        out.extend_from_slice(&(count as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    fn extract_bytes(name: &str, data: &[u8], limits: &ExtractLimits) -> (PathBuf, Result<Vec<ExtractedFile>, String>) {
+        let dir = temp_dir(name);
+        let archive = dir.join("update.archive");
+        fs::write(&archive, data).unwrap();
+        let dest = dir.join("out");
+        fs::create_dir_all(&dest).unwrap();
+        let result = extract(&archive, &dest, limits);
+        (dest, result)
+    }
+
+    #[test]
+    fn extracts_tar_gz_with_hashes_and_modes() {
+        let binary: &[u8] = b"\x7fELF binary";
+        let data = tar_gz(&[
+            ("./", None, 0o755),
+            ("udx710", Some(binary), 0o755),
+            ("www/", None, 0o755),
+            ("www/index.html", Some(b"<html></html>"), 0o600),
+        ]);
+        let (dest, result) = extract_bytes("tar", &data, &LIMITS);
+        let files = result.unwrap();
+
+        assert_eq!(files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["udx710", "www/index.html"]);
+        assert_eq!(files[0].md5, format!("{:x}", md5::compute(binary)));
+        assert_eq!(files[0].sha256, digest_hex(digest::digest(&digest::SHA256, binary).as_ref()));
+        assert_eq!(fs::read(dest.join("www/index.html")).unwrap(), b"<html></html>");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &str| fs::metadata(dest.join(p)).unwrap().permissions().mode() & 0o777;
+            assert_eq!((mode("udx710"), mode("www/index.html")), (0o755, 0o644));
+        }
+    }
+
+    #[test]
+    fn extracts_zip_stored_and_deflated() {
+        let page = b"<html>".repeat(100);
+        let data = zip(&[("udx710", b"\x7fELF", 0o100755), ("www/", b"", 0o040755), ("www/index.html", &page, 0o100644)]);
+        let (dest, result) = extract_bytes("zip", &data, &LIMITS);
+        let files = result.unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read(dest.join("www/index.html")).unwrap(), page);
+        assert_eq!(fs::read(dest.join("udx710")).unwrap(), b"\x7fELF");
+    }
+
+    #[test]
+    fn rejects_traversal_and_links() {
+        let data = tar_gz(&[("../evil", Some(b"x"), 0o644)]);
+        let err = extract_bytes("tar-traversal", &data, &LIMITS).1.unwrap_err();
+        assert!(err.contains("Unsafe entry path"), "{}", err);
+
+        let data = tar_gz(&[("/etc/passwd", Some(b"x"), 0o644)]);
+        assert!(extract_bytes("tar-absolute", &data, &LIMITS).1.is_err());
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "www/link", "/etc").unwrap();
+        let data = builder.into_inner().unwrap().finish().unwrap();
+        let err = extract_bytes("tar-symlink", &data, &LIMITS).1.unwrap_err();
+        assert!(err.contains("Unsupported tar entry type"), "{}", err);
+
+        let data = zip(&[("www/../../evil", b"x", 0o100644)]);
+        assert!(extract_bytes("zip-traversal", &data, &LIMITS).1.is_err());
+
+        let data = zip(&[("www/link", b"/etc", 0o120777)]);
+        let err = extract_bytes("zip-symlink", &data, &LIMITS).1.unwrap_err();
+        assert!(err.contains("Unsupported zip entry type"), "{}", err);
+    }
+
+    #[test]
+    fn enforces_size_and_entry_limits() {
+        let limits = ExtractLimits {
+            max_total_bytes: 1000,
+            max_entries: 2,
+        };
+        let big = vec![0u8; 2000];
+        let err = extract_bytes("tar-size", &tar_gz(&[("big", Some(&big), 0o644)]), &limits).1.unwrap_err();
+        assert!(err.contains("size limit"), "{}", err);
+        let err = extract_bytes("zip-size", &zip(&[("big", &big, 0o100644)]), &limits).1.unwrap_err();
+        assert!(err.contains("size limit"), "{}", err);
+
+        let entries = zip(&[("a", b"1", 0o100644), ("b", b"2", 0o100644), ("c", b"3", 0o100644)]);
+        let err = extract_bytes("zip-entries", &entries, &limits).1.unwrap_err();
+        assert!(err.contains("entries"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_malformed_zip() {
+        let data = b"\x7fELF binary payload, long enough to be deflated";
+        let valid = zip(&[("udx710", data, 0o100755)]);
+        assert!(extract_bytes("zip-valid", &valid, &LIMITS).1.is_ok());
+
+        // 截断的中央目录结尾记录
+        let truncated = &valid[..valid.len() - 10];
+        assert!(extract_bytes("zip-truncated-eocd", truncated, &LIMITS).1.is_err());
+        // 只剩本地文件头，没有中央目录
+        let (local, _) = zip_parts(&[("udx710", data, 0o100755)]);
+        assert!(extract_bytes("zip-no-central", &local, &LIMITS).1.is_err());
+
+        // 中央目录声明的解压大小与实际不符
+        let (local, mut central) = zip_parts(&[("udx710", data, 0o100755)]);
+        let declared = u32::from_le_bytes(central[24..28].try_into().unwrap());
+        central[24..28].copy_from_slice(&(declared + 1).to_le_bytes());
+        let err = extract_bytes("zip-size-mismatch", &zip_finish(local, &central, 1), &LIMITS).1.unwrap_err();
+        assert!(err.contains("udx710"), "{}", err);
+
+        // CRC32 不匹配
+        let (local, mut central) = zip_parts(&[("udx710", data, 0o100755)]);
+        central[16] ^= 0xFF;
+        assert!(extract_bytes("zip-crc", &zip_finish(local, &central, 1), &LIMITS).1.is_err());
+
+        // 多个中央目录条目指向同一份数据（重叠条目），解压总量仍受限制
+        let big = vec![7u8; 600];
+        let (local, central) = zip_parts(&[("a", &big, 0o100644)]);
+        let mut overlapping = central.clone();
+        let mut second = central.clone();
+        let name_at = second.len() - 1;
+        second[name_at] = b'b';
+        overlapping.extend_from_slice(&second);
+        let limits = ExtractLimits {
+            max_total_bytes: 1000,
+            max_entries: 10,
+        };
+        let err = extract_bytes("zip-overlap", &zip_finish(local, &overlapping, 2), &limits).1.unwrap_err();
+        assert!(err.contains("size limit") || err.contains("overlap"), "{}", err);
+    }
+}
@@ -0,0 +1,1069 @@
+//! 登录认证模块
+//!
+//! 管理员密码以 PBKDF2-SHA256 哈希形式保存在 config.json 中，登录成功后签发
+//! 随机会话令牌（保存在内存中，重启后失效）。除健康检查和登录接口外，
+//! 所有 `/api/*` 路由都必须在 `Authorization: Bearer <token>` 中携带令牌。
+//!
+//! 首次启动时尚未设置密码，可使用默认密码登录，但在修改密码之前所有调用方
+//! （包括 API 密钥）都只能访问状态、注销和修改密码接口。
+//!
+//! 浏览器 EventSource 无法设置请求头：前端先通过 `POST /api/auth/sse-ticket`
+//! 换取一次性票据，再以 `GET /api/events?ticket=<ticket>` 打开事件流。
+//! 票据 30 秒内有效且只能使用一次，出现在访问日志中也无法重放。
+//!
+//! 脚本和第三方集成可使用 API 密钥（`X-API-Key` 请求头，或以 `cpe_` 开头的
+//! Bearer 令牌），每个密钥只能访问其权限范围对应的路由分组。
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::num::NonZeroU32;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, pbkdf2};
+use tracing::{info, warn};
+
+use crate::config::{ApiKeyConfig, ApiScope, AuthConfig, ConfigManager};
+use crate::models::{
+    ApiKeyInfo, ApiResponse, AuthStatusResponse, CreateApiKeyResponse, LoginResponse, SseTicketResponse,
+};
+
+/// 首次启动时使用的默认密码
+pub const DEFAULT_PASSWORD: &str = "admin";
+
+const PASSWORD_HASH_SCHEME: &str = "pbkdf2-sha256";
+/// 新密码哈希的迭代次数；旧哈希按其中记录的次数校验，登录成功后自动升级
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const TOKEN_LEN: usize = 32;
+const MIN_PASSWORD_LEN: usize = 6;
+
+/// API 密钥前缀及随机部分长度
+const API_KEY_PREFIX: &str = "cpe_";
+const API_KEY_LEN: usize = 20;
+const API_KEY_ID_LEN: usize = 4;
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// 密钥最后使用时间写回 config.json 的最小间隔，避免频繁写 Flash
+const LAST_USED_PERSIST_INTERVAL_SECS: i64 = 600;
+
+/// 连续登录失败达到该次数后临时锁定
+const MAX_FAILED_LOGINS: u32 = 5;
+const LOGIN_LOCKOUT: Duration = Duration::from_secs(60);
+
+/// 无需登录即可访问的接口
+const PUBLIC_PATHS: &[&str] = &["/api/health", "/api/auth/login", "/api/auth/status"];
+
+/// 允许通过 `ticket` 查询参数传递一次性票据的接口
+const TICKET_PATHS: &[&str] = &["/api/events"];
+const SSE_TICKET_TTL: Duration = Duration::from_secs(30);
+
+/// 首次登录（需修改密码）或默认密码生效期间允许访问的接口
+const PASSWORD_CHANGE_PATHS: &[&str] = &["/api/auth/logout", "/api/auth/password"];
+
+/// 包含密钥、脚本等敏感信息的只读接口，需要 system:admin 权限
+const SENSITIVE_READ_PATHS: &[&str] = &[
+    "/api/webhook/config",
+    "/api/sms-push/config",
+    "/api/init-script",
+    "/api/sms/rules",
+];
+
+/// 修改调制解调器配置的路由分组，需要 modem:config 权限
+const MODEM_CONFIG_PATHS: &[&str] = &[
+    "/api/data",
+    "/api/roaming",
+    "/api/airplane-mode",
+    "/api/radio-mode",
+    "/api/band-lock",
+    "/api/cell-lock",
+    "/api/apn",
+    "/api/network",
+    "/api/sim",
+    "/api/usb-mode",
+    "/api/usb-advance",
+];
+
+/// 非 GET 请求按路径前缀对应的权限，按顺序取第一个匹配项；
+/// 未列出的写接口需要 system:admin，新增路由时须同步更新此表和测试中的路由表
+const WRITE_SCOPES: &[(&str, ApiScope)] = &[
+    ("/api/auth/logout", ApiScope::Read),
+    ("/api/auth/sse-ticket", ApiScope::Read),
+    ("/api/refresh/heartbeat", ApiScope::Read),
+    ("/api/sms/send", ApiScope::SmsSend),
+    ("/api/sms/queue/config", ApiScope::SystemAdmin),
+    ("/api/sms/queue", ApiScope::SmsSend),
+    ("/api/sms/threads", ApiScope::SmsSend),
+    ("/api/sms/messages", ApiScope::SmsSend),
+    ("/api/contacts", ApiScope::SmsSend),
+    ("/api/call", ApiScope::Call),
+    ("/api/ussd", ApiScope::Call),
+];
+
+/// 已认证的调用方
+#[derive(Debug, Clone)]
+pub enum Principal {
+    /// 管理员登录会话，拥有全部权限
+    Session { must_change_password: bool },
+    /// API 密钥
+    ApiKey {
+        id: String,
+        scopes: Vec<ApiScope>,
+        allowed_paths: Vec<String>,
+    },
+}
+
+impl Principal {
+    /// 是否允许以指定权限访问该路径
+    pub fn allows(&self, scope: ApiScope, path: &str) -> bool {
+        match self {
+            Self::Session { .. } => true,
+            Self::ApiKey {
+                scopes,
+                allowed_paths,
+                ..
+            } => {
+                scopes.iter().any(|granted| granted.includes(scope))
+                    && (allowed_paths.is_empty()
+                        || allowed_paths.iter().any(|prefix| path_matches(path, prefix)))
+            }
+        }
+    }
+}
+
+fn path_matches(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 根据请求方法和路径确定所需的权限范围
+pub fn required_scope(method: &Method, path: &str) -> ApiScope {
+    if path == "/api/at" {
+        return ApiScope::AtRaw;
+    }
+    if path_matches(path, "/api/auth/keys") || path == "/api/auth/password" {
+        return ApiScope::SystemAdmin;
+    }
+    if method == Method::GET {
+        return if SENSITIVE_READ_PATHS.contains(&path) {
+            ApiScope::SystemAdmin
+        } else {
+            ApiScope::Read
+        };
+    }
+    if let Some((_, scope)) = WRITE_SCOPES.iter().find(|(prefix, _)| path_matches(path, prefix)) {
+        return *scope;
+    }
+    if MODEM_CONFIG_PATHS.iter().any(|prefix| path_matches(path, prefix)) {
+        return ApiScope::ModemConfig;
+    }
+    ApiScope::SystemAdmin
+}
+
+struct Session {
+    expires_at: Instant,
+    must_change_password: bool,
+}
+
+/// 打开事件流用的一次性票据，代表签发时的调用方
+struct SseTicket {
+    expires_at: Instant,
+    principal: Principal,
+}
+
+/// 单个客户端 IP 的登录失败计数
+struct LoginThrottle {
+    failed_attempts: u32,
+    locked_until: Option<Instant>,
+    last_failed: Instant,
+}
+
+impl LoginThrottle {
+    /// 锁定已过期且最近一个锁定周期内没有失败记录时可以丢弃
+    fn is_stale(&self, now: Instant) -> bool {
+        self.locked_until.is_none_or(|until| until <= now) && self.last_failed + LOGIN_LOCKOUT <= now
+    }
+}
+
+/// 会话与 API 密钥管理器
+pub struct AuthManager {
+    config_manager: Arc<ConfigManager>,
+    sessions: RwLock<HashMap<String, Session>>,
+    sse_tickets: RwLock<HashMap<String, SseTicket>>,
+    throttle: RwLock<HashMap<IpAddr, LoginThrottle>>,
+    key_last_used: RwLock<HashMap<String, DateTime<Utc>>>,
+    rng: SystemRandom,
+}
+
+impl AuthManager {
+    pub fn new(config_manager: Arc<ConfigManager>) -> Self {
+        if config_manager.get_auth().password_hash.is_empty() {
+            warn!("No admin password set, default password is active until changed");
+        }
+
+        Self {
+            config_manager,
+            sessions: RwLock::new(HashMap::new()),
+            sse_tickets: RwLock::new(HashMap::new()),
+            throttle: RwLock::new(HashMap::new()),
+            key_last_used: RwLock::new(HashMap::new()),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    fn session_ttl(config: &AuthConfig) -> Duration {
+        Duration::from_secs(config.session_ttl_secs.max(60))
+    }
+
+    /// 是否已设置管理员密码
+    pub fn password_set(&self) -> bool {
+        !self.config_manager.get_auth().password_hash.is_empty()
+    }
+
+    /// 使用密码登录，成功时返回新的会话令牌
+    ///
+    /// 失败次数按客户端 IP 分别计数，某个地址被锁定不影响其他地址登录。
+    pub fn login(&self, password: &str, client: IpAddr) -> Result<LoginResponse, String> {
+        {
+            let mut throttle = self.throttle.write().unwrap();
+            if let Some(entry) = throttle.get_mut(&client) {
+                if let Some(until) = entry.locked_until {
+                    let now = Instant::now();
+                    if until > now {
+                        return Err(format!(
+                            "Too many failed attempts, try again in {} seconds",
+                            (until - now).as_secs().max(1)
+                        ));
+                    }
+                    entry.locked_until = None;
+                }
+            }
+        }
+
+        let config = self.config_manager.get_auth();
+        let (valid, must_change_password) = if config.password_hash.is_empty() {
+            (password == DEFAULT_PASSWORD, true)
+        } else {
+            (verify_password(password, &config.password_hash), false)
+        };
+
+        if !valid {
+            let now = Instant::now();
+            let mut throttle = self.throttle.write().unwrap();
+            throttle.retain(|_, entry| !entry.is_stale(now));
+            let entry = throttle.entry(client).or_insert(LoginThrottle {
+                failed_attempts: 0,
+                locked_until: None,
+                last_failed: now,
+            });
+            entry.failed_attempts += 1;
+            entry.last_failed = now;
+            if entry.failed_attempts >= MAX_FAILED_LOGINS {
+                warn!(client = %client, attempts = entry.failed_attempts, "Login locked after repeated failures");
+                entry.failed_attempts = 0;
+                entry.locked_until = Some(now + LOGIN_LOCKOUT);
+            }
+            return Err("Invalid password".to_string());
+        }
+
+        self.throttle.write().unwrap().remove(&client);
+        if !must_change_password {
+            self.upgrade_password_hash(password, &config);
+        }
+
+        let token= self.generate_token()?;
+        let ttl = Self::session_ttl(&config);
+        let now = Instant::now();
+
+        let mut sessions = self.sessions.write().unwrap();
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                expires_at: now + ttl,
+                must_change_password,
+            },
+        );
+
+        info!(must_change_password, "Admin logged in");
+
+        Ok(LoginResponse {
+            token,
+            expires_in_secs: ttl.as_secs(),
+            must_change_password,
+        })
+    }
+
+    /// 旧哈希的迭代次数低于当前设置时，用刚校验过的密码重新计算
+    fn upgrade_password_hash(&self, password: &str, config: &AuthConfig) {
+        if hash_iterations(&config.password_hash).is_none_or(|iterations| iterations >= PBKDF2_ITERATIONS) {
+            return;
+        }
+        let mut config = config.clone();
+        match self.hash_password(password) {
+            Ok(hash) => config.password_hash = hash,
+            Err(e) => {
+                warn!(error = %e, "Failed to upgrade password hash");
+                return;
+            }
+        }
+        match self.config_manager.set_auth(config) {
+            Ok(()) => info!(iterations = PBKDF2_ITERATIONS, "Password hash upgraded"),
+            Err(e) => warn!(error = %e, "Failed to save upgraded password hash"),
+        }
+    }
+
+    /// 注销会话
+    pub fn logout(&self, token: &str) {
+        self.sessions.write().unwrap().remove(token);
+    }
+
+    /// 校验令牌并顺延有效期，返回该会话是否仍需修改密码
+    pub fn validate(&self, token: &str) -> Option<bool> {
+        let ttl = Self::session_ttl(&self.config_manager.get_auth());
+        let now = Instant::now();
+
+        let mut sessions = self.sessions.write().unwrap();
+        match sessions.get_mut(token) {
+            Some(session) if session.expires_at > now => {
+                session.expires_at = now + ttl;
+                Some(session.must_change_password)
+            }
+            Some(_) => {
+                sessions.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 查询认证状态（令牌可选）
+    pub fn status(&self, token: Option<&str>) -> AuthStatusResponse {
+        let session = token.and_then(|token| self.validate(token));
+        AuthStatusResponse {
+            password_set: self.password_set(),
+            authenticated: session.is_some(),
+            must_change_password: session.unwrap_or(false),
+        }
+    }
+
+    /// 修改管理员密码，成功后注销其他所有会话
+    pub fn change_password(
+        &self,
+        token: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), String> {
+        let mut config = self.config_manager.get_auth();
+
+        let current_valid = if config.password_hash.is_empty() {
+            current_password == DEFAULT_PASSWORD
+        } else {
+            verify_password(current_password, &config.password_hash)
+        };
+        if !current_valid {
+            return Err("Current password is incorrect".to_string());
+        }
+
+        if new_password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(format!(
+                "New password must be at least {} characters",
+                MIN_PASSWORD_LEN
+            ));
+        }
+        if new_password == DEFAULT_PASSWORD {
+            return Err("New password must not be the default password".to_string());
+        }
+
+        config.password_hash = self.hash_password(new_password)?;
+        self.config_manager.set_auth(config)?;
+
+        let mut sessions = self.sessions.write().unwrap();
+        sessions.retain(|key, _| key == token);
+        if let Some(session) = sessions.get_mut(token) {
+            session.must_change_password = false;
+        }
+
+        info!("Admin password changed");
+        Ok(())
+    }
+
+    /// 为已认证的调用方签发打开事件流用的一次性票据
+    pub fn issue_sse_ticket(&self, principal: Principal) -> Result<SseTicketResponse, String> {
+        let ticket = self.generate_token()?;
+        let now = Instant::now();
+
+        let mut tickets = self.sse_tickets.write().unwrap();
+        tickets.retain(|_, ticket| ticket.expires_at > now);
+        tickets.insert(
+            ticket.clone(),
+            SseTicket {
+                expires_at: now + SSE_TICKET_TTL,
+                principal,
+            },
+        );
+
+        Ok(SseTicketResponse {
+            ticket,
+            expires_in_secs: SSE_TICKET_TTL.as_secs(),
+        })
+    }
+
+    /// 使用一次性票据，票据无论是否过期都会被移除
+    fn redeem_sse_ticket(&self, ticket: &str) -> Option<Principal> {
+        let ticket = self.sse_tickets.write().unwrap().remove(ticket)?;
+        (ticket.expires_at > Instant::now()).then_some(ticket.principal)
+    }
+
+    /// 根据请求头识别调用方（会话令牌或 API 密钥）
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<Principal> {
+        if let Some(key) = extract_api_key(headers) {
+            return self.authenticate_api_key(&key);
+        }
+
+        let token = extract_token(headers)?;
+        self.authenticate_token(&token)
+    }
+
+    /// 校验单个令牌（会话令牌或 `cpe_` 开头的 API 密钥）
+    fn authenticate_token(&self, token: &str) -> Option<Principal> {
+        if token.starts_with(API_KEY_PREFIX) {
+            return self.authenticate_api_key(token);
+        }
+
+        self.validate(token)
+            .map(|must_change_password| Principal::Session { must_change_password })
+    }
+
+    fn authenticate_api_key(&self, key: &str) -> Option<Principal> {
+        let key_hash = hash_api_key(key);
+        let mut api_keys = self.config_manager.get_api_keys();
+        let index = api_keys
+            .iter()
+            .position(|api_key| !api_key.revoked && api_key.key_hash == key_hash)?;
+
+        let now = Utc::now();
+        self.key_last_used
+            .write()
+            .unwrap()
+            .insert(api_keys[index].id.clone(), now);
+
+        let persisted_stale = api_keys[index]
+            .last_used_at
+            .as_deref()
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .is_none_or(|last| (now - last.with_timezone(&Utc)).num_seconds() >= LAST_USED_PERSIST_INTERVAL_SECS);
+        if persisted_stale {
+            api_keys[index].last_used_at = Some(now.to_rfc3339());
+            if let Err(e) = self.config_manager.set_api_keys(api_keys.clone()) {
+                warn!(error = %e, "Failed to persist API key last-used time");
+            }
+        }
+
+        let api_key = &api_keys[index];
+        Some(Principal::ApiKey {
+            id: api_key.id.clone(),
+            scopes: api_key.scopes.clone(),
+            allowed_paths: api_key.allowed_paths.clone(),
+        })
+    }
+
+    /// 列出所有 API 密钥（不含密钥本身）
+    pub fn list_api_keys(&self) -> Vec<ApiKeyInfo> {
+        let last_used = self.key_last_used.read().unwrap();
+        self.config_manager
+            .get_api_keys()
+            .into_iter()
+            .map(|api_key| {
+                let last_used_at = last_used
+                    .get(&api_key.id)
+                    .map(|time| time.to_rfc3339())
+                    .or(api_key.last_used_at);
+                ApiKeyInfo {
+                    id: api_key.id,
+                    name: api_key.name,
+                    scopes: api_key.scopes,
+                    allowed_paths: api_key.allowed_paths,
+                    created_at: api_key.created_at,
+                    last_used_at,
+                    revoked: api_key.revoked,
+                }
+            })
+            .collect()
+    }
+
+    /// 创建新的 API 密钥，明文密钥只在此时返回一次
+    pub fn create_api_key(
+        &self,
+        name: &str,
+        scopes: Vec<ApiScope>,
+        allowed_paths: Vec<String>,
+    ) -> Result<CreateApiKeyResponse, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("API key name must not be empty".to_string());
+        }
+        if scopes.is_empty() {
+            return Err("API key must have at least one scope".to_string());
+        }
+
+        let mut api_keys = self.config_manager.get_api_keys();
+
+        let mut id_bytes = [0u8; API_KEY_ID_LEN];
+        let mut key_bytes = [0u8; API_KEY_LEN];
+        self.rng
+            .fill(&mut id_bytes)
+            .and_then(|_| self.rng.fill(&mut key_bytes))
+            .map_err(|_| "Failed to generate API key".to_string())?;
+
+        let key = format!("{}{}", API_KEY_PREFIX, to_hex(&key_bytes));
+        let api_key = ApiKeyConfig {
+            id: to_hex(&id_bytes),
+            name: name.to_string(),
+            key_hash: hash_api_key(&key),
+            scopes,
+            allowed_paths,
+            created_at: Utc::now().to_rfc3339(),
+            last_used_at: None,
+            revoked: false,
+        };
+
+        let info = ApiKeyInfo {
+            id: api_key.id.clone(),
+            name: api_key.name.clone(),
+            scopes: api_key.scopes.clone(),
+            allowed_paths: api_key.allowed_paths.clone(),
+            created_at: api_key.created_at.clone(),
+            last_used_at: None,
+            revoked: false,
+        };
+
+        api_keys.push(api_key);
+        self.config_manager.set_api_keys(api_keys)?;
+
+        info!(id = %info.id, name = %info.name, "API key created");
+        Ok(CreateApiKeyResponse { key, info })
+    }
+
+    /// 吊销 API 密钥（保留记录以便审计）
+    pub fn revoke_api_key(&self, id: &str) -> Result<(), String> {
+        let mut api_keys = self.config_manager.get_api_keys();
+        let api_key = api_keys
+            .iter_mut()
+            .find(|api_key| api_key.id == id)
+            .ok_or_else(|| format!("API key {} not found", id))?;
+
+        api_key.revoked = true;
+        self.config_manager.set_api_keys(api_keys)?;
+
+        info!(id = %id, "API key revoked");
+        Ok(())
+    }
+
+    fn generate_token(&self) -> Result<String, String> {
+        let mut bytes = [0u8; TOKEN_LEN];
+        self.rng
+            .fill(&mut bytes)
+            .map_err(|_| "Failed to generate session token".to_string())?;
+        Ok(to_hex(&bytes))
+    }
+
+    fn hash_password(&self, password: &str) -> Result<String, String> {
+        let mut salt = [0u8; SALT_LEN];
+        self.rng
+            .fill(&mut salt)
+            .map_err(|_| "Failed to generate password salt".to_string())?;
+        Ok(hash_password_with_salt(password, &salt, PBKDF2_ITERATIONS))
+    }
+}
+
+fn hash_password_with_salt(password: &str, salt: &[u8], iterations: u32) -> String {
+    let mut hash = [0u8; digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations.max(1)).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+
+    format!(
+        "{}${}${}${}",
+        PASSWORD_HASH_SCHEME,
+        iterations,
+        to_hex(salt),
+        to_hex(&hash)
+    )
+}
+
+/// 哈希中记录的迭代次数
+fn hash_iterations(encoded: &str) -> Option<u32> {
+    match encoded.split('$').collect::<Vec<_>>().as_slice() {
+        [PASSWORD_HASH_SCHEME, iterations, _, _] => iterations.parse().ok(),
+        _ => None,
+    }
+}
+
+/// 校验密码是否与存储的哈希匹配
+fn verify_password(password: &str, encoded: &str) -> bool {
+    let parts: Vec<&str> = encoded.split('$').collect();
+    if parts.len() != 4 || parts[0] != PASSWORD_HASH_SCHEME {
+        return false;
+    }
+
+    let iterations = match parts[1].parse::<u32>().ok().and_then(NonZeroU32::new) {
+        Some(iterations) => iterations,
+        None => return false,
+    };
+    let (salt, hash) = match (from_hex(parts[2]), from_hex(parts[3])) {
+        (Some(salt), Some(hash)) => (salt, hash),
+        _ => return false,
+    };
+
+    pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        password.as_bytes(),
+        &hash,
+    )
+    .is_ok()
+}
+
+fn hash_api_key(key: &str) -> String {
+    to_hex(digest::digest(&digest::SHA256, key.as_bytes()).as_ref())
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(input: &str) -> Option<Vec<u8>> {
+    if !input.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(input.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// 从请求头中提取会话令牌
+pub fn extract_token(headers: &HeaderMap) -> Option<String> {
+    bearer_token(headers).filter(|token| !token.starts_with(API_KEY_PREFIX))
+}
+
+/// 从查询参数中提取事件流票据 `ticket`
+fn extract_query_ticket(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "ticket")
+        .map(|(_, value)| value.to_string())
+        .filter(|ticket| !ticket.is_empty())
+}
+
+/// 从请求头中提取 API 密钥
+fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .or_else(|| bearer_token(headers).filter(|token| token.starts_with(API_KEY_PREFIX)))
+}
+
+fn auth_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(ApiResponse::<serde_json::Value>::error(message)),
+    )
+        .into_response()
+}
+
+/// 认证中间件：保护除公开接口以外的所有 `/api/*` 路由，并检查 API 密钥权限
+pub async fn require_auth(
+    State(auth): State<Arc<AuthManager>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+
+    if request.method() == Method::OPTIONS
+        || !path.starts_with("/api/")
+        || PUBLIC_PATHS.contains(&path.as_str())
+    {
+        return next.run(request).await;
+    }
+
+    let principal = auth.authenticate(request.headers()).or_else(|| {
+        if TICKET_PATHS.contains(&path.as_str()) {
+            extract_query_ticket(request.uri().query()).and_then(|ticket| auth.redeem_sse_ticket(&ticket))
+        } else {
+            None
+        }
+    });
+    let principal = match principal {
+        Some(principal) => principal,
+        None => return auth_error(StatusCode::UNAUTHORIZED, "Authentication required"),
+    };
+
+    // 默认密码生效期间，任何调用方都只能修改密码
+    let must_change_password =
+        !auth.password_set() || matches!(principal, Principal::Session { must_change_password: true });
+    if must_change_password && !PASSWORD_CHANGE_PATHS.contains(&path.as_str()) {
+        return auth_error(
+            StatusCode::FORBIDDEN,
+            "Password change required before using the API",
+        );
+    }
+
+    let scope = required_scope(request.method(), &path);
+    if !principal.allows(scope, &path) {
+        return auth_error(
+            StatusCode::FORBIDDEN,
+            &format!("API key is not allowed to access {} (requires scope {})", path, scope.as_str()),
+        );
+    }
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        from_hex, hash_iterations, hash_password_with_salt, required_scope, to_hex, verify_password, AuthManager,
+        Principal, DEFAULT_PASSWORD, MAX_FAILED_LOGINS, PBKDF2_ITERATIONS, PUBLIC_PATHS,
+    };
+    use crate::config::{ApiScope, ConfigManager};
+    use axum::http::Method;
+    use std::collections::HashSet;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Arc;
+
+    #[test]
+    fn password_hash_round_trip() {
+        let encoded = hash_password_with_salt("s3cret-pass", b"0123456789abcdef", 1_000);
+
+        assert!(encoded.starts_with("pbkdf2-sha256$1000$"));
+        assert!(verify_password("s3cret-pass", &encoded));
+        assert!(!verify_password("wrong-pass", &encoded));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_hash() {
+        assert!(!verify_password("admin", ""));
+        assert!(!verify_password("admin", "md5$1$00$00"));
+        assert!(!verify_password("admin", "pbkdf2-sha256$0$00$00"));
+        assert!(!verify_password("admin", "pbkdf2-sha256$10$zz$00"));
+    }
+
+    #[test]
+    fn login_lockout_is_per_client() {
+        let path = std::env::temp_dir().join(format!("udx710-auth-throttle-{}.json", std::process::id()));
+        let auth = AuthManager::new(Arc::new(ConfigManager::new(path)));
+        let attacker = IpAddr::V4(Ipv4Addr::new(192, 168, 66, 200));
+        let admin = IpAddr::V4(Ipv4Addr::new(192, 168, 66, 2));
+
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert_eq!(auth.login("wrong", attacker).unwrap_err(), "Invalid password");
+        }
+        let err = auth.login(DEFAULT_PASSWORD, attacker).unwrap_err();
+        assert!(err.starts_with("Too many failed attempts"), "{}", err);
+
+        assert!(auth.login(DEFAULT_PASSWORD, admin).is_ok());
+    }
+
+    #[test]
+    fn login_upgrades_old_password_hash() {
+        let path = std::env::temp_dir().join(format!("udx710-auth-upgrade-{}.json", std::process::id()));
+        let config_manager = Arc::new(ConfigManager::new(path.clone()));
+        let mut config = config_manager.get_auth();
+        config.password_hash = hash_password_with_salt("old-pass", b"0123456789abcdef", 10_000);
+        config_manager.set_auth(config).unwrap();
+
+        let auth = AuthManager::new(Arc::clone(&config_manager));
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(auth.login("wrong-pass", client).is_err());
+        assert_eq!(hash_iterations(&config_manager.get_auth().password_hash), Some(10_000));
+
+        assert!(auth.login("old-pass", client).is_ok());
+        let upgraded = config_manager.get_auth().password_hash;
+        assert_eq!(hash_iterations(&upgraded), Some(PBKDF2_ITERATIONS));
+        assert!(verify_password("old-pass", &upgraded));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn sse_ticket_is_single_use() {
+        let path = std::env::temp_dir().join(format!("udx710-auth-ticket-{}.json", std::process::id()));
+        let auth = AuthManager::new(Arc::new(ConfigManager::new(path)));
+        let ticket = auth
+            .issue_sse_ticket(Principal::Session { must_change_password: false })
+            .unwrap()
+            .ticket;
+
+        assert!(auth.redeem_sse_ticket(&ticket).is_some());
+        assert!(auth.redeem_sse_ticket(&ticket).is_none());
+        assert!(auth.redeem_sse_ticket("unknown").is_none());
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(to_hex(&bytes), "007fabff");
+        assert_eq!(from_hex("007fabff").unwrap(), bytes);
+        assert!(from_hex("abc").is_none());
+    }
+
+    /// build_router 中注册的每个需认证路由及其期望权限，新增路由时须在此登记
+    const ROUTE_SCOPES: &[(&str, &str, ApiScope)] = {
+        use ApiScope::{AtRaw, Call, ModemConfig, Read, SmsSend, SystemAdmin};
+        &[
+        ("POST", "/api/auth/logout", Read),
+        ("POST", "/api/auth/password", SystemAdmin),
+        ("POST", "/api/auth/sse-ticket", Read),
+        ("GET", "/api/auth/keys", SystemAdmin),
+        ("POST", "/api/auth/keys", SystemAdmin),
+        ("DELETE", "/api/auth/keys/{id}", SystemAdmin),
+        ("POST", "/api/at", AtRaw),
+        ("GET", "/api/device", Read),
+        ("GET", "/api/device/imeisv", Read),
+        ("GET", "/api/sim", Read),
+        ("GET", "/api/sim/slot", Read),
+        ("POST", "/api/sim/slot/switch", ModemConfig),
+        ("POST", "/api/sim/pin/enter", ModemConfig),
+        ("POST", "/api/sim/pin/reset", ModemConfig),
+        ("POST", "/api/sim/pin/change", ModemConfig),
+        ("POST", "/api/sim/pin/lock", ModemConfig),
+        ("POST", "/api/sim/pin/unlock", ModemConfig),
+        ("GET", "/api/sim/pin/config", Read),
+        ("POST", "/api/sim/pin/config", ModemConfig),
+        ("GET", "/api/network", Read),
+        ("GET", "/api/network/interfaces", Read),
+        ("GET", "/api/network/signal-strength", Read),
+        ("GET", "/api/network/nitz", Read),
+        ("GET", "/api/network/operators", Read),
+        ("GET", "/api/network/operators/scan", Read),
+        ("POST", "/api/network/register-manual", ModemConfig),
+        ("POST", "/api/network/register-auto", ModemConfig),
+        ("GET", "/api/cells", Read),
+        ("GET", "/api/location/cell-info", Read),
+        ("GET", "/api/qos", Read),
+        ("GET", "/api/data", Read),
+        ("POST", "/api/data", ModemConfig),
+        ("GET", "/api/roaming", Read),
+        ("POST", "/api/roaming", ModemConfig),
+        ("GET", "/api/airplane-mode", Read),
+        ("POST", "/api/airplane-mode", ModemConfig),
+        ("GET", "/api/radio-mode", Read),
+        ("POST", "/api/radio-mode", ModemConfig),
+        ("GET", "/api/band-lock", Read),
+        ("POST", "/api/band-lock", ModemConfig),
+        ("GET", "/api/cell-lock", Read),
+        ("POST", "/api/cell-lock", ModemConfig),
+        ("POST", "/api/cell-lock/unlock-all", ModemConfig),
+        ("GET", "/api/apn", Read),
+        ("POST", "/api/apn", ModemConfig),
+        ("GET", "/api/calls", Read),
+        ("POST", "/api/call/dial", Call),
+        ("POST", "/api/call/hangup", Call),
+        ("POST", "/api/call/hangup-all", Call),
+        ("POST", "/api/call/answer", Call),
+        ("POST", "/api/call/swap", Call),
+        ("POST", "/api/call/release-and-answer", Call),
+        ("POST", "/api/call/hold-and-answer", Call),
+        ("POST", "/api/call/multiparty", Call),
+        ("POST", "/api/call/multiparty/private-chat", Call),
+        ("POST", "/api/call/multiparty/hangup", Call),
+        ("POST", "/api/call/tones", Call),
+        ("POST", "/api/call/deflect", Call),
+        ("GET", "/api/call/volume", Read),
+        ("POST", "/api/call/volume", Call),
+        ("GET", "/api/call/forwarding", Read),
+        ("POST", "/api/call/forwarding", Call),
+        ("GET", "/api/call/settings", Read),
+        ("POST", "/api/call/settings", Call),
+        ("GET", "/api/call/filter", Read),
+        ("POST", "/api/call/filter", Call),
+        ("GET", "/api/call/policy", Read),
+        ("POST", "/api/call/policy", Call),
+        ("GET", "/api/call/history", Read),
+        ("DELETE", "/api/call/history/{id}", Call),
+        ("POST", "/api/call/history/clear", Call),
+        ("POST", "/api/sms/send", SmsSend),
+        ("GET", "/api/sms/list", Read),
+        ("GET", "/api/sms/conversation", Read),
+        ("GET", "/api/sms/stats", Read),
+        ("POST", "/api/sms/clear", SystemAdmin),
+        ("GET", "/api/sms/threads", Read),
+        ("GET", "/api/sms/threads/{id}", Read),
+        ("DELETE", "/api/sms/threads/{id}", SmsSend),
+        ("POST", "/api/sms/threads/{id}/read", SmsSend),
+        ("POST", "/api/sms/threads/{id}/archive", SmsSend),
+        ("DELETE", "/api/sms/messages/{id}", SmsSend),
+        ("POST", "/api/sms/messages/{id}/read", SmsSend),
+        ("GET", "/api/sms/search", Read),
+        ("GET", "/api/sms/ingest/config", Read),
+        ("POST", "/api/sms/ingest/config", SystemAdmin),
+        ("GET", "/api/sms/queue", Read),
+        ("POST", "/api/sms/queue", SmsSend),
+        ("GET", "/api/sms/queue/config", Read),
+        ("POST", "/api/sms/queue/config", SystemAdmin),
+        ("GET", "/api/sms/rules", SystemAdmin),
+        ("POST", "/api/sms/rules", SystemAdmin),
+        ("GET", "/api/sms/queue/{id}", Read),
+        ("PUT", "/api/sms/queue/{id}", SmsSend),
+        ("DELETE", "/api/sms/queue/{id}", SmsSend),
+        ("GET", "/api/contacts", Read),
+        ("POST", "/api/contacts", SmsSend),
+        ("POST", "/api/contacts/import", SmsSend),
+        ("POST", "/api/contacts/import/sim", SmsSend),
+        ("GET", "/api/contacts/export", Read),
+        ("GET", "/api/contacts/{id}", Read),
+        ("PUT", "/api/contacts/{id}", SmsSend),
+        ("DELETE", "/api/contacts/{id}", SmsSend),
+        ("GET", "/api/ussd/status", Read),
+        ("POST", "/api/ussd/initiate", Call),
+        ("POST", "/api/ussd/respond", Call),
+        ("POST", "/api/ussd/cancel", Call),
+        ("GET", "/api/ussd/history", Read),
+        ("POST", "/api/ussd/history/clear", Call),
+        ("GET", "/api/ims/status", Read),
+        ("GET", "/api/voicemail/status", Read),
+        ("GET", "/api/usb-mode", Read),
+        ("POST", "/api/usb-mode", ModemConfig),
+        ("POST", "/api/usb-advance", ModemConfig),
+        ("GET", "/api/stats", Read),
+        ("GET", "/api/stats/cpu", Read),
+        ("GET", "/api/connectivity", Read),
+        ("POST", "/api/system/reboot", SystemAdmin),
+        ("GET", "/api/init-script", SystemAdmin),
+        ("POST", "/api/init-script", SystemAdmin),
+        ("GET", "/api/webhook/config", SystemAdmin),
+        ("POST", "/api/webhook/config", SystemAdmin),
+        ("POST", "/api/webhook/test", SystemAdmin),
+        ("GET", "/api/sms-push/config", SystemAdmin),
+        ("POST", "/api/sms-push/config", SystemAdmin),
+        ("POST", "/api/sms-push/test", SystemAdmin),
+        ("GET", "/api/refresh/config", Read),
+        ("POST", "/api/refresh/config", SystemAdmin),
+        ("POST", "/api/refresh/heartbeat", Read),
+        ("GET", "/api/events", Read),
+        ("GET", "/api/data-usage", Read),
+        ("GET", "/api/data-usage/history", Read),
+        ("GET", "/api/data-usage/config", Read),
+        ("POST", "/api/data-usage/config", SystemAdmin),
+        ("GET", "/api/history/signal", Read),
+        ("GET", "/api/history/signal/config", Read),
+        ("POST", "/api/history/signal/config", SystemAdmin),
+        ("GET", "/api/ota/status", Read),
+        ("POST", "/api/ota/upload", SystemAdmin),
+        ("POST", "/api/ota/apply", SystemAdmin),
+        ("POST", "/api/ota/cancel", SystemAdmin),
+        ("POST", "/api/ota/rollback", SystemAdmin),
+        ("GET", "/api/ota/channel", Read),
+        ("POST", "/api/ota/channel", SystemAdmin),
+        ("GET", "/api/ota/check", Read),
+        ("POST", "/api/ota/check", SystemAdmin),
+        ("GET", "/api/ota/config", Read),
+        ("POST", "/api/ota/config", SystemAdmin),
+        ]
+    };
+
+    /// 从 main.rs 源码中提取 build_router 注册的 (方法, 路径)
+    fn registered_routes() -> Vec<(String, String)> {
+        let source = include_str!("main.rs");
+        let start = source.find("fn build_router").expect("build_router not found");
+        let end = start + source[start..].find(".layer(").expect("router layers not found");
+
+        let mut routes = Vec::new();
+        for chunk in source[start..end].split(".route(").skip(1) {
+            let path = chunk.split('"').nth(1).expect("route path literal").to_string();
+            for method in ["get", "post", "put", "delete"] {
+                let registered = chunk.match_indices(&format!("{method}(")).any(|(index, _)| {
+                    !chunk[..index]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                });
+                if registered {
+                    routes.push((method.to_uppercase(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn every_route_has_an_explicit_scope() {
+        let registered: HashSet<(String, String)> = registered_routes()
+            .into_iter()
+            .filter(|(_, path)| !PUBLIC_PATHS.contains(&path.as_str()))
+            .collect();
+        let expected: HashSet<(String, String)> = ROUTE_SCOPES
+            .iter()
+            .map(|(method, path, _)| (method.to_string(), path.to_string()))
+            .collect();
+        assert_eq!(registered, expected, "router and ROUTE_SCOPES differ");
+
+        for (method, path, scope) in ROUTE_SCOPES {
+            let method = Method::from_bytes(method.as_bytes()).unwrap();
+            let concrete = path.replace("{id}", "1");
+            assert_eq!(required_scope(&method, &concrete), *scope, "{method} {path}");
+        }
+    }
+
+    #[test]
+    fn api_key_respects_scopes_and_paths() {
+        let monitoring = Principal::ApiKey {
+            id: "a1b2c3d4".to_string(),
+            scopes: vec![ApiScope::Read],
+            allowed_paths: vec!["/api/stats".to_string(), "/api/network/".to_string()],
+        };
+
+        assert!(monitoring.allows(ApiScope::Read, "/api/stats"));
+        assert!(monitoring.allows(ApiScope::Read, "/api/stats/cpu"));
+        assert!(monitoring.allows(ApiScope::Read, "/api/network"));
+        assert!(!monitoring.allows(ApiScope::Read, "/api/networks"));
+        assert!(!monitoring.allows(ApiScope::Read, "/api/sms/list"));
+        assert!(!monitoring.allows(ApiScope::SmsSend, "/api/stats"));
+
+        let admin = Principal::ApiKey {
+            id: "e5f6a7b8".to_string(),
+            scopes: vec![ApiScope::SystemAdmin],
+            allowed_paths: Vec::new(),
+        };
+        assert!(admin.allows(ApiScope::Read, "/api/device"));
+        assert!(admin.allows(ApiScope::SmsSend, "/api/sms/queue"));
+        assert!(admin.allows(ApiScope::ModemConfig, "/api/band-lock"));
+        assert!(!admin.allows(ApiScope::AtRaw, "/api/at"));
+
+        let session = Principal::Session {
+            must_change_password: false,
+        };
+        assert!(session.allows(ApiScope::AtRaw, "/api/at"));
+    }
+}
@@ -0,0 +1,134 @@
+//! 来电拦截与号码黑白名单
+//!
+//! 名单条目可以是精确号码（不区分 `+86`、前导 0 等格式）、号码前缀、正则表达式，
+//! 或"隐藏号码"（来电不显示号码）。白名单优先于黑名单；`allowlist` 模式下
+//! 不在白名单中的号码一律拦截。
+//!
+//! 通话监听收到命中的来电时立即挂断并以 `blocked` 记入通话记录；
+//! 开启 `filter_sms` 后，命中名单的短信仍会保存，但不转发到 Webhook 和推送服务。
+
+use regex::Regex;
+
+use crate::config::{CallFilterConfig, CallFilterMode, NumberMatch, NumberPattern};
+use crate::utils::{normalize_phone_number, same_number};
+
+/// ofono 对隐藏号码上报的 LineIdentification（监听中缺失时记为 "Unknown"）
+const WITHHELD_NUMBERS: [&str; 3] = ["withheld", "unknown", "unavailable"];
+
+/// 号码是否为隐藏号码
+pub fn is_withheld(number: &str) -> bool {
+    let number = number.trim();
+    number.is_empty() || WITHHELD_NUMBERS.iter().any(|w| number.eq_ignore_ascii_case(w))
+}
+
+/// 校验名单配置
+pub fn validate_config(config: &CallFilterConfig) -> Result<(), String> {
+    validate_patterns(&config.block_list)?;
+    validate_patterns(&config.allow_list)
+}
+
+/// 校验号码名单条目
+pub fn validate_patterns(entries: &[NumberPattern]) -> Result<(), String> {
+    for entry in entries {
+        match entry.kind {
+            NumberMatch::Withheld => {}
+            _ if entry.pattern.is_empty() => {
+                return Err(format!("{:?} entry has an empty pattern", entry.kind));
+            }
+            NumberMatch::Regex => {
+                Regex::new(&entry.pattern).map_err(|e| format!("Invalid regex '{}': {}", entry.pattern, e))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 名单条目是否匹配号码
+fn entry_matches(entry: &NumberPattern, number: &str) -> bool {
+    if is_withheld(number) {
+        return entry.kind == NumberMatch::Withheld;
+    }
+    match entry.kind {
+        NumberMatch::Exact => same_number(&entry.pattern, number),
+        NumberMatch::Prefix => {
+            let prefix = normalize_phone_number(&entry.pattern);
+            number.starts_with(&entry.pattern) || (!prefix.is_empty() && normalize_phone_number(number).starts_with(&prefix))
+        }
+        NumberMatch::Regex => Regex::new(&entry.pattern).is_ok_and(|regex| regex.is_match(number)),
+        NumberMatch::Withheld => false,
+    }
+}
+
+/// 号码是否命中名单中的任一条目
+pub fn matches_any(entries: &[NumberPattern], number: &str) -> bool {
+    entries.iter().any(|entry| entry_matches(entry, number))
+}
+
+/// 号码是否应被拦截（未启用时总是 false）
+pub fn is_blocked(config: &CallFilterConfig, number: &str) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    if matches_any(&config.allow_list, number) {
+        return false;
+    }
+    match config.mode {
+        CallFilterMode::Allowlist => true,
+        CallFilterMode::Blocklist => matches_any(&config.block_list, number),
+    }
+}
+
+/// 是否不转发该发件人的短信
+pub fn suppress_sms(config: &CallFilterConfig, sender: &str) -> bool {
+    config.filter_sms && is_blocked(config, sender)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: NumberMatch, pattern: &str) -> NumberPattern {
+        NumberPattern { kind, pattern: pattern.to_string() }
+    }
+
+    #[test]
+    fn block_and_allow_lists() {
+        let mut config = CallFilterConfig {
+            enabled: true,
+            mode: CallFilterMode::Blocklist,
+            block_list: vec![
+                entry(NumberMatch::Exact, "+86 138 0013 8000"),
+                entry(NumberMatch::Prefix, "400"),
+                entry(NumberMatch::Regex, r"^\+?0*95\d{3}$"),
+                entry(NumberMatch::Withheld, ""),
+            ],
+            allow_list: vec![entry(NumberMatch::Exact, "4001234567")],
+            filter_sms: false,
+        };
+        assert!(is_blocked(&config, "013800138000"));
+        assert!(is_blocked(&config, "4008123123"));
+        assert!(is_blocked(&config, "95555"));
+        assert!(is_blocked(&config, "withheld"));
+        assert!(is_blocked(&config, ""));
+        assert!(!is_blocked(&config, "4001234567"));
+        assert!(!is_blocked(&config, "13900139000"));
+        assert!(!suppress_sms(&config, "4008123123"));
+
+        config.filter_sms = true;
+        assert!(suppress_sms(&config, "4008123123"));
+
+        config.mode = CallFilterMode::Allowlist;
+        assert!(is_blocked(&config, "13900139000"));
+        assert!(!is_blocked(&config, "+86 400 123 4567"));
+
+        config.enabled = false;
+        assert!(!is_blocked(&config, "withheld"));
+
+        config.block_list.push(entry(NumberMatch::Regex, "("));
+        assert!(validate_config(&config).is_err());
+        config.block_list.pop();
+        config.block_list.push(entry(NumberMatch::Prefix, ""));
+        assert!(validate_config(&config).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! 通话策略引擎
+//!
+//! 按星期和时段匹配 `CallPolicyConfig.policies`，为无人值守站点自动处理通话：
+//! - `auto_answer`：名单中的号码来电，响铃 N 秒后自动接听
+//! - `reject`：自动拒接（如非工作时间）
+//! - `max_duration`：通话接通后超过时长自动挂断
+//!
+//! 来电按顺序取第一条命中的接听/拒接策略，时长限制取第一条命中的 `max_duration` 策略，
+//! 对去电同样生效。每次自动操作都会写入通话记录的 `reason` 字段。
+
+use chrono::{Datelike, NaiveDateTime};
+
+use crate::call_filter::matches_any;
+use crate::config::{CallPolicy, CallPolicyAction, CallPolicyConfig};
+use crate::utils::{in_time_window, parse_time_of_day};
+
+/// 校验策略配置
+pub fn validate_config(config: &CallPolicyConfig) -> Result<(), String> {
+    for policy in &config.policies {
+        if policy.name.is_empty() {
+            return Err("Policy name is required".to_string());
+        }
+        if let Some(day) = policy.weekdays.iter().find(|day| !(1..=7).contains(*day)) {
+            return Err(format!("Policy '{}': invalid weekday {} (1 = Monday … 7 = Sunday)", policy.name, day));
+        }
+        match (policy.time_window_start.as_str(), policy.time_window_end.as_str()) {
+            ("", "") => {}
+            (start, end) => {
+                for time in [start, end] {
+                    parse_time_of_day(time)
+                        .ok_or_else(|| format!("Policy '{}' has an invalid time: '{}'", policy.name, time))?;
+                }
+            }
+        }
+        crate::call_filter::validate_patterns(&policy.numbers)
+            .map_err(|e| format!("Policy '{}': {}", policy.name, e))?;
+        if let CallPolicyAction::MaxDuration { max_secs: 0 } = policy.action {
+            return Err(format!("Policy '{}': max duration must be greater than 0", policy.name));
+        }
+    }
+    Ok(())
+}
+
+/// 来电的自动处理方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncomingAction {
+    Answer { delay_secs: u64, policy: String },
+    Reject { policy: String },
+}
+
+/// 一通电话适用的策略
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallDecision {
+    /// 仅对来电有效
+    pub incoming: Option<IncomingAction>,
+    /// (最长通话秒数, 策略名称)
+    pub max_duration: Option<(u64, String)>,
+}
+
+/// 策略在当前时间是否生效且号码匹配
+fn policy_applies(policy: &CallPolicy, number: &str, now: NaiveDateTime) -> bool {
+    if !policy.enabled {
+        return false;
+    }
+    let weekday = now.weekday().number_from_monday() as u8;
+    if !policy.weekdays.is_empty() && !policy.weekdays.contains(&weekday) {
+        return false;
+    }
+    if let (Some(start), Some(end)) = (
+        parse_time_of_day(&policy.time_window_start),
+        parse_time_of_day(&policy.time_window_end),
+    ) {
+        if !in_time_window(now.time(), start, end) {
+            return false;
+        }
+    }
+    policy.numbers.is_empty() || matches_any(&policy.numbers, number)
+}
+
+/// 按本地时间匹配策略
+pub fn evaluate(config: &CallPolicyConfig, number: &str, incoming: bool, now: NaiveDateTime) -> CallDecision {
+    let mut decision = CallDecision::default();
+    if !config.enabled {
+        return decision;
+    }
+
+    for policy in config.policies.iter().filter(|p| policy_applies(p, number, now)) {
+        match policy.action {
+            CallPolicyAction::AutoAnswer { delay_secs } if incoming && decision.incoming.is_none() => {
+                decision.incoming = Some(IncomingAction::Answer { delay_secs, policy: policy.name.clone() });
+            }
+            CallPolicyAction::Reject if incoming && decision.incoming.is_none() => {
+                decision.incoming = Some(IncomingAction::Reject { policy: policy.name.clone() });
+            }
+            CallPolicyAction::MaxDuration { max_secs } if decision.max_duration.is_none() => {
+                decision.max_duration = Some((max_secs, policy.name.clone()));
+            }
+            _ => {}
+        }
+    }
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{NumberMatch, NumberPattern};
+
+    fn policy(name: &str, weekdays: Vec<u8>, window: (&str, &str), action: CallPolicyAction) -> CallPolicy {
+        CallPolicy {
+            name: name.to_string(),
+            enabled: true,
+            weekdays,
+            time_window_start: window.0.to_string(),
+            time_window_end: window.1.to_string(),
+            numbers: Vec::new(),
+            action,
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn policies_match_weekday_window_and_number() {
+        let mut answer = policy("noc", vec![], ("", ""), CallPolicyAction::AutoAnswer { delay_secs: 5 });
+        answer.numbers = vec![NumberPattern { kind: NumberMatch::Exact, pattern: "13800138000".to_string() }];
+        let config = CallPolicyConfig {
+            enabled: true,
+            policies: vec![
+                answer,
+                policy("night", vec![1, 2, 3, 4, 5], ("18:00", "09:00"), CallPolicyAction::Reject),
+                policy("weekend", vec![6, 7], ("", ""), CallPolicyAction::Reject),
+                policy("limit", vec![], ("", ""), CallPolicyAction::MaxDuration { max_secs: 600 }),
+            ],
+        };
+
+        // 2025-12-08 是周一
+        let decision = evaluate(&config, "+8613800138000", true, at("2025-12-08 23:00"));
+        assert_eq!(decision.incoming, Some(IncomingAction::Answer { delay_secs: 5, policy: "noc".to_string() }));
+        assert_eq!(decision.max_duration, Some((600, "limit".to_string())));
+
+        let reject = Some(IncomingAction::Reject { policy: "night".to_string() });
+        assert_eq!(evaluate(&config, "10086", true, at("2025-12-09 07:30")).incoming, reject);
+        assert_eq!(evaluate(&config, "10086", true, at("2025-12-09 10:00")).incoming, None);
+        let weekend = evaluate(&config, "10086", true, at("2025-12-13 10:00"));
+        assert_eq!(weekend.incoming, Some(IncomingAction::Reject { policy: "weekend".to_string() }));
+
+        // 去电只受时长限制
+        let outgoing = evaluate(&config, "10086", false, at("2025-12-13 10:00"));
+        assert_eq!(outgoing.incoming, None);
+        assert!(outgoing.max_duration.is_some());
+
+        assert_eq!(evaluate(&CallPolicyConfig::default(), "10086", true, at("2025-12-13 10:00")), CallDecision::default());
+
+        let mut invalid = config.clone();
+        invalid.policies[1].weekdays = vec![0];
+        assert!(validate_config(&invalid).is_err());
+        invalid.policies[1].weekdays = vec![];
+        invalid.policies[1].time_window_end = "25:00".to_string();
+        assert!(validate_config(&invalid).is_err());
+        assert!(validate_config(&config).is_ok());
+    }
+}
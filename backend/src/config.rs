@@ -24,6 +24,66 @@ const DEFAULT_LOADER_SCRIPT: &str = r#"#!/bin/sh
 /home/root/ttyd/start.sh &
 /home/root/udx710 -p 80 &
 "#;
+/// A/B 分区启动脚本
+///
+/// 按 `/home/root/ota/state.env` 选择槽位（base=/home/root，a/b=/home/root/ota/slot_a|b）启动后端，
+/// 后端退出后自动重启。未确认（CONFIRMED=0）的槽位每次启动计数一次，
+/// 达到 MAX_BOOT_ATTEMPTS 后切回 PREVIOUS_SLOT。
+const AB_LOADER_SCRIPT: &str = r#"#!/bin/sh
+# UDX710 OTA bootstrap (A/B slots, managed by udx710; put custom commands in init.sh)
+OTA_STATE_FILE="/home/root/ota/state.env"
+
+save_state() {
+    {
+        echo "ACTIVE_SLOT=$ACTIVE_SLOT"
+        echo "PREVIOUS_SLOT=$PREVIOUS_SLOT"
+        echo "CONFIRMED=$CONFIRMED"
+        echo "BOOT_ATTEMPTS=$BOOT_ATTEMPTS"
+        echo "MAX_BOOT_ATTEMPTS=$MAX_BOOT_ATTEMPTS"
+        echo "ROLLED_BACK_FROM=$ROLLED_BACK_FROM"
+    } > "$OTA_STATE_FILE.tmp"
+    sync
+    mv -f "$OTA_STATE_FILE.tmp" "$OTA_STATE_FILE"
+}
+
+run_backend() {
+    while true; do
+        ACTIVE_SLOT=base
+        PREVIOUS_SLOT=
+        CONFIRMED=1
+        BOOT_ATTEMPTS=0
+        MAX_BOOT_ATTEMPTS=3
+        ROLLED_BACK_FROM=
+        [ -f "$OTA_STATE_FILE" ] && . "$OTA_STATE_FILE"
+
+        if [ "$CONFIRMED" != "1" ]; then
+            if [ "$BOOT_ATTEMPTS" -ge "$MAX_BOOT_ATTEMPTS" ] && [ -n "$PREVIOUS_SLOT" ]; then
+                ROLLED_BACK_FROM=$ACTIVE_SLOT
+                ACTIVE_SLOT=$PREVIOUS_SLOT
+                PREVIOUS_SLOT=
+                CONFIRMED=1
+                BOOT_ATTEMPTS=0
+            else
+                BOOT_ATTEMPTS=$((BOOT_ATTEMPTS + 1))
+            fi
+            save_state
+        fi
+
+        case "$ACTIVE_SLOT" in
+            a|b) SLOT_DIR="/home/root/ota/slot_$ACTIVE_SLOT" ;;
+            *) SLOT_DIR="/home/root" ;;
+        esac
+        [ -x "$SLOT_DIR/udx710" ] || SLOT_DIR="/home/root"
+
+        "$SLOT_DIR/udx710" -p 80
+        sleep 3
+    done
+}
+
+/home/root/ttyd/start.sh &
+run_backend &
+sh /home/root/init.sh &
+"#;
 const LOADER_SCRIPT_PATH: &str = "/home/root/loader.sh";
 const INIT_SCRIPT_PATH: &str = "/home/root/init.sh";
 const INIT_SCRIPT_LOADER_COMMAND: &str = "sh /home/root/init.sh &";
@@ -43,6 +103,106 @@ pub struct WebhookConfig {
     pub sms_template: String,  // 短信 payload 模板
     #[serde(default = "default_call_template")]
     pub call_template: String,  // 通话 payload 模板
+    #[serde(default = "default_forward_data_usage")]
+    pub forward_data_usage: bool,
+    #[serde(default = "default_data_usage_template")]
+    pub data_usage_template: String,  // 流量告警 payload 模板
+    #[serde(default = "default_forward_sms_status")]
+    pub forward_sms_status: bool,     // 转发发出短信的投递结果（已送达/失败）
+    #[serde(default = "default_sms_status_template")]
+    pub sms_status_template: String,  // 投递结果 payload 模板
+    #[serde(default = "default_forward_call_ringing")]
+    pub forward_call_ringing: bool,   // 来电响铃时立即转发（需同时开启 forward_calls）
+    #[serde(default = "default_call_ringing_template")]
+    pub call_ringing_template: String,  // 来电响铃 payload 模板
+    #[serde(default = "default_event_template")]
+    pub event_template: String,  // 设备通知（数据断开/信号告警/重启/OTA）payload 模板
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,  // 额外的 Webhook 目标，按事件类型和号码/内容过滤
+}
+
+/// Webhook 事件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// 收到短信
+    Sms,
+    /// 发出短信的投递结果
+    SmsStatus,
+    /// 来电响铃
+    CallRinging,
+    /// 通话结束汇总
+    Call,
+    /// 流量配额告警
+    DataUsage,
+    /// 数据连接断开
+    DataDown,
+    /// 网络注册丢失/恢复
+    SignalAlert,
+    /// 设备（服务）启动
+    Reboot,
+    /// OTA 新版本可用
+    Ota,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sms => "sms",
+            Self::SmsStatus => "sms_status",
+            Self::CallRinging => "call_ringing",
+            Self::Call => "call",
+            Self::DataUsage => "data_usage",
+            Self::DataDown => "data_down",
+            Self::SignalAlert => "signal_alert",
+            Self::Reboot => "reboot",
+            Self::Ota => "ota",
+        }
+    }
+}
+
+/// Webhook 目标：独立的 URL、请求头、签名密钥、模板、事件类型和过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub name: String,
+    #[serde(default = "default_webhook_endpoint_enabled")]
+    pub enabled: bool,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub secret: String,
+    /// payload 模板，为空时使用全局配置中对应事件的模板
+    #[serde(default)]
+    pub template: String,
+    /// 订阅的事件类型，为空表示全部
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// 短信发件人/通话号码过滤，为空表示不限（设置后不再接收不带号码的设备通知）
+    #[serde(default)]
+    pub numbers: Vec<NumberPattern>,
+    /// 短信内容正则，为空表示不限（设置后只接收短信）
+    #[serde(default)]
+    pub content_regex: String,
+}
+
+fn default_webhook_endpoint_enabled() -> bool {
+    true
+}
+
+impl WebhookConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.url = self.url.trim().to_string();
+        for endpoint in &mut self.endpoints {
+            endpoint.name = endpoint.name.trim().to_string();
+            endpoint.url = endpoint.url.trim().to_string();
+            endpoint.content_regex = endpoint.content_regex.trim().to_string();
+            for entry in &mut endpoint.numbers {
+                entry.pattern = entry.pattern.trim().to_string();
+            }
+        }
+        self
+    }
 }
 
 /// 默认短信模板 (飞书机器人格式)
@@ -65,6 +225,58 @@ fn default_call_template() -> String {
 }"#.to_string()
 }
 
+fn default_forward_data_usage() -> bool {
+    true
+}
+
+/// 默认流量告警模板 (飞书机器人格式)
+fn default_data_usage_template() -> String {
+    r#"{
+  "msg_type": "text",
+  "content": {
+    "text": "📶 流量提醒: {{level_cn}}\n本期已用: {{used_mb}} MB / {{quota_mb}} MB ({{percent}}%)\n计费周期: {{cycle_start}} ~ {{cycle_end}}\n处理: {{action_cn}}"
+  }
+}"#.to_string()
+}
+
+fn default_forward_sms_status() -> bool {
+    true
+}
+
+/// 默认短信投递结果模板 (飞书机器人格式)
+fn default_sms_status_template() -> String {
+    r#"{
+  "msg_type": "text",
+  "content": {
+    "text": "📨 短信投递结果: {{status}}\n接收方: {{phone_number}}\n内容: {{content}}\n发送时间: {{sent_at}}\n送达时间: {{delivered_at}}\n失败时间: {{failed_at}}"
+  }
+}"#.to_string()
+}
+
+fn default_forward_call_ringing() -> bool {
+    true
+}
+
+/// 默认来电响铃模板 (飞书机器人格式)
+fn default_call_ringing_template() -> String {
+    r#"{
+  "msg_type": "text",
+  "content": {
+    "text": "📞 来电响铃\n号码: {{phone_number}}\n联系人: {{contact_name}}\n时间: {{start_time}}"
+  }
+}"#.to_string()
+}
+
+/// 默认设备通知模板 (飞书机器人格式)
+fn default_event_template() -> String {
+    r#"{
+  "msg_type": "text",
+  "content": {
+    "text": "🔔 {{text}}\n时间: {{timestamp}}"
+  }
+}"#.to_string()
+}
+
 impl Default for WebhookConfig {
     fn default() -> Self {
         Self {
@@ -76,10 +288,77 @@ impl Default for WebhookConfig {
             secret: String::new(),
             sms_template: default_sms_template(),
             call_template: default_call_template(),
+            forward_data_usage: default_forward_data_usage(),
+            data_usage_template: default_data_usage_template(),
+            forward_sms_status: default_forward_sms_status(),
+            sms_status_template: default_sms_status_template(),
+            forward_call_ringing: default_forward_call_ringing(),
+            call_ringing_template: default_call_ringing_template(),
+            event_template: default_event_template(),
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+/// API 密钥权限范围
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ApiScope {
+    /// 只读访问（GET 接口，敏感配置除外）
+    #[serde(rename = "read")]
+    Read,
+    /// 发送短信，管理会话、发送队列和通讯录
+    #[serde(rename = "sms:send")]
+    SmsSend,
+    /// 拨打/接听/挂断电话、通话记录及 USSD
+    #[serde(rename = "call")]
+    Call,
+    /// 修改调制解调器配置（数据连接、频段/小区锁定、APN、USB 模式等）
+    #[serde(rename = "modem:config")]
+    ModemConfig,
+    /// 系统管理（重启、OTA、init.sh、推送配置、短信规则、密钥管理等），包含除 at:raw 外的所有权限
+    #[serde(rename = "system:admin")]
+    SystemAdmin,
+    /// 发送原始 AT 指令，需单独授予
+    #[serde(rename = "at:raw")]
+    AtRaw,
+}
+
+impl ApiScope {
+    /// 持有本权限时是否也满足 `other`：system:admin 隐含 read、sms:send、call 和 modem:config
+    pub fn includes(self, other: ApiScope) -> bool {
+        self == other || (self == Self::SystemAdmin && other != Self::AtRaw)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::SmsSend => "sms:send",
+            Self::Call => "call",
+            Self::ModemConfig => "modem:config",
+            Self::SystemAdmin => "system:admin",
+            Self::AtRaw => "at:raw",
         }
     }
 }
 
+/// API 密钥配置（只保存密钥的 SHA-256 哈希）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    pub id: String,
+    pub name: String,
+    pub key_hash: String,
+    #[serde(default)]
+    pub scopes: Vec<ApiScope>,
+    /// 允许访问的路径前缀，为空表示不限制
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub last_used_at: Option<String>,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
 /// 短信推送服务提供商
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -97,6 +376,36 @@ impl Default for SmsPushProvider {
     }
 }
 
+/// 推送的通话事件
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CallPushEvent {
+    /// 来电响铃
+    Ringing,
+    /// 未接来电
+    Missed,
+    /// 已接通的通话结束
+    Completed,
+}
+
+impl CallPushEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ringing => "ringing",
+            Self::Missed => "missed",
+            Self::Completed => "completed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ringing => "来电响铃",
+            Self::Missed => "未接来电",
+            Self::Completed => "通话结束",
+        }
+    }
+}
+
 /// 短信推送配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmsPushConfig {
@@ -113,6 +422,13 @@ pub struct SmsPushConfig {
     pub title_template: String,
     #[serde(default = "default_sms_push_body_template")]
     pub body_template: String,
+    /// 推送的通话事件，为空表示不推送通话
+    #[serde(default = "default_sms_push_call_events")]
+    pub call_events: Vec<CallPushEvent>,
+    #[serde(default = "default_sms_push_call_title_template")]
+    pub call_title_template: String,
+    #[serde(default = "default_sms_push_call_body_template")]
+    pub call_body_template: String,
 }
 
 fn default_sms_push_title_template() -> String {
@@ -123,6 +439,18 @@ fn default_sms_push_body_template() -> String {
     "时间: {{timestamp}}\n号码: {{phone_number}}\n状态: {{status}}\n\n{{content}}".to_string()
 }
 
+fn default_sms_push_call_events() -> Vec<CallPushEvent> {
+    vec![CallPushEvent::Missed]
+}
+
+fn default_sms_push_call_title_template() -> String {
+    "{{event_cn}} · {{phone_number}}".to_string()
+}
+
+fn default_sms_push_call_body_template() -> String {
+    "时间: {{start_time}}\n号码: {{phone_number}}\n联系人: {{contact_name}}\n时长: {{duration}}秒".to_string()
+}
+
 impl Default for SmsPushConfig {
     fn default() -> Self {
         Self {
@@ -133,6 +461,9 @@ impl Default for SmsPushConfig {
             topic: String::new(),
             title_template: default_sms_push_title_template(),
             body_template: default_sms_push_body_template(),
+            call_events: default_sms_push_call_events(),
+            call_title_template: default_sms_push_call_title_template(),
+            call_body_template: default_sms_push_call_body_template(),
         }
     }
 }
@@ -193,15 +524,633 @@ fn sanitize_refresh_interval_ms(interval_ms: u64) -> u64 {
     }
 }
 
+/// 信号质量历史记录配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalHistoryConfig {
+    /// 是否启用后台采样
+    #[serde(default = "default_signal_enabled")]
+    pub enabled: bool,
+    /// 采样间隔（秒）
+    #[serde(default = "default_signal_interval_secs")]
+    pub interval_secs: u64,
+    /// 原始采样保留时长（小时），超过后按小时降采样
+    #[serde(default = "default_signal_raw_retention_hours")]
+    pub raw_retention_hours: u64,
+    /// 降采样数据保留天数
+    #[serde(default = "default_signal_retention_days")]
+    pub retention_days: u64,
+}
+
+fn default_signal_enabled() -> bool {
+    true
+}
+
+fn default_signal_interval_secs() -> u64 {
+    60
+}
+
+fn default_signal_raw_retention_hours() -> u64 {
+    48
+}
+
+fn default_signal_retention_days() -> u64 {
+    90
+}
+
+impl Default for SignalHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_signal_enabled(),
+            interval_secs: default_signal_interval_secs(),
+            raw_retention_hours: default_signal_raw_retention_hours(),
+            retention_days: default_signal_retention_days(),
+        }
+    }
+}
+
+impl SignalHistoryConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.interval_secs = self.interval_secs.clamp(10, 3_600);
+        self.raw_retention_hours = self.raw_retention_hours.clamp(1, 24 * 30);
+        self.retention_days = self.retention_days.clamp(1, 3_650);
+        self
+    }
+}
+
+/// 超出流量配额后的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaAction {
+    /// 仅通过 Webhook 告警
+    #[default]
+    Warn,
+    /// 告警并关闭数据连接
+    Disconnect,
+}
+
+/// 流量统计配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataUsageConfig {
+    /// 是否启用流量统计
+    #[serde(default = "default_data_usage_enabled")]
+    pub enabled: bool,
+    /// 统计的网络接口，为空时自动使用数据连接的接口
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+    /// 采样间隔（秒）
+    #[serde(default = "default_data_usage_interval_secs")]
+    pub interval_secs: u64,
+    /// 计费周期起始日（1-28）
+    #[serde(default = "default_billing_day")]
+    pub billing_day: u32,
+    /// 每月流量配额（MB），0 表示不限制
+    #[serde(default)]
+    pub monthly_quota_mb: u64,
+    /// 用量达到配额的百分比时提前告警，0 表示不提前告警
+    #[serde(default = "default_warn_percent")]
+    pub warn_percent: u8,
+    /// 超出配额后的处理方式
+    #[serde(default)]
+    pub quota_action: QuotaAction,
+}
+
+fn default_data_usage_enabled() -> bool {
+    true
+}
+
+fn default_data_usage_interval_secs() -> u64 {
+    60
+}
+
+fn default_billing_day() -> u32 {
+    1
+}
+
+fn default_warn_percent() -> u8 {
+    90
+}
+
+impl Default for DataUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_data_usage_enabled(),
+            interfaces: Vec::new(),
+            interval_secs: default_data_usage_interval_secs(),
+            billing_day: default_billing_day(),
+            monthly_quota_mb: 0,
+            warn_percent: default_warn_percent(),
+            quota_action: QuotaAction::default(),
+        }
+    }
+}
+
+impl DataUsageConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.interval_secs = self.interval_secs.clamp(10, 3_600);
+        self.billing_day = self.billing_day.clamp(1, 28);
+        self.warn_percent = self.warn_percent.min(99);
+        self.interfaces.retain(|name| !name.trim().is_empty());
+        self
+    }
+}
+
+/// 短信接收方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SmsIngestMode {
+    /// 使用 ofono 拼接好的 IncomingMessage 文本
+    #[default]
+    Text,
+    /// 订阅 MessagePDU 原始 PDU，由本服务解码并拼接长短信
+    Pdu,
+}
+
+/// 短信接收配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsIngestConfig {
+    #[serde(default)]
+    pub mode: SmsIngestMode,
+    /// 长短信分段等待超时（秒），超时后以 partial 状态保存已收到的部分
+    #[serde(default = "default_fragment_timeout_secs")]
+    pub fragment_timeout_secs: u64,
+}
+
+fn default_fragment_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for SmsIngestConfig {
+    fn default() -> Self {
+        Self {
+            mode: SmsIngestMode::default(),
+            fragment_timeout_secs: default_fragment_timeout_secs(),
+        }
+    }
+}
+
+impl SmsIngestConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.fragment_timeout_secs = self.fragment_timeout_secs.clamp(30, 86_400);
+        self
+    }
+}
+
+/// 设备指令（仅授权号码可触发）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmsDeviceCommand {
+    /// 重启设备
+    Reboot,
+    /// 打开数据连接
+    DataOn,
+    /// 关闭数据连接
+    DataOff,
+    /// 回复网络、数据连接和运行时间状态
+    Status,
+}
+
+/// 短信规则动作
+///
+/// 模板中可使用 `{{sender}}`、`{{content}}`、`{{time}}`、`{{rule}}` 及正则分组 `{{1}}`…`{{9}}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SmsRuleAction {
+    /// 回复发件人
+    Reply { template: String },
+    /// 转发到其他号码，模板为空时使用 `[{{sender}}] {{content}}`
+    Forward {
+        to: String,
+        #[serde(default)]
+        template: String,
+    },
+    /// 给短信打标签
+    Tag { tag: String },
+    /// 发送到 Webhook，模板为空时使用 Webhook 配置的短信模板
+    Webhook {
+        #[serde(default)]
+        template: String,
+    },
+    /// 执行设备指令并把结果回复给发件人
+    Device { command: SmsDeviceCommand },
+}
+
+/// 短信规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsRule {
+    pub name: String,
+    #[serde(default = "default_sms_rule_enabled")]
+    pub enabled: bool,
+    /// 发件人号码，逗号分隔，`*` 结尾表示前缀匹配，为空匹配所有发件人
+    #[serde(default)]
+    pub sender: String,
+    /// 内容正则，为空匹配所有内容
+    #[serde(default)]
+    pub content_regex: String,
+    /// 生效时段开始时间（本地时间 HH:MM），与结束时间都为空表示全天
+    #[serde(default)]
+    pub time_window_start: String,
+    /// 生效时段结束时间（本地时间 HH:MM，早于开始时间表示跨零点）
+    #[serde(default)]
+    pub time_window_end: String,
+    /// 命中后不再匹配后续规则
+    #[serde(default)]
+    pub stop: bool,
+    pub actions: Vec<SmsRuleAction>,
+}
+
+fn default_sms_rule_enabled() -> bool {
+    true
+}
+
+impl SmsRule {
+    /// 包含设备指令的规则只接受授权号码
+    pub fn requires_authorization(&self) -> bool {
+        self.actions.iter().any(|action| matches!(action, SmsRuleAction::Device { .. }))
+    }
+}
+
+/// 短信规则配置（自动回复、关键词指令）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SmsRulesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 设备指令 PIN，设置后指令短信需以 PIN 开头（如 `1234 reboot`）
+    #[serde(default)]
+    pub pin: String,
+    /// 允许触发设备指令的号码
+    #[serde(default)]
+    pub authorized_senders: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<SmsRule>,
+}
+
+impl SmsRulesConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.pin = self.pin.trim().to_string();
+        let mut senders: Vec<String> = Vec::new();
+        for sender in &self.authorized_senders {
+            let sender = sender.trim();
+            if !sender.is_empty() && !senders.iter().any(|s| s == sender) {
+                senders.push(sender.to_string());
+            }
+        }
+        self.authorized_senders = senders;
+        for rule in &mut self.rules {
+            rule.name = rule.name.trim().to_string();
+            rule.sender = rule.sender.trim().to_string();
+            rule.time_window_start = rule.time_window_start.trim().to_string();
+            rule.time_window_end = rule.time_window_end.trim().to_string();
+        }
+        self
+    }
+}
+
+/// 号码名单条目的匹配方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberMatch {
+    /// 精确号码（忽略 `+86`、前导 0、空格等格式差异）
+    Exact,
+    /// 号码前缀（如 `400`、`+86170`）
+    Prefix,
+    /// 正则表达式，匹配来电显示的原始号码
+    Regex,
+    /// 隐藏号码（不显示来电号码）
+    Withheld,
+}
+
+/// 号码名单条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumberPattern {
+    #[serde(rename = "type")]
+    pub kind: NumberMatch,
+    /// 号码、前缀或正则，`withheld` 类型不需要
+    #[serde(default)]
+    pub pattern: String,
+}
+
+/// 来电拦截模式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CallFilterMode {
+    /// 拦截黑名单中的号码
+    #[default]
+    Blocklist,
+    /// 只接受白名单中的号码
+    Allowlist,
+}
+
+/// 来电拦截与号码黑白名单配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CallFilterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: CallFilterMode,
+    #[serde(default)]
+    pub block_list: Vec<NumberPattern>,
+    /// 白名单优先于黑名单
+    #[serde(default)]
+    pub allow_list: Vec<NumberPattern>,
+    /// 命中名单的短信发件人不转发到 Webhook 和推送服务（短信仍会保存）
+    #[serde(default)]
+    pub filter_sms: bool,
+}
+
+impl CallFilterConfig {
+    pub fn sanitize(mut self) -> Self {
+        for entry in self.block_list.iter_mut().chain(self.allow_list.iter_mut()) {
+            entry.pattern = match entry.kind {
+                NumberMatch::Withheld => String::new(),
+                _ => entry.pattern.trim().to_string(),
+            };
+        }
+        self
+    }
+}
+
+/// 通话策略动作
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CallPolicyAction {
+    /// 来电响铃 `delay_secs` 秒后自动接听
+    AutoAnswer {
+        #[serde(default)]
+        delay_secs: u64,
+    },
+    /// 自动拒接来电
+    Reject,
+    /// 接通后超过 `max_secs` 秒自动挂断（来电和去电）
+    MaxDuration { max_secs: u64 },
+}
+
+/// 通话策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallPolicy {
+    pub name: String,
+    #[serde(default = "default_call_policy_enabled")]
+    pub enabled: bool,
+    /// 生效的星期（1 = 周一 … 7 = 周日），为空表示每天
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+    /// 生效时段开始时间（本地时间 HH:MM），与结束时间都为空表示全天
+    #[serde(default)]
+    pub time_window_start: String,
+    /// 生效时段结束时间（本地时间 HH:MM，早于开始时间表示跨零点）
+    #[serde(default)]
+    pub time_window_end: String,
+    /// 适用的号码，为空表示所有号码
+    #[serde(default)]
+    pub numbers: Vec<NumberPattern>,
+    pub action: CallPolicyAction,
+}
+
+fn default_call_policy_enabled() -> bool {
+    true
+}
+
+/// 通话策略配置（自动接听、拒接、限时挂断）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CallPolicyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub policies: Vec<CallPolicy>,
+}
+
+impl CallPolicyConfig {
+    pub fn sanitize(mut self) -> Self {
+        for policy in &mut self.policies {
+            policy.name = policy.name.trim().to_string();
+            policy.time_window_start = policy.time_window_start.trim().to_string();
+            policy.time_window_end = policy.time_window_end.trim().to_string();
+            policy.weekdays.sort_unstable();
+            policy.weekdays.dedup();
+            for entry in &mut policy.numbers {
+                entry.pattern = entry.pattern.trim().to_string();
+            }
+        }
+        self
+    }
+}
+
+/// SIM PIN 配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SimPinConfig {
+    /// Watchdog 检测到 SIM 等待 PIN 时自动输入
+    #[serde(default)]
+    pub auto_enter: bool,
+    /// 自动输入的 PIN（接口不返回）
+    #[serde(default)]
+    pub pin: String,
+}
+
+impl SimPinConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.pin = self.pin.trim().to_string();
+        self
+    }
+}
+
+/// 短信发送队列配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsQueueConfig {
+    /// 每个号码每小时最多发送的短信数，0 表示不限制
+    #[serde(default = "default_sms_queue_max_per_hour")]
+    pub max_per_hour_per_number: u32,
+    /// 新建任务的默认最大尝试次数
+    #[serde(default = "default_sms_queue_max_attempts")]
+    pub max_attempts: u32,
+    /// 首次重试等待时间（秒），之后每次翻倍，最长 1 小时
+    #[serde(default = "default_sms_queue_retry_base_secs")]
+    pub retry_base_secs: u64,
+}
+
+fn default_sms_queue_max_per_hour() -> u32 {
+    10
+}
+
+fn default_sms_queue_max_attempts() -> u32 {
+    5
+}
+
+fn default_sms_queue_retry_base_secs() -> u64 {
+    60
+}
+
+impl Default for SmsQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_per_hour_per_number: default_sms_queue_max_per_hour(),
+            max_attempts: default_sms_queue_max_attempts(),
+            retry_base_secs: default_sms_queue_retry_base_secs(),
+        }
+    }
+}
+
+impl SmsQueueConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.max_attempts = self.max_attempts.clamp(1, 20);
+        self.retry_base_secs = self.retry_base_secs.clamp(10, 3_600);
+        self
+    }
+}
+
+/// OTA 信任的签名公钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtaTrustedKey {
+    /// 显示名称（作为签名者身份）
+    pub name: String,
+    /// minisign 公钥（base64 或完整 .pub 文件内容）
+    pub public_key: String,
+}
+
+/// OTA 更新包签名配置
+///
+/// 编译时可通过 `OTA_PUBLIC_KEYS` 环境变量内置公钥，这里的公钥是额外信任的
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OtaConfig {
+    #[serde(default)]
+    pub trusted_keys: Vec<OtaTrustedKey>,
+    /// 开发者模式：允许安装未签名的更新包（签名无效的更新包仍会被拒绝）
+    #[serde(default)]
+    pub developer_mode: bool,
+}
+
+/// 发现新版本后的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OtaUpdatePolicy {
+    /// 下载并验证后推送 `ota_update_available` 事件，由用户手动应用
+    #[default]
+    Notify,
+    /// 下载并验证后在维护窗口内自动应用并重启
+    AutoApply,
+}
+
+/// OTA 更新通道配置（定期拉取更新清单）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtaChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 更新清单 URL
+    #[serde(default)]
+    pub manifest_url: String,
+    /// 订阅的通道（stable / beta）
+    #[serde(default = "default_ota_channel")]
+    pub channel: String,
+    /// 检查间隔（秒）
+    #[serde(default = "default_ota_check_interval_secs")]
+    pub check_interval_secs: u64,
+    #[serde(default)]
+    pub policy: OtaUpdatePolicy,
+    /// 维护窗口开始时间（本地时间 HH:MM）
+    #[serde(default = "default_maintenance_window_start")]
+    pub maintenance_window_start: String,
+    /// 维护窗口结束时间（本地时间 HH:MM，早于开始时间表示跨零点）
+    #[serde(default = "default_maintenance_window_end")]
+    pub maintenance_window_end: String,
+}
+
+fn default_ota_channel() -> String {
+    "stable".to_string()
+}
+
+fn default_ota_check_interval_secs() -> u64 {
+    6 * 60 * 60
+}
+
+fn default_maintenance_window_start() -> String {
+    "03:00".to_string()
+}
+
+fn default_maintenance_window_end() -> String {
+    "05:00".to_string()
+}
+
+impl Default for OtaChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manifest_url: String::new(),
+            channel: default_ota_channel(),
+            check_interval_secs: default_ota_check_interval_secs(),
+            policy: OtaUpdatePolicy::default(),
+            maintenance_window_start: default_maintenance_window_start(),
+            maintenance_window_end: default_maintenance_window_end(),
+        }
+    }
+}
+
+impl OtaChannelConfig {
+    pub fn sanitize(mut self) -> Self {
+        self.manifest_url = self.manifest_url.trim().to_string();
+        self.channel = self.channel.trim().to_string();
+        if self.channel.is_empty() {
+            self.channel = default_ota_channel();
+        }
+        self.check_interval_secs = self.check_interval_secs.clamp(300, 7 * 24 * 60 * 60);
+        self
+    }
+}
+
+/// 登录认证配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// 管理员密码哈希（pbkdf2-sha256$迭代次数$盐$哈希），为空表示尚未设置密码
+    #[serde(default)]
+    pub password_hash: String,
+    /// 会话有效期（秒），每次请求后顺延
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+}
+
+fn default_session_ttl_secs() -> u64 {
+    12 * 60 * 60
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            password_hash: String::new(),
+            session_ttl_secs: default_session_ttl_secs(),
+        }
+    }
+}
+
 /// 应用配置
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppConfig {
+    #[serde(default)]
+    pub auth: AuthConfig,
     #[serde(default)]
     pub webhook: WebhookConfig,
     #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
     pub sms_push: SmsPushConfig,
     #[serde(default)]
     pub refresh: RefreshConfig,
+    #[serde(default)]
+    pub signal_history: SignalHistoryConfig,
+    #[serde(default)]
+    pub data_usage: DataUsageConfig,
+    #[serde(default)]
+    pub sms_ingest: SmsIngestConfig,
+    #[serde(default)]
+    pub sms_queue: SmsQueueConfig,
+    #[serde(default)]
+    pub sms_rules: SmsRulesConfig,
+    #[serde(default)]
+    pub call_filter: CallFilterConfig,
+    #[serde(default)]
+    pub call_policy: CallPolicyConfig,
+    #[serde(default)]
+    pub sim_pin: SimPinConfig,
+    #[serde(default)]
+    pub ota: OtaConfig,
+    #[serde(default)]
+    pub ota_channel: OtaChannelConfig,
 }
 
 
@@ -266,7 +1215,19 @@ impl ConfigManager {
     pub fn set_webhook(&self, webhook: WebhookConfig) -> Result<(), String> {
         {
             let mut config = self.config.write().unwrap();
-            config.webhook = webhook;
+            config.webhook = webhook.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_api_keys(&self) -> Vec<ApiKeyConfig> {
+        self.config.read().unwrap().api_keys.clone()
+    }
+
+    pub fn set_api_keys(&self, api_keys: Vec<ApiKeyConfig>) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.api_keys = api_keys;
         }
         self.save()
     }
@@ -283,6 +1244,18 @@ impl ConfigManager {
         self.save()
     }
 
+    pub fn get_auth(&self) -> AuthConfig {
+        self.config.read().unwrap().auth.clone()
+    }
+
+    pub fn set_auth(&self, auth: AuthConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.auth = auth;
+        }
+        self.save()
+    }
+
     pub fn get_refresh(&self) -> RefreshConfig {
         self.config.read().unwrap().refresh.clone().sanitize()
     }
@@ -295,12 +1268,142 @@ impl ConfigManager {
         self.save()
     }
 
+    pub fn get_signal_history(&self) -> SignalHistoryConfig {
+        self.config.read().unwrap().signal_history.clone().sanitize()
+    }
+
+    pub fn set_signal_history(&self, signal_history: SignalHistoryConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.signal_history = signal_history.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_data_usage(&self) -> DataUsageConfig {
+        self.config.read().unwrap().data_usage.clone().sanitize()
+    }
+
+    pub fn set_data_usage(&self, data_usage: DataUsageConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.data_usage = data_usage.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_sms_ingest(&self) -> SmsIngestConfig {
+        self.config.read().unwrap().sms_ingest.clone().sanitize()
+    }
+
+    pub fn set_sms_ingest(&self, sms_ingest: SmsIngestConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.sms_ingest = sms_ingest.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_sms_queue(&self) -> SmsQueueConfig {
+        self.config.read().unwrap().sms_queue.clone().sanitize()
+    }
+
+    pub fn set_sms_queue(&self, sms_queue: SmsQueueConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.sms_queue = sms_queue.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_sim_pin(&self) -> SimPinConfig {
+        self.config.read().unwrap().sim_pin.clone()
+    }
+
+    pub fn set_sim_pin(&self, sim_pin: SimPinConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.sim_pin = sim_pin.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_sms_rules(&self) -> SmsRulesConfig {
+        self.config.read().unwrap().sms_rules.clone()
+    }
+
+    pub fn set_sms_rules(&self, sms_rules: SmsRulesConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.sms_rules = sms_rules.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_call_filter(&self) -> CallFilterConfig {
+        self.config.read().unwrap().call_filter.clone()
+    }
+
+    pub fn set_call_filter(&self, call_filter: CallFilterConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.call_filter = call_filter.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_call_policy(&self) -> CallPolicyConfig {
+        self.config.read().unwrap().call_policy.clone()
+    }
+
+    pub fn set_call_policy(&self, call_policy: CallPolicyConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.call_policy = call_policy.sanitize();
+        }
+        self.save()
+    }
+
+    pub fn get_ota(&self) -> OtaConfig {
+        self.config.read().unwrap().ota.clone()
+    }
+
+    pub fn set_ota(&self, ota: OtaConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.ota = ota;
+        }
+        self.save()
+    }
+
+    pub fn get_ota_channel(&self) -> OtaChannelConfig {
+        self.config.read().unwrap().ota_channel.clone().sanitize()
+    }
+
+    pub fn set_ota_channel(&self, ota_channel: OtaChannelConfig) -> Result<(), String> {
+        {
+            let mut config = self.config.write().unwrap();
+            config.ota_channel = ota_channel.sanitize();
+        }
+        self.save()
+    }
+
     #[allow(dead_code)]
     pub fn set(&self, config: AppConfig) -> Result<(), String> {
         {
             let mut current = self.config.write().unwrap();
             *current = AppConfig {
                 refresh: config.refresh.sanitize(),
+                signal_history: config.signal_history.sanitize(),
+                data_usage: config.data_usage.sanitize(),
+                sms_ingest: config.sms_ingest.sanitize(),
+                sms_queue: config.sms_queue.sanitize(),
+                sms_rules: config.sms_rules.sanitize(),
+                call_filter: config.call_filter.sanitize(),
+                call_policy: config.call_policy.sanitize(),
+                webhook: config.webhook.sanitize(),
+                sim_pin: config.sim_pin.sanitize(),
+                ota_channel: config.ota_channel.sanitize(),
                 ..config
             };
         }
@@ -471,6 +1574,28 @@ fn loader_is_plain_legacy_bootstrap(content: &str) -> bool {
         .all(|line| *line == INIT_SCRIPT_LOADER_COMMAND)
 }
 
+/// 先写临时文件再重命名，避免断电后留下不完整的文件
+pub fn write_file_atomic(path: &Path, content: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("Failed to create {}: {}", tmp_path.display(), e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
 fn set_executable_permissions(path: &Path) -> Result<(), String> {
     #[cfg(unix)]
     {
@@ -487,6 +1612,17 @@ fn set_executable_permissions(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// 安装 A/B 分区启动脚本（整体替换 loader.sh，自定义命令保留在 init.sh 中）
+pub fn install_ab_loader() -> Result<(), String> {
+    let loader_path = PathBuf::from(LOADER_SCRIPT_PATH);
+    write_file_atomic(&loader_path, AB_LOADER_SCRIPT)?;
+    set_executable_permissions(&loader_path)?;
+
+    let _ = fs::remove_file("/home/root/ota.sh");
+
+    Ok(())
+}
+
 pub fn ensure_loader_hooks_init() -> Result<(), String> {
     let loader_path = PathBuf::from(LOADER_SCRIPT_PATH);
     let current_content = if loader_path.exists() {
@@ -496,13 +1632,20 @@ pub fn ensure_loader_hooks_init() -> Result<(), String> {
         String::new()
     };
 
+    // 已切换到 A/B 分区启动：只同步为当前版本的启动脚本
+    if loader_uses_ab_bootstrap(&current_content) {
+        if normalize_newlines(&current_content) == AB_LOADER_SCRIPT {
+            return Ok(());
+        }
+        return install_ab_loader();
+    }
+
     let stripped_content = remove_ota_command_from_loader(&current_content);
     let missing_backend_command = !stripped_content
         .lines()
         .any(|line| line.trim() == "/home/root/udx710 -p 80 &");
 
-    let base_content = if loader_uses_ab_bootstrap(&current_content)
-        || loader_contains_ota_command(&current_content)
+    let base_content = if loader_contains_ota_command(&current_content)
         || missing_backend_command
     {
         DEFAULT_LOADER_SCRIPT.to_string()
@@ -574,7 +1717,10 @@ mod tests {
         append_init_command_to_loader,
         loader_contains_init_command,
         loader_contains_ota_command,
+        loader_uses_ab_bootstrap,
         remove_ota_command_from_loader,
+        AB_LOADER_SCRIPT,
+        DEFAULT_LOADER_SCRIPT,
         INIT_SCRIPT_LOADER_COMMAND,
     };
 
@@ -618,4 +1764,12 @@ mod tests {
         assert!(!loader_contains_ota_command(&updated));
         assert!(updated.contains("/home/root/udx710 -p 80 &"));
     }
+
+    #[test]
+    fn ab_loader_is_detected_and_keeps_init_hook() {
+        assert!(loader_uses_ab_bootstrap(AB_LOADER_SCRIPT));
+        assert!(!loader_uses_ab_bootstrap(DEFAULT_LOADER_SCRIPT));
+        assert!(loader_contains_init_command(AB_LOADER_SCRIPT));
+        assert!(!loader_contains_ota_command(AB_LOADER_SCRIPT));
+    }
 }
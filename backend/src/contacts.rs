@@ -0,0 +1,375 @@
+//! 联系人导入导出
+//!
+//! 解析 vCard（2.1 / 3.0，含 ofono Phonebook `Import` 导出的 SIM 电话本）和 CSV，
+//! 并把联系人导出为 vCard 3.0 或 CSV。一个 vCard 中的多个号码会拆成多条联系人。
+
+use serde::Serialize;
+
+use crate::db::{Contact, Database};
+
+/// 联系人名称最大长度
+const MAX_NAME_LENGTH: usize = 100;
+
+/// 待导入的联系人
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContactEntry {
+    pub name: String,
+    pub phone_number: String,
+    pub email: Option<String>,
+    pub note: Option<String>,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// 已存在或无效而跳过的条目
+    pub skipped: usize,
+}
+
+/// 校验联系人名称和号码，返回去掉首尾空白的 (名称, 号码)
+pub fn validate_contact<'a>(name: &'a str, phone_number: &'a str) -> Result<(&'a str, &'a str), String> {
+    let name = name.trim();
+    let phone_number = phone_number.trim();
+    if name.is_empty() {
+        return Err("Contact name is empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Contact name exceeds {} characters", MAX_NAME_LENGTH));
+    }
+    let valid_chars = phone_number
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | ' ' | '(' | ')' | '*' | '#'));
+    if !valid_chars || !phone_number.chars().any(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid phone number: {}", phone_number));
+    }
+    Ok((name, phone_number))
+}
+
+/// 导入联系人，同名同号码（按归一化号码比较）的条目跳过
+pub fn import_entries(db: &Database, entries: &[ContactEntry], source: &str) -> rusqlite::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    for entry in entries {
+        let Ok((name, phone_number)) = validate_contact(&entry.name, &entry.phone_number) else {
+            summary.skipped += 1;
+            continue;
+        };
+        if db.contact_exists(name, phone_number)? {
+            summary.skipped += 1;
+            continue;
+        }
+        db.insert_contact(name, phone_number, entry.email.as_deref(), entry.note.as_deref(), source)?;
+        summary.imported += 1;
+    }
+    Ok(summary)
+}
+
+// ============ vCard ============
+
+/// 展开折行（以空格或 Tab 开头的行接在上一行后），以及 quoted-printable 的软换行（行尾 `=`）
+fn unfold_lines(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut soft_break = false;
+    for line in data.lines() {
+        let line = line.trim_end_matches('\r');
+        match lines.last_mut() {
+            Some(last) if soft_break => last.push_str(line),
+            Some(last) if line.starts_with([' ', '\t']) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+        soft_break = lines.last().is_some_and(|l| {
+            l.to_ascii_uppercase().contains("QUOTED-PRINTABLE") && l.ends_with('=')
+        });
+        if soft_break {
+            lines.last_mut().unwrap().pop();
+        }
+    }
+    lines
+}
+
+fn decode_quoted_printable(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn unescape_vcard(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn escape_vcard(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// 解析 vCard 文本
+pub fn parse_vcards(data: &str) -> Vec<ContactEntry> {
+    let mut entries = Vec::new();
+    let mut in_card = false;
+    let (mut full_name, mut structured_name) = (String::new(), String::new());
+    let mut numbers: Vec<String> = Vec::new();
+    let (mut email, mut note) = (None, None);
+
+    for line in unfold_lines(data) {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = key.split(';');
+        // 去掉分组前缀（如 item1.TEL）
+        let property = params.next().unwrap_or_default();
+        let property = property.rsplit('.').next().unwrap_or_default().to_ascii_uppercase();
+        let quoted_printable = params.any(|p| p.to_ascii_uppercase().contains("QUOTED-PRINTABLE"));
+        let value = if quoted_printable {
+            decode_quoted_printable(value)
+        } else {
+            value.to_string()
+        };
+
+        match property.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => {
+                in_card = true;
+                full_name.clear();
+                structured_name.clear();
+                numbers.clear();
+                email = None;
+                note = None;
+            }
+            "END" if in_card && value.eq_ignore_ascii_case("VCARD") => {
+                in_card = false;
+                let name = if full_name.trim().is_empty() { &structured_name } else { &full_name };
+                for number in &numbers {
+                    entries.push(ContactEntry {
+                        name: name.trim().to_string(),
+                        phone_number: number.clone(),
+                        email: email.clone(),
+                        note: note.clone(),
+                    });
+                }
+            }
+            _ if !in_card => {}
+            "FN" => full_name = unescape_vcard(&value),
+            "N" => {
+                // N:姓;名;中间名;前缀;后缀
+                let parts: Vec<String> = value.split(';').map(unescape_vcard).collect();
+                let family = parts.first().map(String::as_str).unwrap_or_default();
+                let given = parts.get(1).map(String::as_str).unwrap_or_default();
+                // 中文姓名按“姓名”顺序连写，其他按“名 姓”
+                structured_name = if !family.is_ascii() {
+                    format!("{}{}", family, given)
+                } else {
+                    [given, family].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ")
+                };
+            }
+            "TEL" => {
+                let number = value.trim().trim_start_matches("tel:").to_string();
+                if !number.is_empty() && !numbers.contains(&number) {
+                    numbers.push(number);
+                }
+            }
+            "EMAIL" if email.is_none() => email = Some(unescape_vcard(value.trim())).filter(|e| !e.is_empty()),
+            "NOTE" => note = Some(unescape_vcard(&value)).filter(|n| !n.is_empty()),
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// 导出为 vCard 3.0
+pub fn to_vcards(contacts: &[Contact]) -> String {
+    let mut out = String::new();
+    for contact in contacts {
+        out.push_str("BEGIN:VCARD\r\nVERSION:3.0\r\n");
+        out.push_str(&format!("FN:{}\r\n", escape_vcard(&contact.name)));
+        out.push_str(&format!("N:;{};;;\r\n", escape_vcard(&contact.name)));
+        out.push_str(&format!("TEL;TYPE=CELL:{}\r\n", contact.phone_number));
+        if let Some(email) = &contact.email {
+            out.push_str(&format!("EMAIL:{}\r\n", escape_vcard(email)));
+        }
+        if let Some(note) = &contact.note {
+            out.push_str(&format!("NOTE:{}\r\n", escape_vcard(note)));
+        }
+        out.push_str("END:VCARD\r\n");
+    }
+    out
+}
+
+// ============ CSV ============
+
+/// 解析 CSV 记录（支持引号、`""` 转义和引号内换行）
+fn parse_csv_records(data: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = data.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// 解析 CSV
+///
+/// 首行包含 name / phone_number（或 phone、number、tel）等列名时按列名取值，
+/// 否则按 `name,phone_number,email,note` 的顺序读取
+pub fn parse_csv(data: &str) -> Vec<ContactEntry> {
+    let mut records = parse_csv_records(data).into_iter();
+    let Some(first) = records.next() else {
+        return Vec::new();
+    };
+
+    let column = |names: &[&str]| {
+        first
+            .iter()
+            .position(|h| names.contains(&h.trim().to_ascii_lowercase().as_str()))
+    };
+    let name_col = column(&["name", "姓名", "名称"]);
+    let phone_col = column(&["phone_number", "phone", "number", "tel", "电话", "号码"]);
+    let (columns, header) = match (name_col, phone_col) {
+        (Some(name), Some(phone)) => ((name, phone, column(&["email", "邮箱"]), column(&["note", "备注"])), true),
+        _ => ((0, 1, Some(2), Some(3)), false),
+    };
+
+    let (name_col, phone_col, email_col, note_col) = columns;
+    let optional = |record: &[String], col: Option<usize>| {
+        col.and_then(|c| record.get(c))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let data_rows: Vec<Vec<String>> = if header {
+        records.collect()
+    } else {
+        std::iter::once(first.clone()).chain(records).collect()
+    };
+
+    data_rows
+        .iter()
+        .map(|record| ContactEntry {
+            name: record.get(name_col).map(|v| v.trim().to_string()).unwrap_or_default(),
+            phone_number: record.get(phone_col).map(|v| v.trim().to_string()).unwrap_or_default(),
+            email: optional(record, email_col),
+            note: optional(record, note_col),
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 导出为 CSV（带列名）
+pub fn to_csv(contacts: &[Contact]) -> String {
+    let mut out = String::from("name,phone_number,email,note\r\n");
+    for contact in contacts {
+        let fields = [
+            contact.name.as_str(),
+            contact.phone_number.as_str(),
+            contact.email.as_deref().unwrap_or_default(),
+            contact.note.as_deref().unwrap_or_default(),
+        ];
+        out.push_str(&fields.map(csv_field).join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_vcards_with_folding_multiple_numbers_and_quoted_printable() {
+        let data = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Zhang\r\n  San\r\nTEL;TYPE=CELL:+86 138 0013 8000\r\n\
+                    item1.TEL:010-12345678\r\nEMAIL:zs@example.com\r\nEND:VCARD\r\n\
+                    BEGIN:VCARD\r\nVERSION:2.1\r\nN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:=E6=9D=8E;=E5=9B=9B\r\n\
+                    TEL;CELL:13900139000\r\nEND:VCARD\r\n\
+                    BEGIN:VCARD\r\nFN:No Number\r\nEND:VCARD\r\n";
+        let entries = parse_vcards(data);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "Zhang San");
+        assert_eq!(entries[0].phone_number, "+86 138 0013 8000");
+        assert_eq!(entries[1].phone_number, "010-12345678");
+        assert_eq!(entries[1].email.as_deref(), Some("zs@example.com"));
+        assert_eq!(entries[2].name, "李四");
+        assert_eq!(entries[2].phone_number, "13900139000");
+    }
+
+    #[test]
+    fn csv_round_trips_and_accepts_headerless_rows() {
+        let contacts = vec![Contact {
+            id: 1,
+            name: "Li, \"Boss\"".to_string(),
+            phone_number: "+8613800138000".to_string(),
+            email: None,
+            note: Some("line1\nline2".to_string()),
+            source: "manual".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }];
+        let parsed = parse_csv(&to_csv(&contacts));
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, "Li, \"Boss\"");
+        assert_eq!(parsed[0].note.as_deref(), Some("line1\nline2"));
+
+        let parsed = parse_csv("Wang Wu,10086\nZhao Liu,13700137000,zl@example.com");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].email.as_deref(), Some("zl@example.com"));
+
+        let vcard = parse_vcards(&to_vcards(&contacts));
+        assert_eq!(vcard[0].name, contacts[0].name);
+        assert_eq!(vcard[0].note, contacts[0].note);
+    }
+}
@@ -0,0 +1,163 @@
+//! Cron 表达式
+//!
+//! 支持标准 5 字段格式（分 时 日 月 周），字段可使用 `*`、`a`、`a-b`、`*/n`、`a-b/n`
+//! 及逗号分隔的列表；周取值 0-7（0 和 7 都表示周日）。日和周同时被限制时，
+//! 两者满足其一即可（与 Vixie cron 一致）。另支持 `@hourly`、`@daily`、`@weekly`、`@monthly`。
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// 解析单个字段为位图
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("Invalid step: {}", part))?;
+                if step == 0 {
+                    return Err(format!("Invalid step: {}", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a: u32 = a.parse().map_err(|_| format!("Invalid range: {}", part))?;
+            let b: u32 = b.parse().map_err(|_| format!("Invalid range: {}", part))?;
+            (a, b)
+        } else {
+            let value: u32 = range.parse().map_err(|_| format!("Invalid value: {}", part))?;
+            // `a/n` 表示从 a 开始到最大值
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("Value out of range {}-{}: {}", min, max, part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("Cron expression must have 5 fields: {}", expression));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// `after` 之后（不含）的下一个触发时间，5 年内没有触发时间时返回 None
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN);
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let give_up = after.year() + 5;
+
+        while t.year() <= give_up {
+            if !has(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = midnight(NaiveDate::from_ymd_opt(year, month, 1)?);
+            } else if !self.day_matches(t.date()) {
+                t = midnight(t.date().succ_opt()?);
+            } else if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        CronSchedule::parse(expr)
+            .unwrap()
+            .next_after(at(after))
+            .unwrap()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn next_run_times() {
+        assert_eq!(next("*/15 * * * *", "2025-12-20 10:14:59"), "2025-12-20 10:15");
+        assert_eq!(next("*/15 * * * *", "2025-12-20 10:15:00"), "2025-12-20 10:30");
+        assert_eq!(next("30 8 * * *", "2025-12-20 09:00:00"), "2025-12-21 08:30");
+        assert_eq!(next("@monthly", "2025-12-20 09:00:00"), "2026-01-01 00:00");
+        assert_eq!(next("0 9 * * 1-5", "2025-12-20 09:00:00"), "2025-12-22 09:00");
+        assert_eq!(next("0 0 29 2 *", "2025-03-01 00:00:00"), "2028-02-29 00:00");
+        // 日和周同时限制时满足其一即可：1 号或周日
+        assert_eq!(next("0 12 1 * 7", "2025-12-20 13:00:00"), "2025-12-21 12:00");
+        assert_eq!(next("0 12 1 * 0", "2025-12-22 13:00:00"), "2025-12-28 12:00");
+        assert_eq!(next("0 12 1 * 0", "2025-12-29 13:00:00"), "2026-01-01 12:00");
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expr in ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(CronSchedule::parse(expr).is_err(), "{}", expr);
+        }
+        assert!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(at("2025-01-01 00:00:00")).is_none());
+    }
+}
@@ -0,0 +1,326 @@
+//! 流量统计模块
+//!
+//! 周期读取蜂窝数据接口的 RX/TX 计数器，把增量累加到 SQLite 的小时/日/月统计中。
+//! 计数器快照连同内核 boot_id 一起持久化，因此进程重启、设备重启、接口重建和
+//! 32 位计数器回绕都不会丢失或重复计算流量。
+//!
+//! 配置了月配额时，按计费周期统计用量：达到告警阈值或超出配额时通过 Webhook
+//! 告警（每个周期每个级别一次），`QuotaAction::Disconnect` 时还会关闭数据连接，
+//! 并让数据连接 Watchdog 在本周期内不再自动恢复。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Local, Months, NaiveDate};
+use serde::Serialize;
+use tracing::{info, warn};
+use zbus::Connection;
+
+use crate::config::{ConfigManager, DataUsageConfig, QuotaAction};
+use crate::db::{Database, InterfaceCounter};
+use crate::dbus::{get_data_connection_status, get_data_interface, set_data_connection};
+use crate::utils::read_interface_stats;
+use crate::webhook::WebhookSender;
+
+/// 小时统计保留天数
+const HOURLY_RETENTION_DAYS: u64 = 35;
+
+/// 日统计保留天数
+const DAILY_RETENTION_DAYS: u64 = 400;
+
+/// 过期统计清理间隔
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 超出配额且处理方式为断网时置位，Watchdog 据此跳过自动恢复数据连接
+static QUOTA_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// 数据连接是否因超出流量配额被暂停
+pub fn is_quota_suspended() -> bool {
+    QUOTA_SUSPENDED.load(Ordering::Relaxed)
+}
+
+/// 流量配额告警
+#[derive(Debug, Clone, Serialize)]
+pub struct DataUsageAlert {
+    /// warning（达到告警阈值）或 exceeded（超出配额）
+    pub level: String,
+    pub used_bytes: u64,
+    pub quota_bytes: u64,
+    pub percent: f64,
+    pub cycle_start: String,
+    pub cycle_end: String,
+    /// none / warn / disconnect
+    pub action: String,
+}
+
+/// 当前计费周期用量汇总
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DataUsageSummary {
+    /// 计费周期起始日（含）
+    pub cycle_start: String,
+    /// 计费周期结束日（不含）
+    pub cycle_end: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub total_bytes: u64,
+    /// 配额（字节），0 表示不限制
+    pub quota_bytes: u64,
+    /// 已用百分比，未设置配额时为 0
+    pub percent: f64,
+    pub exceeded: bool,
+    /// 数据连接是否因超出配额被暂停
+    pub suspended: bool,
+    /// 正在统计的接口
+    pub interfaces: Vec<String>,
+}
+
+/// 计算两次计数器读数之间的增量
+///
+/// 计数器变小时有两种可能：32 位计数器回绕（上次读数接近 4 GiB、本次很小），
+/// 或接口被重建/驱动复位后从 0 重新计数。前者按回绕计算，后者以本次读数作为增量。
+pub fn counter_delta(previous: u64, current: u64) -> u64 {
+    const WRAP: u64 = 1 << 32;
+
+    if current >= previous {
+        return current - previous;
+    }
+    if (WRAP / 4 * 3..WRAP).contains(&previous) && current < WRAP / 4 {
+        return WRAP - previous + current;
+    }
+    current
+}
+
+/// 计算 `today` 所在计费周期 [start, end)
+pub fn billing_cycle(today: NaiveDate, billing_day: u32) -> (NaiveDate, NaiveDate) {
+    let billing_day = billing_day.clamp(1, 28);
+    let this_month = today.with_day(billing_day).unwrap_or(today);
+    let start = if today.day() >= billing_day {
+        this_month
+    } else {
+        this_month - Months::new(1)
+    };
+    (start, start + Months::new(1))
+}
+
+/// 读取内核 boot_id，用于识别设备重启
+fn read_boot_id() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// 确定需要统计的接口：配置的接口，或数据连接当前接口加上历史统计过的接口
+async fn resolve_interfaces(conn: &Connection, db: &Database, config: &DataUsageConfig) -> Vec<String> {
+    if !config.interfaces.is_empty() {
+        return config.interfaces.clone();
+    }
+
+    let mut interfaces = db.get_counted_interfaces().unwrap_or_default();
+    if let Ok(Some(interface)) = get_data_interface(conn).await {
+        if !interfaces.contains(&interface) {
+            interfaces.push(interface);
+        }
+    }
+    interfaces
+}
+
+/// 读取一次计数器并累加增量
+fn record_usage(db: &Database, interfaces: &[String], boot_id: &str) {
+    let now = Local::now();
+    let buckets = [
+        ("hour", now.format("%Y-%m-%dT%H").to_string()),
+        ("day", now.format("%Y-%m-%d").to_string()),
+        ("month", now.format("%Y-%m").to_string()),
+    ];
+
+    for interface in interfaces {
+        // 接口不存在（数据连接断开后可能被删除）时跳过，保留上次快照
+        let Ok((rx_bytes, tx_bytes)) = read_interface_stats(interface) else {
+            continue;
+        };
+        let current = InterfaceCounter {
+            rx_bytes,
+            tx_bytes,
+            boot_id: boot_id.to_string(),
+        };
+
+        let (rx_delta, tx_delta) = match db.get_interface_counter(interface) {
+            // 首次统计该接口，只记录基准值
+            Ok(None) => (0, 0),
+            // 设备重启后计数器从 0 开始
+            Ok(Some(previous)) if previous.boot_id != current.boot_id => (rx_bytes, tx_bytes),
+            Ok(Some(previous)) => (
+                counter_delta(previous.rx_bytes, rx_bytes),
+                counter_delta(previous.tx_bytes, tx_bytes),
+            ),
+            Err(e) => {
+                warn!(interface = %interface, error = %e, "Failed to read interface counter");
+                continue;
+            }
+        };
+
+        if let Err(e) = db.record_data_usage(interface, &current, rx_delta, tx_delta, &buckets) {
+            warn!(interface = %interface, error = %e, "Failed to record data usage");
+        }
+    }
+}
+
+/// 汇总当前计费周期的用量
+pub fn usage_summary(db: &Database, config: &DataUsageConfig) -> Result<DataUsageSummary, String> {
+    let (start, end) = billing_cycle(Local::now().date_naive(), config.billing_day);
+    let cycle_start = start.format("%Y-%m-%d").to_string();
+    let cycle_end = end.format("%Y-%m-%d").to_string();
+
+    let (rx_bytes, tx_bytes) = db
+        .sum_daily_usage(&cycle_start, &cycle_end)
+        .map_err(|e| format!("Failed to sum data usage: {}", e))?;
+    let total_bytes = rx_bytes + tx_bytes;
+    let quota_bytes = config.monthly_quota_mb.saturating_mul(1024 * 1024);
+    let percent = if quota_bytes > 0 {
+        (total_bytes as f64 / quota_bytes as f64 * 1000.0).round() / 10.0
+    } else {
+        0.0
+    };
+
+    Ok(DataUsageSummary {
+        cycle_start,
+        cycle_end,
+        rx_bytes,
+        tx_bytes,
+        total_bytes,
+        quota_bytes,
+        percent,
+        exceeded: quota_bytes > 0 && total_bytes >= quota_bytes,
+        suspended: is_quota_suspended(),
+        interfaces: if config.interfaces.is_empty() {
+            db.get_counted_interfaces().unwrap_or_default()
+        } else {
+            config.interfaces.clone()
+        },
+    })
+}
+
+/// 检查配额，按配置告警或断网
+async fn enforce_quota(conn: &Connection, db: &Database, webhook: &WebhookSender, config: &DataUsageConfig) {
+    let summary = match usage_summary(db, config) {
+        Ok(summary) => summary,
+        Err(e) => {
+            warn!(error = %e, "Data usage quota check failed");
+            return;
+        }
+    };
+
+    let suspend = summary.exceeded && config.quota_action == QuotaAction::Disconnect;
+    if QUOTA_SUSPENDED.swap(suspend, Ordering::Relaxed) != suspend {
+        info!(suspended = suspend, "Data quota suspension changed");
+    }
+
+    if suspend && get_data_connection_status(conn).await.unwrap_or(false) {
+        match set_data_connection(conn, false).await {
+            Ok(_) => info!(used = summary.total_bytes, quota = summary.quota_bytes, "Data quota exceeded, data disabled"),
+            Err(e) => warn!(error = %e, "Failed to disable data after quota exceeded"),
+        }
+    }
+
+    let (level, action) = if summary.exceeded {
+        let action = match config.quota_action {
+            QuotaAction::Warn => "warn",
+            QuotaAction::Disconnect => "disconnect",
+        };
+        ("exceeded", action)
+    } else if config.warn_percent > 0 && summary.quota_bytes > 0 && summary.percent >= config.warn_percent as f64 {
+        ("warning", "none")
+    } else {
+        return;
+    };
+
+    match db.try_mark_data_usage_alert(&summary.cycle_start, level) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            warn!(error = %e, "Failed to record data usage alert");
+            return;
+        }
+    }
+
+    let alert = DataUsageAlert {
+        level: level.to_string(),
+        used_bytes: summary.total_bytes,
+        quota_bytes: summary.quota_bytes,
+        percent: summary.percent,
+        cycle_start: summary.cycle_start,
+        cycle_end: summary.cycle_end,
+        action: action.to_string(),
+    };
+    info!(level = %alert.level, percent = alert.percent, "Data usage alert");
+    if let Err(e) = webhook.forward_data_usage(&alert).await {
+        warn!(error = %e, "Failed to send data usage webhook");
+    }
+}
+
+/// 流量统计任务
+pub async fn start_data_usage_monitor(
+    conn: Arc<Connection>,
+    db: Arc<Database>,
+    config_manager: Arc<ConfigManager>,
+    webhook: Arc<WebhookSender>,
+) {
+    let boot_id = read_boot_id();
+    let mut last_purge: Option<Instant> = None;
+
+    loop {
+        let config = config_manager.get_data_usage();
+
+        if config.enabled {
+            let interfaces = resolve_interfaces(&conn, &db, &config).await;
+            record_usage(&db, &interfaces, &boot_id);
+            enforce_quota(&conn, &db, &webhook, &config).await;
+        } else {
+            QUOTA_SUSPENDED.store(false, Ordering::Relaxed);
+        }
+
+        if last_purge.is_none_or(|t| t.elapsed() >= PURGE_INTERVAL) {
+            let today = Local::now().date_naive();
+            let hourly_cutoff = (today - chrono::Days::new(HOURLY_RETENTION_DAYS)).format("%Y-%m-%d").to_string();
+            let daily_cutoff = (today - chrono::Days::new(DAILY_RETENTION_DAYS)).format("%Y-%m-%d").to_string();
+            if let Err(e) = db.purge_data_usage("hour", &hourly_cutoff) {
+                warn!(error = %e, "Failed to purge hourly data usage");
+            }
+            if let Err(e) = db.purge_data_usage("day", &daily_cutoff) {
+                warn!(error = %e, "Failed to purge daily data usage");
+            }
+            last_purge = Some(Instant::now());
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_delta_handles_wrap_and_reset() {
+        assert_eq!(counter_delta(100, 250), 150);
+        // 32 位计数器回绕
+        assert_eq!(counter_delta(u32::MAX as u64 - 99, 50), 150);
+        // 接口重建后从 0 重新计数
+        assert_eq!(counter_delta(5_000_000, 1_000), 1_000);
+        // 64 位计数器变小只可能是复位
+        assert_eq!(counter_delta(10_000_000_000, 1_000), 1_000);
+    }
+
+    #[test]
+    fn billing_cycle_boundaries() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(billing_cycle(date(2025, 12, 8), 1), (date(2025, 12, 1), date(2026, 1, 1)));
+        assert_eq!(billing_cycle(date(2025, 12, 8), 15), (date(2025, 11, 15), date(2025, 12, 15)));
+        assert_eq!(billing_cycle(date(2025, 12, 15), 15), (date(2025, 12, 15), date(2026, 1, 15)));
+        assert_eq!(billing_cycle(date(2026, 1, 3), 20), (date(2025, 12, 20), date(2026, 1, 20)));
+        // 超出范围的起始日按 28 处理
+        assert_eq!(billing_cycle(date(2026, 3, 30), 31), (date(2026, 3, 28), date(2026, 4, 28)));
+    }
+}
@@ -10,7 +10,7 @@
  */
 //! 数据库模块
 //!
-//! 使用 SQLite 存储短信历史记录和通话记录
+//! 使用 SQLite 存储短信历史记录、通话记录、信号质量历史和流量统计
 
 use chrono::Utc;
 use rusqlite::{params, Connection, Result};
@@ -18,6 +18,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::utils::{normalize_phone_number, same_number};
+
 /// 短信记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmsMessage {
@@ -26,8 +28,143 @@ pub struct SmsMessage {
     pub phone_number: String,   // 发件人或收件人
     pub content: String,        // 短信内容
     pub timestamp: String,      // ISO 8601 格式时间
-    pub status: String,         // "pending", "sent", "failed", "received"
+    pub status: String,         // "pending", "sent", "delivered", "failed", "received", "partial"
     pub pdu: Option<String>,    // 原始 PDU（如果有）
+    #[serde(default)]
+    pub message_path: Option<String>, // ofono Message 对象路径（发出的短信）
+    #[serde(default)]
+    pub sent_at: Option<String>,      // 发送成功时间 ISO 8601
+    #[serde(default)]
+    pub delivered_at: Option<String>, // 送达（状态报告）时间 ISO 8601
+    #[serde(default)]
+    pub failed_at: Option<String>,    // 发送失败时间 ISO 8601
+    #[serde(default)]
+    pub tags: Vec<String>,            // 短信规则添加的标签
+    #[serde(default)]
+    pub contact_name: Option<String>, // 通讯录中的联系人名称
+    #[serde(default)]
+    pub thread_id: Option<i64>,       // 所属会话
+    #[serde(default)]
+    pub read: bool,                   // 是否已读（发出的短信总是已读）
+}
+
+/// 短信会话（同一号码的不同格式归为一个会话）
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsThread {
+    pub id: i64,
+    /// 会话中首次出现的号码格式
+    pub phone_number: String,
+    pub contact_name: Option<String>,
+    pub archived: bool,
+    pub message_count: i64,
+    pub unread_count: i64,
+    /// 最新一条短信（预览）
+    pub last_message: SmsMessage,
+}
+
+/// USSD 记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UssdRecord {
+    pub id: i64,
+    /// "outgoing"（本机发起/回复）或 "incoming"（网络主动下发）
+    pub direction: String,
+    /// 发送的指令或回复
+    pub request: Option<String>,
+    /// 网络返回的消息
+    pub response: Option<String>,
+    /// 结果类型（USSD 或补充业务名称）
+    pub result_type: Option<String>,
+    /// 交互后的会话状态
+    pub state: String,
+    pub error: Option<String>,
+    pub timestamp: String, // ISO 8601 格式时间
+}
+
+/// 短信发送队列中的任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsQueueItem {
+    pub id: i64,
+    pub phone_number: String,
+    pub content: String,
+    /// cron 表达式，为空表示只发送一次
+    pub schedule: Option<String>,
+    /// 下次发送（或重试）时间，Unix 时间戳（秒）
+    pub send_at: i64,
+    /// "scheduled" / "sent" / "failed"
+    pub status: String,
+    /// 本次发送已失败的次数
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub last_error: Option<String>,
+    /// 最近一次发送成功的时间，Unix 时间戳（秒）
+    pub last_sent_at: Option<i64>,
+    /// 最近一次发送对应的短信记录 ID
+    pub last_sms_id: Option<i64>,
+    pub created_at: i64,
+}
+
+const SMS_QUEUE_COLUMNS: &str = "id, phone_number, content, schedule, send_at, status, attempts, max_attempts, \
+     last_error, last_sent_at, last_sms_id, created_at";
+
+fn sms_queue_from_row(row: &rusqlite::Row<'_>) -> Result<SmsQueueItem> {
+    Ok(SmsQueueItem {
+        id: row.get(0)?,
+        phone_number: row.get(1)?,
+        content: row.get(2)?,
+        schedule: row.get(3)?,
+        send_at: row.get(4)?,
+        status: row.get(5)?,
+        attempts: row.get(6)?,
+        max_attempts: row.get(7)?,
+        last_error: row.get(8)?,
+        last_sent_at: row.get(9)?,
+        last_sms_id: row.get(10)?,
+        created_at: row.get(11)?,
+    })
+}
+
+/// 查询短信时选取的列（与 [`sms_from_row`] 顺序一致）
+const SMS_COLUMNS: &str = "id, direction, phone_number, content, timestamp, status, pdu, message_path, sent_at, \
+     delivered_at, failed_at, tags, thread_id, is_read";
+
+/// 标签以逗号分隔存储
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    tags.unwrap_or_default()
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn sms_from_row(row: &rusqlite::Row<'_>) -> Result<SmsMessage> {
+    Ok(SmsMessage {
+        id: row.get(0)?,
+        direction: row.get(1)?,
+        phone_number: row.get(2)?,
+        content: row.get(3)?,
+        timestamp: row.get(4)?,
+        status: row.get(5)?,
+        pdu: row.get(6)?,
+        message_path: row.get(7)?,
+        sent_at: row.get(8)?,
+        delivered_at: row.get(9)?,
+        failed_at: row.get(10)?,
+        tags: split_tags(row.get(11)?),
+        contact_name: None,
+        thread_id: row.get(12)?,
+        read: row.get::<_, i64>(13)? != 0,
+    })
+}
+
+/// 为旧数据库补充新增的列
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+        .exists(params![column])?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
 }
 
 /// 通话记录
@@ -40,6 +177,162 @@ pub struct CallRecord {
     pub start_time: String,     // 开始时间 ISO 8601
     pub end_time: Option<String>, // 结束时间 ISO 8601
     pub answered: bool,         // 是否接通
+    #[serde(default)]
+    pub contact_name: Option<String>, // 通讯录中的联系人名称
+    #[serde(default)]
+    pub reason: Option<String>, // 自动处理原因（拦截、策略自动接听/拒接/限时挂断）
+}
+
+/// 联系人
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub id: i64,
+    pub name: String,
+    pub phone_number: String,
+    pub email: Option<String>,
+    pub note: Option<String>,
+    /// 来源: manual / sim / vcard / csv
+    pub source: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const CONTACT_COLUMNS: &str = "id, name, phone_number, email, note, source, created_at, updated_at";
+
+fn contact_from_row(row: &rusqlite::Row<'_>) -> Result<Contact> {
+    Ok(Contact {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        phone_number: row.get(2)?,
+        email: row.get(3)?,
+        note: row.get(4)?,
+        source: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// 号码查找键：归一化号码的后 7 位（短号码取全部），再用 `same_number` 精确比较
+fn number_key(phone_number: &str) -> String {
+    let normalized = normalize_phone_number(phone_number);
+    normalized[normalized.len().saturating_sub(7)..].to_string()
+}
+
+/// 按号码查找联系人名称（多个匹配时取最早添加的）
+fn lookup_contact_name(conn: &Connection, phone_number: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare_cached("SELECT name, phone_number FROM contacts WHERE number_key = ?1 ORDER BY id")?;
+    let rows = stmt.query_map(params![number_key(phone_number)], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (name, number) = row?;
+        if same_number(&number, phone_number) {
+            return Ok(Some(name));
+        }
+    }
+    Ok(None)
+}
+
+fn fill_sms_contact_names(conn: &Connection, messages: &mut [SmsMessage]) -> Result<()> {
+    for message in messages {
+        message.contact_name = lookup_contact_name(conn, &message.phone_number)?;
+    }
+    Ok(())
+}
+
+/// 查找号码所属的会话，没有时新建
+fn resolve_thread(conn: &Connection, phone_number: &str) -> Result<i64> {
+    let key = number_key(phone_number);
+    let mut stmt = conn.prepare_cached("SELECT id, phone_number FROM sms_threads WHERE number_key = ?1 ORDER BY id")?;
+    let rows = stmt.query_map(params![key], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (id, number) = row?;
+        if same_number(&number, phone_number) {
+            return Ok(id);
+        }
+    }
+    conn.execute(
+        "INSERT INTO sms_threads (phone_number, number_key) VALUES (?1, ?2)",
+        params![phone_number, key],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn sms_by_id(conn: &Connection, id: i64) -> Result<Option<SmsMessage>> {
+    let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM sms_messages WHERE id = ?1", SMS_COLUMNS))?;
+    let mut rows = stmt.query_map(params![id], sms_from_row)?;
+    let Some(mut message) = rows.next().transpose()? else {
+        return Ok(None);
+    };
+    message.contact_name = lookup_contact_name(conn, &message.phone_number)?;
+    Ok(Some(message))
+}
+
+/// 分页查询会话摘要，`filter` 为作用于 sms_threads（别名 t）的条件，只能使用参数 ?1
+fn query_threads(conn: &Connection, filter: &str, value: &dyn rusqlite::ToSql, limit: i64, offset: i64) -> Result<Vec<SmsThread>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.id, t.phone_number, t.archived, COUNT(m.id), SUM(m.is_read = 0),
+                (SELECT id FROM sms_messages WHERE thread_id = t.id ORDER BY timestamp DESC, id DESC LIMIT 1),
+                MAX(m.timestamp) AS last_time
+         FROM sms_threads t JOIN sms_messages m ON m.thread_id = t.id
+         WHERE {}
+         GROUP BY t.id
+         ORDER BY last_time DESC, t.id DESC
+         LIMIT ?2 OFFSET ?3",
+        filter
+    ))?;
+    let rows = stmt.query_map(params![value, limit, offset], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)? != 0,
+            row.get::<_, i64>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, i64>(5)?,
+        ))
+    })?;
+
+    let mut threads = Vec::new();
+    for row in rows {
+        let (id, phone_number, archived, message_count, unread_count, last_id) = row?;
+        let Some(last_message) = sms_by_id(conn, last_id)? else {
+            continue;
+        };
+        threads.push(SmsThread {
+            id,
+            contact_name: lookup_contact_name(conn, &phone_number)?,
+            phone_number,
+            archived,
+            message_count,
+            unread_count,
+            last_message,
+        });
+    }
+    Ok(threads)
+}
+
+/// FTS5 短语查询（trigram 分词，按子串匹配）
+fn fts_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+fn fill_call_contact_names(conn: &Connection, records: &mut [CallRecord]) -> Result<()> {
+    for record in records {
+        record.contact_name = lookup_contact_name(conn, &record.phone_number)?;
+    }
+    Ok(())
+}
+
+/// 长短信分段（原始 PDU 接收模式下等待拼接）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsFragment {
+    pub sender: String,
+    pub reference: i64,     // 拼接参考号（8 位或 16 位）
+    pub total: i64,         // 总分段数
+    pub sequence: i64,      // 分段序号（从 1 开始）
+    pub content: String,    // 本段解码后的文本
+    pub pdu: String,        // 本段原始 PDU
+    pub received_at: i64,   // 接收时间（Unix 秒）
 }
 
 /// 短信统计
@@ -48,6 +341,8 @@ pub struct SmsStats {
     pub total: i64,
     pub incoming: i64,
     pub outgoing: i64,
+    #[serde(default)]
+    pub unread: i64,
 }
 
 /// 通话统计
@@ -60,6 +355,87 @@ pub struct CallStats {
     pub total_duration: i64,  // 总通话时长（秒）
 }
 
+/// 信号质量原始采样
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalSample {
+    pub timestamp: i64,                 // Unix 时间戳（秒）
+    pub tech: String,                   // 网络制式：nr / lte / unknown
+    pub registration_status: String,    // 注册状态：registered / searching / ...
+    pub rsrp: Option<f64>,              // dBm
+    pub rsrq: Option<f64>,              // dB
+    pub sinr: Option<f64>,              // dB
+    pub band: String,
+    pub arfcn: String,
+    pub pci: String,
+    pub cell_id: Option<u32>,
+    pub tac: Option<u32>,
+}
+
+/// 信号质量历史数据点（原始采样或按时间桶聚合）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignalHistoryPoint {
+    pub timestamp: i64,                 // 时间桶起点（秒），原始采样即采样时间
+    pub samples: i64,                   // 聚合的采样数
+    pub tech: String,                   // 以下服务小区字段取时间桶内最后一次采样
+    pub registration_status: String,
+    pub rsrp_avg: Option<f64>,
+    pub rsrp_min: Option<f64>,
+    pub rsrp_max: Option<f64>,
+    pub rsrq_avg: Option<f64>,
+    pub rsrq_min: Option<f64>,
+    pub rsrq_max: Option<f64>,
+    pub sinr_avg: Option<f64>,
+    pub sinr_min: Option<f64>,
+    pub sinr_max: Option<f64>,
+    pub band: String,
+    pub arfcn: String,
+    pub pci: String,
+    pub cell_id: Option<u32>,
+    pub tac: Option<u32>,
+}
+
+impl From<SignalSample> for SignalHistoryPoint {
+    fn from(sample: SignalSample) -> Self {
+        Self {
+            timestamp: sample.timestamp,
+            samples: 1,
+            tech: sample.tech,
+            registration_status: sample.registration_status,
+            rsrp_avg: sample.rsrp,
+            rsrp_min: sample.rsrp,
+            rsrp_max: sample.rsrp,
+            rsrq_avg: sample.rsrq,
+            rsrq_min: sample.rsrq,
+            rsrq_max: sample.rsrq,
+            sinr_avg: sample.sinr,
+            sinr_min: sample.sinr,
+            sinr_max: sample.sinr,
+            band: sample.band,
+            arfcn: sample.arfcn,
+            pci: sample.pci,
+            cell_id: sample.cell_id,
+            tac: sample.tac,
+        }
+    }
+}
+
+/// 接口计数器快照（用于计算两次采样之间的增量）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceCounter {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub boot_id: String,    // 内核 boot_id，变化说明设备重启过
+}
+
+/// 流量统计桶
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataUsageBucket {
+    pub bucket: String,     // 小时 "2025-12-08T13" / 日 "2025-12-08" / 月 "2025-12"（本地时间）
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub total_bytes: u64,
+}
+
 /// 数据库管理器
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
@@ -85,6 +461,15 @@ impl Database {
             [],
         )?;
         
+        // 发送状态跟踪列（旧版本数据库中没有）
+        for column in ["message_path", "sent_at", "delivered_at", "failed_at", "tags"] {
+            ensure_column(&conn, "sms_messages", column, "TEXT")?;
+        }
+        
+        // 会话和已读状态（升级前的短信视为已读）
+        ensure_column(&conn, "sms_messages", "thread_id", "INTEGER")?;
+        ensure_column(&conn, "sms_messages", "is_read", "INTEGER NOT NULL DEFAULT 1")?;
+        
         // 创建短信索引
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_sms_timestamp ON sms_messages(timestamp DESC)",
@@ -96,6 +481,68 @@ impl Database {
             [],
         )?;
         
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sms_message_path ON sms_messages(message_path)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sms_thread ON sms_messages(thread_id, timestamp DESC)",
+            [],
+        )?;
+
+        // 短信会话（按归一化号码分组）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sms_threads (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                phone_number TEXT NOT NULL,
+                number_key TEXT NOT NULL,
+                archived INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sms_threads_number_key ON sms_threads(number_key)",
+            [],
+        )?;
+
+        // 旧短信归入会话
+        {
+            let tx = conn.unchecked_transaction()?;
+            let orphans: Vec<(i64, String)> = tx
+                .prepare("SELECT id, phone_number FROM sms_messages WHERE thread_id IS NULL")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_>>()?;
+            for (id, phone_number) in orphans {
+                let thread_id = resolve_thread(&tx, &phone_number)?;
+                tx.execute("UPDATE sms_messages SET thread_id = ?1 WHERE id = ?2", params![thread_id, id])?;
+            }
+            tx.commit()?;
+        }
+
+        // 短信内容全文索引（trigram 分词，支持中文子串搜索），由触发器与短信表同步
+        let fts_exists = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sms_fts'")?
+            .exists([])?;
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS sms_fts
+                 USING fts5(content, content = 'sms_messages', content_rowid = 'id', tokenize = 'trigram');
+             CREATE TRIGGER IF NOT EXISTS sms_fts_insert AFTER INSERT ON sms_messages BEGIN
+                 INSERT INTO sms_fts (rowid, content) VALUES (new.id, new.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS sms_fts_delete AFTER DELETE ON sms_messages BEGIN
+                 INSERT INTO sms_fts (sms_fts, rowid, content) VALUES ('delete', old.id, old.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS sms_fts_update AFTER UPDATE OF content ON sms_messages BEGIN
+                 INSERT INTO sms_fts (sms_fts, rowid, content) VALUES ('delete', old.id, old.content);
+                 INSERT INTO sms_fts (rowid, content) VALUES (new.id, new.content);
+             END;",
+        )?;
+        if !fts_exists {
+            conn.execute("INSERT INTO sms_fts (sms_fts) VALUES ('rebuild')", [])?;
+        }
+        
         // 创建通话记录表（如果不存在）
         conn.execute(
             "CREATE TABLE IF NOT EXISTS call_history (
@@ -121,6 +568,168 @@ impl Database {
             "CREATE INDEX IF NOT EXISTS idx_call_phone ON call_history(phone_number)",
             [],
         )?;
+
+        ensure_column(&conn, "call_history", "reason", "TEXT")?;
+        
+        // 创建信号质量原始采样表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS signal_samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                tech TEXT NOT NULL,
+                registration_status TEXT NOT NULL,
+                rsrp REAL,
+                rsrq REAL,
+                sinr REAL,
+                band TEXT NOT NULL DEFAULT '',
+                arfcn TEXT NOT NULL DEFAULT '',
+                pci TEXT NOT NULL DEFAULT '',
+                cell_id INTEGER,
+                tac INTEGER
+            )",
+            [],
+        )?;
+        
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_signal_timestamp ON signal_samples(timestamp)",
+            [],
+        )?;
+        
+        // 创建流量统计表（period: hour / day / month）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS data_usage (
+                period TEXT NOT NULL,
+                bucket TEXT NOT NULL,
+                interface TEXT NOT NULL,
+                rx_bytes INTEGER NOT NULL DEFAULT 0,
+                tx_bytes INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (period, bucket, interface)
+            )",
+            [],
+        )?;
+        
+        // 接口计数器快照（跨进程重启计算增量）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS data_usage_counters (
+                interface TEXT PRIMARY KEY,
+                rx_bytes INTEGER NOT NULL,
+                tx_bytes INTEGER NOT NULL,
+                boot_id TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        
+        // 已发送的配额告警（每个计费周期每个级别只告警一次）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS data_usage_alerts (
+                cycle_start TEXT NOT NULL,
+                level TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (cycle_start, level)
+            )",
+            [],
+        )?;
+        
+        // 创建信号质量小时聚合表（原始采样过期后降采样到这里）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS signal_samples_hourly (
+                bucket_start INTEGER PRIMARY KEY,
+                samples INTEGER NOT NULL,
+                tech TEXT NOT NULL,
+                registration_status TEXT NOT NULL,
+                rsrp_avg REAL,
+                rsrp_min REAL,
+                rsrp_max REAL,
+                rsrq_avg REAL,
+                rsrq_min REAL,
+                rsrq_max REAL,
+                sinr_avg REAL,
+                sinr_min REAL,
+                sinr_max REAL,
+                band TEXT NOT NULL DEFAULT '',
+                arfcn TEXT NOT NULL DEFAULT '',
+                pci TEXT NOT NULL DEFAULT '',
+                cell_id INTEGER,
+                tac INTEGER
+            )",
+            [],
+        )?;
+        
+        // 长短信分段暂存表（按发送方 + 参考号拼接）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sms_fragments (
+                sender TEXT NOT NULL,
+                reference INTEGER NOT NULL,
+                total INTEGER NOT NULL,
+                sequence INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                pdu TEXT NOT NULL,
+                received_at INTEGER NOT NULL,
+                PRIMARY KEY (sender, reference, sequence)
+            )",
+            [],
+        )?;
+
+        // 通讯录
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                phone_number TEXT NOT NULL,
+                number_key TEXT NOT NULL,
+                email TEXT,
+                note TEXT,
+                source TEXT NOT NULL DEFAULT 'manual',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_contacts_number_key ON contacts(number_key)",
+            [],
+        )?;
+
+        // USSD 记录
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ussd_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                direction TEXT NOT NULL,
+                request TEXT,
+                response TEXT,
+                result_type TEXT,
+                state TEXT NOT NULL,
+                error TEXT,
+                timestamp TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // 短信发送队列（定时、周期发送和失败重试）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sms_queue (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                phone_number TEXT NOT NULL,
+                content TEXT NOT NULL,
+                schedule TEXT,
+                send_at INTEGER NOT NULL,
+                status TEXT NOT NULL DEFAULT 'scheduled',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL,
+                last_error TEXT,
+                last_sent_at INTEGER,
+                last_sms_id INTEGER,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sms_queue_due ON sms_queue(status, send_at)",
+            [],
+        )?;
         
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -140,11 +749,17 @@ impl Database {
     ) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
         let timestamp = Utc::now().to_rfc3339();
+        let thread_id = resolve_thread(&conn, phone_number)?;
+        let incoming = direction == "incoming";
+        if incoming {
+            // 归档的会话收到新短信时重新出现在收件箱
+            conn.execute("UPDATE sms_threads SET archived = 0 WHERE id = ?1", params![thread_id])?;
+        }
         
         conn.execute(
-            "INSERT INTO sms_messages (direction, phone_number, content, timestamp, status, pdu)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![direction, phone_number, content, timestamp, status, pdu],
+            "INSERT INTO sms_messages (direction, phone_number, content, timestamp, status, pdu, thread_id, is_read)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![direction, phone_number, content, timestamp, status, pdu, thread_id, !incoming],
         )?;
         
         Ok(conn.last_insert_rowid())
@@ -161,67 +776,147 @@ impl Database {
         Ok(())
     }
     
+    /// 记录发出短信对应的 ofono Message 对象路径
+    pub fn set_sms_message_path(&self, id: i64, message_path: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE sms_messages SET message_path = ?1 WHERE id = ?2",
+            params![message_path, id],
+        )?;
+        Ok(())
+    }
+    
+    /// 更新发出短信的投递状态并记录对应时间
+    ///
+    /// `status` 为 "sent" / "delivered" / "failed"。状态只会前进
+    /// （pending → sent → delivered，未送达前可变为 failed），过时或重复的
+    /// 通知不会覆盖已有状态。返回更新后的短信，状态未变化时返回 None。
+    pub fn update_sms_delivery(&self, id: i64, status: &str, at: &str) -> Result<Option<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let sql = match status {
+            "sent" => {
+                "UPDATE sms_messages SET status = 'sent', sent_at = COALESCE(sent_at, ?1)
+                 WHERE id = ?2 AND status = 'pending'"
+            }
+            "delivered" => {
+                "UPDATE sms_messages SET status = 'delivered', sent_at = COALESCE(sent_at, ?1), delivered_at = ?1
+                 WHERE id = ?2 AND status IN ('pending', 'sent')"
+            }
+            "failed" => {
+                "UPDATE sms_messages SET status = 'failed', failed_at = ?1
+                 WHERE id = ?2 AND status IN ('pending', 'sent')"
+            }
+            _ => return Ok(None),
+        };
+        if conn.execute(sql, params![at, id])? == 0 {
+            return Ok(None);
+        }
+        let mut message = conn.query_row(
+            &format!("SELECT {} FROM sms_messages WHERE id = ?1", SMS_COLUMNS),
+            params![id],
+            sms_from_row,
+        )?;
+        message.contact_name = lookup_contact_name(&conn, &message.phone_number)?;
+        Ok(Some(message))
+    }
+    
+    /// 按 ofono Message 对象路径查找尚未送达的发出短信
+    ///
+    /// ofono 重启后路径会被复用，因此取最新的一条
+    pub fn find_sms_by_message_path(&self, message_path: &str) -> Result<Option<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
+             WHERE direction = 'outgoing' AND message_path = ?1 AND status IN ('pending', 'sent')
+             ORDER BY id DESC LIMIT 1",
+            SMS_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map(params![message_path], sms_from_row)?;
+        rows.next().transpose()
+    }
+    
+    /// 等待状态报告的发出短信（最新的在前）
+    pub fn get_sms_awaiting_report(&self, limit: i64) -> Result<Vec<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
+             WHERE direction = 'outgoing' AND status IN ('pending', 'sent')
+             ORDER BY id DESC LIMIT ?1",
+            SMS_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit], sms_from_row)?;
+        rows.collect()
+    }
+    
+    /// 获取单条短信
+    pub fn get_sms(&self, id: i64) -> Result<Option<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        sms_by_id(&conn, id)
+    }
+
     /// 获取所有短信（分页）
     pub fn get_sms_messages(&self, limit: i64, offset: i64) -> Result<Vec<SmsMessage>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, direction, phone_number, content, timestamp, status, pdu
-             FROM sms_messages
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
              ORDER BY timestamp DESC
-             LIMIT ?1 OFFSET ?2"
-        )?;
+             LIMIT ?1 OFFSET ?2",
+            SMS_COLUMNS
+        ))?;
         
-        let messages = stmt.query_map(params![limit, offset], |row| {
-            Ok(SmsMessage {
-                id: row.get(0)?,
-                direction: row.get(1)?,
-                phone_number: row.get(2)?,
-                content: row.get(3)?,
-                timestamp: row.get(4)?,
-                status: row.get(5)?,
-                pdu: row.get(6)?,
-            })
-        })?;
+        let messages = stmt.query_map(params![limit, offset], sms_from_row)?;
         
         let mut result = Vec::new();
         for message in messages {
             result.push(message?);
         }
         
+        fill_sms_contact_names(&conn, &mut result)?;
         Ok(result)
     }
     
     /// 获取与特定号码的对话历史
+    ///
+    /// 号码按 [`same_number`] 比较，"+8613800138000" 和 "13800138000" 属于同一对话
     pub fn get_sms_conversation(&self, phone_number: &str, limit: i64) -> Result<Vec<SmsMessage>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, direction, phone_number, content, timestamp, status, pdu
-             FROM sms_messages
-             WHERE phone_number = ?1
-             ORDER BY timestamp DESC
-             LIMIT ?2"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
+             WHERE phone_number = ?1 OR phone_number LIKE '%' || ?2
+             ORDER BY timestamp DESC",
+            SMS_COLUMNS
+        ))?;
         
-        let messages = stmt.query_map(params![phone_number, limit], |row| {
-            Ok(SmsMessage {
-                id: row.get(0)?,
-                direction: row.get(1)?,
-                phone_number: row.get(2)?,
-                content: row.get(3)?,
-                timestamp: row.get(4)?,
-                status: row.get(5)?,
-                pdu: row.get(6)?,
-            })
-        })?;
+        let messages = stmt.query_map(params![phone_number, number_key(phone_number)], sms_from_row)?;
         
         let mut result = Vec::new();
         for message in messages {
-            result.push(message?);
+            let message = message?;
+            if message.phone_number == phone_number || same_number(&message.phone_number, phone_number) {
+                result.push(message);
+                if result.len() as i64 >= limit {
+                    break;
+                }
+            }
         }
         
+        fill_sms_contact_names(&conn, &mut result)?;
         Ok(result)
     }
     
+    /// 给短信添加标签（已有时忽略），返回更新后的标签
+    pub fn add_sms_tag(&self, id: i64, tag: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let current: Option<String> =
+            conn.query_row("SELECT tags FROM sms_messages WHERE id = ?1", params![id], |row| row.get(0))?;
+        let mut tags = split_tags(current);
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+            conn.execute("UPDATE sms_messages SET tags = ?1 WHERE id = ?2", params![tags.join(","), id])?;
+        }
+        Ok(tags)
+    }
+
     /// 获取短信统计
     pub fn get_sms_stats(&self) -> Result<SmsStats> {
         let conn = self.conn.lock().unwrap();
@@ -244,10 +939,17 @@ impl Database {
             |row| row.get(0),
         )?;
         
+        let unread: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sms_messages WHERE is_read = 0",
+            [],
+            |row| row.get(0),
+        )?;
+
         Ok(SmsStats {
             total,
             incoming,
             outgoing,
+            unread,
         })
     }
     
@@ -268,9 +970,434 @@ impl Database {
     pub fn clear_all_sms(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM sms_messages", [])?;
+        conn.execute("DELETE FROM sms_threads", [])?;
+        Ok(())
+    }
+
+    // ==================== 短信会话相关方法 ====================
+
+    /// 获取会话列表（最近有短信的在前）
+    pub fn list_sms_threads(&self, archived: bool, limit: i64, offset: i64) -> Result<Vec<SmsThread>> {
+        let conn = self.conn.lock().unwrap();
+        query_threads(&conn, "t.archived = ?1", &archived, limit, offset)
+    }
+
+    /// 获取单个会话摘要（会话中没有短信时返回 None）
+    pub fn get_sms_thread(&self, id: i64) -> Result<Option<SmsThread>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(query_threads(&conn, "t.id = ?1", &id, 1, 0)?.into_iter().next())
+    }
+
+    /// 获取会话中的短信（最新的在前）
+    pub fn get_sms_thread_messages(&self, thread_id: i64, limit: i64, offset: i64) -> Result<Vec<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
+             WHERE thread_id = ?1
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?2 OFFSET ?3",
+            SMS_COLUMNS
+        ))?;
+        let mut messages = stmt
+            .query_map(params![thread_id, limit, offset], sms_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        fill_sms_contact_names(&conn, &mut messages)?;
+        Ok(messages)
+    }
+
+    /// 标记会话中的所有短信为已读或未读（只影响收到的短信），返回会话是否存在
+    pub fn set_sms_thread_read(&self, thread_id: i64, read: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE sms_messages SET is_read = ?1 WHERE thread_id = ?2 AND direction = 'incoming'",
+            params![read, thread_id],
+        )?;
+        let exists = conn.prepare("SELECT 1 FROM sms_threads WHERE id = ?1")?.exists(params![thread_id])?;
+        Ok(exists)
+    }
+
+    /// 标记单条短信为已读或未读，返回短信是否存在
+    pub fn set_sms_read(&self, id: i64, read: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("UPDATE sms_messages SET is_read = ?1 WHERE id = ?2", params![read, id])? > 0)
+    }
+
+    /// 归档或取消归档会话，返回会话是否存在
+    pub fn set_sms_thread_archived(&self, thread_id: i64, archived: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("UPDATE sms_threads SET archived = ?1 WHERE id = ?2", params![archived, thread_id])? > 0)
+    }
+
+    /// 删除单条短信，返回是否存在
+    pub fn delete_sms(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM sms_messages WHERE id = ?1", params![id])? > 0)
+    }
+
+    /// 删除会话及其中的所有短信，返回会话是否存在
+    pub fn delete_sms_thread(&self, thread_id: i64) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM sms_messages WHERE thread_id = ?1", params![thread_id])?;
+        let deleted = tx.execute("DELETE FROM sms_threads WHERE id = ?1", params![thread_id])? > 0;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// 全文搜索短信内容（最新的在前），可限定会话
+    ///
+    /// 3 个字符及以上使用 FTS5 trigram 索引，更短的关键词退回 LIKE 扫描
+    pub fn search_sms(&self, query: &str, thread_id: Option<i64>, limit: i64, offset: i64) -> Result<Vec<SmsMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let (condition, pattern) = if query.chars().count() >= 3 {
+            ("id IN (SELECT rowid FROM sms_fts WHERE sms_fts MATCH ?1)", fts_phrase(query))
+        } else {
+            let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            ("content LIKE ?1 ESCAPE '\\'", format!("%{}%", escaped))
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_messages
+             WHERE {} AND (?2 IS NULL OR thread_id = ?2)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?3 OFFSET ?4",
+            SMS_COLUMNS, condition
+        ))?;
+        let mut messages = stmt
+            .query_map(params![pattern, thread_id, limit, offset], sms_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        fill_sms_contact_names(&conn, &mut messages)?;
+        Ok(messages)
+    }
+    
+    // ==================== 长短信分段相关方法 ====================
+    
+    /// 保存分段（重复收到同一分段时覆盖）
+    pub fn insert_sms_fragment(&self, fragment: &SmsFragment) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO sms_fragments
+             (sender, reference, total, sequence, content, pdu, received_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                fragment.sender,
+                fragment.reference,
+                fragment.total,
+                fragment.sequence,
+                fragment.content,
+                fragment.pdu,
+                fragment.received_at,
+            ],
+        )?;
         Ok(())
     }
     
+    /// 所有分段到齐时取出（按序号排序）并从暂存表删除，否则返回 None
+    pub fn take_complete_sms_fragments(&self, sender: &str, reference: i64) -> Result<Option<Vec<SmsFragment>>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let fragments = Self::query_sms_fragments(&tx, sender, reference)?;
+        let total = fragments.iter().map(|f| f.total).max().unwrap_or(0);
+        if fragments.is_empty() || (fragments.len() as i64) < total {
+            return Ok(None);
+        }
+        tx.execute(
+            "DELETE FROM sms_fragments WHERE sender = ?1 AND reference = ?2",
+            params![sender, reference],
+        )?;
+        tx.commit()?;
+        Ok(Some(fragments))
+    }
+    
+    /// 取出最早分段在 `before` 之前收到的未完成长短信，并从暂存表删除
+    pub fn take_expired_sms_fragments(&self, before: i64) -> Result<Vec<Vec<SmsFragment>>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let keys: Vec<(String, i64)> = {
+            let mut stmt = tx.prepare(
+                "SELECT sender, reference FROM sms_fragments
+                 GROUP BY sender, reference
+                 HAVING MIN(received_at) < ?1",
+            )?;
+            let rows = stmt.query_map(params![before], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        
+        let mut expired = Vec::new();
+        for (sender, reference) in keys {
+            expired.push(Self::query_sms_fragments(&tx, &sender, reference)?);
+            tx.execute(
+                "DELETE FROM sms_fragments WHERE sender = ?1 AND reference = ?2",
+                params![sender, reference],
+            )?;
+        }
+        tx.commit()?;
+        Ok(expired)
+    }
+    
+    fn query_sms_fragments(conn: &Connection, sender: &str, reference: i64) -> Result<Vec<SmsFragment>> {
+        let mut stmt = conn.prepare(
+            "SELECT sender, reference, total, sequence, content, pdu, received_at
+             FROM sms_fragments
+             WHERE sender = ?1 AND reference = ?2
+             ORDER BY sequence ASC",
+        )?;
+        let rows = stmt.query_map(params![sender, reference], |row| {
+            Ok(SmsFragment {
+                sender: row.get(0)?,
+                reference: row.get(1)?,
+                total: row.get(2)?,
+                sequence: row.get(3)?,
+                content: row.get(4)?,
+                pdu: row.get(5)?,
+                received_at: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+    
+    // ==================== 通讯录相关方法 ====================
+
+    /// 添加联系人
+    pub fn insert_contact(
+        &self,
+        name: &str,
+        phone_number: &str,
+        email: Option<&str>,
+        note: Option<&str>,
+        source: &str,
+    ) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO contacts (name, phone_number, number_key, email, note, source, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![name, phone_number, number_key(phone_number), email, note, source, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取单个联系人
+    pub fn get_contact(&self, id: i64) -> Result<Option<Contact>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM contacts WHERE id = ?1", CONTACT_COLUMNS))?;
+        let mut rows = stmt.query_map(params![id], contact_from_row)?;
+        rows.next().transpose()
+    }
+
+    /// 获取联系人列表（按名称排序），`search` 匹配名称或号码
+    pub fn list_contacts(&self, search: Option<&str>) -> Result<Vec<Contact>> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = format!("%{}%", search.unwrap_or_default().trim());
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM contacts
+             WHERE name LIKE ?1 OR phone_number LIKE ?1
+             ORDER BY name COLLATE NOCASE, id",
+            CONTACT_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![pattern], contact_from_row)?;
+        rows.collect()
+    }
+
+    /// 更新联系人，返回是否存在
+    pub fn update_contact(
+        &self,
+        id: i64,
+        name: &str,
+        phone_number: &str,
+        email: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE contacts SET name = ?1, phone_number = ?2, number_key = ?3, email = ?4, note = ?5, updated_at = ?6
+             WHERE id = ?7",
+            params![name, phone_number, number_key(phone_number), email, note, Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// 删除联系人，返回是否存在
+    pub fn delete_contact(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM contacts WHERE id = ?1", params![id])? > 0)
+    }
+
+    /// 是否已有同名且号码相同的联系人
+    pub fn contact_exists(&self, name: &str, phone_number: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT phone_number FROM contacts WHERE name = ?1 AND number_key = ?2")?;
+        let numbers = stmt.query_map(params![name, number_key(phone_number)], |row| row.get::<_, String>(0))?;
+        for number in numbers {
+            if same_number(&number?, phone_number) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 按号码查找联系人名称
+    pub fn find_contact_name(&self, phone_number: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        lookup_contact_name(&conn, phone_number)
+    }
+    
+    // ==================== USSD 记录相关方法 ====================
+
+    /// 保存一次 USSD 交互
+    pub fn insert_ussd(
+        &self,
+        direction: &str,
+        request: Option<&str>,
+        response: Option<&str>,
+        result_type: Option<&str>,
+        state: &str,
+        error: Option<&str>,
+    ) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO ussd_history (direction, request, response, result_type, state, error, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![direction, request, response, result_type, state, error, Utc::now().to_rfc3339()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取 USSD 记录（最新的在前）
+    pub fn get_ussd_history(&self, limit: i64, offset: i64) -> Result<Vec<UssdRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, direction, request, response, result_type, state, error, timestamp
+             FROM ussd_history ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let rows = stmt.query_map(params![limit, offset], |row| {
+            Ok(UssdRecord {
+                id: row.get(0)?,
+                direction: row.get(1)?,
+                request: row.get(2)?,
+                response: row.get(3)?,
+                result_type: row.get(4)?,
+                state: row.get(5)?,
+                error: row.get(6)?,
+                timestamp: row.get(7)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 清空 USSD 记录
+    pub fn clear_ussd_history(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM ussd_history", [])?;
+        Ok(())
+    }
+
+    // ==================== 短信发送队列相关方法 ====================
+
+    /// 添加发送任务
+    pub fn insert_sms_queue(
+        &self,
+        phone_number: &str,
+        content: &str,
+        schedule: Option<&str>,
+        send_at: i64,
+        max_attempts: u32,
+    ) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sms_queue (phone_number, content, schedule, send_at, max_attempts, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![phone_number, content, schedule, send_at, max_attempts, Utc::now().timestamp()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn get_sms_queue_item(&self, id: i64) -> Result<Option<SmsQueueItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM sms_queue WHERE id = ?1", SMS_QUEUE_COLUMNS))?;
+        let mut rows = stmt.query_map(params![id], sms_queue_from_row)?;
+        rows.next().transpose()
+    }
+
+    /// 所有发送任务（按下次发送时间排序）
+    pub fn list_sms_queue(&self) -> Result<Vec<SmsQueueItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_queue ORDER BY status = 'scheduled' DESC, send_at ASC, id ASC",
+            SMS_QUEUE_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], sms_queue_from_row)?;
+        rows.collect()
+    }
+
+    /// 到期待发送的任务
+    pub fn get_due_sms_queue(&self, now: i64, limit: i64) -> Result<Vec<SmsQueueItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sms_queue WHERE status = 'scheduled' AND send_at <= ?1
+             ORDER BY send_at ASC, id ASC LIMIT ?2",
+            SMS_QUEUE_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![now, limit], sms_queue_from_row)?;
+        rows.collect()
+    }
+
+    /// 修改任务内容和计划，重新进入待发送状态。任务不存在时返回 false
+    pub fn update_sms_queue_item(
+        &self,
+        id: i64,
+        phone_number: &str,
+        content: &str,
+        schedule: Option<&str>,
+        send_at: i64,
+        max_attempts: u32,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE sms_queue SET phone_number = ?1, content = ?2, schedule = ?3, send_at = ?4,
+             max_attempts = ?5, status = 'scheduled', attempts = 0, last_error = NULL
+             WHERE id = ?6",
+            params![phone_number, content, schedule, send_at, max_attempts, id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// 保存一次发送尝试后的任务状态
+    pub fn save_sms_queue_progress(&self, item: &SmsQueueItem) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE sms_queue SET send_at = ?1, status = ?2, attempts = ?3, last_error = ?4,
+             last_sent_at = ?5, last_sms_id = ?6
+             WHERE id = ?7",
+            params![
+                item.send_at,
+                item.status,
+                item.attempts,
+                item.last_error,
+                item.last_sent_at,
+                item.last_sms_id,
+                item.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除任务，任务不存在时返回 false
+    pub fn delete_sms_queue_item(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM sms_queue WHERE id = ?1", params![id])? > 0)
+    }
+
+    /// 统计某时间之后发往指定号码的短信数（用于按号码限速）
+    pub fn count_outgoing_sms_since(&self, phone_number: &str, since: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM sms_messages
+             WHERE direction = 'outgoing' AND status != 'failed' AND phone_number = ?1 AND timestamp >= ?2",
+            params![phone_number, since],
+            |row| row.get(0),
+        )
+    }
+
     // ==================== 通话记录相关方法 ====================
     
     /// 插入新通话记录
@@ -305,6 +1432,25 @@ impl Database {
     }
     
     /// 标记通话为未接来电
+    pub fn insert_blocked_call(&self, phone_number: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO call_history (direction, phone_number, duration, start_time, end_time, answered, reason)
+             VALUES ('blocked', ?1, 0, ?2, ?2, 0, 'call_filter')",
+            params![phone_number, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 记录自动处理原因
+    pub fn set_call_reason(&self, id: i64, reason: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("UPDATE call_history SET reason = ?1 WHERE id = ?2", params![reason, id])?;
+        Ok(())
+    }
+
     pub fn mark_call_missed(&self, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let end_time = Utc::now().to_rfc3339();
@@ -320,7 +1466,7 @@ impl Database {
     pub fn get_call_history(&self, limit: i64, offset: i64) -> Result<Vec<CallRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, direction, phone_number, duration, start_time, end_time, answered
+            "SELECT id, direction, phone_number, duration, start_time, end_time, answered, reason
              FROM call_history
              ORDER BY start_time DESC
              LIMIT ?1 OFFSET ?2"
@@ -335,6 +1481,8 @@ impl Database {
                 start_time: row.get(4)?,
                 end_time: row.get(5)?,
                 answered: row.get::<_, i32>(6)? != 0,
+                contact_name: None,
+                reason: row.get(7)?,
             })
         })?;
         
@@ -343,6 +1491,7 @@ impl Database {
             result.push(record?);
         }
         
+        fill_call_contact_names(&conn, &mut result)?;
         Ok(result)
     }
     
@@ -351,7 +1500,7 @@ impl Database {
     pub fn get_call_history_by_number(&self, phone_number: &str, limit: i64) -> Result<Vec<CallRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, direction, phone_number, duration, start_time, end_time, answered
+            "SELECT id, direction, phone_number, duration, start_time, end_time, answered, reason
              FROM call_history
              WHERE phone_number = ?1
              ORDER BY start_time DESC
@@ -367,6 +1516,8 @@ impl Database {
                 start_time: row.get(4)?,
                 end_time: row.get(5)?,
                 answered: row.get::<_, i32>(6)? != 0,
+                contact_name: None,
+                reason: row.get(7)?,
             })
         })?;
         
@@ -375,6 +1526,7 @@ impl Database {
             result.push(record?);
         }
         
+        fill_call_contact_names(&conn, &mut result)?;
         Ok(result)
     }
     
@@ -434,6 +1586,300 @@ impl Database {
         conn.execute("DELETE FROM call_history", [])?;
         Ok(())
     }
+    
+    // ==================== 信号质量历史相关方法 ====================
+    
+    /// 插入一条信号质量采样
+    pub fn insert_signal_sample(&self, sample: &SignalSample) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO signal_samples
+             (timestamp, tech, registration_status, rsrp, rsrq, sinr, band, arfcn, pci, cell_id, tac)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                sample.timestamp,
+                sample.tech,
+                sample.registration_status,
+                sample.rsrp,
+                sample.rsrq,
+                sample.sinr,
+                sample.band,
+                sample.arfcn,
+                sample.pci,
+                sample.cell_id,
+                sample.tac,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+    
+    /// 查询时间范围 [from, to) 内的信号历史（小时聚合数据 + 原始采样，按时间升序）
+    pub fn get_signal_history(&self, from: i64, to: i64) -> Result<Vec<SignalHistoryPoint>> {
+        let conn = self.conn.lock().unwrap();
+        let mut result = Self::query_signal_hourly(&conn, "bucket_start >= ?1 AND bucket_start < ?2", from, to)?;
+        result.extend(Self::query_signal_raw(&conn, "timestamp >= ?1 AND timestamp < ?2", from, to)?);
+        result.sort_by_key(|p| p.timestamp);
+        Ok(result)
+    }
+    
+    /// 将 `before` 之前的原始采样按小时降采样到聚合表，并删除这些原始采样
+    ///
+    /// `before` 会向下取整到整点，保证每个小时只被聚合一次
+    pub fn compact_signal_samples(&self, before: i64) -> Result<usize> {
+        let before = before - before.rem_euclid(3600);
+        let mut conn = self.conn.lock().unwrap();
+        let raw = Self::query_signal_raw(&conn, "timestamp >= ?1 AND timestamp < ?2", i64::MIN, before)?;
+        if raw.is_empty() {
+            return Ok(0);
+        }
+        
+        let tx = conn.transaction()?;
+        for point in crate::signal_history::bucket_points(&raw, 3600) {
+            tx.execute(
+                "INSERT OR REPLACE INTO signal_samples_hourly
+                 (bucket_start, samples, tech, registration_status,
+                  rsrp_avg, rsrp_min, rsrp_max, rsrq_avg, rsrq_min, rsrq_max,
+                  sinr_avg, sinr_min, sinr_max, band, arfcn, pci, cell_id, tac)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+                params![
+                    point.timestamp,
+                    point.samples,
+                    point.tech,
+                    point.registration_status,
+                    point.rsrp_avg,
+                    point.rsrp_min,
+                    point.rsrp_max,
+                    point.rsrq_avg,
+                    point.rsrq_min,
+                    point.rsrq_max,
+                    point.sinr_avg,
+                    point.sinr_min,
+                    point.sinr_max,
+                    point.band,
+                    point.arfcn,
+                    point.pci,
+                    point.cell_id,
+                    point.tac,
+                ],
+            )?;
+        }
+        let deleted = tx.execute("DELETE FROM signal_samples WHERE timestamp < ?1", params![before])?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+    
+    /// 删除 `before` 之前的所有信号历史（原始 + 聚合）
+    pub fn purge_signal_history(&self, before: i64) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let raw = conn.execute("DELETE FROM signal_samples WHERE timestamp < ?1", params![before])?;
+        let hourly = conn.execute("DELETE FROM signal_samples_hourly WHERE bucket_start < ?1", params![before])?;
+        Ok(raw + hourly)
+    }
+    
+    fn query_signal_raw(conn: &Connection, filter: &str, from: i64, to: i64) -> Result<Vec<SignalHistoryPoint>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT timestamp, tech, registration_status, rsrp, rsrq, sinr, band, arfcn, pci, cell_id, tac
+             FROM signal_samples
+             WHERE {}
+             ORDER BY timestamp ASC",
+            filter
+        ))?;
+        
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok(SignalSample {
+                timestamp: row.get(0)?,
+                tech: row.get(1)?,
+                registration_status: row.get(2)?,
+                rsrp: row.get(3)?,
+                rsrq: row.get(4)?,
+                sinr: row.get(5)?,
+                band: row.get(6)?,
+                arfcn: row.get(7)?,
+                pci: row.get(8)?,
+                cell_id: row.get(9)?,
+                tac: row.get(10)?,
+            })
+        })?;
+        
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(SignalHistoryPoint::from(row?));
+        }
+        Ok(result)
+    }
+    
+    fn query_signal_hourly(conn: &Connection, filter: &str, from: i64, to: i64) -> Result<Vec<SignalHistoryPoint>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT bucket_start, samples, tech, registration_status,
+                    rsrp_avg, rsrp_min, rsrp_max, rsrq_avg, rsrq_min, rsrq_max,
+                    sinr_avg, sinr_min, sinr_max, band, arfcn, pci, cell_id, tac
+             FROM signal_samples_hourly
+             WHERE {}
+             ORDER BY bucket_start ASC",
+            filter
+        ))?;
+        
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok(SignalHistoryPoint {
+                timestamp: row.get(0)?,
+                samples: row.get(1)?,
+                tech: row.get(2)?,
+                registration_status: row.get(3)?,
+                rsrp_avg: row.get(4)?,
+                rsrp_min: row.get(5)?,
+                rsrp_max: row.get(6)?,
+                rsrq_avg: row.get(7)?,
+                rsrq_min: row.get(8)?,
+                rsrq_max: row.get(9)?,
+                sinr_avg: row.get(10)?,
+                sinr_min: row.get(11)?,
+                sinr_max: row.get(12)?,
+                band: row.get(13)?,
+                arfcn: row.get(14)?,
+                pci: row.get(15)?,
+                cell_id: row.get(16)?,
+                tac: row.get(17)?,
+            })
+        })?;
+        
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+    
+    // ==================== 流量统计相关方法 ====================
+    
+    /// 获取接口上次记录的计数器
+    pub fn get_interface_counter(&self, interface: &str) -> Result<Option<InterfaceCounter>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT rx_bytes, tx_bytes, boot_id FROM data_usage_counters WHERE interface = ?1"
+        )?;
+        let mut rows = stmt.query(params![interface])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(InterfaceCounter {
+                rx_bytes: row.get::<_, i64>(0)? as u64,
+                tx_bytes: row.get::<_, i64>(1)? as u64,
+                boot_id: row.get(2)?,
+            })),
+            None => Ok(None),
+        }
+    }
+    
+    /// 获取所有记录过计数器的接口
+    pub fn get_counted_interfaces(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT interface FROM data_usage_counters ORDER BY interface")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+    
+    /// 记录一次流量增量：更新计数器快照并累加到小时/日/月统计
+    ///
+    /// `buckets` 为 (period, bucket) 列表，如 `[("hour", "2025-12-08T13"), ("day", "2025-12-08"), ("month", "2025-12")]`
+    pub fn record_data_usage(
+        &self,
+        interface: &str,
+        counter: &InterfaceCounter,
+        rx_delta: u64,
+        tx_delta: u64,
+        buckets: &[(&str, String)],
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        
+        tx.execute(
+            "INSERT OR REPLACE INTO data_usage_counters (interface, rx_bytes, tx_bytes, boot_id, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                interface,
+                counter.rx_bytes as i64,
+                counter.tx_bytes as i64,
+                counter.boot_id,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        
+        if rx_delta > 0 || tx_delta > 0 {
+            for (period, bucket) in buckets {
+                tx.execute(
+                    "INSERT INTO data_usage (period, bucket, interface, rx_bytes, tx_bytes)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT(period, bucket, interface) DO UPDATE SET
+                         rx_bytes = rx_bytes + excluded.rx_bytes,
+                         tx_bytes = tx_bytes + excluded.tx_bytes",
+                    params![period, bucket, interface, rx_delta as i64, tx_delta as i64],
+                )?;
+            }
+        }
+        
+        tx.commit()
+    }
+    
+    /// 统计 [from, to) 区间内的日流量合计（所有接口），日期格式 YYYY-MM-DD
+    pub fn sum_daily_usage(&self, from: &str, to: &str) -> Result<(u64, u64)> {
+        let conn = self.conn.lock().unwrap();
+        let (rx, tx): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(rx_bytes), 0), COALESCE(SUM(tx_bytes), 0)
+             FROM data_usage
+             WHERE period = 'day' AND bucket >= ?1 AND bucket < ?2",
+            params![from, to],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((rx as u64, tx as u64))
+    }
+    
+    /// 获取最近 `limit` 个统计桶（所有接口合计，按时间升序）
+    pub fn get_data_usage_history(&self, period: &str, limit: i64) -> Result<Vec<DataUsageBucket>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT bucket, SUM(rx_bytes), SUM(tx_bytes)
+             FROM data_usage
+             WHERE period = ?1
+             GROUP BY bucket
+             ORDER BY bucket DESC
+             LIMIT ?2"
+        )?;
+        
+        let rows = stmt.query_map(params![period, limit], |row| {
+            let rx_bytes = row.get::<_, i64>(1)? as u64;
+            let tx_bytes = row.get::<_, i64>(2)? as u64;
+            Ok(DataUsageBucket {
+                bucket: row.get(0)?,
+                rx_bytes,
+                tx_bytes,
+                total_bytes: rx_bytes + tx_bytes,
+            })
+        })?;
+        
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        result.reverse();
+        Ok(result)
+    }
+    
+    /// 删除某一粒度下 `before` 之前的统计桶
+    pub fn purge_data_usage(&self, period: &str, before: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM data_usage WHERE period = ?1 AND bucket < ?2",
+            params![period, before],
+        )
+    }
+    
+    /// 记录配额告警，返回 false 表示本周期该级别已经告警过
+    pub fn try_mark_data_usage_alert(&self, cycle_start: &str, level: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO data_usage_alerts (cycle_start, level, created_at) VALUES (?1, ?2, ?3)",
+            params![cycle_start, level, Utc::now().to_rfc3339()],
+        )?;
+        Ok(inserted > 0)
+    }
 }
 
 
@@ -13,17 +13,19 @@
 //! 处理与 ofono D-Bus 服务的通信
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{info, warn};
 use zbus::{proxy, zvariant::OwnedValue, Connection, Proxy};
 
 use crate::config::ConfigManager;
+use crate::events::{DeviceEvent, EventBus};
 use crate::models::{
     AirplaneModeResponse, ApnContext, DeviceInfoResponse, NetworkInfoResponse, QosInfoResponse, RadioMode,
     RadioModeResponse, ServingCell, SimInfoResponse,
 };
-use crate::serial::with_serial;
+use crate::serial::{with_serial, with_ussd_serial};
 use crate::state::FrontendRuntime;
 
 /// ofono NetworkMonitor 代理接口
@@ -65,6 +67,21 @@ pub trait ConnectionContext {
 pub trait SimManager {
     /// 获取SIM卡所有属性
     fn get_properties(&self) -> zbus::Result<HashMap<String, zbus::zvariant::OwnedValue>>;
+
+    /// 输入 PIN（类型为 PinRequired 的值）
+    fn enter_pin(&self, pin_type: &str, pin: &str) -> zbus::Result<()>;
+
+    /// 使用 PUK 解锁并设置新 PIN
+    fn reset_pin(&self, puk_type: &str, puk: &str, new_pin: &str) -> zbus::Result<()>;
+
+    /// 修改 PIN
+    fn change_pin(&self, pin_type: &str, old_pin: &str, new_pin: &str) -> zbus::Result<()>;
+
+    /// 启用 PIN 锁
+    fn lock_pin(&self, pin_type: &str, pin: &str) -> zbus::Result<()>;
+
+    /// 关闭 PIN 锁
+    fn unlock_pin(&self, pin_type: &str, pin: &str) -> zbus::Result<()>;
 }
 
 /// ofono MessageManager 代理接口
@@ -427,6 +444,31 @@ pub async fn get_data_connection_status(conn: &Connection) -> zbus::Result<bool>
     Ok(active)
 }
 
+/// 获取数据连接对应的网络接口名（来自 context 的 Settings.Interface）
+///
+/// # Arguments
+/// * `conn` - D-Bus 连接
+///
+/// # Returns
+/// 接口名，数据连接未激活时返回 None
+pub async fn get_data_interface(conn: &Connection) -> zbus::Result<Option<String>> {
+    let context_path = find_internet_context(conn).await?;
+    
+    let proxy = ConnectionContextProxy::builder(conn)
+        .path(context_path)?
+        .build()
+        .await?;
+    let properties = proxy.get_properties().await?;
+    
+    let interface = properties
+        .get("Settings")
+        .and_then(|v| HashMap::<String, OwnedValue>::try_from(v.clone()).ok())
+        .and_then(|settings| settings.get("Interface").and_then(|v| String::try_from(v.clone()).ok()))
+        .filter(|name| !name.is_empty());
+    
+    Ok(interface)
+}
+
 /// 获取漫游状态
 ///
 /// # Arguments
@@ -630,6 +672,19 @@ async fn auto_configure_apn(conn: &Connection, context_path: &str) -> Result<Str
     Ok(format!("Auto-configured APN: {} ({})", apn, protocol))
 }
 
+/// 短信指令 `data_off` 关闭数据连接时置位，`data_on` 清除，Watchdog 据此跳过自动恢复
+static USER_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// 设置数据连接是否被用户主动关闭
+pub fn set_user_suspended(suspended: bool) {
+    USER_SUSPENDED.store(suspended, Ordering::Relaxed);
+}
+
+/// 数据连接是否被用户主动关闭
+pub fn is_user_suspended() -> bool {
+    USER_SUSPENDED.load(Ordering::Relaxed)
+}
+
 /// 检查并恢复数据连接
 ///
 /// 这个函数被 watchdog 调用，检查数据连接状态并在需要时恢复
@@ -639,7 +694,15 @@ async fn auto_configure_apn(conn: &Connection, context_path: &str) -> Result<Str
 ///
 /// # Returns
 /// 当前状态描述字符串
-async fn check_and_restore_data_connection(conn: &Connection) -> String {
+pub(crate) async fn check_and_restore_data_connection(conn: &Connection) -> String {
+    // 超出流量配额或被用户关闭时不自动恢复
+    if crate::data_usage::is_quota_suspended() {
+        return "Suspended by data quota".to_string();
+    }
+    if is_user_suspended() {
+        return "Suspended by SMS command".to_string();
+    }
+    
     // 1. 检查网络注册状态
     let net_status = match NetworkRegistrationProxy::new(conn).await {
         Ok(net_proxy) => {
@@ -726,20 +789,25 @@ async fn check_and_restore_data_connection(conn: &Connection) -> String {
 /// # Arguments
 /// * `conn` - D-Bus 连接
 /// * `interval_secs` - 检查间隔（秒）
+/// * `events` - 事件总线，数据连接状态变化时发布事件
 pub async fn data_connection_watchdog(
     conn: Arc<Connection>,
     config_manager: Arc<ConfigManager>,
     frontend_runtime: Arc<FrontendRuntime>,
+    events: Arc<EventBus>,
 ) {
     use crate::iptables::{flush_iptables, get_iptables_rule_count};
     
     let mut last_data_log = String::new();
     let mut last_iptables_action = false; // 上次是否清空了 iptables
+    let mut rejected_pin: Option<String> = None; // 被 SIM 拒绝的自动输入 PIN
     
     loop {
         let refresh = config_manager.get_refresh();
         let heartbeat_timeout = Duration::from_millis(refresh.heartbeat_timeout_ms());
-        let interval = if frontend_runtime.is_recent(heartbeat_timeout) {
+        let frontend_active =
+            frontend_runtime.is_recent(heartbeat_timeout) || events.subscriber_count() > 0;
+        let interval = if frontend_active {
             Duration::from_millis(refresh.active_watchdog_interval_ms())
         } else {
             Duration::from_millis(refresh.idle_watchdog_interval_ms())
@@ -776,12 +844,23 @@ pub async fn data_connection_watchdog(
             }
         }
         
-        // 2. 检查并恢复数据连接
+        // 2. SIM 等待 PIN 时自动输入配置中的 PIN（重启后 SIM 锁定无法联网）
+        let sim_pin = config_manager.get_sim_pin();
+        if sim_pin.auto_enter && !sim_pin.pin.is_empty() {
+            auto_enter_sim_pin(&conn, &sim_pin.pin, &mut rejected_pin).await;
+        }
+
+        // 3. 检查并恢复数据连接
         let result = check_and_restore_data_connection(&conn).await;
         
         // 只在状态变化时打印日志，避免刷屏
         if result != last_data_log {
             info!(status = %result, "Watchdog: data connection");
+            let active = get_data_connection_status(&conn).await.unwrap_or(false);
+            events.publish(DeviceEvent::DataConnectionChanged {
+                active,
+                status: result.clone(),
+            });
             last_data_log = result;
         }
     }
@@ -848,6 +927,18 @@ pub async fn get_sim_info_data(conn: &Connection) -> zbus::Result<SimInfoRespons
         .and_then(|v| String::try_from(v.clone()).ok())
         .unwrap_or_else(|| "none".to_string());
 
+    // 已启用的 PIN 锁
+    let locked_pins: Vec<String> = sim_props
+        .get("LockedPins")
+        .and_then(|v| <Vec<String>>::try_from(v.clone()).ok())
+        .unwrap_or_default();
+
+    // 各类 PIN/PUK 剩余尝试次数
+    let retries: HashMap<String, u8> = sim_props
+        .get("Retries")
+        .and_then(|v| <HashMap<String, u8>>::try_from(v.clone()).ok())
+        .unwrap_or_default();
+
     // 首选语言
     let preferred_languages: Vec<String> = sim_props
         .get("PreferredLanguages")
@@ -863,10 +954,81 @@ pub async fn get_sim_info_data(conn: &Connection) -> zbus::Result<SimInfoRespons
         mcc,
         mnc,
         pin_required,
+        locked_pins,
+        retries,
         preferred_languages,
     })
 }
 
+/// 输入 SIM PIN
+pub async fn sim_enter_pin(conn: &Connection, pin_type: &str, pin: &str) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = SimManagerProxy::new(conn).await?;
+        proxy.enter_pin(pin_type, pin).await
+    }).await
+}
+
+/// 使用 PUK 解锁 SIM 并设置新 PIN
+pub async fn sim_reset_pin(conn: &Connection, puk_type: &str, puk: &str, new_pin: &str) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = SimManagerProxy::new(conn).await?;
+        proxy.reset_pin(puk_type, puk, new_pin).await
+    }).await
+}
+
+/// 修改 SIM PIN
+pub async fn sim_change_pin(conn: &Connection, pin_type: &str, old_pin: &str, new_pin: &str) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = SimManagerProxy::new(conn).await?;
+        proxy.change_pin(pin_type, old_pin, new_pin).await
+    }).await
+}
+
+/// 启用（`enabled` 为 true）或关闭 SIM PIN 锁
+pub async fn sim_set_pin_lock(conn: &Connection, pin_type: &str, pin: &str, enabled: bool) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = SimManagerProxy::new(conn).await?;
+        if enabled {
+            proxy.lock_pin(pin_type, pin).await
+        } else {
+            proxy.unlock_pin(pin_type, pin).await
+        }
+    }).await
+}
+
+/// 是否应自动输入 PIN：SIM 正在等待 PIN、剩余次数大于 1，且这个 PIN 没有被拒绝过
+fn should_auto_enter_pin(info: &SimInfoResponse, pin: &str, rejected: Option<&str>) -> bool {
+    info.present
+        && info.pin_required == "pin"
+        && rejected != Some(pin)
+        && info.retries.get("pin").is_none_or(|&left| left > 1)
+}
+
+/// 自动输入配置中的 SIM PIN，返回是否进行了尝试
+///
+/// 被拒绝的 PIN 记录在 `rejected` 中，配置的 PIN 修改前不再重试，
+/// 也不会用掉最后一次机会，避免 SIM 被锁到需要 PUK
+pub async fn auto_enter_sim_pin(conn: &Connection, pin: &str, rejected: &mut Option<String>) -> bool {
+    let Ok(info) = get_sim_info_data(conn).await else {
+        return false;
+    };
+    if !should_auto_enter_pin(&info, pin, rejected.as_deref()) {
+        return false;
+    }
+
+    match sim_enter_pin(conn, "pin", pin).await {
+        Ok(()) => {
+            info!("Watchdog: SIM PIN entered");
+            *rejected = None;
+        }
+        Err(e) => {
+            warn!(error = %e, "Watchdog: SIM PIN rejected, waiting for a new PIN in config");
+            *rejected = Some(pin.to_string());
+        }
+    }
+    true
+}
+
 /// 获取网络信息
 ///
 /// # Arguments
@@ -1204,6 +1366,27 @@ pub trait VoiceCallManager {
     
     /// 挂断所有通话
     fn hangup_all(&self) -> zbus::Result<()>;
+
+    /// 交换通话：保持中的通话变为活动，活动通话变为保持
+    fn swap_calls(&self) -> zbus::Result<()>;
+
+    /// 挂断活动通话并接听等待中的来电
+    fn release_and_answer(&self) -> zbus::Result<()>;
+
+    /// 保持活动通话并接听等待中的来电
+    fn hold_and_answer(&self) -> zbus::Result<()>;
+
+    /// 将活动通话和保持通话合并为多方通话，返回多方通话中的通话列表
+    fn create_multiparty(&self) -> zbus::Result<Vec<zbus::zvariant::OwnedObjectPath>>;
+
+    /// 从多方通话中分离出一路私聊，其余通话变为保持，返回仍在多方通话中的通话列表
+    fn private_chat(&self, call: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<Vec<zbus::zvariant::OwnedObjectPath>>;
+
+    /// 挂断多方通话
+    fn hangup_multiparty(&self) -> zbus::Result<()>;
+
+    /// 向活动通话发送 DTMF 按键音
+    fn send_tones(&self, tones: &str) -> zbus::Result<()>;
 }
 
 /// ofono VoiceCall 代理接口（单个通话）
@@ -1218,6 +1401,9 @@ pub trait VoiceCall {
     
     /// 接听来电
     fn answer(&self) -> zbus::Result<()>;
+
+    /// 将来电或等待中的来电转接到其他号码
+    fn deflect(&self, number: &str) -> zbus::Result<()>;
     
     /// 获取通话属性
     fn get_properties(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
@@ -1245,8 +1431,13 @@ pub async fn get_active_calls(conn: &Connection) -> zbus::Result<Vec<CallInfo>>
                 .get("StartTime")
                 .and_then(|v| String::try_from(v.clone()).ok());
             
+            let multiparty = props
+                .get("Multiparty")
+                .and_then(|v| bool::try_from(v).ok())
+                .unwrap_or(false);
+
             // 判断方向：incoming 或 outgoing
-            let direction = if state == "incoming" {
+            let direction = if state == "incoming" || state == "waiting" {
                 "incoming".to_string()
             } else {
                 "outgoing".to_string()
@@ -1255,9 +1446,11 @@ pub async fn get_active_calls(conn: &Connection) -> zbus::Result<Vec<CallInfo>>
             result.push(CallInfo {
                 path: path.to_string(),
                 phone_number,
+                held: state == "held",
                 state,
                 direction,
                 start_time,
+                multiparty,
             });
         }
         
@@ -1277,6 +1470,8 @@ pub async fn dial_call(conn: &Connection, phone_number: &str) -> zbus::Result<Ca
             state: "dialing".to_string(),
             direction: "outgoing".to_string(),
             start_time: Some(chrono::Utc::now().to_rfc3339()),
+            held: false,
+            multiparty: false,
         })
     }).await
 }
@@ -1320,6 +1515,167 @@ pub async fn answer_call(conn: &Connection, call_path: &str) -> zbus::Result<()>
     }).await
 }
 
+/// 交换活动通话和保持通话
+pub async fn swap_calls(conn: &Connection) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        proxy.swap_calls().await
+    }).await
+}
+
+/// 挂断活动通话并接听等待中的来电
+pub async fn release_and_answer(conn: &Connection) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        proxy.release_and_answer().await
+    }).await
+}
+
+/// 保持活动通话并接听等待中的来电
+pub async fn hold_and_answer(conn: &Connection) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        proxy.hold_and_answer().await
+    }).await
+}
+
+/// 创建多方通话，返回多方通话中的通话路径
+pub async fn create_multiparty(conn: &Connection) -> zbus::Result<Vec<String>> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        let calls = proxy.create_multiparty().await?;
+        Ok(calls.into_iter().map(|path| path.to_string()).collect())
+    }).await
+}
+
+/// 从多方通话中分离出指定通话，返回仍在多方通话中的通话路径
+pub async fn private_chat(conn: &Connection, call_path: &str) -> zbus::Result<Vec<String>> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        let path = zbus::zvariant::ObjectPath::try_from(call_path)?;
+        let calls = proxy.private_chat(&path).await?;
+        Ok(calls.into_iter().map(|path| path.to_string()).collect())
+    }).await
+}
+
+/// 挂断多方通话
+pub async fn hangup_multiparty(conn: &Connection) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        proxy.hangup_multiparty().await
+    }).await
+}
+
+/// 发送 DTMF 按键音
+pub async fn send_tones(conn: &Connection, tones: &str) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallManagerProxy::new(conn).await?;
+        proxy.send_tones(tones).await
+    }).await
+}
+
+/// 将来电转接到其他号码
+pub async fn deflect_call(conn: &Connection, call_path: &str, phone_number: &str) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = VoiceCallProxy::builder(conn)
+            .path(call_path)?
+            .build()
+            .await?;
+
+        proxy.deflect(phone_number).await
+    }).await
+}
+
+// ============ 电话本相关 D-Bus 接口 ============
+
+/// ofono Phonebook 代理接口
+#[proxy(
+    interface = "org.ofono.Phonebook",
+    default_service = "org.ofono",
+    default_path = "/ril_0",
+    assume_defaults = true
+)]
+pub trait Phonebook {
+    /// 导出 SIM 电话本（vCard 3.0 文本）
+    fn import(&self) -> zbus::Result<String>;
+}
+
+/// 读取 SIM 电话本，返回 vCard 文本
+pub async fn import_sim_phonebook(conn: &Connection) -> zbus::Result<String> {
+    with_serial(async {
+        let proxy = PhonebookProxy::new(conn).await?;
+        proxy.import().await
+    }).await
+}
+
+// ============ USSD 相关 D-Bus 接口 ============
+
+/// ofono SupplementaryServices 代理接口
+#[proxy(
+    interface = "org.ofono.SupplementaryServices",
+    default_service = "org.ofono",
+    default_path = "/ril_0",
+    assume_defaults = true
+)]
+pub trait SupplementaryServices {
+    /// 发起 USSD（或补充业务）请求，返回 (结果类型, 结果)
+    fn initiate(&self, command: &str) -> zbus::Result<(String, OwnedValue)>;
+
+    /// 回复网络下发的 USSD 菜单
+    fn respond(&self, reply: &str) -> zbus::Result<String>;
+
+    /// 取消当前 USSD 会话
+    fn cancel(&self) -> zbus::Result<()>;
+
+    /// 获取属性（State: idle / active / user-response）
+    fn get_properties(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+/// 发起 USSD 请求，返回 (结果类型, 结果文本)
+///
+/// 结果类型为 `USSD` 时结果是网络返回的文本；补充业务指令（如 `*#21#`）返回对应的业务名称和结果
+///
+/// 等待网络响应可能长达 D-Bus 超时，因此只持有 USSD 锁，不阻塞其他 D-Bus 操作
+pub async fn ussd_initiate(conn: &Connection, command: &str) -> zbus::Result<(String, String)> {
+    with_ussd_serial(async {
+        let proxy = SupplementaryServicesProxy::new(conn).await?;
+        let (result_type, value) = proxy.initiate(command).await?;
+        let text = match String::try_from(value.clone()) {
+            Ok(text) => text,
+            Err(_) => crate::events::dbus_value_to_json(&value).to_string(),
+        };
+        Ok((result_type, text))
+    }).await
+}
+
+/// 回复 USSD 菜单，返回网络的下一条消息
+pub async fn ussd_respond(conn: &Connection, reply: &str) -> zbus::Result<String> {
+    with_ussd_serial(async {
+        let proxy = SupplementaryServicesProxy::new(conn).await?;
+        proxy.respond(reply).await
+    }).await
+}
+
+/// 取消 USSD 会话
+pub async fn ussd_cancel(conn: &Connection) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = SupplementaryServicesProxy::new(conn).await?;
+        proxy.cancel().await
+    }).await
+}
+
+/// 获取 USSD 会话状态
+pub async fn get_ussd_state(conn: &Connection) -> zbus::Result<String> {
+    with_serial(async {
+        let proxy = SupplementaryServicesProxy::new(conn).await?;
+        let props = proxy.get_properties().await?;
+        Ok(props
+            .get("State")
+            .and_then(|v| String::try_from(v.clone()).ok())
+            .unwrap_or_else(|| "idle".to_string()))
+    }).await
+}
+
 // ============ 短信相关 D-Bus 接口 ============
 
 /// 发送短信
@@ -1331,6 +1687,16 @@ pub async fn send_sms(conn: &Connection, phone_number: &str, content: &str) -> z
     }).await
 }
 
+/// 设置是否请求短信状态报告（MessageManager.UseDeliveryReports）
+pub async fn set_sms_delivery_reports(conn: &Connection, enabled: bool) -> zbus::Result<()> {
+    with_serial(async {
+        let proxy = Proxy::new(conn, "org.ofono", "/ril_0", "org.ofono.MessageManager").await?;
+        let value = zbus::zvariant::Value::Bool(enabled);
+        proxy.call::<_, _, ()>("SetProperty", &("UseDeliveryReports", value)).await?;
+        Ok(())
+    }).await
+}
+
 // ============ 新增功能接口 ============
 
 use crate::models::{
@@ -0,0 +1,203 @@
+//! 实时事件模块
+//!
+//! 后台监听任务（短信、通话、数据连接 Watchdog、ofono 属性变化）把事件发布到
+//! [`EventBus`]，`/api/events` 以 Server-Sent Events 的形式推送给前端，
+//! 前端无需再按 `RefreshConfig.interval_ms` 轮询。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::broadcast;
+use tracing::warn;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{Connection, Message, MessageStream, Proxy};
+
+use crate::db::SmsMessage;
+
+/// 每个订阅者最多缓存的未读事件数，超出后最旧的事件会被丢弃
+const EVENT_BUFFER_SIZE: usize = 128;
+
+/// 属性监听信号流结束后重新订阅的退避间隔（最小 / 最大）
+const RESUBSCRIBE_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESUBSCRIBE_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// 推送给前端的设备事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeviceEvent {
+    /// 收到新短信
+    SmsReceived { message: SmsMessage },
+    /// 发出短信的投递状态变化（sent/delivered/failed）
+    SmsStatusChanged { message: SmsMessage },
+    /// 新通话（来电或去电）
+    CallAdded {
+        path: String,
+        phone_number: String,
+        state: String,
+        direction: String,
+    },
+    /// 命中黑白名单的来电已被自动挂断
+    CallBlocked { path: String, phone_number: String },
+    /// 通话状态变化（dialing/alerting/active/held ...）
+    CallStateChanged { path: String, state: String },
+    /// 通话结束
+    CallRemoved {
+        path: String,
+        phone_number: String,
+        direction: String,
+        duration: i64,
+    },
+    /// 数据连接状态变化（由 Watchdog 检测）
+    DataConnectionChanged { active: bool, status: String },
+    /// NetworkRegistration 属性变化（注册状态、信号强度、运营商等）
+    NetworkRegistrationChanged { property: String, value: JsonValue },
+    /// SimManager 属性变化（插拔卡、PIN 状态等）
+    SimManagerChanged { property: String, value: JsonValue },
+    /// 网络主动下发的 USSD 消息，`awaiting_response` 为 true 时需通过 `/api/ussd/respond` 回复
+    UssdReceived { message: String, awaiting_response: bool },
+    /// 更新通道中的新版本已下载并验证，等待应用
+    OtaUpdateAvailable {
+        version: String,
+        channel: String,
+        notes: Option<String>,
+    },
+}
+
+impl DeviceEvent {
+    /// SSE 事件名，与 JSON 中的 `type` 字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SmsReceived { .. } => "sms_received",
+            Self::SmsStatusChanged { .. } => "sms_status_changed",
+            Self::CallAdded { .. } => "call_added",
+            Self::CallBlocked { .. } => "call_blocked",
+            Self::CallStateChanged { .. } => "call_state_changed",
+            Self::CallRemoved { .. } => "call_removed",
+            Self::DataConnectionChanged { .. } => "data_connection_changed",
+            Self::NetworkRegistrationChanged { .. } => "network_registration_changed",
+            Self::SimManagerChanged { .. } => "sim_manager_changed",
+            Self::UssdReceived { .. } => "ussd_received",
+            Self::OtaUpdateAvailable { .. } => "ota_update_available",
+        }
+    }
+}
+
+/// 事件总线（多生产者、多订阅者）
+pub struct EventBus {
+    sender: broadcast::Sender<DeviceEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        Self { sender }
+    }
+
+    /// 发布事件，没有订阅者时直接丢弃
+    pub fn publish(&self, event: DeviceEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 当前订阅者数量（有前端连接事件流时 Watchdog 使用活跃轮询间隔）
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// 将 D-Bus 属性值转换为 JSON
+pub fn dbus_value_to_json(value: &Value<'_>) -> JsonValue {
+    match value {
+        Value::U8(v) => json!(v),
+        Value::Bool(v) => json!(v),
+        Value::I16(v) => json!(v),
+        Value::U16(v) => json!(v),
+        Value::I32(v) => json!(v),
+        Value::U32(v) => json!(v),
+        Value::I64(v) => json!(v),
+        Value::U64(v) => json!(v),
+        Value::F64(v) => json!(v),
+        Value::Str(v) => json!(v.as_str()),
+        Value::ObjectPath(v) => json!(v.as_str()),
+        Value::Value(inner) => dbus_value_to_json(inner),
+        Value::Array(items) => JsonValue::Array(items.iter().map(dbus_value_to_json).collect()),
+        other => json!(other.to_string()),
+    }
+}
+
+/// 订阅 ofono NetworkRegistration / SimManager 的 PropertyChanged 信号
+async fn subscribe_property_changes(conn: &Connection) -> zbus::Result<MessageStream> {
+    let dbus_proxy = Proxy::new(conn, "org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus").await?;
+
+    for interface in ["org.ofono.NetworkRegistration", "org.ofono.SimManager"] {
+        let rule = format!(
+            "type='signal',sender='org.ofono',interface='{}',member='PropertyChanged'",
+            interface
+        );
+        dbus_proxy.call::<_, _, ()>("AddMatch", &(rule,)).await?;
+    }
+
+    Ok(MessageStream::from(conn))
+}
+
+/// 把一条 PropertyChanged 信号转换为事件并发布
+fn publish_property_change(msg: &Message, events: &EventBus) {
+    let header = msg.header();
+    if header.member().map(|m| m.as_str()) != Some("PropertyChanged") {
+        return;
+    }
+
+    let Some(interface) = header.interface() else {
+        return;
+    };
+
+    if let Ok((property, value)) = msg.body().deserialize::<(String, OwnedValue)>() {
+        let value = dbus_value_to_json(&value);
+        let event = match interface.as_str() {
+            "org.ofono.NetworkRegistration" => DeviceEvent::NetworkRegistrationChanged { property, value },
+            "org.ofono.SimManager" => DeviceEvent::SimManagerChanged { property, value },
+            _ => return,
+        };
+        events.publish(event);
+    }
+}
+
+/// 监听 ofono NetworkRegistration / SimManager 的 PropertyChanged 信号并发布事件
+///
+/// 信号流结束（D-Bus 连接断开、ofono 重启等）后按指数退避重新订阅，
+/// 退避间隔在收到消息后复位。
+pub async fn start_property_listener(conn: Connection, events: Arc<EventBus>) -> zbus::Result<()> {
+    let mut stream = subscribe_property_changes(&conn).await?;
+    let mut backoff = RESUBSCRIBE_BACKOFF_MIN;
+
+    loop {
+        while let Some(msg) = stream.next().await {
+            match msg {
+                Ok(msg) => {
+                    backoff = RESUBSCRIBE_BACKOFF_MIN;
+                    publish_property_change(&msg, &events);
+                }
+                Err(e) => warn!(error = %e, "Property listener: failed to read message"),
+            }
+        }
+
+        loop {
+            warn!(retry_in_secs = backoff.as_secs(), "Property listener: signal stream closed, re-subscribing");
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RESUBSCRIBE_BACKOFF_MAX);
+            match subscribe_property_changes(&conn).await {
+                Ok(new_stream) => {
+                    stream = new_stream;
+                    break;
+                }
+                Err(e) => warn!(error = %e, "Property listener: failed to re-subscribe"),
+            }
+        }
+    }
+}
@@ -13,37 +13,38 @@
 //! 包含所有 HTTP API 的处理函数
 
 use axum::{
-    extract::{Query, State},
+    extract::{ConnectInfo, Query, State},
     http::{HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use zbus::Connection;
 
 use crate::{
     config::{ConfigManager, RefreshConfig},
     dbus::{
-        get_airplane_mode, get_all_apn_contexts, get_data_connection_status, get_device_info_data,
-        get_network_info_data, get_qos_info_data, get_radio_mode, get_roaming_status, get_serving_cell_info,
-        get_sim_info_data, send_at_command, set_airplane_mode, set_apn_properties, set_data_connection,
-        set_radio_mode, set_roaming_allowed,
+        get_airplane_mode, get_data_connection_status, get_network_info_data, get_qos_info_data,
+        get_roaming_status, get_sim_info_data, send_at_command, set_airplane_mode, set_data_connection,
+        set_roaming_allowed,
     },
     iptables::flush_iptables,
+    modem::{CellRat, ModemBackend, ModemError},
     models::*,
     usb_switch,
     utils::{
-        bands_to_bitmask, bitmask_to_bands, build_splband_lte_command, build_splband_nr_command,
-        format_uptime, get_active_interfaces, get_cell_command_config,
-        parse_at_response_to_2d_vec, parse_neighbor_cells, parse_primary_cell,
-        parse_splband_lte_response, parse_splband_nr_response, read_cpu_info, read_cpu_load_sync,
+        format_uptime, get_active_interfaces, read_cpu_info, read_cpu_load_sync,
         read_disk_info, read_interface_stats, read_memory_info, read_network_interfaces, read_system_info,
         read_uptime, sample_cpu_usage,
     },
 };
+use crate::events::EventBus;
 use crate::state::FrontendRuntime;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
 use std::process::Command;
+use tokio::sync::broadcast::error::RecvError;
 
 /// 处理 OPTIONS 请求（CORS 预检）
 pub async fn options_handler() -> impl IntoResponse {
@@ -76,54 +77,6 @@ pub async fn post_at_command(
     (status, headers, body_text)
 }
 
-/// 获取主小区信息
-///
-/// # Arguments
-/// * `conn` - D-Bus 连接
-/// * `cmd` - AT 指令
-/// * `tech` - 网络制式
-///
-/// # Returns
-/// 解析后的主小区信息
-async fn fetch_primary_cell(
-    conn: &Connection,
-    cmd: &str,
-    tech: &str,
-) -> Result<CellInfo, String> {
-    let response = send_at_command(conn, cmd)
-        .await
-        .map_err(|e| format!("Primary cell AT command failed: {}", e))?;
-
-    let parsed = parse_at_response_to_2d_vec(&response);
-    let cell = parse_primary_cell(tech, &parsed);
-
-    Ok(cell)
-}
-
-/// 获取邻区信息列表
-///
-/// # Arguments
-/// * `conn` - D-Bus 连接
-/// * `cmd` - AT 指令
-/// * `tech` - 网络制式
-///
-/// # Returns
-/// 解析后的邻区信息列表
-async fn fetch_neighbor_cells(
-    conn: &Connection,
-    cmd: &str,
-    tech: &str,
-) -> Result<Vec<CellInfo>, String> {
-    let response = send_at_command(conn, cmd)
-        .await
-        .map_err(|e| format!("Neighbor cell AT command failed: {}", e))?;
-
-    let parsed = parse_at_response_to_2d_vec(&response);
-    let cells = parse_neighbor_cells(tech, &parsed);
-
-    Ok(cells)
-}
-
 /// GET /api/cells - Get cell information
 ///
 /// # Response example
@@ -141,32 +94,18 @@ async fn fetch_neighbor_cells(
 ///   }
 /// }
 /// ```
-pub async fn get_cells(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
+pub async fn get_cells(State(modem): State<Arc<dyn ModemBackend>>) -> impl IntoResponse {
     let result = async {
         // 1. 获取服务小区信息（包含网络制式）
-        let serving_cell = get_serving_cell_info(&conn)
+        let serving_cell = modem
+            .serving_cell()
             .await
             .map_err(|e| format!("Failed to get serving cell info: {}", e))?;
 
-        let tech = serving_cell.tech.as_str();
-
-        // 2. 根据网络制式获取对应的 AT 指令配置
-        let cmd_config = get_cell_command_config(tech)
-            .ok_or_else(|| format!("Unsupported network type: {}", tech))?;
-
-        // 3. 顺序获取主小区和邻区信息
-        // 注意：ofono D-Bus 不支持并发 AT 指令，必须串行执行
-        let primary_cell = fetch_primary_cell(&conn, cmd_config.primary, tech).await?;
-        let neighbor_cells = fetch_neighbor_cells(&conn, cmd_config.neighbor, tech).await?;
+        // 2. 获取主小区和邻区信息（主小区在前）
+        let cells = modem.cell_list(&serving_cell).await.map_err(|e| e.to_string())?;
 
-        // 4. 合并主小区和邻区
-        let mut all_cells = vec![primary_cell];
-        all_cells.extend(neighbor_cells);
-
-        Ok::<_, String>(CellsResponse {
-            serving_cell,
-            cells: all_cells,
-        })
+        Ok::<_, String>(CellsResponse { serving_cell, cells })
     }
     .await;
 
@@ -199,8 +138,8 @@ pub async fn get_cells(State(conn): State<Arc<Connection>>) -> impl IntoResponse
 ///   }
 /// }
 /// ```
-pub async fn get_device_info(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
-    match get_device_info_data(&conn).await {
+pub async fn get_device_info(State(modem): State<Arc<dyn ModemBackend>>) -> impl IntoResponse {
+    match modem.device_info().await {
         Ok(data) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Success", data)),
@@ -247,7 +186,10 @@ pub async fn set_data_status(
     // 2. 设置数据连接状态
     match set_data_connection(&conn, payload.active).await {
         Ok(_) => {
-            
+            // 手动打开后恢复 Watchdog 的自动重连（取消短信指令 data_off 的暂停）
+            if payload.active {
+                crate::dbus::set_user_suspended(false);
+            }
             (
                 StatusCode::OK,
                 Json(ApiResponse::success_with_message(
@@ -974,7 +916,10 @@ pub async fn get_system_stats() -> impl IntoResponse {
 /// GET /api/location/cell-info - 获取基站定位参数
 /// 
 /// 返回格式化的基站定位参数，可用于调用第三方定位API（如Google Geolocation、OpenCellID等）
-pub async fn get_cell_location_info(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
+pub async fn get_cell_location_info(
+    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> impl IntoResponse {
     // 获取网络信息（MCC、MNC）
     let network_info = match get_network_info_data(&conn).await {
         Ok(info) => info,
@@ -1027,7 +972,7 @@ pub async fn get_cell_location_info(State(conn): State<Arc<Connection>>) -> impl
     };
 
     // 获取服务小区信息（TAC、CID）
-    let serving_cell = match get_serving_cell_info(&conn).await {
+    let serving_cell = match modem.serving_cell().await {
         Ok(cell) => cell,
         Err(e) => {
             return (
@@ -1042,9 +987,9 @@ pub async fn get_cell_location_info(State(conn): State<Arc<Connection>>) -> impl
 
     // 获取详细的小区信息（信号强度等）
     let tech = serving_cell.tech.as_str();
-    let cmd_config = match get_cell_command_config(tech) {
-        Some(config) => config,
-        None => {
+    let cells = match modem.cell_list(&serving_cell).await {
+        Ok(cells) => cells,
+        Err(ModemError::Unsupported(_)) => {
             // 如果不支持当前网络制式，返回基本信息（不含信号强度）
             let cell_info = if serving_cell.cell_id > 0 {
                 Some(CellLocationInfo {
@@ -1087,11 +1032,13 @@ pub async fn get_cell_location_info(State(conn): State<Arc<Connection>>) -> impl
                 )),
             );
         }
+        Err(_) => vec![],
     };
 
-    // 获取主小区和邻区详细信息
-    let serving_cell_detail = fetch_primary_cell(&conn, cmd_config.primary, tech).await.ok();
-    let neighbor_cells = fetch_neighbor_cells(&conn, cmd_config.neighbor, tech).await.unwrap_or_default();
+    // 拆分主小区和邻区详细信息
+    let mut cells = cells.into_iter();
+    let serving_cell_detail = cells.next();
+    let neighbor_cells: Vec<CellInfo> = cells.collect();
 
     // 构建主服务小区定位信息
     let cell_info = if serving_cell.cell_id > 0 {
@@ -1228,8 +1175,8 @@ pub async fn get_network_interfaces_info() -> impl IntoResponse {
 ///   }
 /// }
 /// ```
-pub async fn get_radio_mode_handler(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
-    match get_radio_mode(&conn).await {
+pub async fn get_radio_mode_handler(State(modem): State<Arc<dyn ModemBackend>>) -> impl IntoResponse {
+    match modem.radio_mode().await {
         Ok(data) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Success", data)),
@@ -1258,10 +1205,10 @@ pub async fn get_radio_mode_handler(State(conn): State<Arc<Connection>>) -> impl
 /// - lte: 仅 4G LTE
 /// - nr: 仅 5G NR
 pub async fn set_radio_mode_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(payload): Json<RadioModeRequest>,
 ) -> impl IntoResponse {
-    match set_radio_mode(&conn, payload.mode.clone()).await {
+    match modem.set_radio_mode(payload.mode.clone()).await {
         Ok(_) => {
             let mode_str = match payload.mode {
                 RadioMode::Auto => "4G/5G Auto",
@@ -1302,80 +1249,20 @@ pub async fn set_radio_mode_handler(
 ///   }
 /// }
 /// ```
-pub async fn get_band_lock_handler(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
-    // 读取 LTE 频段锁定状态
-    let lte_result = send_at_command(&conn, "AT+SPLBAND=0").await;
-    let (lte_fdd_mask, lte_tdd_mask, lte_raw) = match lte_result {
-        Ok(response) => {
-            let (fdd, tdd) = parse_splband_lte_response(&response);
-            (fdd, tdd, Some(response))
-        }
-        Err(e) => (0, 0, Some(format!("Error: {}", e))),
-    };
-
-    // 读取 NR 频段锁定状态
-    let nr_result = send_at_command(&conn, "AT+SPLBAND=3").await;
-    let (nr_fdd_mask, nr_tdd_mask, nr_raw) = match nr_result {
-        Ok(response) => {
-            let (fdd, tdd) = parse_splband_nr_response(&response);
-            (fdd, tdd, Some(response))
-        }
-        Err(e) => (0, 0, Some(format!("Error: {}", e))),
-    };
-
-    // UDX710 设备支持的全部频段掩码
-    // LTE: FDD=149 (B1+B3+B5+B8), TDD=320 (B39+B41)
-    // NR: FDD=517 (N1+N3+N28), TDD=912 (N41+N77+N78+N79)
-    const LTE_FDD_ALL: u16 = 149;
-    const LTE_TDD_ALL: u16 = 320;
-    const NR_FDD_ALL: u16 = 517;
-    const NR_TDD_ALL: u16 = 912;
-    
-    // 判断是否有频段锁定
-    // 如果返回的频段等于设备支持的全部频段，则认为"未锁定"（全部可用）
-    // 如果返回 0 或小于全部，则认为"已锁定"（限制了可用频段）
-    let lte_is_all_or_zero = (lte_fdd_mask == LTE_FDD_ALL && lte_tdd_mask == LTE_TDD_ALL) 
-                            || (lte_fdd_mask == 0 && lte_tdd_mask == 0);
-    let nr_is_all_or_zero = (nr_fdd_mask == NR_FDD_ALL && nr_tdd_mask == NR_TDD_ALL)
-                           || (nr_fdd_mask == 0 && nr_tdd_mask == 0);
-    let locked = !(lte_is_all_or_zero && nr_is_all_or_zero);
-    
-    // 将位掩码转换为频段号列表
-    // 未锁定时返回空数组（前端显示为"未锁定模式"）
-    // 已锁定时返回具体频段列表（前端显示为"自定义锁定模式"）
-    let (lte_fdd_bands, lte_tdd_bands, nr_fdd_bands, nr_tdd_bands) = if !locked {
-        // 未锁定：返回空数组
-        (vec![], vec![], vec![], vec![])
-    } else {
-        // 已锁定：返回具体频段
-        (
-            bitmask_to_bands(lte_fdd_mask, 1),    // LTE FDD: B1-B16
-            bitmask_to_bands(lte_tdd_mask, 33),   // LTE TDD: B33-B48
-            bitmask_to_bands(nr_fdd_mask, 100),   // NR FDD: 展锐特殊映射
-            bitmask_to_bands(nr_tdd_mask, 41),    // NR TDD: 展锐特殊映射
-        )
-    };
-
-    // 构建调试信息
-    let raw_response = Some(format!(
-        "LTE(fdd={},tdd={}): {}\nNR(fdd={},tdd={}): {}",
-        lte_fdd_mask, lte_tdd_mask, lte_raw.unwrap_or_default().trim(),
-        nr_fdd_mask, nr_tdd_mask, nr_raw.unwrap_or_default().trim()
-    ));
-
-    let status = BandLockStatus {
-        locked,
-        lte_fdd_bands,
-        lte_tdd_bands,
-        nr_fdd_bands,
-        nr_tdd_bands,
-        raw_response,
-    };
-
-    (
-        StatusCode::OK,
-        Json(ApiResponse::success_with_message("Success", status)),
-    )
+pub async fn get_band_lock_handler(State(modem): State<Arc<dyn ModemBackend>>) -> impl IntoResponse {
+    match modem.band_lock_status().await {
+        Ok(status) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Success", status)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::<BandLockStatus>::error(format!(
+                "Failed to get band lock status: {}",
+                e
+            ))),
+        ),
+    }
 }
 
 /// POST /api/system/reboot - 系统重启
@@ -1435,121 +1322,30 @@ pub async fn system_reboot(
 /// - LTE FDD: B1-B16, TDD: B33-B48
 /// - NR FDD: N1-N16, TDD: N41-N56 (实际支持 N41-N79)
 pub async fn set_band_lock_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(payload): Json<BandLockRequest>,
 ) -> impl IntoResponse {
-    // LTE 频段锁定
-    let lte_fdd_mask = bands_to_bitmask(&payload.lte_fdd_bands, 1);
-    let lte_tdd_mask = bands_to_bitmask(&payload.lte_tdd_bands, 33);
-    
-    if lte_fdd_mask != 0 || lte_tdd_mask != 0 {
-        let lte_cmd = build_splband_lte_command(lte_fdd_mask, lte_tdd_mask);
-        if let Err(e) = send_at_command(&conn, &lte_cmd).await {
-            return (
-                StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "Failed to set LTE band lock: {}",
-                    e
-                ))),
-            );
-        }
-    }
-
-    // NR 频段锁定
-    let nr_fdd_mask = bands_to_bitmask(&payload.nr_fdd_bands, 100); // NR FDD: 展锐特殊映射
-    let nr_tdd_mask = bands_to_bitmask(&payload.nr_tdd_bands, 41);  // NR TDD: 展锐特殊映射
-    
-    if nr_fdd_mask != 0 || nr_tdd_mask != 0 {
-        let nr_cmd = build_splband_nr_command(nr_fdd_mask, nr_tdd_mask);
-        if let Err(e) = send_at_command(&conn, &nr_cmd).await {
+    let change = match modem.set_band_lock(&payload).await {
+        Ok(change) => change,
+        Err(e) => {
             return (
                 StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "Failed to set NR band lock: {}",
-                    e
-                ))),
+                Json(ApiResponse::<serde_json::Value>::error(e.to_string())),
             );
         }
-    }
+    };
 
-    // 如果所有频段都为空，则解除锁定
-    if payload.lte_fdd_bands.is_empty()
-        && payload.lte_tdd_bands.is_empty()
-        && payload.nr_fdd_bands.is_empty()
-        && payload.nr_tdd_bands.is_empty()
-    {
-        let mut lte_unlocked = false;
-        let mut nr_unlocked = false;
-        
-        // 先读取当前 LTE 锁定状态
-        let lte_result = send_at_command(&conn, "AT+SPLBAND=0").await;
-        if let Ok(lte_response) = lte_result {
-            let (lte_fdd_mask, lte_tdd_mask) = parse_splband_lte_response(&lte_response);
-            
-            // 只有当前有 LTE 锁定时才执行解锁
-            if lte_fdd_mask != 0 || lte_tdd_mask != 0 {
-                // 格式: AT+SPLBAND=1,0,<TDD>,0,<FDD>,0 (6 参数)
-                if let Err(e) = send_at_command(&conn, "AT+SPLBAND=1,0,0,0,0,0").await {
-                    return (
-                        StatusCode::OK,
-                        Json(ApiResponse::<serde_json::Value>::error(format!(
-                            "Failed to unlock LTE bands: {}",
-                            e
-                        ))),
-                    );
-                }
-                lte_unlocked = true;
-            }
-        }
-        
-        // 先读取当前 NR 锁定状态
-        let nr_result = send_at_command(&conn, "AT+SPLBAND=3").await;
-        if let Ok(nr_response) = nr_result {
-            let (nr_fdd_mask, nr_tdd_mask) = parse_splband_nr_response(&nr_response);
-            
-            // 只有当前有 NR 锁定时才执行解锁
-            if nr_fdd_mask != 0 || nr_tdd_mask != 0 {
-                if let Err(e) = send_at_command(&conn, "AT+SPLBAND=2,0,0,0,0").await {
-                    return (
-                        StatusCode::OK,
-                        Json(ApiResponse::<serde_json::Value>::error(format!(
-                            "Failed to unlock NR bands: {}",
-                            e
-                        ))),
-                    );
-                }
-                nr_unlocked = true;
-            }
+    // 根据实际执行的操作返回友好的提示信息
+    let message = if payload.is_unlock() {
+        match (change.lte, change.nr) {
+            (true, true) => "已解除所有频段锁定（LTE + NR）",
+            (true, false) => "已解除 LTE 频段锁定（NR 未锁定）",
+            (false, true) => "已解除 NR 频段锁定（LTE 未锁定）",
+            (false, false) => "当前没有锁定的频段，无需解锁",
         }
-        
-        // 根据实际执行的解锁操作返回友好的提示信息
-        let message = if lte_unlocked || nr_unlocked {
-            if lte_unlocked && nr_unlocked {
-                "已解除所有频段锁定（LTE + NR）"
-            } else if lte_unlocked {
-                "已解除 LTE 频段锁定（NR 未锁定）"
-            } else {
-                "已解除 NR 频段锁定（LTE 未锁定）"
-            }
-        } else {
-            "当前没有锁定的频段，无需解锁"
-        };
-        
-        return (
-            StatusCode::OK,
-            Json(ApiResponse::success_with_message(
-                message,
-                json!({}),
-            )),
-        );
-    }
-
-    // 生成友好的提示信息
-    let has_lte = lte_fdd_mask != 0 || lte_tdd_mask != 0;
-    let has_nr = nr_fdd_mask != 0 || nr_tdd_mask != 0;
-    let message = if has_lte && has_nr {
+    } else if change.lte && change.nr {
         "已同时锁定 LTE 和 NR 频段"
-    } else if has_lte {
+    } else if change.lte {
         "LTE 频段锁定已应用"
     } else {
         "NR 频段锁定已应用"
@@ -1565,77 +1361,13 @@ pub async fn set_band_lock_handler(
 }
 
 // ============ 小区锁定 API ============
-// 使用 AT+SPFORCEFRQ 指令实现小区锁定
-// 发现来源：通过 dbus-monitor 监听实际锁频操作
-
-use crate::models::{CellLockStatusResponse, CellLockRatStatus, CellLockRequest, CellUnlockRequest};
+// 具体指令由调制解调器后端实现（见 `ModemBackend::lock_cell` 等）
 
-/// SPFORCEFRQ 网络类型常量
-const FORCEFRQ_TYPE_LTE: u8 = 12;
-const FORCEFRQ_TYPE_NR: u8 = 16;
-
-/// 获取 RAT 类型名称
-fn get_rat_name(rat: u8) -> String {
-    match rat {
-        12 => "LTE".to_string(),
-        16 => "NR".to_string(),
-        _ => format!("Unknown({})", rat),
-    }
-}
-
-/// 解析 AT+SPFORCEFRQ 查询响应
-/// 
-/// 响应格式:
-/// - 未锁定: +SPFORCEFRQ: 16,3
-/// - 已锁定: +SPFORCEFRQ: 16,3,633984,597
-fn parse_spforcefrq_query_response(response: &str, rat: u8) -> CellLockRatStatus {
-    let prefix = format!("+SPFORCEFRQ: {},3", rat);
-    
-    if let Some(line) = response.lines().find(|l| l.starts_with(&prefix)) {
-        let data = line.strip_prefix(&format!("+SPFORCEFRQ: {},3", rat)).unwrap_or("");
-        let data = data.trim_start_matches(',');
-        
-        if data.is_empty() {
-            // 未锁定
-            CellLockRatStatus {
-                rat,
-                rat_name: get_rat_name(rat),
-                enabled: false,
-                lock_type: 0,
-                pci: None,
-                arfcn: None,
-            }
-        } else {
-            // 已锁定，解析 arfcn,pci
-            let parts: Vec<&str> = data.split(',').collect();
-            let arfcn = parts.first().and_then(|s| s.trim().parse::<u32>().ok());
-            let pci = parts.get(1).and_then(|s| s.trim().parse::<u16>().ok());
-            
-            CellLockRatStatus {
-                rat,
-                rat_name: get_rat_name(rat),
-                enabled: arfcn.is_some() && pci.is_some(),
-                lock_type: 3,
-                pci,
-                arfcn,
-            }
-        }
-    } else {
-        // 解析失败，返回未锁定状态
-        CellLockRatStatus {
-            rat,
-            rat_name: get_rat_name(rat),
-            enabled: false,
-            lock_type: 0,
-            pci: None,
-            arfcn: None,
-        }
-    }
-}
+use crate::models::{CellLockStatusResponse, CellLockRequest, CellUnlockRequest};
 
 /// GET /api/cell-lock - 获取小区锁定状态
 /// 
-/// 使用 AT+SPFORCEFRQ=<type>,3 查询锁定状态
+/// 返回 NR 和 LTE 各自的锁定状态
 /// 
 /// ## 响应示例
 /// ```json
@@ -1650,69 +1382,25 @@ fn parse_spforcefrq_query_response(response: &str, rat: u8) -> CellLockRatStatus
 ///   }
 /// }
 /// ```
-pub async fn get_cell_lock_handler(State(conn): State<Arc<Connection>>) -> impl IntoResponse {
-    let mut rat_status = Vec::new();
-    let mut any_locked = false;
-    
-    // 查询 NR 锁定状态
-    let nr_cmd = format!("AT+SPFORCEFRQ={},3", FORCEFRQ_TYPE_NR);
-    if let Ok(response) = send_at_command(&conn, &nr_cmd).await {
-        let status = parse_spforcefrq_query_response(&response, FORCEFRQ_TYPE_NR);
-        if status.enabled {
-            any_locked = true;
-        }
-        rat_status.push(status);
-    } else {
-        rat_status.push(CellLockRatStatus {
-            rat: FORCEFRQ_TYPE_NR,
-            rat_name: "NR".to_string(),
-            enabled: false,
-            lock_type: 0,
-            pci: None,
-            arfcn: None,
-        });
-    }
-    
-    // 查询 LTE 锁定状态
-    let lte_cmd = format!("AT+SPFORCEFRQ={},3", FORCEFRQ_TYPE_LTE);
-    if let Ok(response) = send_at_command(&conn, &lte_cmd).await {
-        let status = parse_spforcefrq_query_response(&response, FORCEFRQ_TYPE_LTE);
-        if status.enabled {
-            any_locked = true;
-        }
-        rat_status.push(status);
-    } else {
-        rat_status.push(CellLockRatStatus {
-            rat: FORCEFRQ_TYPE_LTE,
-            rat_name: "LTE".to_string(),
-            enabled: false,
-            lock_type: 0,
-            pci: None,
-            arfcn: None,
-        });
+pub async fn get_cell_lock_handler(State(modem): State<Arc<dyn ModemBackend>>) -> impl IntoResponse {
+    match modem.cell_lock_status().await {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Success", response)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::<CellLockStatusResponse>::error(format!(
+                "Failed to get cell lock status: {}",
+                e
+            ))),
+        ),
     }
-    
-    let response = CellLockStatusResponse {
-        rat_status,
-        any_locked,
-    };
-    
-    (
-        StatusCode::OK,
-        Json(ApiResponse::success_with_message("Success", response)),
-    )
 }
 
 /// POST /api/cell-lock - 设置小区锁定
 /// 
-/// 使用 AT+SPFORCEFRQ 指令锁定到指定小区
-/// 
-/// ## 锁定流程
-/// 1. AT+SFUN=5 - 进入工程模式
-/// 2. AT+SPFORCEFRQ=16,0 - 清空 NR 锁定
-/// 3. AT+SPFORCEFRQ=12,0 - 清空 LTE 锁定
-/// 4. AT+SPFORCEFRQ=<type>,2,<arfcn>,<pci> - 设置锁定
-/// 5. AT+SFUN=4 - 恢复正常模式
+/// `enable=true` 时锁定到指定小区（需提供 ARFCN 和 PCI），否则解除该制式的锁定
 /// 
 /// ## 请求示例
 /// ```json
@@ -1724,21 +1412,11 @@ pub async fn get_cell_lock_handler(State(conn): State<Arc<Connection>>) -> impl
 /// }
 /// ```
 pub async fn set_cell_lock_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(payload): Json<CellLockRequest>,
 ) -> impl IntoResponse {
-    // 确定网络类型
-    let forcefrq_type = if payload.rat == FORCEFRQ_TYPE_LTE || payload.rat == FORCEFRQ_TYPE_NR {
-        payload.rat
-    } else {
-        // 根据 rat 值推断类型
-        match payload.rat {
-            1 | 2 => FORCEFRQ_TYPE_LTE,  // LTE FDD/TDD
-            5 | 6 | 7 => FORCEFRQ_TYPE_NR, // NR SA/NSA
-            _ => FORCEFRQ_TYPE_NR,
-        }
-    };
-    
+    let rat = CellRat::from_api(payload.rat);
+
     if payload.enable {
         // 锁定小区需要 ARFCN 和 PCI
         let (arfcn, pci) = match (payload.arfcn, payload.pci) {
@@ -1752,113 +1430,42 @@ pub async fn set_cell_lock_handler(
                 );
             }
         };
-        
-        // 执行锁定流程
-        let steps = vec![
-            ("AT+SFUN=5", "进入工程模式"),
-            ("AT+SPFORCEFRQ=16,0", "清空 NR 锁定"),
-            ("AT+SPFORCEFRQ=12,0", "清空 LTE 锁定"),
-        ];
-        
-        for (cmd, desc) in &steps {
-            if let Err(e) = send_at_command(&conn, cmd).await {
-                // 恢复正常模式
-                let _ = send_at_command(&conn, "AT+SFUN=4").await;
-                return (
-                    StatusCode::OK,
-                    Json(ApiResponse::<serde_json::Value>::error(format!(
-                        "{}失败: {}",
-                        desc, e
-                    ))),
-                );
-            }
-        }
-        
-        // 设置锁定
-        let lock_cmd = format!("AT+SPFORCEFRQ={},2,{},{}", forcefrq_type, arfcn, pci);
-        if let Err(e) = send_at_command(&conn, &lock_cmd).await {
-            // 恢复正常模式
-            let _ = send_at_command(&conn, "AT+SFUN=4").await;
-            return (
+
+        match modem.lock_cell(rat, arfcn, pci).await {
+            Ok(()) => (
                 StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "设置锁定失败: {}",
-                    e
-                ))),
-            );
-        }
-        
-        // 恢复正常模式
-        if let Err(e) = send_at_command(&conn, "AT+SFUN=4").await {
-            return (
+                Json(ApiResponse::success_with_message(
+                    format!("{} 小区锁定已设置 (ARFCN={}, PCI={})", rat.name(), arfcn, pci),
+                    json!({
+                        "locked": true,
+                        "tech": rat.name(),
+                        "arfcn": arfcn,
+                        "pci": pci
+                    }),
+                )),
+            ),
+            Err(e) => (
                 StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "恢复正常模式失败: {}",
-                    e
-                ))),
-            );
+                Json(ApiResponse::<serde_json::Value>::error(e.to_string())),
+            ),
         }
-        
-        (
-            StatusCode::OK,
-            Json(ApiResponse::success_with_message(
-                format!("{} 小区锁定已设置 (ARFCN={}, PCI={})", get_rat_name(forcefrq_type), arfcn, pci),
-                json!({
-                    "locked": true,
-                    "tech": get_rat_name(forcefrq_type),
-                    "arfcn": arfcn,
-                    "pci": pci
-                }),
-            )),
-        )
     } else {
-        // 解锁：清空指定类型的锁定
-        // 1. 进入工程模式
-        if let Err(e) = send_at_command(&conn, "AT+SFUN=5").await {
-            return (
-                StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "进入工程模式失败: {}",
-                    e
-                ))),
-            );
-        }
-        
-        // 2. 清空锁定
-        let clear_cmd = format!("AT+SPFORCEFRQ={},0", forcefrq_type);
-        if let Err(e) = send_at_command(&conn, &clear_cmd).await {
-            // 恢复正常模式
-            let _ = send_at_command(&conn, "AT+SFUN=4").await;
-            return (
+        match modem.unlock_cell(rat).await {
+            Ok(()) => (
                 StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "清空锁定失败: {}",
-                    e
-                ))),
-            );
-        }
-        
-        // 3. 恢复正常模式
-        if let Err(e) = send_at_command(&conn, "AT+SFUN=4").await {
-            return (
+                Json(ApiResponse::success_with_message(
+                    format!("{} 小区锁定已解除", rat.name()),
+                    json!({
+                        "locked": false,
+                        "tech": rat.name()
+                    }),
+                )),
+            ),
+            Err(e) => (
                 StatusCode::OK,
-                Json(ApiResponse::<serde_json::Value>::error(format!(
-                    "恢复正常模式失败: {}",
-                    e
-                ))),
-            );
+                Json(ApiResponse::<serde_json::Value>::error(e.to_string())),
+            ),
         }
-        
-        (
-            StatusCode::OK,
-            Json(ApiResponse::success_with_message(
-                format!("{} 小区锁定已解除", get_rat_name(forcefrq_type)),
-                json!({
-                    "locked": false,
-                    "tech": get_rat_name(forcefrq_type)
-                }),
-            )),
-        )
     }
 }
 
@@ -1866,53 +1473,27 @@ pub async fn set_cell_lock_handler(
 /// 
 /// 清除 NR 和 LTE 的小区锁定
 pub async fn unlock_all_cells_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(_payload): Json<CellUnlockRequest>,
 ) -> impl IntoResponse {
-    // 完整的解锁流程
-    let steps = vec![
-        ("AT+SFUN=5", "进入工程模式"),
-        ("AT+SPFORCEFRQ=16,0", "清空 NR 锁定"),
-        ("AT+SPFORCEFRQ=12,0", "清空 LTE 锁定"),
-        ("AT+SFUN=4", "恢复正常模式"),
-    ];
-    
-    let mut success_steps = Vec::new();
-    let mut errors = Vec::new();
-    
-    for (cmd, desc) in &steps {
-        match send_at_command(&conn, cmd).await {
-            Ok(_) => success_steps.push(*desc),
-            Err(e) => {
-                errors.push(format!("{}: {}", desc, e));
-                // 尝试恢复正常模式
-                if !cmd.contains("SFUN=4") {
-                    let _ = send_at_command(&conn, "AT+SFUN=4").await;
-                }
-                break;
-            }
-        }
-    }
-    
-    if errors.is_empty() {
-        (
+    match modem.unlock_all_cells().await {
+        Ok(steps) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
                 "已解除所有小区锁定 (NR + LTE)",
                 json!({
                     "success": true,
-                    "steps": success_steps
+                    "steps": steps
                 }),
             )),
-        )
-    } else {
-        (
+        ),
+        Err(e) => (
             StatusCode::OK,
             Json(ApiResponse::<serde_json::Value>::error(format!(
                 "解锁失败: {}",
-                errors.join("; ")
+                e
             ))),
-        )
+        ),
     }
 }
 
@@ -1922,10 +1503,9 @@ use crate::db::Database;
 
 /// GET /api/calls - 获取当前通话列表
 pub async fn get_calls_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
 ) -> (StatusCode, Json<ApiResponse<CallListResponse>>) {
-    // 获取 VoiceCallManager 接口下的所有通话
-    match crate::dbus::get_active_calls(&conn).await {
+    match modem.calls().await {
         Ok(calls) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Success", CallListResponse { calls })),
@@ -1940,10 +1520,10 @@ pub async fn get_calls_handler(
 
 /// POST /api/call/dial - 拨打电话
 pub async fn dial_call_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(req): Json<MakeCallRequest>,
 ) -> (StatusCode, Json<ApiResponse<CallInfo>>) {
-    match crate::dbus::dial_call(&conn, &req.phone_number).await {
+    match modem.dial(&req.phone_number).await {
         Ok(call_info) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Call initiated", call_info)),
@@ -1957,10 +1537,10 @@ pub async fn dial_call_handler(
 
 /// POST /api/call/hangup - 挂断电话
 pub async fn hangup_call_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(req): Json<HangupCallRequest>,
 ) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    match crate::dbus::hangup_call(&conn, &req.path).await {
+    match modem.hangup(&req.path).await {
         Ok(_) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Call ended", json!({}))),
@@ -1974,9 +1554,9 @@ pub async fn hangup_call_handler(
 
 /// POST /api/call/hangup-all - 挂断所有电话
 pub async fn hangup_all_calls_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
 ) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    match crate::dbus::hangup_all_calls(&conn).await {
+    match modem.hangup_all().await {
         Ok(count) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
@@ -1993,10 +1573,10 @@ pub async fn hangup_all_calls_handler(
 
 /// POST /api/call/answer - 接听来电
 pub async fn answer_call_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(req): Json<HangupCallRequest>, // 复用结构，只需要 path
 ) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    match crate::dbus::answer_call(&conn, &req.path).await {
+    match modem.answer(&req.path).await {
         Ok(_) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message("Call answered", json!({}))),
@@ -2008,62 +1588,189 @@ pub async fn answer_call_handler(
     }
 }
 
-// ============ 短信相关 API ============
-
-/// POST /api/sms/send - 发送短信
-pub async fn send_sms_handler(
-    State((conn, db)): State<(Arc<Connection>, Arc<Database>)>,
-    Json(req): Json<SendSmsRequest>,
-) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    // 发送短信
-    match crate::dbus::send_sms(&conn, &req.phone_number, &req.content).await {
-        Ok(message_path) => {
-            // 存储到数据库
-            match db.insert_sms("outgoing", &req.phone_number, &req.content, "sent", None) {
-                Ok(id) => (
-                    StatusCode::OK,
-                    Json(ApiResponse::success_with_message(
-                        "SMS sent successfully",
-                        json!({
-                            "message_path": message_path,
-                            "db_id": id,
-                        }),
-                    )),
-                ),
-                Err(_e) => {
-                    (
-                        StatusCode::OK,
-                        Json(ApiResponse::success_with_message(
-                            "SMS sent but failed to save to database",
-                            json!({ "message_path": message_path }),
-                        )),
-                    )
-                }
-            }
-        }
+/// 补充业务操作的统一返回
+fn call_control_reply<T: serde::Serialize + Default>(
+    result: Result<T, ModemError>,
+    message: &str,
+    action: &str,
+) -> (StatusCode, Json<ApiResponse<T>>) {
+    match result {
+        Ok(data) => (StatusCode::OK, Json(ApiResponse::success_with_message(message, data))),
         Err(e) => (
             StatusCode::OK,
-            Json(ApiResponse::error(format!("Failed to send SMS: {}", e))),
+            Json(ApiResponse::error(format!("Failed to {}: {}", action, e))),
         ),
     }
 }
 
-/// GET /api/sms/list - 获取短信列表
-pub async fn get_sms_list_handler(
-    State(db): State<Arc<Database>>,
-    axum::extract::Query(req): axum::extract::Query<SmsListRequest>,
-) -> (StatusCode, Json<ApiResponse<Vec<crate::db::SmsMessage>>>) {
-    match db.get_sms_messages(req.limit, req.offset) {
-        Ok(messages) => (
-            StatusCode::OK,
-            Json(ApiResponse::success_with_message(
-                format!("Retrieved {} messages", messages.len()),
-                messages,
-            )),
-        ),
-        Err(e) => (
-            StatusCode::OK,
-            Json(ApiResponse::error(format!("Failed to get messages: {}", e))),
+/// POST /api/call/swap - 交换活动通话和保持通话
+pub async fn swap_calls_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    call_control_reply(modem.swap_calls().await.map(|_| json!({})), "Calls swapped", "swap calls")
+}
+
+/// POST /api/call/release-and-answer - 挂断活动通话并接听等待中的来电
+pub async fn release_and_answer_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    call_control_reply(
+        modem.release_and_answer().await.map(|_| json!({})),
+        "Active call released, waiting call answered",
+        "release and answer",
+    )
+}
+
+/// POST /api/call/hold-and-answer - 保持活动通话并接听等待中的来电
+pub async fn hold_and_answer_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    call_control_reply(
+        modem.hold_and_answer().await.map(|_| json!({})),
+        "Active call held, waiting call answered",
+        "hold and answer",
+    )
+}
+
+/// POST /api/call/multiparty - 合并活动通话和保持通话为多方通话
+pub async fn create_multiparty_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> (StatusCode, Json<ApiResponse<MultipartyResponse>>) {
+    call_control_reply(
+        modem.create_multiparty().await.map(|calls| MultipartyResponse { calls }),
+        "Multiparty call created",
+        "create multiparty call",
+    )
+}
+
+/// POST /api/call/multiparty/private-chat - 从多方通话中分离出一路私聊
+pub async fn private_chat_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+    Json(req): Json<HangupCallRequest>, // 复用结构，只需要 path
+) -> (StatusCode, Json<ApiResponse<MultipartyResponse>>) {
+    call_control_reply(
+        modem.private_chat(&req.path).await.map(|calls| MultipartyResponse { calls }),
+        "Private chat started",
+        "start private chat",
+    )
+}
+
+/// POST /api/call/multiparty/hangup - 挂断多方通话
+pub async fn hangup_multiparty_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    call_control_reply(
+        modem.hangup_multiparty().await.map(|_| json!({})),
+        "Multiparty call ended",
+        "hangup multiparty call",
+    )
+}
+
+/// DTMF 按键序列的最大长度
+const MAX_DTMF_TONES: usize = 64;
+
+/// 校验 DTMF 按键序列（0-9、*、#、A-D，`p` 表示暂停），返回规范化后的序列
+fn validate_dtmf_tones(tones: &str) -> Result<String, String> {
+    let tones: String = tones.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    if tones.is_empty() {
+        return Err("Tones are empty".to_string());
+    }
+    if tones.len() > MAX_DTMF_TONES {
+        return Err(format!("At most {} tones can be sent at once", MAX_DTMF_TONES));
+    }
+    if let Some(c) = tones.chars().find(|c| !matches!(c, '0'..='9' | '*' | '#' | 'A'..='D' | 'P')) {
+        return Err(format!("Invalid DTMF tone: {}", c));
+    }
+    Ok(tones.replace('P', "p"))
+}
+
+/// POST /api/call/tones - 向活动通话发送 DTMF 按键音
+pub async fn send_tones_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+    Json(req): Json<SendTonesRequest>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    let tones = match validate_dtmf_tones(&req.tones) {
+        Ok(tones) => tones,
+        Err(e) => return (StatusCode::OK, Json(ApiResponse::error(e))),
+    };
+
+    call_control_reply(
+        modem.send_tones(&tones).await.map(|_| json!({ "tones": tones })),
+        "Tones sent",
+        "send tones",
+    )
+}
+
+/// POST /api/call/deflect - 将来电转接到其他号码
+pub async fn deflect_call_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+    Json(req): Json<DeflectCallRequest>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    let phone_number = req.phone_number.trim();
+    if phone_number.is_empty() {
+        return (StatusCode::OK, Json(ApiResponse::error("Phone number is empty")));
+    }
+
+    call_control_reply(
+        modem.deflect(&req.path, phone_number).await.map(|_| json!({})),
+        "Call deflected",
+        "deflect call",
+    )
+}
+
+// ============ 短信相关 API ============
+
+/// POST /api/sms/send - 发送短信
+pub async fn send_sms_handler(
+    State(modem): State<Arc<dyn ModemBackend>>,
+    State(db): State<Arc<Database>>,
+    Json(req): Json<SendSmsRequest>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    // 发送短信并存储到数据库
+    match crate::sms_queue::send_and_record(modem.as_ref(), &db, &req.phone_number, &req.content).await {
+        Ok(sent) => match sent.db_id {
+            Some(id) => (
+                StatusCode::OK,
+                Json(ApiResponse::success_with_message(
+                    "SMS sent successfully",
+                    json!({
+                        "message_path": sent.message_path,
+                        "db_id": id,
+                        "status": sent.status,
+                    }),
+                )),
+            ),
+            None => (
+                StatusCode::OK,
+                Json(ApiResponse::success_with_message(
+                    "SMS sent but failed to save to database",
+                    json!({ "message_path": sent.message_path }),
+                )),
+            ),
+        },
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to send SMS: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/list - 获取短信列表
+pub async fn get_sms_list_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Query(req): axum::extract::Query<SmsListRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::SmsMessage>>>) {
+    match db.get_sms_messages(req.limit, req.offset) {
+        Ok(messages) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Retrieved {} messages", messages.len()),
+                messages,
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get messages: {}", e))),
         ),
     }
 }
@@ -2110,6 +1817,7 @@ impl Default for crate::db::SmsStats {
             total: 0,
             incoming: 0,
             outgoing: 0,
+            unread: 0,
         }
     }
 }
@@ -2130,9 +1838,188 @@ pub async fn clear_sms_handler(
     }
 }
 
+/// GET /api/sms/threads - 获取短信会话列表
+pub async fn list_sms_threads_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Query(req): axum::extract::Query<SmsThreadListRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::SmsThread>>>) {
+    match db.list_sms_threads(req.archived, req.limit, req.offset) {
+        Ok(threads) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Retrieved {} threads", threads.len()),
+                threads,
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get threads: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/threads/{id} - 获取会话及其中的短信
+pub async fn get_sms_thread_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    axum::extract::Query(req): axum::extract::Query<SmsListRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<SmsThreadResponse>>>) {
+    let result = db.get_sms_thread(id).and_then(|thread| match thread {
+        Some(thread) => Ok(Some(SmsThreadResponse {
+            thread,
+            messages: db.get_sms_thread_messages(id, req.limit, req.offset)?,
+        })),
+        None => Ok(None),
+    });
+
+    match result {
+        Ok(Some(response)) => (StatusCode::OK, Json(ApiResponse::success_with_message("Success", Some(response)))),
+        Ok(None) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Thread {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get thread: {}", e))),
+        ),
+    }
+}
+
+/// DELETE /api/sms/threads/{id} - 删除会话及其中的所有短信
+pub async fn delete_sms_thread_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match db.delete_sms_thread(id) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Thread deleted", json!({}))),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Thread {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to delete thread: {}", e))),
+        ),
+    }
+}
+
+/// 返回修改后的会话摘要
+fn sms_thread_reply(
+    db: &Database,
+    id: i64,
+    result: rusqlite::Result<bool>,
+    message: &str,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsThread>>>) {
+    match result {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(message, db.get_sms_thread(id).ok().flatten())),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Thread {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update thread: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/sms/threads/{id}/read - 标记会话为已读/未读
+pub async fn mark_sms_thread_read_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<SmsReadRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsThread>>>) {
+    let message = if req.read { "Thread marked as read" } else { "Thread marked as unread" };
+    sms_thread_reply(&db, id, db.set_sms_thread_read(id, req.read), message)
+}
+
+/// POST /api/sms/threads/{id}/archive - 归档/取消归档会话
+pub async fn archive_sms_thread_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<SmsArchiveRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsThread>>>) {
+    let message = if req.archived { "Thread archived" } else { "Thread unarchived" };
+    sms_thread_reply(&db, id, db.set_sms_thread_archived(id, req.archived), message)
+}
+
+/// POST /api/sms/messages/{id}/read - 标记单条短信为已读/未读
+pub async fn mark_sms_read_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<SmsReadRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsMessage>>>) {
+    match db.set_sms_read(id, req.read) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Message updated", db.get_sms(id).ok().flatten())),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Message {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update message: {}", e))),
+        ),
+    }
+}
+
+/// DELETE /api/sms/messages/{id} - 删除单条短信
+pub async fn delete_sms_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match db.delete_sms(id) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Message deleted", json!({}))),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Message {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to delete message: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/search - 全文搜索短信内容
+pub async fn search_sms_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Query(req): axum::extract::Query<SmsSearchRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::SmsMessage>>>) {
+    let query = req.q.trim();
+    if query.is_empty() {
+        return (StatusCode::OK, Json(ApiResponse::error("Search query is empty")));
+    }
+
+    match db.search_sms(query, req.thread_id, req.limit, req.offset) {
+        Ok(messages) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Found {} messages", messages.len()),
+                messages,
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to search messages: {}", e))),
+        ),
+    }
+}
+
 // ============ 新增功能 API ============
 
-/// GET /api/device/imeisv - 获取 IMEISV（软件版本号）
+/// GET /api/device/imeisv- 获取 IMEISV（软件版本号）
 pub async fn get_imeisv_handler(
     State(conn): State<Arc<Connection>>,
 ) -> (StatusCode, Json<ApiResponse<crate::models::ImeisvResponse>>) {
@@ -2417,15 +2304,215 @@ pub async fn switch_sim_slot_handler(
     }
 }
 
+// ============ SIM PIN 管理 ============
+
+/// ofono 支持的 PIN/PUK 类型
+const SIM_PIN_TYPES: &[&str] = &[
+    "pin", "pin2", "puk", "puk2", "phone", "firstphone", "firstphonepuk", "network", "networkpuk",
+    "netsub", "netsubpuk", "service", "servicepuk", "corp", "corppuk",
+];
+
+/// 校验 PIN 类型和号码：PIN 为 4-8 位数字，PUK 为 8 位数字，其他锁为 4-16 位数字
+fn validate_sim_code(pin_type: &str, code: &str, label: &str) -> Result<(), String> {
+    if !SIM_PIN_TYPES.contains(&pin_type) {
+        return Err(format!("Unknown PIN type: {}", pin_type));
+    }
+    let (min, max) = match pin_type {
+        "pin" | "pin2" => (4, 8),
+        "puk" | "puk2" => (8, 8),
+        _ => (4, 16),
+    };
+    if code.len() < min || code.len() > max || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(if min == max {
+            format!("{} must be {} digits", label, min)
+        } else {
+            format!("{} must be {}-{} digits", label, min, max)
+        });
+    }
+    Ok(())
+}
+
+/// 操作成功时返回最新的 SIM 信息，失败时在错误中附带剩余尝试次数
+async fn sim_pin_reply(
+    conn: &Connection,
+    result: zbus::Result<()>,
+    success: &str,
+    failure: &str,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    let info = get_sim_info_data(conn).await;
+    match (result, info) {
+        (Ok(()), Ok(info)) => (StatusCode::OK, Json(ApiResponse::success_with_message(success, info))),
+        (Ok(()), Err(e)) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("{}, but failed to read SIM info: {}", success, e))),
+        ),
+        (Err(e), Ok(info)) if !info.retries.is_empty() => {
+            let mut retries: Vec<String> = info.retries.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            retries.sort();
+            (
+                StatusCode::OK,
+                Json(ApiResponse::error(format!(
+                    "{}: {} (PIN required: {}, retries left: {})",
+                    failure,
+                    e,
+                    info.pin_required,
+                    retries.join(", ")
+                ))),
+            )
+        }
+        (Err(e), _) => (StatusCode::OK, Json(ApiResponse::error(format!("{}: {}", failure, e)))),
+    }
+}
+
+/// 修改或用 PUK 重置 PIN 后同步自动输入的 PIN
+fn sync_saved_pin(config_manager: &ConfigManager, new_pin: &str) {
+    let mut sim_pin = config_manager.get_sim_pin();
+    if sim_pin.pin.is_empty() || sim_pin.pin == new_pin {
+        return;
+    }
+    sim_pin.pin = new_pin.to_string();
+    if let Err(e) = config_manager.set_sim_pin(sim_pin) {
+        tracing::warn!(error = %e, "Failed to update saved SIM PIN");
+    }
+}
+
+/// POST /api/sim/pin/enter - 输入 PIN（SIM 锁定时）
+pub async fn enter_sim_pin_handler(
+    State(conn): State<Arc<Connection>>,
+    Json(req): Json<SimPinRequest>,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    if let Err(e) = validate_sim_code(&req.pin_type, &req.pin, "PIN") {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    let result = crate::dbus::sim_enter_pin(&conn, &req.pin_type, &req.pin).await;
+    sim_pin_reply(&conn, result, "PIN accepted", "Failed to enter PIN").await
+}
+
+/// POST /api/sim/pin/reset - 使用 PUK 解锁并设置新 PIN
+pub async fn reset_sim_pin_handler(
+    State(conn): State<Arc<Connection>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(req): Json<SimPukRequest>,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    let pin_type = if req.puk_type == "puk2" { "pin2" } else { "pin" };
+    let valid = validate_sim_code(&req.puk_type, &req.puk, "PUK")
+        .and_then(|_| validate_sim_code(pin_type, &req.new_pin, "New PIN"));
+    if let Err(e) = valid {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    let result = crate::dbus::sim_reset_pin(&conn, &req.puk_type, &req.puk, &req.new_pin).await;
+    if result.is_ok() && req.puk_type == "puk" {
+        sync_saved_pin(&config_manager, &req.new_pin);
+    }
+    sim_pin_reply(&conn, result, "SIM unblocked", "Failed to unblock SIM").await
+}
+
+/// POST /api/sim/pin/change - 修改 PIN
+pub async fn change_sim_pin_handler(
+    State(conn): State<Arc<Connection>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(req): Json<SimChangePinRequest>,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    let valid = validate_sim_code(&req.pin_type, &req.old_pin, "Old PIN")
+        .and_then(|_| validate_sim_code(&req.pin_type, &req.new_pin, "New PIN"));
+    if let Err(e) = valid {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    let result = crate::dbus::sim_change_pin(&conn, &req.pin_type, &req.old_pin, &req.new_pin).await;
+    if result.is_ok() && req.pin_type == "pin" {
+        sync_saved_pin(&config_manager, &req.new_pin);
+    }
+    sim_pin_reply(&conn, result, "PIN changed", "Failed to change PIN").await
+}
+
+/// POST /api/sim/pin/lock - 启用 PIN 锁
+pub async fn lock_sim_pin_handler(
+    State(conn): State<Arc<Connection>>,
+    Json(req): Json<SimPinRequest>,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    if let Err(e) = validate_sim_code(&req.pin_type, &req.pin, "PIN") {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    let result = crate::dbus::sim_set_pin_lock(&conn, &req.pin_type, &req.pin, true).await;
+    sim_pin_reply(&conn, result, "PIN lock enabled", "Failed to enable PIN lock").await
+}
+
+/// POST /api/sim/pin/unlock - 关闭 PIN 锁
+pub async fn unlock_sim_pin_handler(
+    State(conn): State<Arc<Connection>>,
+    Json(req): Json<SimPinRequest>,
+) -> (StatusCode, Json<ApiResponse<SimInfoResponse>>) {
+    if let Err(e) = validate_sim_code(&req.pin_type, &req.pin, "PIN") {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    let result = crate::dbus::sim_set_pin_lock(&conn, &req.pin_type, &req.pin, false).await;
+    sim_pin_reply(&conn, result, "PIN lock disabled", "Failed to disable PIN lock").await
+}
+
+fn sim_pin_config_response(config: &crate::config::SimPinConfig) -> SimPinConfigResponse {
+    SimPinConfigResponse {
+        auto_enter: config.auto_enter,
+        pin_set: !config.pin.is_empty(),
+    }
+}
+
+/// GET /api/sim/pin/config - 获取自动输入 PIN 配置
+pub async fn get_sim_pin_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<SimPinConfigResponse>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message(
+            "Success",
+            sim_pin_config_response(&config_manager.get_sim_pin()),
+        )),
+    )
+}
+
+/// POST /api/sim/pin/config - 设置自动输入 PIN（重启后 Watchdog 自动解锁 SIM）
+pub async fn set_sim_pin_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(req): Json<SimPinConfigRequest>,
+) -> (StatusCode, Json<ApiResponse<SimPinConfigResponse>>) {
+    let mut config = config_manager.get_sim_pin();
+    if let Some(pin) = req.pin {
+        let pin = pin.trim().to_string();
+        if !pin.is_empty() {
+            if let Err(e) = validate_sim_code("pin", &pin, "PIN") {
+                return (StatusCode::OK, Json(ApiResponse::error(e)));
+            }
+        }
+        config.pin = pin;
+    }
+    if req.auto_enter && config.pin.is_empty() {
+        return (StatusCode::OK, Json(ApiResponse::error("PIN is required for auto entry")));
+    }
+    config.auto_enter = req.auto_enter;
+
+    match config_manager.set_sim_pin(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "SIM PIN config updated",
+                sim_pin_config_response(&config_manager.get_sim_pin()),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update SIM PIN config: {}", e))),
+        ),
+    }
+}
+
 // ============ APN 管理功能 ============
 
 /// GET /api/apn - 获取 APN 列表
 ///
 /// 返回所有 internet 类型的 APN context 配置
 pub async fn get_apn_list_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
 ) -> (StatusCode, Json<ApiResponse<ApnListResponse>>) {
-    match get_all_apn_contexts(&conn).await {
+    match modem.apn_contexts().await {
         Ok(contexts) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
@@ -2454,7 +2541,7 @@ pub async fn get_apn_list_handler(
 /// }
 /// ```
 pub async fn set_apn_handler(
-    State(conn): State<Arc<Connection>>,
+    State(modem): State<Arc<dyn ModemBackend>>,
     Json(req): Json<SetApnRequest>,
 ) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
     // 验证 context_path
@@ -2465,19 +2552,10 @@ pub async fn set_apn_handler(
         );
     }
     
-    // 调用 D-Bus 设置 APN 属性
-    match set_apn_properties(
-        &conn,
-        &req.context_path,
-        req.apn.as_deref(),
-        req.protocol.as_deref(),
-        req.username.as_deref(),
-        req.password.as_deref(),
-        req.auth_method.as_deref(),
-    ).await {
+    match modem.set_apn(&req).await {
         Ok(_) => {
             // 获取更新后的 APN 配置
-            match get_all_apn_contexts(&conn).await {
+            match modem.apn_contexts().await {
                 Ok(contexts) => {
                     // 找到刚刚修改的 context
                     let updated_context = contexts
@@ -2595,6 +2673,7 @@ fn parse_ping_latency(output: &str) -> Option<f64> {
 
 // ============ 通话记录 API ============
 
+use crate::auth::{extract_token, AuthManager, Principal};
 use crate::sms_push::SmsPushSender;
 use crate::webhook::WebhookSender;
 
@@ -2696,117 +2775,1043 @@ pub async fn set_init_script_handler(
     }
 }
 
-// ============ Webhook 配置 API ============
+// ============ 登录认证 API ============
+
+/// POST /api/auth/login - 使用管理员密码登录（失败次数按客户端 IP 限制）
+pub async fn login_handler(
+    State(auth): State<Arc<AuthManager>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(req): Json<LoginRequest>,
+) -> (StatusCode, Json<ApiResponse<LoginResponse>>) {
+    match auth.login(&req.password, client.ip()) {
+        Ok(response) => {
+            let message = if response.must_change_password {
+                "Login successful, password change required"
+            } else {
+                "Login successful"
+            };
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success_with_message(message, response)),
+            )
+        }
+        Err(e) => (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponse::error(format!("Login failed: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/auth/logout - 注销当前会话
+pub async fn logout_handler(
+    State(auth): State<Arc<AuthManager>>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    if let Some(token) = extract_token(&headers) {
+        auth.logout(&token);
+    }
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Logged out", json!({}))),
+    )
+}
+
+/// GET /api/auth/status - 查询是否已设置密码以及当前令牌是否有效
+pub async fn auth_status_handler(
+    State(auth): State<Arc<AuthManager>>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<ApiResponse<AuthStatusResponse>>) {
+    let token = extract_token(&headers);
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message(
+            "Success",
+            auth.status(token.as_deref()),
+        )),
+    )
+}
+
+/// POST /api/auth/password - 修改管理员密码
+pub async fn change_password_handler(
+    State(auth): State<Arc<AuthManager>>,
+    headers: HeaderMap,
+    Json(req): Json<ChangePasswordRequest>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    let token = extract_token(&headers).unwrap_or_default();
+    match auth.change_password(&token, &req.current_password, &req.new_password) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Password changed", json!({}))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to change password: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/auth/sse-ticket - 换取打开事件流用的一次性票据（30 秒内有效）
+pub async fn create_sse_ticket_handler(
+    State(auth): State<Arc<AuthManager>>,
+    axum::Extension(principal): axum::Extension<Principal>,
+) -> (StatusCode, Json<ApiResponse<SseTicketResponse>>) {
+    match auth.issue_sse_ticket(principal) {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Success", response)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to create SSE ticket: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/auth/keys - 列出 API 密钥
+pub async fn list_api_keys_handler(
+    State(auth): State<Arc<AuthManager>>,
+) -> (StatusCode, Json<ApiResponse<Vec<ApiKeyInfo>>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", auth.list_api_keys())),
+    )
+}
+
+/// POST /api/auth/keys - 创建 API 密钥
+///
+/// # 请求体
+/// ```json
+/// {
+///   "name": "monitoring",
+///   "scopes": ["read"],
+///   "allowed_paths": ["/api/stats", "/api/network"]
+/// }
+/// ```
+pub async fn create_api_key_handler(
+    State(auth): State<Arc<AuthManager>>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> (StatusCode, Json<ApiResponse<CreateApiKeyResponse>>) {
+    match auth.create_api_key(&req.name, req.scopes, req.allowed_paths) {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("API key created", response)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to create API key: {}", e))),
+        ),
+    }
+}
+
+/// DELETE /api/auth/keys/{id} - 吊销 API 密钥
+pub async fn revoke_api_key_handler(
+    State(auth): State<Arc<AuthManager>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match auth.revoke_api_key(&id) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("API key revoked", json!({}))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to revoke API key: {}", e))),
+        ),
+    }
+}
+
+// ============ Webhook 配置 API ============
 
 /// GET /api/webhook/config - 获取 Webhook 配置
 pub async fn get_webhook_config_handler(
     State(config_manager): State<Arc<ConfigManager>>,
-) -> (StatusCode, Json<ApiResponse<crate::config::WebhookConfig>>) {
-    let config = config_manager.get_webhook();
+) -> (StatusCode, Json<ApiResponse<crate::config::WebhookConfig>>) {
+    let config = config_manager.get_webhook();
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config)),
+    )
+}
+
+/// POST /api/webhook/config - 设置 Webhook 配置
+pub async fn set_webhook_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(webhook_config): Json<crate::config::WebhookConfig>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    let webhook_config = webhook_config.sanitize();
+    if let Err(e) = crate::webhook::validate_config(&webhook_config) {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+
+    match config_manager.set_webhook(webhook_config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Webhook config updated", json!({}))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update webhook config: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/webhook/test - 测试 Webhook 连接（主 URL 和所有目标，返回各目标结果）
+pub async fn test_webhook_handler(
+    State(webhook_sender): State<Arc<WebhookSender>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::WebhookTestResponse>>) {
+    let results = webhook_sender.test_webhook().await;
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let success = !results.is_empty() && succeeded == results.len();
+    let message = if results.is_empty() {
+        "Webhook URL is not configured".to_string()
+    } else {
+        format!("{}/{} webhook endpoints succeeded", succeeded, results.len())
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message(
+            if success { "Webhook test successful" } else { "Webhook test failed" },
+            crate::models::WebhookTestResponse { success, message, results },
+        )),
+    )
+}
+
+/// GET /api/sms-push/config - 获取短信推送配置
+pub async fn get_sms_push_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SmsPushConfig>>) {
+    let config = config_manager.get_sms_push();
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config)),
+    )
+}
+
+/// POST /api/sms-push/config - 设置短信推送配置
+pub async fn set_sms_push_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(sms_push_config): Json<crate::config::SmsPushConfig>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match config_manager.set_sms_push(sms_push_config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("SMS push config updated", json!({}))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update SMS push config: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/sms-push/test - 测试短信推送连接
+pub async fn test_sms_push_handler(
+    State(sms_push_sender): State<Arc<SmsPushSender>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::WebhookTestResponse>>) {
+    match sms_push_sender.test_sms_push().await {
+        Ok(message) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "SMS push test successful",
+                crate::models::WebhookTestResponse {
+                    success: true,
+                    message,
+                    results: Vec::new(),
+                },
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "SMS push test failed",
+                crate::models::WebhookTestResponse {
+                    success: false,
+                    message: e,
+                    results: Vec::new(),
+                },
+            )),
+        ),
+    }
+}
+
+// ============ 信号历史 API ============
+
+/// 解析时间参数：Unix 时间戳（秒）或 RFC 3339
+fn parse_history_time(value: &str) -> Result<i64, String> {
+    let value = value.trim();
+    if let Ok(ts) = value.parse::<i64>() {
+        return Ok(ts);
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp())
+        .map_err(|_| format!("Invalid time: {}", value))
+}
+
+/// GET /api/history/signal - 查询信号质量历史
+///
+/// # Query
+/// * `from` / `to` - Unix 时间戳（秒）或 RFC 3339，默认最近 24 小时
+/// * `resolution` - `raw`、`auto`（默认）或时间桶秒数
+pub async fn get_signal_history_handler(
+    State(db): State<Arc<Database>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+    Query(query): Query<SignalHistoryQuery>,
+) -> (StatusCode, Json<ApiResponse<SignalHistoryResponse>>) {
+    let result = (|| {
+        let to = match query.to.as_deref() {
+            Some(value) => parse_history_time(value)?,
+            None => chrono::Utc::now().timestamp(),
+        };
+        let from = match query.from.as_deref() {
+            Some(value) => parse_history_time(value)?,
+            None => to - 86_400,
+        };
+        if from >= to {
+            return Err("'from' must be earlier than 'to'".to_string());
+        }
+
+        let interval_secs = config_manager.get_signal_history().interval_secs;
+        let resolution = crate::signal_history::resolve_resolution(query.resolution.as_deref(), from, to, interval_secs)?;
+        let points = db
+            .get_signal_history(from, to)
+            .map_err(|e| format!("Failed to query signal history: {}", e))?;
+
+        Ok(SignalHistoryResponse {
+            from,
+            to,
+            resolution,
+            points: crate::signal_history::bucket_points(&points, resolution),
+        })
+    })();
+
+    match result {
+        Ok(data) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Retrieved {} points", data.points.len()),
+                data,
+            )),
+        ),
+        Err(msg) => (StatusCode::OK, Json(ApiResponse::error(msg))),
+    }
+}
+
+/// GET /api/history/signal/config - 获取信号采样配置
+pub async fn get_signal_history_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SignalHistoryConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_signal_history())),
+    )
+}
+
+/// POST /api/history/signal/config - 设置信号采样配置（下一个采样周期生效）
+pub async fn set_signal_history_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::SignalHistoryConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SignalHistoryConfig>>) {
+    match config_manager.set_signal_history(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "Signal history config updated",
+                config_manager.get_signal_history(),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update signal history config: {}", e))),
+        ),
+    }
+}
+
+// ============ 流量统计 API ============
+
+/// GET /api/data-usage - 获取当前计费周期的流量用量
+pub async fn get_data_usage_handler(
+    State(db): State<Arc<Database>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::data_usage::DataUsageSummary>>) {
+    match crate::data_usage::usage_summary(&db, &config_manager.get_data_usage()) {
+        Ok(summary) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Success", summary)),
+        ),
+        Err(e) => (StatusCode::OK, Json(ApiResponse::error(e))),
+    }
+}
+
+/// GET /api/data-usage/history - 获取小时/日/月流量统计
+pub async fn get_data_usage_history_handler(
+    State(db): State<Arc<Database>>,
+    Query(query): Query<DataUsageHistoryQuery>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::DataUsageBucket>>>) {
+    if !matches!(query.period.as_str(), "hour" | "day" | "month") {
+        return (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Invalid period: {}", query.period))),
+        );
+    }
+
+    match db.get_data_usage_history(&query.period, query.limit.clamp(1, 1000)) {
+        Ok(buckets) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Retrieved {} buckets", buckets.len()),
+                buckets,
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get data usage: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/data-usage/config - 获取流量统计配置
+pub async fn get_data_usage_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::DataUsageConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_data_usage())),
+    )
+}
+
+/// POST /api/data-usage/config - 设置流量统计配置（计费日、配额、超额处理方式）
+pub async fn set_data_usage_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::DataUsageConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::DataUsageConfig>>) {
+    match config_manager.set_data_usage(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "Data usage config updated",
+                config_manager.get_data_usage(),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update data usage config: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/ingest/config - 获取短信接收配置（接收方式、长短信分段超时）
+pub async fn get_sms_ingest_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SmsIngestConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_sms_ingest())),
+    )
+}
+
+/// POST /api/sms/ingest/config - 设置短信接收配置
+///
+/// `mode` 为 `pdu` 时订阅 MessagePDU 原始 PDU，由本服务拼接长短信；
+/// 超过 `fragment_timeout_secs` 仍未收齐的长短信以 partial 状态保存
+pub async fn set_sms_ingest_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::SmsIngestConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SmsIngestConfig>>) {
+    match config_manager.set_sms_ingest(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "SMS ingest config updated",
+                config_manager.get_sms_ingest(),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update SMS ingest config: {}", e))),
+        ),
+    }
+}
+
+// ============ 通讯录 ============
+
+/// 可选文本字段：去掉首尾空白，空字符串视为未设置
+fn optional_text(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// GET /api/contacts - 获取联系人列表
+pub async fn list_contacts_handler(
+    State(db): State<Arc<Database>>,
+    Query(req): Query<ContactListRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::Contact>>>) {
+    match db.list_contacts(req.search.as_deref()) {
+        Ok(contacts) => (StatusCode::OK, Json(ApiResponse::success_with_message("Success", contacts))),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get contacts: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/contacts - 添加联系人
+pub async fn create_contact_handler(
+    State(db): State<Arc<Database>>,
+    Json(req): Json<ContactRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::Contact>>>) {
+    let (name, phone_number) = match crate::contacts::validate_contact(&req.name, &req.phone_number) {
+        Ok(validated) => validated,
+        Err(e) => return (StatusCode::OK, Json(ApiResponse::error(e))),
+    };
+
+    match db.insert_contact(name, phone_number, optional_text(&req.email), optional_text(&req.note), "manual") {
+        Ok(id) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Contact added", db.get_contact(id).ok().flatten())),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to add contact: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/contacts/{id} - 获取单个联系人
+pub async fn get_contact_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::Contact>>>) {
+    match db.get_contact(id) {
+        Ok(Some(contact)) => (StatusCode::OK, Json(ApiResponse::success_with_message("Success", Some(contact)))),
+        Ok(None) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Contact {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get contact: {}", e))),
+        ),
+    }
+}
+
+/// PUT /api/contacts/{id} - 更新联系人
+pub async fn update_contact_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<ContactRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::Contact>>>) {
+    let (name, phone_number) = match crate::contacts::validate_contact(&req.name, &req.phone_number) {
+        Ok(validated) => validated,
+        Err(e) => return (StatusCode::OK, Json(ApiResponse::error(e))),
+    };
+
+    match db.update_contact(id, name, phone_number, optional_text(&req.email), optional_text(&req.note)) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Contact updated", db.get_contact(id).ok().flatten())),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Contact {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update contact: {}", e))),
+        ),
+    }
+}
+
+/// DELETE /api/contacts/{id} - 删除联系人
+pub async fn delete_contact_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match db.delete_contact(id) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Contact deleted", json!({}))),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Contact {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to delete contact: {}", e))),
+        ),
+    }
+}
+
+fn import_reply(
+    db: &Database,
+    entries: Vec<crate::contacts::ContactEntry>,
+    source: &str,
+) -> (StatusCode, Json<ApiResponse<crate::contacts::ImportSummary>>) {
+    match crate::contacts::import_entries(db, &entries, source) {
+        Ok(summary) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                format!("Imported {} contacts, skipped {}", summary.imported, summary.skipped),
+                summary,
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to import contacts: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/contacts/import - 从 vCard 或 CSV 导入联系人（已存在的同名同号码联系人跳过）
+pub async fn import_contacts_handler(
+    State(db): State<Arc<Database>>,
+    Json(req): Json<ContactImportRequest>,
+) -> (StatusCode, Json<ApiResponse<crate::contacts::ImportSummary>>) {
+    let (entries, source) = match req.format.to_ascii_lowercase().as_str() {
+        "vcard" | "vcf" => (crate::contacts::parse_vcards(&req.data), "vcard"),
+        "csv" => (crate::contacts::parse_csv(&req.data), "csv"),
+        other => {
+            return (
+                StatusCode::OK,
+                Json(ApiResponse::error(format!("Unsupported import format: {}", other))),
+            )
+        }
+    };
+    import_reply(&db, entries, source)
+}
+
+/// POST /api/contacts/import/sim - 导入 SIM 卡电话本
+pub async fn import_sim_contacts_handler(
+    State(conn): State<Arc<Connection>>,
+    State(db): State<Arc<Database>>,
+) -> (StatusCode, Json<ApiResponse<crate::contacts::ImportSummary>>) {
+    match crate::dbus::import_sim_phonebook(&conn).await {
+        Ok(vcards) => import_reply(&db, crate::contacts::parse_vcards(&vcards), "sim"),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to read SIM phonebook: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/contacts/export - 导出联系人（format=vcard|csv），返回文件内容
+pub async fn export_contacts_handler(
+    State(db): State<Arc<Database>>,
+    Query(req): Query<ContactExportRequest>,
+) -> axum::response::Response {
+    let contacts = match db.list_contacts(None) {
+        Ok(contacts) => contacts,
+        Err(e) => {
+            return (
+                StatusCode::OK,
+                Json(ApiResponse::<serde_json::Value>::error(format!("Failed to export contacts: {}", e))),
+            )
+                .into_response()
+        }
+    };
+
+    let (body, content_type, filename) = match req.format.to_ascii_lowercase().as_str() {
+        "vcard" | "vcf" => (crate::contacts::to_vcards(&contacts), "text/vcard; charset=utf-8", "contacts.vcf"),
+        "csv" => (crate::contacts::to_csv(&contacts), "text/csv; charset=utf-8", "contacts.csv"),
+        other => {
+            return (
+                StatusCode::OK,
+                Json(ApiResponse::<serde_json::Value>::error(format!("Unsupported export format: {}", other))),
+            )
+                .into_response()
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(axum::http::header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename)).unwrap(),
+    );
+    (StatusCode::OK, headers, body).into_response()
+}
+
+// ============ USSD ============
+
+fn ussd_reply(
+    result: Result<UssdResponse, String>,
+    message: &str,
+) -> (StatusCode, Json<ApiResponse<UssdResponse>>) {
+    match result {
+        Ok(response) => (StatusCode::OK, Json(ApiResponse::success_with_message(message, response))),
+        Err(e) => (StatusCode::OK, Json(ApiResponse::error(e))),
+    }
+}
+
+/// GET /api/ussd/status - 获取 USSD 会话状态
+pub async fn get_ussd_status_handler(
+    State(conn): State<Arc<Connection>>,
+) -> (StatusCode, Json<ApiResponse<UssdResponse>>) {
+    ussd_reply(crate::ussd::status(&conn).await, "Success")
+}
+
+/// POST /api/ussd/initiate - 发起 USSD 请求
+pub async fn initiate_ussd_handler(
+    State(conn): State<Arc<Connection>>,
+    State(db): State<Arc<Database>>,
+    Json(payload): Json<UssdInitiateRequest>,
+) -> (StatusCode, Json<ApiResponse<UssdResponse>>) {
+    ussd_reply(crate::ussd::initiate(&conn, &db, &payload.command).await, "USSD request sent")
+}
+
+/// POST /api/ussd/respond - 回复 USSD 菜单
+pub async fn respond_ussd_handler(
+    State(conn): State<Arc<Connection>>,
+    State(db): State<Arc<Database>>,
+    Json(payload): Json<UssdRespondRequest>,
+) -> (StatusCode, Json<ApiResponse<UssdResponse>>) {
+    ussd_reply(crate::ussd::respond(&conn, &db, &payload.response).await, "USSD response sent")
+}
+
+/// POST /api/ussd/cancel - 取消 USSD 会话
+pub async fn cancel_ussd_handler(
+    State(conn): State<Arc<Connection>>,
+) -> (StatusCode, Json<ApiResponse<UssdResponse>>) {
+    ussd_reply(crate::ussd::cancel(&conn).await, "USSD session cancelled")
+}
+
+/// GET /api/ussd/history - 获取 USSD 记录
+pub async fn get_ussd_history_handler(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<UssdHistoryRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::UssdRecord>>>) {
+    let limit = if params.limit > 0 { params.limit } else { 50 };
+    let offset = if params.offset >= 0 { params.offset } else { 0 };
+
+    match db.get_ussd_history(limit, offset) {
+        Ok(records) => (StatusCode::OK, Json(ApiResponse::success_with_message("Success", records))),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get USSD history: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/ussd/history/clear - 清空 USSD 记录
+pub async fn clear_ussd_history_handler(
+    State(db): State<Arc<Database>>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match db.clear_ussd_history() {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("USSD history cleared", json!({}))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to clear USSD history: {}", e))),
+        ),
+    }
+}
+
+// ============ 短信规则 ============
+
+/// GET /api/sms/rules - 获取短信规则配置
+pub async fn get_sms_rules_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::SmsRulesResponse>>) {
     (
         StatusCode::OK,
-        Json(ApiResponse::success_with_message("Success", config)),
+        Json(ApiResponse::success_with_message(
+            "Success",
+            sms_rules_response(config_manager.get_sms_rules()),
+        )),
     )
 }
 
-/// POST /api/webhook/config - 设置 Webhook 配置
-pub async fn set_webhook_config_handler(
+fn sms_rules_response(config: crate::config::SmsRulesConfig) -> crate::models::SmsRulesResponse {
+    crate::models::SmsRulesResponse {
+        enabled: config.enabled,
+        pin_set: !config.pin.is_empty(),
+        authorized_senders: config.authorized_senders,
+        rules: config.rules,
+    }
+}
+
+/// POST /api/sms/rules - 设置短信规则（自动回复、转发、标签、Webhook 和设备指令）
+///
+/// 包含设备指令的规则必须配置 `pin`（至少 6 位），只对 `authorized_senders` 中以 PIN 开头的短信生效
+pub async fn set_sms_rules_handler(
     State(config_manager): State<Arc<ConfigManager>>,
-    Json(webhook_config): Json<crate::config::WebhookConfig>,
-) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    match config_manager.set_webhook(webhook_config) {
+    Json(req): Json<crate::models::SmsRulesRequest>,
+) -> (StatusCode, Json<ApiResponse<crate::models::SmsRulesResponse>>) {
+    let config = crate::config::SmsRulesConfig {
+        enabled: req.enabled,
+        pin: req.pin.unwrap_or_else(|| config_manager.get_sms_rules().pin),
+        authorized_senders: req.authorized_senders,
+        rules: req.rules,
+    }
+    .sanitize();
+    if let Err(e) = crate::sms_rules::validate_config(&config) {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+
+    match config_manager.set_sms_rules(config) {
         Ok(_) => (
             StatusCode::OK,
-            Json(ApiResponse::success_with_message("Webhook config updated", json!({}))),
+            Json(ApiResponse::success_with_message(
+                "SMS rules updated",
+                sms_rules_response(config_manager.get_sms_rules()),
+            )),
         ),
         Err(e) => (
             StatusCode::OK,
-            Json(ApiResponse::error(format!("Failed to update webhook config: {}", e))),
+            Json(ApiResponse::error(format!("Failed to update SMS rules: {}", e))),
         ),
     }
 }
 
-/// POST /api/webhook/test - 测试 Webhook 连接
-pub async fn test_webhook_handler(
-    State(webhook_sender): State<Arc<WebhookSender>>,
-) -> (StatusCode, Json<ApiResponse<crate::models::WebhookTestResponse>>) {
-    match webhook_sender.test_webhook().await {
-        Ok(message) => (
+// ============ 来电拦截 ============
+
+/// GET /api/call/filter - 获取来电拦截与黑白名单配置
+pub async fn get_call_filter_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::CallFilterConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_call_filter())),
+    )
+}
+
+/// POST /api/call/filter - 设置来电拦截与黑白名单
+///
+/// 命中的来电自动挂断并以 `blocked` 记入通话记录；`filter_sms` 为 true 时同样不转发命中号码的短信
+pub async fn set_call_filter_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::CallFilterConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::CallFilterConfig>>) {
+    let config = config.sanitize();
+    if let Err(e) = crate::call_filter::validate_config(&config) {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+
+    match config_manager.set_call_filter(config) {
+        Ok(_) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
-                "Webhook test successful",
-                crate::models::WebhookTestResponse {
-                    success: true,
-                    message,
-                },
+                "Call filter updated",
+                config_manager.get_call_filter(),
             )),
         ),
         Err(e) => (
             StatusCode::OK,
-            Json(ApiResponse::success_with_message(
-                "Webhook test failed",
-                crate::models::WebhookTestResponse {
-                    success: false,
-                    message: e,
-                },
-            )),
+            Json(ApiResponse::error(format!("Failed to update call filter: {}", e))),
         ),
     }
 }
 
-/// GET /api/sms-push/config - 获取短信推送配置
-pub async fn get_sms_push_config_handler(
+/// GET /api/call/policy - 获取通话策略配置
+pub async fn get_call_policy_handler(
     State(config_manager): State<Arc<ConfigManager>>,
-) -> (StatusCode, Json<ApiResponse<crate::config::SmsPushConfig>>) {
-    let config = config_manager.get_sms_push();
+) -> (StatusCode, Json<ApiResponse<crate::config::CallPolicyConfig>>) {
     (
         StatusCode::OK,
-        Json(ApiResponse::success_with_message("Success", config)),
+        Json(ApiResponse::success_with_message("Success", config_manager.get_call_policy())),
     )
 }
 
-/// POST /api/sms-push/config - 设置短信推送配置
-pub async fn set_sms_push_config_handler(
+/// POST /api/call/policy - 设置通话策略（自动接听、拒接、限时挂断）
+pub async fn set_call_policy_handler(
     State(config_manager): State<Arc<ConfigManager>>,
-    Json(sms_push_config): Json<crate::config::SmsPushConfig>,
-) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
-    match config_manager.set_sms_push(sms_push_config) {
+    Json(config): Json<crate::config::CallPolicyConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::CallPolicyConfig>>) {
+    let config = config.sanitize();
+    if let Err(e) = crate::call_policy::validate_config(&config) {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+
+    match config_manager.set_call_policy(config) {
         Ok(_) => (
             StatusCode::OK,
-            Json(ApiResponse::success_with_message("SMS push config updated", json!({}))),
+            Json(ApiResponse::success_with_message(
+                "Call policy updated",
+                config_manager.get_call_policy(),
+            )),
         ),
         Err(e) => (
             StatusCode::OK,
-            Json(ApiResponse::error(format!("Failed to update SMS push config: {}", e))),
+            Json(ApiResponse::error(format!("Failed to update call policy: {}", e))),
         ),
     }
 }
 
-/// POST /api/sms-push/test - 测试短信推送连接
-pub async fn test_sms_push_handler(
-    State(sms_push_sender): State<Arc<SmsPushSender>>,
-) -> (StatusCode, Json<ApiResponse<crate::models::WebhookTestResponse>>) {
-    match sms_push_sender.test_sms_push().await {
-        Ok(message) => (
+// ============ 短信发送队列 ============
+
+/// 校验队列任务请求，返回 (规范化的 cron 表达式, 首次发送时间, 最大尝试次数)
+fn validate_sms_queue_request(
+    req: &SmsQueueRequest,
+    config: &crate::config::SmsQueueConfig,
+) -> Result<(Option<String>, i64, u32), String> {
+    if req.phone_number.trim().is_empty() {
+        return Err("Phone number is empty".to_string());
+    }
+    if req.content.is_empty() {
+        return Err("Message content is empty".to_string());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let schedule = req.schedule.as_deref().map(str::trim).filter(|expr| !expr.is_empty());
+    let cron = schedule.map(crate::cron::CronSchedule::parse).transpose()?;
+    let send_at = match (req.send_at, &cron) {
+        (Some(send_at), _) => send_at,
+        (None, Some(cron)) => crate::sms_queue::next_occurrence(cron, now)
+            .ok_or_else(|| format!("Schedule never fires: {}", schedule.unwrap_or_default()))?,
+        (None, None) => now,
+    };
+
+    let max_attempts = req.max_attempts.unwrap_or(config.max_attempts).clamp(1, 20);
+    Ok((schedule.map(str::to_string), send_at, max_attempts))
+}
+
+/// GET /api/sms/queue - 获取短信发送队列
+pub async fn list_sms_queue_handler(
+    State(db): State<Arc<Database>>,
+) -> (StatusCode, Json<ApiResponse<Vec<crate::db::SmsQueueItem>>>) {
+    match db.list_sms_queue() {
+        Ok(items) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(format!("Retrieved {} queued messages", items.len()), items)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get SMS queue: {}", e))),
+        ),
+    }
+}
+
+/// POST /api/sms/queue - 添加定时/周期短信
+///
+/// `send_at` 为首次发送时间（Unix 时间戳），`schedule` 为 cron 表达式（本地时间）；
+/// 都为空时立即进入发送队列
+pub async fn create_sms_queue_handler(
+    State(db): State<Arc<Database>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(req): Json<SmsQueueRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsQueueItem>>>) {
+    let (schedule, send_at, max_attempts) = match validate_sms_queue_request(&req, &config_manager.get_sms_queue()) {
+        Ok(validated) => validated,
+        Err(e) => return (StatusCode::OK, Json(ApiResponse::error(e))),
+    };
+
+    match db
+        .insert_sms_queue(req.phone_number.trim(), &req.content, schedule.as_deref(), send_at, max_attempts)
+        .and_then(|id| db.get_sms_queue_item(id))
+    {
+        Ok(item) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("SMS queued", item)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to queue SMS: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/queue/{id} - 获取队列任务
+pub async fn get_sms_queue_item_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsQueueItem>>>) {
+    match db.get_sms_queue_item(id) {
+        Ok(Some(item)) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Success", Some(item))),
+        ),
+        Ok(None) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Queued SMS {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to get queued SMS: {}", e))),
+        ),
+    }
+}
+
+/// PUT /api/sms/queue/{id} - 修改队列任务（重置失败次数并重新进入发送队列）
+pub async fn update_sms_queue_item_handler(
+    State(db): State<Arc<Database>>,
+    State(config_manager): State<Arc<ConfigManager>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(req): Json<SmsQueueRequest>,
+) -> (StatusCode, Json<ApiResponse<Option<crate::db::SmsQueueItem>>>) {
+    let (schedule, send_at, max_attempts) = match validate_sms_queue_request(&req, &config_manager.get_sms_queue()) {
+        Ok(validated) => validated,
+        Err(e) => return (StatusCode::OK, Json(ApiResponse::error(e))),
+    };
+
+    match db.update_sms_queue_item(id, req.phone_number.trim(), &req.content, schedule.as_deref(), send_at, max_attempts) {
+        Ok(true) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
-                "SMS push test successful",
-                crate::models::WebhookTestResponse {
-                    success: true,
-                    message,
-                },
+                "Queued SMS updated",
+                db.get_sms_queue_item(id).ok().flatten(),
             )),
         ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Queued SMS {} not found", id))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update queued SMS: {}", e))),
+        ),
+    }
+}
+
+/// DELETE /api/sms/queue/{id} - 删除队列任务
+pub async fn delete_sms_queue_item_handler(
+    State(db): State<Arc<Database>>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    match db.delete_sms_queue_item(id) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Queued SMS deleted", json!({}))),
+        ),
+        Ok(false) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Queued SMS {} not found", id))),
+        ),
         Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to delete queued SMS: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/sms/queue/config - 获取短信发送队列配置
+pub async fn get_sms_queue_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SmsQueueConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_sms_queue())),
+    )
+}
+
+/// POST /api/sms/queue/config - 设置短信发送队列配置
+pub async fn set_sms_queue_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::SmsQueueConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::SmsQueueConfig>>) {
+    match config_manager.set_sms_queue(config) {
+        Ok(_) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(
-                "SMS push test failed",
-                crate::models::WebhookTestResponse {
-                    success: false,
-                    message: e,
-                },
+                "SMS queue config updated",
+                config_manager.get_sms_queue(),
             )),
         ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update SMS queue config: {}", e))),
+        ),
     }
 }
 
@@ -2886,6 +3891,38 @@ pub async fn frontend_refresh_heartbeat_handler(
     )
 }
 
+/// GET /api/events - 实时事件流（Server-Sent Events）
+///
+/// 每个事件的 `event` 字段为事件类型，`data` 为 JSON，例如：
+/// ```text
+/// event: sms_received
+/// data: {"type":"sms_received","message":{...}}
+/// ```
+/// 浏览器 EventSource 无法设置请求头，先调用 `POST /api/auth/sse-ticket`，
+/// 再通过 `?ticket=<ticket>` 传递一次性票据。
+/// 订阅者处理过慢导致事件丢失时会收到 `resync` 事件，前端应重新拉取完整状态。
+pub async fn events_handler(
+    State(events): State<Arc<EventBus>>,
+) -> Sse<impl futures_util::Stream<Item = Result<SseEvent, std::convert::Infallible>>> {
+    let receiver = events.subscribe();
+
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => SseEvent::default()
+                .event(event.name())
+                .json_data(&event)
+                .unwrap_or_else(|_| SseEvent::default().comment("serialize error")),
+            Err(RecvError::Lagged(skipped)) => SseEvent::default()
+                .event("resync")
+                .data(json!({ "type": "resync", "skipped": skipped }).to_string()),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 pub async fn get_ota_status_handler() -> impl IntoResponse {
     let status = crate::ota::get_ota_status();
     (
@@ -2895,10 +3932,13 @@ pub async fn get_ota_status_handler() -> impl IntoResponse {
 }
 
 /// POST /api/ota/upload - 上传 OTA 更新包
+///
+/// 更新包需包含由受信任公钥签名的 `meta.json.minisig`，开发者模式下允许未签名的更新包
 pub async fn upload_ota_handler(
-    body: axum::body::Bytes,
+    State(config_manager): State<Arc<ConfigManager>>,
+    body: axum::body::Body,
 ) -> impl IntoResponse {
-    match crate::ota::handle_ota_upload(&body) {
+    match crate::ota::handle_ota_upload(body, &config_manager.get_ota()).await {
         Ok(response) => {
             let message = if response.validation.valid {
                 "OTA package uploaded and validated"
@@ -2922,9 +3962,16 @@ pub async fn upload_ota_handler(
 
 /// POST /api/ota/apply - 应用 OTA 更新
 pub async fn apply_ota_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
     Json(req): Json<crate::models::OtaApplyRequest>,
 ) -> impl IntoResponse {
-    match crate::ota::apply_ota_update(req.restart_now) {
+    let Ok(_staging) = crate::ota::STAGING_LOCK.try_lock() else {
+        return (
+            StatusCode::OK,
+            Json(ApiResponse::<serde_json::Value>::error("An OTA package is being staged")),
+        );
+    };
+    match crate::ota::apply_ota_update(req.restart_now, &config_manager.get_ota()) {
         Ok(message) => (
             StatusCode::OK,
             Json(ApiResponse::success_with_message(&message, json!({ "applied": true }))),
@@ -2939,8 +3986,130 @@ pub async fn apply_ota_handler(
     }
 }
 
+/// POST /api/ota/rollback - 回滚到上一个 A/B 槽位
+pub async fn rollback_ota_handler(
+    Json(req): Json<crate::models::OtaRollbackRequest>,
+) -> impl IntoResponse {
+    match crate::ota::rollback_ota(req.restart_now) {
+        Ok(message) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(&message, json!({ "rolled_back": true }))),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::<serde_json::Value>::error(format!(
+                "Failed to roll back OTA update: {}",
+                e
+            ))),
+        ),
+    }
+}
+
+/// GET /api/ota/config - 获取 OTA 签名配置（额外信任的公钥、开发者模式）
+pub async fn get_ota_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::OtaConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_ota())),
+    )
+}
+
+/// POST /api/ota/config - 设置 OTA 签名配置
+pub async fn set_ota_config_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::OtaConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::OtaConfig>>) {
+    if let Err(e) = crate::ota::validate_trusted_keys(&config) {
+        return (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Invalid OTA public key: {}", e))),
+        );
+    }
+    match config_manager.set_ota(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("OTA config updated", config_manager.get_ota())),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update OTA config: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/ota/channel - 获取 OTA 更新通道配置
+pub async fn get_ota_channel_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::config::OtaChannelConfig>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", config_manager.get_ota_channel())),
+    )
+}
+
+/// POST /api/ota/channel - 设置 OTA 更新通道配置
+pub async fn set_ota_channel_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+    Json(config): Json<crate::config::OtaChannelConfig>,
+) -> (StatusCode, Json<ApiResponse<crate::config::OtaChannelConfig>>) {
+    let config = config.sanitize();
+    if let Err(e) = crate::ota_channel::validate_channel_config(&config) {
+        return (StatusCode::OK, Json(ApiResponse::error(e)));
+    }
+    match config_manager.set_ota_channel(config) {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message(
+                "OTA channel config updated",
+                config_manager.get_ota_channel(),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Failed to update OTA channel config: {}", e))),
+        ),
+    }
+}
+
+/// GET /api/ota/check - 获取最近一次更新检查结果
+pub async fn get_ota_check_handler() -> (StatusCode, Json<ApiResponse<Option<crate::models::OtaCheckResult>>>) {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success_with_message("Success", crate::ota_channel::last_check())),
+    )
+}
+
+/// POST /api/ota/check - 立即检查更新通道（有新版本时下载并暂存，不会自动应用）
+pub async fn check_ota_update_handler(
+    State(config_manager): State<Arc<ConfigManager>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::OtaCheckResult>>) {
+    if config_manager.get_ota_channel().manifest_url.is_empty() {
+        return (
+            StatusCode::OK,
+            Json(ApiResponse::error("OTA manifest URL is not configured".to_string())),
+        );
+    }
+    match crate::ota_channel::check_for_update(&config_manager).await {
+        Ok(result) => (
+            StatusCode::OK,
+            Json(ApiResponse::success_with_message("Update check completed", result)),
+        ),
+        Err(e) => (
+            StatusCode::OK,
+            Json(ApiResponse::error(format!("Update check failed: {}", e))),
+        ),
+    }
+}
+
 /// POST /api/ota/cancel - 取消待安装的更新
 pub async fn cancel_ota_handler() -> impl IntoResponse {
+    let Ok(_staging) = crate::ota::STAGING_LOCK.try_lock() else {
+        return (
+            StatusCode::OK,
+            Json(ApiResponse::<serde_json::Value>::error("An OTA package is being staged")),
+        );
+    };
     match crate::ota::cancel_pending_update() {
         Ok(()) => (
             StatusCode::OK,
@@ -25,10 +25,11 @@ use axum::{
     routing::post, 
     Router,
     response::{IntoResponse, Response},
-    http::{StatusCode, Uri},
-    extract::DefaultBodyLimit,
+    http::{HeaderValue, StatusCode, Uri},
+    middleware,
 };
 use clap::Parser;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::path::PathBuf;
 use tower_http::cors::{CorsLayer, Any};
@@ -36,25 +37,45 @@ use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use zbus::Connection;
 
+mod archive;
+mod auth;
+mod call_filter;
+mod call_policy;
 mod config;
+mod contacts;
+mod cron;
+mod data_usage;
 mod db;
 mod dbus;
+mod events;
 mod handlers;
 mod iptables;
+mod minisign;
+mod modem;
 mod models;
 mod ota;
+mod ota_channel;
 mod serial;
+mod signal_history;
 mod sms_push;
 mod sms_listener;
+mod sms_pdu;
+mod sms_queue;
+mod sms_rules;
 mod state;
+#[cfg(test)]
+mod tests;
 mod usb_switch;
+mod ussd;
 mod utils;
 mod webhook;
 
+use auth::AuthManager;
 use config::{ensure_loader_hooks_init, get_default_config_path, get_persistent_root_dir, ConfigManager};
 use dbus::init_data_connection;
 use handlers::*;
 use db::Database;
+use events::EventBus;
 use sms_push::SmsPushSender;
 use state::{AppState, FrontendRuntime};
 use webhook::WebhookSender;
@@ -130,6 +151,184 @@ async fn spa_fallback(uri: Uri) -> Response {
     }
 }
 
+/// 构建 HTTP 路由（所有 API 路由、认证中间件、CORS 和前端静态文件）
+///
+/// `cors_origins` 为允许跨域访问的前端地址，为空时只允许同源访问。
+fn build_router(app_state: AppState, cors_origins: &[String]) -> Router {
+    let auth_manager = Arc::clone(&app_state.auth_manager);
+
+    // CORS 配置：只允许配置的前端地址跨域访问
+    let origins: Vec<HeaderValue> = cors_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin.trim()) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!(origin = %origin, "Ignoring invalid CORS origin");
+                None
+            }
+        })
+        .collect();
+    let cors = CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    Router::new()
+        // ========== 登录认证接口 ==========
+        .route("/api/auth/login", post(login_handler).options(options_handler))
+        .route("/api/auth/logout", post(logout_handler).options(options_handler))
+        .route("/api/auth/status", get(auth_status_handler).options(options_handler))
+        .route("/api/auth/password", post(change_password_handler).options(options_handler))
+        .route("/api/auth/sse-ticket", post(create_sse_ticket_handler).options(options_handler))
+        .route("/api/auth/keys", get(list_api_keys_handler).post(create_api_key_handler).options(options_handler))
+        .route("/api/auth/keys/{id}", axum::routing::delete(revoke_api_key_handler).options(options_handler))
+        // ========== AT 指令接口 ==========
+        .route("/api/at", post(post_at_command).options(options_handler))
+        // ========== 设备信息接口 ==========
+        .route("/api/device", get(get_device_info).options(options_handler))
+        .route("/api/device/imeisv", get(get_imeisv_handler).options(options_handler))
+        // ========== SIM 卡接口 ==========
+        .route("/api/sim", get(get_sim_info).options(options_handler))
+        .route("/api/sim/slot", get(get_sim_slot_handler).options(options_handler))
+        .route("/api/sim/slot/switch", post(switch_sim_slot_handler).options(options_handler))
+        .route("/api/sim/pin/enter", post(enter_sim_pin_handler).options(options_handler))
+        .route("/api/sim/pin/reset", post(reset_sim_pin_handler).options(options_handler))
+        .route("/api/sim/pin/change", post(change_sim_pin_handler).options(options_handler))
+        .route("/api/sim/pin/lock", post(lock_sim_pin_handler).options(options_handler))
+        .route("/api/sim/pin/unlock", post(unlock_sim_pin_handler).options(options_handler))
+        .route("/api/sim/pin/config", get(get_sim_pin_config_handler).post(set_sim_pin_config_handler).options(options_handler))
+        // ========== 网络接口 ==========
+        .route("/api/network", get(get_network_info).options(options_handler))
+        .route("/api/network/interfaces", get(get_network_interfaces_info).options(options_handler))
+        .route("/api/network/signal-strength", get(get_signal_strength_handler).options(options_handler))
+        .route("/api/network/nitz", get(get_nitz_handler).options(options_handler))
+        .route("/api/network/operators", get(get_operators_handler).options(options_handler))
+        .route("/api/network/operators/scan", get(scan_operators_handler).options(options_handler))
+        .route("/api/network/register-manual", post(register_operator_manual_handler).options(options_handler))
+        .route("/api/network/register-auto", post(register_operator_auto_handler).options(options_handler))
+        // ========== 小区信息接口 ==========
+        .route("/api/cells", get(get_cells).options(options_handler))
+        .route("/api/location/cell-info", get(get_cell_location_info).options(options_handler))
+        // ========== QoS 接口 ==========
+        .route("/api/qos", get(get_qos_info).options(options_handler))
+        // ========== 数据连接接口 ==========
+        .route("/api/data", get(get_data_status).post(set_data_status).options(options_handler))
+        .route("/api/roaming", get(get_roaming_status_handler).post(set_roaming_status_handler).options(options_handler))
+        .route("/api/airplane-mode", get(get_airplane_mode_handler).post(set_airplane_mode_handler).options(options_handler))
+        // ========== 射频模式接口 ==========
+        .route("/api/radio-mode", get(get_radio_mode_handler).post(set_radio_mode_handler).options(options_handler))
+        .route("/api/band-lock", get(get_band_lock_handler).post(set_band_lock_handler).options(options_handler))
+        .route("/api/cell-lock", get(get_cell_lock_handler).post(set_cell_lock_handler).options(options_handler))
+        .route("/api/cell-lock/unlock-all", post(unlock_all_cells_handler).options(options_handler))
+        // ========== APN 管理接口 ==========
+        .route("/api/apn", get(get_apn_list_handler).post(set_apn_handler).options(options_handler))
+        // ========== 电话功能接口 ==========
+        .route("/api/calls", get(get_calls_handler).options(options_handler))
+        .route("/api/call/dial", post(dial_call_handler).options(options_handler))
+        .route("/api/call/hangup", post(hangup_call_handler).options(options_handler))
+        .route("/api/call/hangup-all", post(hangup_all_calls_handler).options(options_handler))
+        .route("/api/call/answer", post(answer_call_handler).options(options_handler))
+        .route("/api/call/swap", post(swap_calls_handler).options(options_handler))
+        .route("/api/call/release-and-answer", post(release_and_answer_handler).options(options_handler))
+        .route("/api/call/hold-and-answer", post(hold_and_answer_handler).options(options_handler))
+        .route("/api/call/multiparty", post(create_multiparty_handler).options(options_handler))
+        .route("/api/call/multiparty/private-chat", post(private_chat_handler).options(options_handler))
+        .route("/api/call/multiparty/hangup", post(hangup_multiparty_handler).options(options_handler))
+        .route("/api/call/tones", post(send_tones_handler).options(options_handler))
+        .route("/api/call/deflect", post(deflect_call_handler).options(options_handler))
+        .route("/api/call/volume", get(get_call_volume_handler).post(set_call_volume_handler).options(options_handler))
+        .route("/api/call/forwarding", get(get_call_forwarding_handler).post(set_call_forwarding_handler).options(options_handler))
+        .route("/api/call/settings", get(get_call_settings_handler).post(set_call_settings_handler).options(options_handler))
+        .route("/api/call/filter", get(get_call_filter_handler).post(set_call_filter_handler).options(options_handler))
+        .route("/api/call/policy", get(get_call_policy_handler).post(set_call_policy_handler).options(options_handler))
+        .route("/api/call/history", get(get_call_history_handler).options(options_handler))
+        .route("/api/call/history/{id}", axum::routing::delete(delete_call_history_handler).options(options_handler))
+        .route("/api/call/history/clear", post(clear_call_history_handler).options(options_handler))
+        // ========== 短信功能接口 ==========
+        .route("/api/sms/send", post(send_sms_handler).options(options_handler))
+        .route("/api/sms/list", get(get_sms_list_handler).options(options_handler))
+        .route("/api/sms/conversation", get(get_sms_conversation_handler).options(options_handler))
+        .route("/api/sms/stats", get(get_sms_stats_handler).options(options_handler))
+        .route("/api/sms/clear", post(clear_sms_handler).options(options_handler))
+        .route("/api/sms/threads", get(list_sms_threads_handler).options(options_handler))
+        .route("/api/sms/threads/{id}", get(get_sms_thread_handler).delete(delete_sms_thread_handler).options(options_handler))
+        .route("/api/sms/threads/{id}/read", post(mark_sms_thread_read_handler).options(options_handler))
+        .route("/api/sms/threads/{id}/archive", post(archive_sms_thread_handler).options(options_handler))
+        .route("/api/sms/messages/{id}", axum::routing::delete(delete_sms_handler).options(options_handler))
+        .route("/api/sms/messages/{id}/read", post(mark_sms_read_handler).options(options_handler))
+        .route("/api/sms/search", get(search_sms_handler).options(options_handler))
+        .route("/api/sms/ingest/config", get(get_sms_ingest_config_handler).post(set_sms_ingest_config_handler).options(options_handler))
+        .route("/api/sms/queue", get(list_sms_queue_handler).post(create_sms_queue_handler).options(options_handler))
+        .route("/api/sms/queue/config", get(get_sms_queue_config_handler).post(set_sms_queue_config_handler).options(options_handler))
+        .route("/api/sms/rules", get(get_sms_rules_handler).post(set_sms_rules_handler).options(options_handler))
+        .route("/api/sms/queue/{id}", get(get_sms_queue_item_handler).put(update_sms_queue_item_handler).delete(delete_sms_queue_item_handler).options(options_handler))
+        // ========== 通讯录接口 ==========
+        .route("/api/contacts", get(list_contacts_handler).post(create_contact_handler).options(options_handler))
+        .route("/api/contacts/import", post(import_contacts_handler).options(options_handler))
+        .route("/api/contacts/import/sim", post(import_sim_contacts_handler).options(options_handler))
+        .route("/api/contacts/export", get(export_contacts_handler).options(options_handler))
+        .route(
+            "/api/contacts/{id}",
+            get(get_contact_handler)
+                .put(update_contact_handler)
+                .delete(delete_contact_handler)
+                .options(options_handler),
+        )
+        // ========== USSD 接口 ==========
+        .route("/api/ussd/status", get(get_ussd_status_handler).options(options_handler))
+        .route("/api/ussd/initiate", post(initiate_ussd_handler).options(options_handler))
+        .route("/api/ussd/respond", post(respond_ussd_handler).options(options_handler))
+        .route("/api/ussd/cancel", post(cancel_ussd_handler).options(options_handler))
+        .route("/api/ussd/history", get(get_ussd_history_handler).options(options_handler))
+        .route("/api/ussd/history/clear", post(clear_ussd_history_handler).options(options_handler))
+        // ========== IMS/VoLTE 接口 ==========
+        .route("/api/ims/status", get(get_ims_status_handler).options(options_handler))
+        .route("/api/voicemail/status", get(get_voicemail_status_handler).options(options_handler))
+        // ========== USB 模式接口 ==========
+        .route("/api/usb-mode", get(get_usb_mode).post(set_usb_mode).options(options_handler))
+        .route("/api/usb-advance", post(set_usb_mode_advanced).options(options_handler))
+        // ========== 系统接口 ==========
+        .route("/api/stats", get(get_system_stats).options(options_handler))
+        .route("/api/stats/cpu", get(get_cpu_info).options(options_handler))
+        .route("/api/connectivity", get(get_connectivity_check).options(options_handler))
+        .route("/api/system/reboot", post(system_reboot).options(options_handler))
+        .route("/api/health", get(health_check))
+        // ========== init.sh 管理接口 ==========
+        .route("/api/init-script", get(get_init_script_handler).post(set_init_script_handler).options(options_handler))
+        // ========== Webhook 配置接口 ==========
+        .route("/api/webhook/config", get(get_webhook_config_handler).post(set_webhook_config_handler).options(options_handler))
+        .route("/api/webhook/test", post(test_webhook_handler).options(options_handler))
+        // ========== 短信推送配置接口 ==========
+        .route("/api/sms-push/config", get(get_sms_push_config_handler).post(set_sms_push_config_handler).options(options_handler))
+        .route("/api/sms-push/test", post(test_sms_push_handler).options(options_handler))
+        .route("/api/refresh/config", get(get_refresh_config_handler).post(set_refresh_config_handler).options(options_handler))
+        .route("/api/refresh/heartbeat", post(frontend_refresh_heartbeat_handler).options(options_handler))
+        // ========== 实时事件流 ==========
+        .route("/api/events", get(events_handler).options(options_handler))
+        // ========== 流量统计接口 ==========
+        .route("/api/data-usage", get(get_data_usage_handler).options(options_handler))
+        .route("/api/data-usage/history", get(get_data_usage_history_handler).options(options_handler))
+        .route("/api/data-usage/config", get(get_data_usage_config_handler).post(set_data_usage_config_handler).options(options_handler))
+        // ========== 信号历史接口 ==========
+        .route("/api/history/signal", get(get_signal_history_handler).options(options_handler))
+        .route("/api/history/signal/config", get(get_signal_history_config_handler).post(set_signal_history_config_handler).options(options_handler))
+        // ========== OTA 更新接口 ==========
+        .route("/api/ota/status", get(get_ota_status_handler).options(options_handler))
+        // 请求体流式写入暂存目录，大小由 ota::OTA_MAX_UPLOAD_BYTES 限制
+        .route("/api/ota/upload", post(upload_ota_handler).options(options_handler))
+        .route("/api/ota/apply", post(apply_ota_handler).options(options_handler))
+        .route("/api/ota/cancel", post(cancel_ota_handler).options(options_handler))
+        .route("/api/ota/rollback", post(rollback_ota_handler).options(options_handler))
+        .route("/api/ota/channel", get(get_ota_channel_handler).post(set_ota_channel_handler).options(options_handler))
+        .route("/api/ota/check", get(get_ota_check_handler).post(check_ota_update_handler).options(options_handler))
+        .route("/api/ota/config", get(get_ota_config_handler).post(set_ota_config_handler).options(options_handler))
+        // ========== 统一状态和中间件 ==========
+        .layer(middleware::from_fn_with_state(auth_manager, auth::require_auth))
+        .with_state(app_state)
+        .layer(cors)
+        .fallback(spa_fallback)
+}
+
 /// R106 Backend Service - UDX710 5G/LTE 模块管理服务
 #[derive(Parser, Debug)]
 #[command(name = "udx710")]
@@ -142,6 +341,14 @@ struct Args {
     /// 监听地址 (默认: 0.0.0.0)
     #[arg(short = 'H', long, default_value = "0.0.0.0", env = "HOST")]
     host: String,
+
+    /// 调制解调器后端 (默认: ofono)
+    #[arg(long, value_enum, default_value = "ofono", env = "MODEM_BACKEND")]
+    modem: modem::BackendKind,
+
+    /// 允许跨域访问的前端地址，多个以逗号分隔 (默认: 仅同源)
+    #[arg(long, value_delimiter = ',', env = "CORS_ORIGIN")]
+    cors_origin: Vec<String>,
 }
 
 #[tokio::main]
@@ -194,15 +401,19 @@ async fn main() -> Result<()> {
     let webhook_sender = Arc::new(WebhookSender::new(Arc::clone(&config_manager)));
     let sms_push_sender = Arc::new(SmsPushSender::new(Arc::clone(&config_manager)));
     let frontend_runtime = Arc::new(FrontendRuntime::new());
+    let auth_manager = Arc::new(AuthManager::new(Arc::clone(&config_manager)));
+    let event_bus = Arc::new(EventBus::new());
     
     // 启动 SMS 监听线程
     {
         let conn_clone = Connection::system().await?;
         let db_clone = Arc::clone(&app_db);
+        let config_clone = Arc::clone(&config_manager);
         let webhook_clone = Arc::clone(&webhook_sender);
         let sms_push_clone = Arc::clone(&sms_push_sender);
+        let events_clone = Arc::clone(&event_bus);
         tokio::spawn(async move {
-            let _ = sms_listener::start_sms_listener(conn_clone, db_clone, webhook_clone, sms_push_clone).await;
+            let _ = sms_listener::start_sms_listener(conn_clone, db_clone, config_clone, webhook_clone, sms_push_clone, events_clone).await;
         });
     }
     
@@ -210,9 +421,35 @@ async fn main() -> Result<()> {
     {
         let conn_clone = Connection::system().await?;
         let db_clone = Arc::clone(&app_db);
+        let config_clone = Arc::clone(&config_manager);
         let webhook_clone = Arc::clone(&webhook_sender);
+        let sms_push_clone = Arc::clone(&sms_push_sender);
+        let events_clone = Arc::clone(&event_bus);
         tokio::spawn(async move {
-            let _ = sms_listener::start_call_listener(conn_clone, db_clone, webhook_clone).await;
+            let _ = sms_listener::start_call_listener(conn_clone, db_clone, config_clone, webhook_clone, sms_push_clone, events_clone).await;
+        });
+    }
+    
+    // 启动 USSD 监听（记录网络主动下发的 USSD 消息）
+    {
+        let conn_clone = Connection::system().await?;
+        let db_clone = Arc::clone(&app_db);
+        let events_clone = Arc::clone(&event_bus);
+        tokio::spawn(async move {
+            if let Err(e) = ussd::start_ussd_listener(conn_clone, db_clone, events_clone).await {
+                warn!(error = %e, "USSD listener stopped");
+            }
+        });
+    }
+
+    // 启动 ofono 属性变化监听（网络注册、SIM 卡状态推送到事件流）
+    {
+        let conn_clone = Connection::system().await?;
+        let events_clone = Arc::clone(&event_bus);
+        tokio::spawn(async move {
+            if let Err(e) = events::start_property_listener(conn_clone, events_clone).await {
+                warn!(error = %e, "Property listener stopped");
+            }
         });
     }
     
@@ -232,19 +469,68 @@ async fn main() -> Result<()> {
         let conn_clone = Arc::clone(&dbus_conn);
         let config_manager = Arc::clone(&config_manager);
         let frontend_runtime = Arc::clone(&frontend_runtime);
+        let events_clone = Arc::clone(&event_bus);
         tokio::spawn(async move {
             // 初始延迟 5 秒，等待系统稳定
             tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             tracing::info!("Watchdog started");
-            dbus::data_connection_watchdog(conn_clone, config_manager, frontend_runtime).await;
+            dbus::data_connection_watchdog(conn_clone, config_manager, frontend_runtime, events_clone).await;
         });
     }
 
-    // CORS 配置：允许前端开发服务器跨域访问
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 启动信号质量采样（写入信号历史）
+    {
+        let conn_clone = Arc::clone(&dbus_conn);
+        let db_clone = Arc::clone(&app_db);
+        let config_manager = Arc::clone(&config_manager);
+        tokio::spawn(async move {
+            signal_history::start_signal_sampler(conn_clone, db_clone, config_manager).await;
+        });
+    }
+
+    // 启动流量统计（累计接口流量、检查月配额）
+    {
+        let conn_clone = Arc::clone(&dbus_conn);
+        let db_clone = Arc::clone(&app_db);
+        let config_manager = Arc::clone(&config_manager);
+        let webhook_clone = Arc::clone(&webhook_sender);
+        tokio::spawn(async move {
+            data_usage::start_data_usage_monitor(conn_clone, db_clone, config_manager, webhook_clone).await;
+        });
+    }
+
+    // 启动 Webhook 设备通知（启动、数据断开、网络注册丢失/恢复、OTA）
+    {
+        let webhook_clone = Arc::clone(&webhook_sender);
+        let events_clone = Arc::clone(&event_bus);
+        tokio::spawn(async move {
+            webhook::start_event_forwarder(webhook_clone, events_clone).await;
+        });
+    }
+
+    // 启动 OTA 更新通道（定期检查更新清单）
+    {
+        let config_manager = Arc::clone(&config_manager);
+        let events_clone = Arc::clone(&event_bus);
+        tokio::spawn(async move {
+            ota_channel::start_ota_channel_poller(config_manager, events_clone).await;
+        });
+    }
+
+    // 创建调制解调器后端
+    let modem_backend = modem::create_backend(args.modem, Arc::clone(&dbus_conn));
+    info!(backend = modem_backend.name(), "Modem backend initialized");
+    let boot_confirm_modem = Arc::clone(&modem_backend);
+
+    // 启动短信发送队列（定时/周期短信和失败重试）
+    {
+        let db_clone = Arc::clone(&app_db);
+        let modem_clone = Arc::clone(&modem_backend);
+        let config_manager = Arc::clone(&config_manager);
+        tokio::spawn(async move {
+            sms_queue::start_sms_queue_worker(db_clone, modem_clone, config_manager).await;
+        });
+    }
 
     // 创建统一的应用状态
     let app_state = AppState::new(
@@ -254,94 +540,12 @@ async fn main() -> Result<()> {
         webhook_sender,
         sms_push_sender,
         frontend_runtime,
+        auth_manager,
+        event_bus,
+        modem_backend,
     );
 
-    // Build routes - 使用统一的 AppState
-    let app = Router::new()
-        // ========== AT 指令接口 ==========
-        .route("/api/at", post(post_at_command).options(options_handler))
-        // ========== 设备信息接口 ==========
-        .route("/api/device", get(get_device_info).options(options_handler))
-        .route("/api/device/imeisv", get(get_imeisv_handler).options(options_handler))
-        // ========== SIM 卡接口 ==========
-        .route("/api/sim", get(get_sim_info).options(options_handler))
-        .route("/api/sim/slot", get(get_sim_slot_handler).options(options_handler))
-        .route("/api/sim/slot/switch", post(switch_sim_slot_handler).options(options_handler))
-        // ========== 网络接口 ==========
-        .route("/api/network", get(get_network_info).options(options_handler))
-        .route("/api/network/interfaces", get(get_network_interfaces_info).options(options_handler))
-        .route("/api/network/signal-strength", get(get_signal_strength_handler).options(options_handler))
-        .route("/api/network/nitz", get(get_nitz_handler).options(options_handler))
-        .route("/api/network/operators", get(get_operators_handler).options(options_handler))
-        .route("/api/network/operators/scan", get(scan_operators_handler).options(options_handler))
-        .route("/api/network/register-manual", post(register_operator_manual_handler).options(options_handler))
-        .route("/api/network/register-auto", post(register_operator_auto_handler).options(options_handler))
-        // ========== 小区信息接口 ==========
-        .route("/api/cells", get(get_cells).options(options_handler))
-        .route("/api/location/cell-info", get(get_cell_location_info).options(options_handler))
-        // ========== QoS 接口 ==========
-        .route("/api/qos", get(get_qos_info).options(options_handler))
-        // ========== 数据连接接口 ==========
-        .route("/api/data", get(get_data_status).post(set_data_status).options(options_handler))
-        .route("/api/roaming", get(get_roaming_status_handler).post(set_roaming_status_handler).options(options_handler))
-        .route("/api/airplane-mode", get(get_airplane_mode_handler).post(set_airplane_mode_handler).options(options_handler))
-        // ========== 射频模式接口 ==========
-        .route("/api/radio-mode", get(get_radio_mode_handler).post(set_radio_mode_handler).options(options_handler))
-        .route("/api/band-lock", get(get_band_lock_handler).post(set_band_lock_handler).options(options_handler))
-        .route("/api/cell-lock", get(get_cell_lock_handler).post(set_cell_lock_handler).options(options_handler))
-        .route("/api/cell-lock/unlock-all", post(unlock_all_cells_handler).options(options_handler))
-        // ========== APN 管理接口 ==========
-        .route("/api/apn", get(get_apn_list_handler).post(set_apn_handler).options(options_handler))
-        // ========== 电话功能接口 ==========
-        .route("/api/calls", get(get_calls_handler).options(options_handler))
-        .route("/api/call/dial", post(dial_call_handler).options(options_handler))
-        .route("/api/call/hangup", post(hangup_call_handler).options(options_handler))
-        .route("/api/call/hangup-all", post(hangup_all_calls_handler).options(options_handler))
-        .route("/api/call/answer", post(answer_call_handler).options(options_handler))
-        .route("/api/call/volume", get(get_call_volume_handler).post(set_call_volume_handler).options(options_handler))
-        .route("/api/call/forwarding", get(get_call_forwarding_handler).post(set_call_forwarding_handler).options(options_handler))
-        .route("/api/call/settings", get(get_call_settings_handler).post(set_call_settings_handler).options(options_handler))
-        .route("/api/call/history", get(get_call_history_handler).options(options_handler))
-        .route("/api/call/history/{id}", axum::routing::delete(delete_call_history_handler).options(options_handler))
-        .route("/api/call/history/clear", post(clear_call_history_handler).options(options_handler))
-        // ========== 短信功能接口 ==========
-        .route("/api/sms/send", post(send_sms_handler).options(options_handler))
-        .route("/api/sms/list", get(get_sms_list_handler).options(options_handler))
-        .route("/api/sms/conversation", get(get_sms_conversation_handler).options(options_handler))
-        .route("/api/sms/stats", get(get_sms_stats_handler).options(options_handler))
-        .route("/api/sms/clear", post(clear_sms_handler).options(options_handler))
-        // ========== IMS/VoLTE 接口 ==========
-        .route("/api/ims/status", get(get_ims_status_handler).options(options_handler))
-        .route("/api/voicemail/status", get(get_voicemail_status_handler).options(options_handler))
-        // ========== USB 模式接口 ==========
-        .route("/api/usb-mode", get(get_usb_mode).post(set_usb_mode).options(options_handler))
-        .route("/api/usb-advance", post(set_usb_mode_advanced).options(options_handler))
-        // ========== 系统接口 ==========
-        .route("/api/stats", get(get_system_stats).options(options_handler))
-        .route("/api/stats/cpu", get(get_cpu_info).options(options_handler))
-        .route("/api/connectivity", get(get_connectivity_check).options(options_handler))
-        .route("/api/system/reboot", post(system_reboot).options(options_handler))
-        .route("/api/health", get(health_check))
-        // ========== init.sh 管理接口 ==========
-        .route("/api/init-script", get(get_init_script_handler).post(set_init_script_handler).options(options_handler))
-        // ========== Webhook 配置接口 ==========
-        .route("/api/webhook/config", get(get_webhook_config_handler).post(set_webhook_config_handler).options(options_handler))
-        .route("/api/webhook/test", post(test_webhook_handler).options(options_handler))
-        // ========== 短信推送配置接口 ==========
-        .route("/api/sms-push/config", get(get_sms_push_config_handler).post(set_sms_push_config_handler).options(options_handler))
-        .route("/api/sms-push/test", post(test_sms_push_handler).options(options_handler))
-        .route("/api/refresh/config", get(get_refresh_config_handler).post(set_refresh_config_handler).options(options_handler))
-        .route("/api/refresh/heartbeat", post(frontend_refresh_heartbeat_handler).options(options_handler))
-        // ========== OTA 更新接口 ==========
-        .route("/api/ota/status", get(get_ota_status_handler).options(options_handler))
-        .route("/api/ota/upload", post(upload_ota_handler).options(options_handler)
-            .layer(DefaultBodyLimit::max(50 * 1024 * 1024))) // 50MB 限制
-        .route("/api/ota/apply", post(apply_ota_handler).options(options_handler))
-        .route("/api/ota/cancel", post(cancel_ota_handler).options(options_handler))
-        // ========== 统一状态和中间件 ==========
-        .with_state(app_state)
-        .layer(cors)
-        .fallback(spa_fallback);
+    let app = build_router(app_state, &args.cors_origin);
 
     // Start server - 显示版权信息
     info!(
@@ -355,8 +559,18 @@ async fn main() -> Result<()> {
     // 绑定端口，如果被占用则轮询等待（最多 30 秒）
     let listener = bind_with_retry(&bind_addr, 30).await?;
     info!(addr = %bind_addr, "Server listening");
+
+    // OTA 新槽位启动后，健康检查通过才确认启动成功（否则 loader.sh 会自动回滚）
+    {
+        let health_host = match args.host.as_str() {
+            "0.0.0.0" | "::" | "[::]" => "127.0.0.1",
+            host => host,
+        };
+        let health_url = format!("http://{}:{}/api/health", health_host, args.port);
+        tokio::spawn(ota::confirm_boot_when_healthy(health_url, boot_confirm_modem));
+    }
     // 使用优雅关闭
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
@@ -0,0 +1,270 @@
+//! minisign 签名验证
+//!
+//! 兼容 [minisign](https://jedisct1.github.io/minisign/) 的公钥和 `.minisig` 签名文件：
+//! - 公钥：`base64("Ed" + 8 字节密钥 ID + 32 字节 Ed25519 公钥)`
+//! - 签名：`base64(算法 + 8 字节密钥 ID + 64 字节签名)`，算法 `Ed` 直接签名原文，
+//!   `ED`（minisign 0.10 起的默认值）签名原文的 BLAKE2b-512 摘要
+//! - 全局签名：对 `签名 + 可信注释` 的 Ed25519 签名，保证可信注释未被篡改
+//!
+//! Ed25519 验证使用 ring，BLAKE2b 摘要使用 blake2。
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ring::signature::{UnparsedPublicKey, ED25519};
+
+const UNTRUSTED_COMMENT_PREFIX: &str = "untrusted comment:";
+const TRUSTED_COMMENT_PREFIX: &str = "trusted comment: ";
+
+/// minisign 公钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// 解析公钥，可以是 base64 字符串或完整的 `.pub` 文件内容
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .rfind(|l| !l.is_empty() && !l.starts_with(UNTRUSTED_COMMENT_PREFIX))
+            .ok_or_else(|| "Empty public key".to_string())?;
+        let bytes = BASE64.decode(line).map_err(|e| format!("Invalid public key encoding: {}", e))?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err("Invalid public key: not a minisign Ed25519 key".to_string());
+        }
+
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&bytes[2..10]);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&bytes[10..]);
+        Ok(Self { key_id, key })
+    }
+
+    /// 密钥 ID（与 minisign 显示的格式一致）
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+
+    fn verify_raw(&self, message: &[u8], signature: &[u8]) -> bool {
+        UnparsedPublicKey::new(&ED25519, self.key).verify(message, signature).is_ok()
+    }
+}
+
+/// minisign 签名文件（`.minisig`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    prehashed: bool,
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    trusted_comment: String,
+    global_signature: [u8; 64],
+}
+
+impl Signature {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().map(|l| l.trim_end_matches('\r'));
+        let mut next = |what: &str| lines.next().ok_or_else(|| format!("Invalid signature: missing {}", what));
+
+        if !next("untrusted comment")?.starts_with(UNTRUSTED_COMMENT_PREFIX) {
+            return Err("Invalid signature: missing untrusted comment".to_string());
+        }
+        let bytes = BASE64
+            .decode(next("signature")?.trim())
+            .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+        let trusted_comment = next("trusted comment")?
+            .strip_prefix(TRUSTED_COMMENT_PREFIX)
+            .ok_or_else(|| "Invalid signature: missing trusted comment".to_string())?
+            .to_string();
+        let global = BASE64
+            .decode(next("global signature")?.trim())
+            .map_err(|e| format!("Invalid global signature encoding: {}", e))?;
+
+        if bytes.len() != 74 || global.len() != 64 {
+            return Err("Invalid signature length".to_string());
+        }
+        let prehashed = match &bytes[..2] {
+            b"Ed" => false,
+            b"ED" => true,
+            _ => return Err("Unsupported signature algorithm".to_string()),
+        };
+
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&bytes[2..10]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&bytes[10..]);
+        let mut global_signature = [0u8; 64];
+        global_signature.copy_from_slice(&global);
+        Ok(Self {
+            prehashed,
+            key_id,
+            signature,
+            trusted_comment,
+            global_signature,
+        })
+    }
+
+    /// 签名所用密钥的 ID
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+
+    /// 可信注释（受全局签名保护）
+    pub fn trusted_comment(&self) -> &str {
+        &self.trusted_comment
+    }
+
+    /// 使用指定公钥验证数据
+    pub fn verify(&self, public_key: &PublicKey, data: &[u8]) -> Result<(), String> {
+        if self.key_id != public_key.key_id {
+            return Err(format!(
+                "Signature key ID {} does not match public key {}",
+                self.key_id(),
+                public_key.key_id()
+            ));
+        }
+
+        let verified = if self.prehashed {
+            public_key.verify_raw(&Blake2b512::digest(data), &self.signature)
+        } else {
+            public_key.verify_raw(data, &self.signature)
+        };
+        if !verified {
+            return Err("Signature verification failed".to_string());
+        }
+
+        let mut global_data = self.signature.to_vec();
+        global_data.extend_from_slice(self.trusted_comment.as_bytes());
+        if !public_key.verify_raw(&global_data, &self.global_signature) {
+            return Err("Trusted comment signature verification failed".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// minisign 以小端 u64 的十六进制显示密钥 ID
+fn format_key_id(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ring::signature::Ed25519KeyPair;
+
+    /// 测试用密钥对，返回（公钥文本, 密钥对）
+    pub(crate) fn test_keypair(seed: u8) -> (String, Ed25519KeyPair) {
+        use ring::signature::KeyPair;
+        let keypair = Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap();
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&[seed; 8]);
+        bytes.extend_from_slice(keypair.public_key().as_ref());
+        (format!("untrusted comment: minisign public key\n{}\n", BASE64.encode(bytes)), keypair)
+    }
+
+    /// 按 minisign 格式签名（默认预哈希）
+    pub(crate) fn sign(keypair: &Ed25519KeyPair, key_seed: u8, data: &[u8], trusted_comment: &str) -> String {
+        let signature = keypair.sign(&Blake2b512::digest(data));
+        let mut bytes = b"ED".to_vec();
+        bytes.extend_from_slice(&[key_seed; 8]);
+        bytes.extend_from_slice(signature.as_ref());
+
+        let mut global_data = signature.as_ref().to_vec();
+        global_data.extend_from_slice(trusted_comment.as_bytes());
+        let global = keypair.sign(&global_data);
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+            BASE64.encode(bytes),
+            trusted_comment,
+            BASE64.encode(global.as_ref())
+        )
+    }
+
+    /// minisign 生成的公钥和签名（签名内容均为 `test`），与 minisign-verify 的测试向量相同
+    const MINISIGN_PUBLIC_KEY: &str = "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+";
+    const MINISIGN_PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==
+";
+    const MINISIGN_LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+
+    #[test]
+    fn verifies_signatures_created_by_minisign() {
+        let public_key = PublicKey::parse(MINISIGN_PUBLIC_KEY).unwrap();
+        assert_eq!(public_key.key_id(), "E7620F1842B4E81F");
+
+        let prehashed = Signature::parse(MINISIGN_PREHASHED_SIGNATURE).unwrap();
+        assert!(prehashed.prehashed);
+        assert_eq!(prehashed.key_id(), "E7620F1842B4E81F");
+        assert_eq!(prehashed.trusted_comment(), "timestamp:1556193335\tfile:test");
+        prehashed.verify(&public_key, b"test").unwrap();
+        assert!(prehashed.verify(&public_key, b"Test").is_err());
+
+        let legacy = Signature::parse(MINISIGN_LEGACY_SIGNATURE).unwrap();
+        assert!(!legacy.prehashed);
+        legacy.verify(&public_key, b"test").unwrap();
+        assert!(legacy.verify(&public_key, b"Test").is_err());
+    }
+
+    #[test]
+    fn verifies_prehashed_and_legacy_signatures() {
+        let (public_key, keypair) = test_keypair(1);
+        let public_key = PublicKey::parse(&public_key).unwrap();
+        assert_eq!(public_key.key_id(), "0101010101010101");
+
+        let data = b"{\"version\":\"9.9.9\"}";
+        let signature = Signature::parse(&sign(&keypair, 1, data, "timestamp:1 file:meta.json")).unwrap();
+        assert_eq!(signature.trusted_comment(), "timestamp:1 file:meta.json");
+        signature.verify(&public_key, data).unwrap();
+        assert!(signature.verify(&public_key, b"tampered").is_err());
+
+        let legacy = Signature {
+            prehashed: false,
+            signature: keypair.sign(data).as_ref().try_into().unwrap(),
+            ..signature.clone()
+        };
+        assert!(legacy.verify(&public_key, data).is_err(), "global signature covers the original signature");
+        let mut global_data = legacy.signature.to_vec();
+        global_data.extend_from_slice(legacy.trusted_comment.as_bytes());
+        let legacy = Signature {
+            global_signature: keypair.sign(&global_data).as_ref().try_into().unwrap(),
+            ..legacy
+        };
+        legacy.verify(&public_key, data).unwrap();
+    }
+
+    #[test]
+    fn rejects_wrong_key_and_tampered_comment() {
+        let (_, keypair) = test_keypair(1);
+        let (other_key, other_keypair) = test_keypair(2);
+        let data = b"payload";
+
+        let signature = Signature::parse(&sign(&keypair, 1, data, "release")).unwrap();
+        let other_key = PublicKey::parse(&other_key).unwrap();
+        assert!(signature.verify(&other_key, data).unwrap_err().contains("does not match"));
+
+        // 伪造密钥 ID 也无法通过验证
+        let forged = Signature::parse(&sign(&keypair, 2, data, "release")).unwrap();
+        assert!(forged.verify(&other_key, data).is_err());
+
+        let text = sign(&other_keypair, 2, data, "release").replace("trusted comment: release", "trusted comment: hacked");
+        assert!(Signature::parse(&text).unwrap().verify(&other_key, data).unwrap_err().contains("Trusted comment"));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(PublicKey::parse("").is_err());
+        assert!(PublicKey::parse("not base64!").is_err());
+        assert!(PublicKey::parse(&BASE64.encode([0u8; 42])).is_err());
+        assert!(Signature::parse("untrusted comment: x\nAAAA\n").is_err());
+    }
+}
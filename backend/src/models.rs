@@ -13,6 +13,7 @@
 //! 包含所有API的请求和响应数据结构
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// 统一的 API 响应结构
 #[derive(Debug, Serialize)]
@@ -199,10 +200,68 @@ pub struct SimInfoResponse {
     pub mnc: String,
     /// PIN 状态（none/pin/puk）
     pub pin_required: String,
+    /// 已启用的 PIN 锁（如 pin）
+    pub locked_pins: Vec<String>,
+    /// 剩余尝试次数（如 pin: 3, puk: 10）
+    pub retries: HashMap<String, u8>,
     /// 首选语言列表
     pub preferred_languages: Vec<String>,
 }
 
+/// 输入 PIN / 启用或关闭 PIN 锁请求
+#[derive(Debug, Deserialize)]
+pub struct SimPinRequest {
+    /// PIN 类型，默认 pin
+    #[serde(default = "default_pin_type")]
+    pub pin_type: String,
+    pub pin: String,
+}
+
+fn default_pin_type() -> String {
+    "pin".to_string()
+}
+
+/// PUK 解锁请求
+#[derive(Debug, Deserialize)]
+pub struct SimPukRequest {
+    /// PUK 类型，默认 puk
+    #[serde(default = "default_puk_type")]
+    pub puk_type: String,
+    pub puk: String,
+    pub new_pin: String,
+}
+
+fn default_puk_type() -> String {
+    "puk".to_string()
+}
+
+/// 修改 PIN 请求
+#[derive(Debug, Deserialize)]
+pub struct SimChangePinRequest {
+    /// PIN 类型，默认 pin
+    #[serde(default = "default_pin_type")]
+    pub pin_type: String,
+    pub old_pin: String,
+    pub new_pin: String,
+}
+
+/// 设置自动输入 PIN 请求
+#[derive(Debug, Deserialize)]
+pub struct SimPinConfigRequest {
+    pub auto_enter: bool,
+    /// 不传时保留已保存的 PIN，传空字符串时清除
+    #[serde(default)]
+    pub pin: Option<String>,
+}
+
+/// 自动输入 PIN 配置（不返回 PIN 本身）
+#[derive(Debug, Serialize, Default)]
+pub struct SimPinConfigResponse {
+    pub auto_enter: bool,
+    /// 是否已保存 PIN
+    pub pin_set: bool,
+}
+
 /// SIM 卡槽信息
 #[derive(Debug, Serialize, Default)]
 pub struct SimSlotResponse {
@@ -662,10 +721,18 @@ pub struct BandLockRequest {
     pub nr_tdd_bands: Vec<u8>,
 }
 
+impl BandLockRequest {
+    /// 所有频段列表都为空时表示解除锁定
+    pub fn is_unlock(&self) -> bool {
+        self.lte_fdd_bands.is_empty()
+            && self.lte_tdd_bands.is_empty()
+            && self.nr_fdd_bands.is_empty()
+            && self.nr_tdd_bands.is_empty()
+    }
+}
+
 // ============ 小区锁定模型 ============
-// 使用展锐 AT+SPFORCEFRQ 指令实现
-// 类型: 12=LTE, 16=NR
-// 操作: 0=清除, 2=设置
+// rat: 12=LTE, 16=NR
 
 /// 单个 RAT 的小区锁定状态
 #[derive(Debug, Serialize, Default, Clone)]
@@ -746,13 +813,17 @@ pub struct CallInfo {
     pub path: String,
     /// 电话号码
     pub phone_number: String,
-    /// 通话状态：active, dialing, alerting, incoming, held
+    /// 通话状态：active, dialing, alerting, incoming, waiting, held
     pub state: String,
     /// 通话方向：incoming 或 outgoing
     pub direction: String,
     /// 开始时间（可选）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_time: Option<String>,
+    /// 是否处于保持状态
+    pub held: bool,
+    /// 是否属于多方通话
+    pub multiparty: bool,
 }
 
 /// 通话列表响应
@@ -776,6 +847,8 @@ impl Default for CallInfo {
             state: String::new(),
             direction: String::new(),
             start_time: None,
+            held: false,
+            multiparty: false,
         }
     }
 }
@@ -787,6 +860,29 @@ pub struct HangupCallRequest {
     pub path: String,
 }
 
+/// 多方通话响应
+#[derive(Debug, Serialize, Default)]
+pub struct MultipartyResponse {
+    /// 多方通话中的通话路径
+    pub calls: Vec<String>,
+}
+
+/// DTMF 按键音请求
+#[derive(Debug, Deserialize)]
+pub struct SendTonesRequest {
+    /// 按键序列：0-9、*、#、A-D，`p` 表示暂停
+    pub tones: String,
+}
+
+/// 来电转接请求
+#[derive(Debug, Deserialize)]
+pub struct DeflectCallRequest {
+    /// 通话路径
+    pub path: String,
+    /// 转接目标号码
+    pub phone_number: String,
+}
+
 // ============ NITZ 网络时间模型 ============
 
 /// NITZ 网络时间响应
@@ -976,6 +1072,100 @@ pub struct SendSmsRequest {
     pub content: String,
 }
 
+/// 创建/修改短信发送队列任务请求
+#[derive(Debug, Deserialize)]
+pub struct SmsQueueRequest {
+    /// 目标电话号码
+    pub phone_number: String,
+    /// 短信内容
+    pub content: String,
+    /// 首次发送时间（Unix 时间戳，秒），为空时立即发送或按 `schedule` 的下次触发时间发送
+    #[serde(default)]
+    pub send_at: Option<i64>,
+    /// cron 表达式（本地时间），设置后周期发送
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// 最大尝试次数，为空时使用队列配置
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+/// 新建或更新联系人请求
+#[derive(Debug, Deserialize)]
+pub struct ContactRequest {
+    pub name: String,
+    pub phone_number: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// 联系人列表请求
+#[derive(Debug, Deserialize)]
+pub struct ContactListRequest {
+    /// 按名称或号码搜索
+    #[serde(default)]
+    pub search: Option<String>,
+}
+
+/// 导入联系人请求
+#[derive(Debug, Deserialize)]
+pub struct ContactImportRequest {
+    /// vcard 或 csv
+    pub format: String,
+    /// 文件内容
+    pub data: String,
+}
+
+/// 导出联系人请求
+#[derive(Debug, Deserialize)]
+pub struct ContactExportRequest {
+    /// vcard（默认）或 csv
+    #[serde(default = "default_contact_export_format")]
+    pub format: String,
+}
+
+fn default_contact_export_format() -> String {
+    "vcard".to_string()
+}
+
+/// 发起 USSD 请求
+#[derive(Debug, Deserialize)]
+pub struct UssdInitiateRequest {
+    /// USSD 指令，如 `*100#`
+    pub command: String,
+}
+
+/// 回复 USSD 菜单请求
+#[derive(Debug, Deserialize)]
+pub struct UssdRespondRequest {
+    /// 回复内容（菜单选项等）
+    pub response: String,
+}
+
+/// USSD 会话响应
+#[derive(Debug, Serialize, Default)]
+pub struct UssdResponse {
+    /// 会话状态: idle / active / user-response（等待回复）
+    pub state: String,
+    /// 结果类型（USSD 或补充业务名称）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_type: Option<String>,
+    /// 网络返回的消息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// USSD 记录查询请求
+#[derive(Debug, Deserialize)]
+pub struct UssdHistoryRequest {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
 /// 短信列表请求
 #[derive(Debug, Deserialize)]
 pub struct SmsListRequest {
@@ -1001,6 +1191,84 @@ pub struct SmsConversationRequest {
     pub limit: i64,
 }
 
+/// 短信会话列表请求
+#[derive(Debug, Deserialize)]
+pub struct SmsThreadListRequest {
+    /// 是否列出已归档的会话（默认 false）
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default = "default_page_size")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// 短信会话详情
+#[derive(Debug, Serialize)]
+pub struct SmsThreadResponse {
+    pub thread: crate::db::SmsThread,
+    /// 会话中的短信（最新的在前）
+    pub messages: Vec<crate::db::SmsMessage>,
+}
+
+/// 标记已读/未读请求
+#[derive(Debug, Deserialize)]
+pub struct SmsReadRequest {
+    /// true 为已读，false 为未读（默认 true）
+    #[serde(default = "default_true")]
+    pub read: bool,
+}
+
+/// 归档请求
+#[derive(Debug, Deserialize)]
+pub struct SmsArchiveRequest {
+    /// true 为归档，false 为取消归档（默认 true）
+    #[serde(default = "default_true")]
+    pub archived: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 短信搜索请求
+#[derive(Debug, Deserialize)]
+pub struct SmsSearchRequest {
+    /// 搜索关键词（匹配短信内容）
+    pub q: String,
+    /// 限定会话
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    #[serde(default = "default_page_size")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// 短信规则设置请求
+#[derive(Debug, Deserialize)]
+pub struct SmsRulesRequest {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 设备指令 PIN：不传时保留已保存的 PIN，传空字符串时清除
+    #[serde(default)]
+    pub pin: Option<String>,
+    #[serde(default)]
+    pub authorized_senders: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<crate::config::SmsRule>,
+}
+
+/// 短信规则配置（不返回设备指令 PIN 本身）
+#[derive(Debug, Serialize, Default)]
+pub struct SmsRulesResponse {
+    pub enabled: bool,
+    /// 是否已保存设备指令 PIN
+    pub pin_set: bool,
+    pub authorized_senders: Vec<String>,
+    pub rules: Vec<crate::config::SmsRule>,
+}
+
 // ============ APN 管理模型 ============
 
 /// APN Context 信息
@@ -1109,6 +1377,18 @@ pub struct SetInitScriptRequest {
 pub struct WebhookTestResponse {
     pub success: bool,
     pub message: String,
+    /// 各目标的测试结果（主 URL 名为 `default`）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<WebhookEndpointResult>,
+}
+
+/// 单个 Webhook 目标的测试结果
+#[derive(Debug, Serialize)]
+pub struct WebhookEndpointResult {
+    pub name: String,
+    pub url: String,
+    pub success: bool,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -1135,6 +1415,12 @@ pub struct OtaMeta {
     pub binary_md5: String,
     /// 前端目录 MD5（所有文件 hash 的 hash）
     pub frontend_md5: String,
+    /// 后端二进制 SHA-256（签名的更新包必须提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary_sha256: Option<String>,
+    /// 前端目录 SHA-256（计算方式同 frontend_md5，签名的更新包必须提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frontend_sha256: Option<String>,
     /// 目标架构
     pub arch: String,
     /// 最低兼容版本（可选，用于阻止降级）
@@ -1154,6 +1440,25 @@ pub struct OtaStatusResponse {
     /// 待安装的更新信息
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pending_meta: Option<OtaMeta>,
+    /// A/B 分区状态
+    pub slot: OtaSlotStatus,
+}
+
+/// OTA A/B 分区状态
+#[derive(Debug, Serialize)]
+pub struct OtaSlotStatus {
+    /// 当前启动的槽位（base / a / b）
+    pub active_slot: String,
+    /// 可回滚的上一个槽位
+    pub previous_slot: Option<String>,
+    /// 当前槽位是否已确认启动成功
+    pub boot_confirmed: bool,
+    /// 未确认状态下已尝试启动的次数
+    pub boot_attempts: u32,
+    /// 超过该次数仍未确认则自动回滚
+    pub max_boot_attempts: u32,
+    /// 最近一次自动回滚前的槽位
+    pub rolled_back_from: Option<String>,
 }
 
 /// OTA 上传响应
@@ -1178,11 +1483,29 @@ pub struct OtaValidation {
     pub frontend_md5_match: bool,
     /// 架构是否匹配
     pub arch_match: bool,
+    /// 是否带有签名（meta.json.minisig）
+    pub signed: bool,
+    /// 签名是否由受信任的公钥验证通过
+    pub signature_valid: bool,
+    /// 签名者（签名验证通过时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<OtaSigner>,
     /// 错误消息（如果验证失败）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// OTA 更新包签名者
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct OtaSigner {
+    /// 公钥名称（编译时内置的公钥为 "builtin"）
+    pub name: String,
+    /// minisign 密钥 ID
+    pub key_id: String,
+    /// 签名中的可信注释
+    pub trusted_comment: String,
+}
+
 /// OTA 应用更新请求
 #[derive(Debug, Deserialize)]
 pub struct OtaApplyRequest {
@@ -1191,3 +1514,171 @@ pub struct OtaApplyRequest {
     pub restart_now: bool,
 }
 
+/// 更新清单中某个通道的发布信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtaRelease {
+    /// 版本号
+    pub version: String,
+    /// 更新包下载地址（可以是相对清单 URL 的路径）
+    pub url: String,
+    /// 更新包 SHA-256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// 更新包大小（字节）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// 更新说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// 更新通道检查结果
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct OtaCheckResult {
+    /// 订阅的通道
+    pub channel: String,
+    /// 当前版本
+    pub current_version: String,
+    /// 检查时间
+    pub checked_at: String,
+    /// 通道中的版本是否比当前新
+    pub update_available: bool,
+    /// 通道中的最新发布
+    pub release: Option<OtaRelease>,
+    /// 新版本是否已下载并通过验证（等待应用）
+    pub staged: bool,
+    /// 检查或下载失败的原因
+    pub error: Option<String>,
+}
+
+/// OTA 回滚请求
+#[derive(Debug, Deserialize)]
+pub struct OtaRollbackRequest {
+    /// 是否立即重启
+    #[serde(default)]
+    pub restart_now: bool,
+}
+
+
+// ============ 登录认证模型 ============
+
+/// 登录请求
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub password: String,
+}
+
+/// 登录响应
+#[derive(Debug, Serialize, Default)]
+pub struct LoginResponse {
+    /// 会话令牌，后续请求放在 `Authorization: Bearer <token>` 中
+    pub token: String,
+    /// 会话有效期（秒，每次请求后顺延）
+    pub expires_in_secs: u64,
+    /// 是否必须先修改密码（首次启动使用默认密码登录时为 true）
+    pub must_change_password: bool,
+}
+
+/// 事件流票据响应
+#[derive(Debug, Serialize, Default)]
+pub struct SseTicketResponse {
+    /// 一次性票据，打开事件流时放在 `?ticket=` 中
+    pub ticket: String,
+    /// 票据有效期（秒）
+    pub expires_in_secs: u64,
+}
+
+/// 认证状态响应
+#[derive(Debug, Serialize, Default)]
+pub struct AuthStatusResponse {
+    /// 是否已设置管理员密码
+    pub password_set: bool,
+    /// 当前请求携带的令牌是否有效
+    pub authenticated: bool,
+    /// 当前会话是否必须先修改密码
+    pub must_change_password: bool,
+}
+
+/// 修改密码请求
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// API 密钥信息（不含密钥本身）
+#[derive(Debug, Serialize, Default)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<crate::config::ApiScope>,
+    /// 允许访问的路径前缀，为空表示不限制
+    pub allowed_paths: Vec<String>,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+}
+
+/// 创建 API 密钥请求
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<crate::config::ApiScope>,
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+}
+
+/// 创建 API 密钥响应
+#[derive(Debug, Serialize, Default)]
+pub struct CreateApiKeyResponse {
+    /// 明文密钥，仅在创建时返回一次
+    pub key: String,
+    pub info: ApiKeyInfo,
+}
+
+// ============ 信号历史模型 ============
+
+/// 信号历史查询参数
+///
+/// `from`/`to` 支持 Unix 时间戳（秒）或 RFC 3339，默认最近 24 小时；
+/// `resolution` 支持 `raw`、`auto`（默认）或时间桶秒数
+#[derive(Debug, Deserialize)]
+pub struct SignalHistoryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub resolution: Option<String>,
+}
+
+/// 信号历史响应
+#[derive(Debug, Serialize, Default)]
+pub struct SignalHistoryResponse {
+    /// 查询起点（Unix 时间戳，秒）
+    pub from: i64,
+    /// 查询终点（Unix 时间戳，秒）
+    pub to: i64,
+    /// 实际使用的时间桶长度（秒），0 表示原始数据点
+    pub resolution: i64,
+    pub points: Vec<crate::db::SignalHistoryPoint>,
+}
+
+// ============ 流量统计模型 ============
+
+/// 流量历史查询参数
+#[derive(Debug, Deserialize)]
+pub struct DataUsageHistoryQuery {
+    /// 统计粒度：hour / day（默认） / month
+    #[serde(default = "default_data_usage_period")]
+    pub period: String,
+    /// 返回最近多少个统计桶（默认 31）
+    #[serde(default = "default_data_usage_limit")]
+    pub limit: i64,
+}
+
+fn default_data_usage_period() -> String {
+    "day".to_string()
+}
+
+fn default_data_usage_limit() -> i64 {
+    31
+}
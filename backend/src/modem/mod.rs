@@ -0,0 +1,215 @@
+//! 调制解调器后端抽象
+//!
+//! HTTP 处理器只依赖 [`ModemBackend`] trait，不直接拼接厂商 AT 指令或调用 ofono 接口。
+//! - [`ofono::OfonoBackend`]：ofono + 展锐 UDX710（`AT+SPLBAND`、`AT+SPFORCEFRQ`、`AT+SPENGMD`）
+//! - [`simulated::SimulatedBackend`]：内存模拟，用于无硬件时开发前端和演示
+//!
+//! 移植到其他模组（如移远）时新增一个实现，并在 [`create_backend`] 中注册即可。
+
+pub mod ofono;
+pub mod simulated;
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use zbus::Connection;
+
+use crate::models::{
+    ApnContext, BandLockRequest, BandLockStatus, CallInfo, CellInfo, CellLockStatusResponse, DeviceInfoResponse,
+    RadioMode, RadioModeResponse, ServingCell, SetApnRequest,
+};
+
+/// 调制解调器后端错误
+#[derive(Debug)]
+pub enum ModemError {
+    /// 当前后端或网络制式不支持该功能
+    Unsupported(String),
+    /// 请求参数无效
+    InvalidArgument(String),
+    /// 模组或底层服务返回错误
+    Failed(String),
+}
+
+impl fmt::Display for ModemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModemError::Unsupported(msg) | ModemError::InvalidArgument(msg) | ModemError::Failed(msg) => {
+                f.write_str(msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModemError {}
+
+impl From<zbus::Error> for ModemError {
+    fn from(e: zbus::Error) -> Self {
+        ModemError::Failed(e.to_string())
+    }
+}
+
+pub type ModemResult<T> = Result<T, ModemError>;
+
+/// 小区锁定的网络制式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellRat {
+    Lte,
+    Nr,
+}
+
+impl CellRat {
+    /// 从 API 的 `rat` 参数解析
+    ///
+    /// 12=LTE, 16=NR；兼容旧值 1/2=LTE, 5/6/7=NR，其他值按 NR 处理
+    pub fn from_api(rat: u8) -> Self {
+        match rat {
+            12 | 1 | 2 => CellRat::Lte,
+            _ => CellRat::Nr,
+        }
+    }
+
+    /// API 中使用的 RAT 编号
+    pub fn code(self) -> u8 {
+        match self {
+            CellRat::Lte => 12,
+            CellRat::Nr => 16,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellRat::Lte => "LTE",
+            CellRat::Nr => "NR",
+        }
+    }
+}
+
+/// 频段锁定操作实际改动的制式
+///
+/// 锁定时表示下发了锁定的制式；解锁时表示原本有锁定、已被解除的制式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BandLockChange {
+    pub lte: bool,
+    pub nr: bool,
+}
+
+/// 调制解调器后端
+///
+/// 覆盖设备信息、小区、频段/小区锁定、射频模式、短信、通话和 APN。
+/// 网络注册、SIM 卡等通用 ofono 功能仍由 `dbus` 模块直接提供。
+#[async_trait]
+pub trait ModemBackend: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    // ========== 设备信息 ==========
+
+    async fn device_info(&self) -> ModemResult<DeviceInfoResponse>;
+
+    // ========== 小区信息 ==========
+
+    /// 服务小区（制式、小区 ID、TAC）
+    async fn serving_cell(&self) -> ModemResult<ServingCell>;
+
+    /// 主小区和邻区测量值（主小区在前）
+    ///
+    /// 当前制式不支持查询时返回 [`ModemError::Unsupported`]
+    async fn cell_list(&self, serving: &ServingCell) -> ModemResult<Vec<CellInfo>>;
+
+    // ========== 频段锁定 ==========
+
+    async fn band_lock_status(&self) -> ModemResult<BandLockStatus>;
+
+    /// 设置频段锁定，所有频段列表为空时解除锁定
+    async fn set_band_lock(&self, request: &BandLockRequest) -> ModemResult<BandLockChange>;
+
+    // ========== 小区锁定 ==========
+
+    async fn cell_lock_status(&self) -> ModemResult<CellLockStatusResponse>;
+
+    async fn lock_cell(&self, rat: CellRat, arfcn: u32, pci: u16) -> ModemResult<()>;
+
+    async fn unlock_cell(&self, rat: CellRat) -> ModemResult<()>;
+
+    /// 解除所有制式的小区锁定，返回已完成的步骤说明
+    async fn unlock_all_cells(&self) -> ModemResult<Vec<String>>;
+
+    // ========== 射频模式 ==========
+
+    async fn radio_mode(&self) -> ModemResult<RadioModeResponse>;
+
+    async fn set_radio_mode(&self, mode: RadioMode) -> ModemResult<()>;
+
+    // ========== 短信 ==========
+
+    /// 发送短信，返回消息标识
+    async fn send_sms(&self, phone_number: &str, content: &str) -> ModemResult<String>;
+
+    /// 发送结果是否会通过 ofono Message 信号异步上报
+    ///
+    /// 返回 false 时 `send_sms` 成功即视为已发送
+    fn tracks_sms_status(&self) -> bool {
+        false
+    }
+
+    // ========== 通话 ==========
+
+    async fn calls(&self) -> ModemResult<Vec<CallInfo>>;
+
+    async fn dial(&self, phone_number: &str) -> ModemResult<CallInfo>;
+
+    async fn hangup(&self, call_path: &str) -> ModemResult<()>;
+
+    /// 挂断所有通话，返回挂断的数量
+    async fn hangup_all(&self) -> ModemResult<usize>;
+
+    async fn answer(&self, call_path: &str) -> ModemResult<()>;
+
+    /// 交换活动通话和保持通话
+    async fn swap_calls(&self) -> ModemResult<()>;
+
+    /// 挂断活动通话并接听等待中的来电
+    async fn release_and_answer(&self) -> ModemResult<()>;
+
+    /// 保持活动通话并接听等待中的来电
+    async fn hold_and_answer(&self) -> ModemResult<()>;
+
+    /// 合并活动通话和保持通话，返回多方通话中的通话路径
+    async fn create_multiparty(&self) -> ModemResult<Vec<String>>;
+
+    /// 从多方通话中分离出一路私聊，返回仍在多方通话中的通话路径
+    async fn private_chat(&self, call_path: &str) -> ModemResult<Vec<String>>;
+
+    async fn hangup_multiparty(&self) -> ModemResult<()>;
+
+    /// 向活动通话发送 DTMF 按键音（已校验）
+    async fn send_tones(&self, tones: &str) -> ModemResult<()>;
+
+    /// 将来电转接到其他号码
+    async fn deflect(&self, call_path: &str, phone_number: &str) -> ModemResult<()>;
+
+    // ========== APN ==========
+
+    /// internet 类型的 APN 配置列表
+    async fn apn_contexts(&self) -> ModemResult<Vec<ApnContext>>;
+
+    async fn set_apn(&self, request: &SetApnRequest) -> ModemResult<()>;
+}
+
+/// 可选的调制解调器后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BackendKind {
+    /// ofono + 展锐 UDX710
+    Ofono,
+    /// 内存模拟
+    Simulated,
+}
+
+/// 创建调制解调器后端
+pub fn create_backend(kind: BackendKind, conn: Arc<Connection>) -> Arc<dyn ModemBackend> {
+    match kind {
+        BackendKind::Ofono => Arc::new(ofono::OfonoBackend::new(conn)),
+        BackendKind::Simulated => Arc::new(simulated::SimulatedBackend::new()),
+    }
+}
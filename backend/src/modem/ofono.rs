@@ -0,0 +1,418 @@
+//! ofono + 展锐 UDX710 后端
+//!
+//! 通用功能走 ofono D-Bus 接口（见 `dbus` 模块），小区测量、频段锁定和小区锁定
+//! 通过 `Modem.SendAtcmd` 下发展锐私有 AT 指令。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+use zbus::Connection;
+
+use super::{BandLockChange, CellRat, ModemBackend, ModemError, ModemResult};
+use crate::dbus::{
+    answer_call, create_multiparty, deflect_call, dial_call, get_active_calls, get_all_apn_contexts,
+    get_device_info_data, get_radio_mode, get_serving_cell_info, hangup_all_calls, hangup_call, hangup_multiparty,
+    hold_and_answer, private_chat, release_and_answer, send_at_command, send_sms, send_tones, set_apn_properties,
+    set_radio_mode, set_sms_delivery_reports, swap_calls,
+};
+use crate::models::{
+    ApnContext, BandLockRequest, BandLockStatus, CallInfo, CellInfo, CellLockRatStatus, CellLockStatusResponse,
+    DeviceInfoResponse, RadioMode, RadioModeResponse, ServingCell, SetApnRequest,
+};
+use crate::utils::{
+    bands_to_bitmask, bitmask_to_bands, build_splband_lte_command, build_splband_nr_command,
+    get_cell_command_config, parse_at_response_to_2d_vec, parse_neighbor_cells, parse_primary_cell,
+    parse_splband_lte_response, parse_splband_nr_response,
+};
+
+/// UDX710 设备支持的全部频段掩码
+/// LTE: FDD=149 (B1+B3+B5+B8), TDD=320 (B39+B41)
+/// NR: FDD=517 (N1+N3+N28), TDD=912 (N41+N77+N78+N79)
+const LTE_FDD_ALL: u16 = 149;
+const LTE_TDD_ALL: u16 = 320;
+const NR_FDD_ALL: u16 = 517;
+const NR_TDD_ALL: u16 = 912;
+
+/// 解析 AT+SPFORCEFRQ 查询响应
+///
+/// 响应格式:
+/// - 未锁定: +SPFORCEFRQ: 16,3
+/// - 已锁定: +SPFORCEFRQ: 16,3,633984,597
+fn parse_spforcefrq_query_response(response: &str, rat: CellRat) -> CellLockRatStatus {
+    let prefix = format!("+SPFORCEFRQ: {},3", rat.code());
+    let unlocked = CellLockRatStatus {
+        rat: rat.code(),
+        rat_name: rat.name().to_string(),
+        enabled: false,
+        lock_type: 0,
+        pci: None,
+        arfcn: None,
+    };
+
+    let Some(line) = response.lines().find(|l| l.starts_with(&prefix)) else {
+        // 解析失败，返回未锁定状态
+        return unlocked;
+    };
+
+    let data = line.strip_prefix(&prefix).unwrap_or("").trim_start_matches(',');
+    if data.is_empty() {
+        return unlocked;
+    }
+
+    // 已锁定，解析 arfcn,pci
+    let parts: Vec<&str> = data.split(',').collect();
+    let arfcn = parts.first().and_then(|s| s.trim().parse::<u32>().ok());
+    let pci = parts.get(1).and_then(|s| s.trim().parse::<u16>().ok());
+
+    CellLockRatStatus {
+        enabled: arfcn.is_some() && pci.is_some(),
+        lock_type: 3,
+        pci,
+        arfcn,
+        ..unlocked
+    }
+}
+
+/// ofono + UDX710 后端
+pub struct OfonoBackend {
+    conn: Arc<Connection>,
+    /// 是否已开启短信状态报告（首次发送前开启一次）
+    delivery_reports: AtomicBool,
+}
+
+impl OfonoBackend {
+    pub fn new(conn: Arc<Connection>) -> Self {
+        Self {
+            conn,
+            delivery_reports: AtomicBool::new(false),
+        }
+    }
+
+    async fn at(&self, cmd: &str) -> zbus::Result<String> {
+        send_at_command(&self.conn, cmd).await
+    }
+
+    /// 依次执行 AT 指令，失败时恢复正常模式（AT+SFUN=4）
+    ///
+    /// 错误信息格式为 `<步骤说明>失败: <原因>`
+    async fn run_engineering_steps(&self, steps: &[(&str, &str)]) -> ModemResult<()> {
+        for (cmd, desc) in steps {
+            if let Err(e) = self.at(cmd).await {
+                if *cmd != "AT+SFUN=4" {
+                    let _ = self.at("AT+SFUN=4").await;
+                }
+                return Err(ModemError::Failed(format!("{}失败: {}", desc, e)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ModemBackend for OfonoBackend {
+    fn name(&self) -> &'static str {
+        "ofono"
+    }
+
+    async fn device_info(&self) -> ModemResult<DeviceInfoResponse> {
+        Ok(get_device_info_data(&self.conn).await?)
+    }
+
+    async fn serving_cell(&self) -> ModemResult<ServingCell> {
+        Ok(get_serving_cell_info(&self.conn).await?)
+    }
+
+    async fn cell_list(&self, serving: &ServingCell) -> ModemResult<Vec<CellInfo>> {
+        let tech = serving.tech.as_str();
+        let cmd_config = get_cell_command_config(tech)
+            .ok_or_else(|| ModemError::Unsupported(format!("Unsupported network type: {}", tech)))?;
+
+        // ofono D-Bus 不支持并发 AT 指令，必须串行执行
+        let primary = self
+            .at(cmd_config.primary)
+            .await
+            .map_err(|e| ModemError::Failed(format!("Primary cell AT command failed: {}", e)))?;
+        let neighbor = self
+            .at(cmd_config.neighbor)
+            .await
+            .map_err(|e| ModemError::Failed(format!("Neighbor cell AT command failed: {}", e)))?;
+
+        let mut cells = vec![parse_primary_cell(tech, &parse_at_response_to_2d_vec(&primary))];
+        cells.extend(parse_neighbor_cells(tech, &parse_at_response_to_2d_vec(&neighbor)));
+        Ok(cells)
+    }
+
+    async fn band_lock_status(&self) -> ModemResult<BandLockStatus> {
+        // 读取 LTE 频段锁定状态
+        let (lte_fdd_mask, lte_tdd_mask, lte_raw) = match self.at("AT+SPLBAND=0").await {
+            Ok(response) => {
+                let (fdd, tdd) = parse_splband_lte_response(&response);
+                (fdd, tdd, response)
+            }
+            Err(e) => (0, 0, format!("Error: {}", e)),
+        };
+
+        // 读取 NR 频段锁定状态
+        let (nr_fdd_mask, nr_tdd_mask, nr_raw) = match self.at("AT+SPLBAND=3").await {
+            Ok(response) => {
+                let (fdd, tdd) = parse_splband_nr_response(&response);
+                (fdd, tdd, response)
+            }
+            Err(e) => (0, 0, format!("Error: {}", e)),
+        };
+
+        // 如果返回的频段等于设备支持的全部频段或为 0，则认为"未锁定"（全部可用）
+        let lte_is_all_or_zero = (lte_fdd_mask == LTE_FDD_ALL && lte_tdd_mask == LTE_TDD_ALL)
+            || (lte_fdd_mask == 0 && lte_tdd_mask == 0);
+        let nr_is_all_or_zero = (nr_fdd_mask == NR_FDD_ALL && nr_tdd_mask == NR_TDD_ALL)
+            || (nr_fdd_mask == 0 && nr_tdd_mask == 0);
+        let locked = !(lte_is_all_or_zero && nr_is_all_or_zero);
+
+        // 未锁定时返回空数组（前端显示为"未锁定模式"）
+        let (lte_fdd_bands, lte_tdd_bands, nr_fdd_bands, nr_tdd_bands) = if !locked {
+            (vec![], vec![], vec![], vec![])
+        } else {
+            (
+                bitmask_to_bands(lte_fdd_mask, 1),  // LTE FDD: B1-B16
+                bitmask_to_bands(lte_tdd_mask, 33), // LTE TDD: B33-B48
+                bitmask_to_bands(nr_fdd_mask, 100), // NR FDD: 展锐特殊映射
+                bitmask_to_bands(nr_tdd_mask, 41),  // NR TDD: 展锐特殊映射
+            )
+        };
+
+        Ok(BandLockStatus {
+            locked,
+            lte_fdd_bands,
+            lte_tdd_bands,
+            nr_fdd_bands,
+            nr_tdd_bands,
+            raw_response: Some(format!(
+                "LTE(fdd={},tdd={}): {}\nNR(fdd={},tdd={}): {}",
+                lte_fdd_mask,
+                lte_tdd_mask,
+                lte_raw.trim(),
+                nr_fdd_mask,
+                nr_tdd_mask,
+                nr_raw.trim()
+            )),
+        })
+    }
+
+    async fn set_band_lock(&self, request: &BandLockRequest) -> ModemResult<BandLockChange> {
+        if request.is_unlock() {
+            let mut change = BandLockChange::default();
+
+            // 只有当前有锁定时才执行解锁
+            if let Ok(response) = self.at("AT+SPLBAND=0").await {
+                let (fdd, tdd) = parse_splband_lte_response(&response);
+                if fdd != 0 || tdd != 0 {
+                    // 格式: AT+SPLBAND=1,0,<TDD>,0,<FDD>,0 (6 参数)
+                    self.at("AT+SPLBAND=1,0,0,0,0,0")
+                        .await
+                        .map_err(|e| ModemError::Failed(format!("Failed to unlock LTE bands: {}", e)))?;
+                    change.lte = true;
+                }
+            }
+
+            if let Ok(response) = self.at("AT+SPLBAND=3").await {
+                let (fdd, tdd) = parse_splband_nr_response(&response);
+                if fdd != 0 || tdd != 0 {
+                    self.at("AT+SPLBAND=2,0,0,0,0")
+                        .await
+                        .map_err(|e| ModemError::Failed(format!("Failed to unlock NR bands: {}", e)))?;
+                    change.nr = true;
+                }
+            }
+
+            return Ok(change);
+        }
+
+        let lte_fdd_mask = bands_to_bitmask(&request.lte_fdd_bands, 1);
+        let lte_tdd_mask = bands_to_bitmask(&request.lte_tdd_bands, 33);
+        let nr_fdd_mask = bands_to_bitmask(&request.nr_fdd_bands, 100); // NR FDD: 展锐特殊映射
+        let nr_tdd_mask = bands_to_bitmask(&request.nr_tdd_bands, 41); // NR TDD: 展锐特殊映射
+
+        let change = BandLockChange {
+            lte: lte_fdd_mask != 0 || lte_tdd_mask != 0,
+            nr: nr_fdd_mask != 0 || nr_tdd_mask != 0,
+        };
+
+        if change.lte {
+            self.at(&build_splband_lte_command(lte_fdd_mask, lte_tdd_mask))
+                .await
+                .map_err(|e| ModemError::Failed(format!("Failed to set LTE band lock: {}", e)))?;
+        }
+        if change.nr {
+            self.at(&build_splband_nr_command(nr_fdd_mask, nr_tdd_mask))
+                .await
+                .map_err(|e| ModemError::Failed(format!("Failed to set NR band lock: {}", e)))?;
+        }
+
+        Ok(change)
+    }
+
+    // 小区锁定使用 AT+SPFORCEFRQ=<type>,<op>[,<arfcn>,<pci>]（通过 dbus-monitor 监听实际锁频操作发现）
+    // type: 12=LTE, 16=NR；op: 0=清除, 2=设置, 3=查询
+    // 设置和清除需先 AT+SFUN=5 进入工程模式，完成后 AT+SFUN=4 恢复正常模式
+
+    async fn cell_lock_status(&self) -> ModemResult<CellLockStatusResponse> {
+        let mut rat_status = Vec::new();
+
+        for rat in [CellRat::Nr, CellRat::Lte] {
+            let cmd = format!("AT+SPFORCEFRQ={},3", rat.code());
+            let response = self.at(&cmd).await.unwrap_or_default();
+            rat_status.push(parse_spforcefrq_query_response(&response, rat));
+        }
+
+        let any_locked = rat_status.iter().any(|s| s.enabled);
+        Ok(CellLockStatusResponse { rat_status, any_locked })
+    }
+
+    /// 先清空两种制式的锁定，只保留本次设置的小区
+    async fn lock_cell(&self, rat: CellRat, arfcn: u32, pci: u16) -> ModemResult<()> {
+        let lock_cmd = format!("AT+SPFORCEFRQ={},2,{},{}", rat.code(), arfcn, pci);
+        self.run_engineering_steps(&[
+            ("AT+SFUN=5", "进入工程模式"),
+            ("AT+SPFORCEFRQ=16,0", "清空 NR 锁定"),
+            ("AT+SPFORCEFRQ=12,0", "清空 LTE 锁定"),
+            (&lock_cmd, "设置锁定"),
+            ("AT+SFUN=4", "恢复正常模式"),
+        ])
+        .await
+    }
+
+    async fn unlock_cell(&self, rat: CellRat) -> ModemResult<()> {
+        let clear_cmd = format!("AT+SPFORCEFRQ={},0", rat.code());
+        self.run_engineering_steps(&[
+            ("AT+SFUN=5", "进入工程模式"),
+            (&clear_cmd, "清空锁定"),
+            ("AT+SFUN=4", "恢复正常模式"),
+        ])
+        .await
+    }
+
+    async fn unlock_all_cells(&self) -> ModemResult<Vec<String>> {
+        let steps = [
+            ("AT+SFUN=5", "进入工程模式"),
+            ("AT+SPFORCEFRQ=16,0", "清空 NR 锁定"),
+            ("AT+SPFORCEFRQ=12,0", "清空 LTE 锁定"),
+            ("AT+SFUN=4", "恢复正常模式"),
+        ];
+        self.run_engineering_steps(&steps).await?;
+        Ok(steps.iter().map(|(_, desc)| desc.to_string()).collect())
+    }
+
+    async fn radio_mode(&self) -> ModemResult<RadioModeResponse> {
+        Ok(get_radio_mode(&self.conn).await?)
+    }
+
+    async fn set_radio_mode(&self, mode: RadioMode) -> ModemResult<()> {
+        Ok(set_radio_mode(&self.conn, mode).await?)
+    }
+
+    async fn send_sms(&self, phone_number: &str, content: &str) -> ModemResult<String> {
+        // 请求状态报告失败不影响发送，只是无法得到"已送达"状态
+        if !self.delivery_reports.load(Ordering::Relaxed) {
+            match set_sms_delivery_reports(&self.conn, true).await {
+                Ok(()) => self.delivery_reports.store(true, Ordering::Relaxed),
+                Err(e) => warn!(error = %e, "Failed to enable SMS delivery reports"),
+            }
+        }
+        Ok(send_sms(&self.conn, phone_number, content).await?)
+    }
+
+    fn tracks_sms_status(&self) -> bool {
+        true
+    }
+
+    async fn calls(&self) -> ModemResult<Vec<CallInfo>> {
+        Ok(get_active_calls(&self.conn).await?)
+    }
+
+    async fn dial(&self, phone_number: &str) -> ModemResult<CallInfo> {
+        Ok(dial_call(&self.conn, phone_number).await?)
+    }
+
+    async fn hangup(&self, call_path: &str) -> ModemResult<()> {
+        Ok(hangup_call(&self.conn, call_path).await?)
+    }
+
+    async fn hangup_all(&self) -> ModemResult<usize> {
+        Ok(hangup_all_calls(&self.conn).await?)
+    }
+
+    async fn answer(&self, call_path: &str) -> ModemResult<()> {
+        Ok(answer_call(&self.conn, call_path).await?)
+    }
+
+    async fn swap_calls(&self) -> ModemResult<()> {
+        Ok(swap_calls(&self.conn).await?)
+    }
+
+    async fn release_and_answer(&self) -> ModemResult<()> {
+        Ok(release_and_answer(&self.conn).await?)
+    }
+
+    async fn hold_and_answer(&self) -> ModemResult<()> {
+        Ok(hold_and_answer(&self.conn).await?)
+    }
+
+    async fn create_multiparty(&self) -> ModemResult<Vec<String>> {
+        Ok(create_multiparty(&self.conn).await?)
+    }
+
+    async fn private_chat(&self, call_path: &str) -> ModemResult<Vec<String>> {
+        Ok(private_chat(&self.conn, call_path).await?)
+    }
+
+    async fn hangup_multiparty(&self) -> ModemResult<()> {
+        Ok(hangup_multiparty(&self.conn).await?)
+    }
+
+    async fn send_tones(&self, tones: &str) -> ModemResult<()> {
+        Ok(send_tones(&self.conn, tones).await?)
+    }
+
+    async fn deflect(&self, call_path: &str, phone_number: &str) -> ModemResult<()> {
+        Ok(deflect_call(&self.conn, call_path, phone_number).await?)
+    }
+
+    async fn apn_contexts(&self) -> ModemResult<Vec<ApnContext>> {
+        Ok(get_all_apn_contexts(&self.conn).await?)
+    }
+
+    async fn set_apn(&self, request: &SetApnRequest) -> ModemResult<()> {
+        Ok(set_apn_properties(
+            &self.conn,
+            &request.context_path,
+            request.apn.as_deref(),
+            request.protocol.as_deref(),
+            request.username.as_deref(),
+            request.password.as_deref(),
+            request.auth_method.as_deref(),
+        )
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spforcefrq_query_parsing() {
+        let locked = parse_spforcefrq_query_response("+SPFORCEFRQ: 16,3,633984,597\r\nOK", CellRat::Nr);
+        assert!(locked.enabled);
+        assert_eq!(locked.arfcn, Some(633984));
+        assert_eq!(locked.pci, Some(597));
+
+        let unlocked = parse_spforcefrq_query_response("+SPFORCEFRQ: 12,3\r\nOK", CellRat::Lte);
+        assert!(!unlocked.enabled);
+        assert_eq!(unlocked.rat, 12);
+        assert_eq!(unlocked.rat_name, "LTE");
+
+        assert!(!parse_spforcefrq_query_response("ERROR", CellRat::Nr).enabled);
+    }
+}
@@ -0,0 +1,552 @@
+//! 模拟后端
+//!
+//! 所有状态保存在内存中，不访问 D-Bus 和 AT 串口，用于无硬件时开发前端和演示。
+//! 频段锁定、小区锁定、射频模式、通话和 APN 的修改会在后续查询中体现。
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::{BandLockChange, CellRat, ModemBackend, ModemError, ModemResult};
+use crate::models::{
+    ApnContext, BandLockRequest, BandLockStatus, CallInfo, CellInfo, CellLockRatStatus, CellLockStatusResponse,
+    DeviceInfoResponse, RadioMode, RadioModeResponse, ServingCell, SetApnRequest,
+};
+
+struct SimulatedState {
+    band_lock: BandLockRequest,
+    cell_locks: BTreeMap<u8, (u32, u16)>,
+    radio_mode: RadioMode,
+    calls: BTreeMap<String, CallInfo>,
+    next_call_id: u32,
+    next_message_id: u32,
+    apn_contexts: Vec<ApnContext>,
+}
+
+impl SimulatedState {
+    /// 指定状态的通话路径
+    fn calls_in(&self, states: &[&str]) -> Vec<String> {
+        self.calls
+            .values()
+            .filter(|call| states.contains(&call.state.as_str()))
+            .map(|call| call.path.clone())
+            .collect()
+    }
+
+    fn set_call_state(&mut self, path: &str, state: &str) {
+        if let Some(call) = self.calls.get_mut(path) {
+            call.state = state.to_string();
+            call.held = state == "held";
+        }
+    }
+}
+
+/// 内存模拟后端
+pub struct SimulatedBackend {
+    state: Mutex<SimulatedState>,
+}
+
+impl SimulatedBackend {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(SimulatedState {
+                band_lock: BandLockRequest {
+                    lte_fdd_bands: vec![],
+                    lte_tdd_bands: vec![],
+                    nr_fdd_bands: vec![],
+                    nr_tdd_bands: vec![],
+                },
+                cell_locks: BTreeMap::new(),
+                radio_mode: RadioMode::Auto,
+                calls: BTreeMap::new(),
+                next_call_id: 1,
+                next_message_id: 1,
+                apn_contexts: vec![ApnContext {
+                    path: "/sim_0/context1".to_string(),
+                    name: "Internet".to_string(),
+                    active: true,
+                    apn: "cmnet".to_string(),
+                    protocol: "dual".to_string(),
+                    username: String::new(),
+                    password: String::new(),
+                    auth_method: "none".to_string(),
+                    context_type: "internet".to_string(),
+                }],
+            }),
+        }
+    }
+
+    fn tech(&self) -> &'static str {
+        match self.state.lock().unwrap().radio_mode {
+            RadioMode::LteOnly => "lte",
+            _ => "nr",
+        }
+    }
+}
+
+impl Default for SimulatedBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 构造模拟小区，信号值按 API 约定为原始值 ×100
+fn simulated_cell(is_serving: bool, tech: &str, band: &str, arfcn: u32, pci: u16, rsrp: i32) -> CellInfo {
+    CellInfo {
+        is_serving,
+        tech: tech.to_string(),
+        band: band.to_string(),
+        arfcn: arfcn.to_string(),
+        pci: pci.to_string(),
+        rsrp: (rsrp * 100).to_string(),
+        rsrq: "-1050".to_string(),
+        sinr: "1500".to_string(),
+    }
+}
+
+#[async_trait]
+impl ModemBackend for SimulatedBackend {
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+
+    async fn device_info(&self) -> ModemResult<DeviceInfoResponse> {
+        Ok(DeviceInfoResponse {
+            imei: "860000000000000".to_string(),
+            manufacturer: "Simulated".to_string(),
+            model: "SIM-5G".to_string(),
+            revision: Some("simulated-1.0".to_string()),
+            online: true,
+            powered: true,
+        })
+    }
+
+    async fn serving_cell(&self) -> ModemResult<ServingCell> {
+        Ok(ServingCell {
+            tech: self.tech().to_string(),
+            cell_id: 0x1A2B3C,
+            tac: 100,
+        })
+    }
+
+    async fn cell_list(&self, serving: &ServingCell) -> ModemResult<Vec<CellInfo>> {
+        let cells = match serving.tech.as_str() {
+            "nr" => vec![
+                simulated_cell(true, "nr", "78", 633984, 597, -85),
+                simulated_cell(false, "nr", "78", 633984, 598, -97),
+                simulated_cell(false, "nr", "41", 504990, 120, -104),
+            ],
+            "lte" => vec![
+                simulated_cell(true, "lte", "3", 1650, 123, -90),
+                simulated_cell(false, "lte", "3", 1650, 124, -101),
+                simulated_cell(false, "lte", "41", 40936, 300, -108),
+            ],
+            tech => return Err(ModemError::Unsupported(format!("Unsupported network type: {}", tech))),
+        };
+        Ok(cells)
+    }
+
+    async fn band_lock_status(&self) -> ModemResult<BandLockStatus> {
+        let state = self.state.lock().unwrap();
+        let lock = &state.band_lock;
+        Ok(BandLockStatus {
+            locked: !lock.is_unlock(),
+            lte_fdd_bands: lock.lte_fdd_bands.clone(),
+            lte_tdd_bands: lock.lte_tdd_bands.clone(),
+            nr_fdd_bands: lock.nr_fdd_bands.clone(),
+            nr_tdd_bands: lock.nr_tdd_bands.clone(),
+            raw_response: None,
+        })
+    }
+
+    async fn set_band_lock(&self, request: &BandLockRequest) -> ModemResult<BandLockChange> {
+        let mut state = self.state.lock().unwrap();
+        let lock = &mut state.band_lock;
+
+        if request.is_unlock() {
+            let change = BandLockChange {
+                lte: !lock.lte_fdd_bands.is_empty() || !lock.lte_tdd_bands.is_empty(),
+                nr: !lock.nr_fdd_bands.is_empty() || !lock.nr_tdd_bands.is_empty(),
+            };
+            *lock = BandLockRequest {
+                lte_fdd_bands: vec![],
+                lte_tdd_bands: vec![],
+                nr_fdd_bands: vec![],
+                nr_tdd_bands: vec![],
+            };
+            return Ok(change);
+        }
+
+        let change = BandLockChange {
+            lte: !request.lte_fdd_bands.is_empty() || !request.lte_tdd_bands.is_empty(),
+            nr: !request.nr_fdd_bands.is_empty() || !request.nr_tdd_bands.is_empty(),
+        };
+        if change.lte {
+            lock.lte_fdd_bands = request.lte_fdd_bands.clone();
+            lock.lte_tdd_bands = request.lte_tdd_bands.clone();
+        }
+        if change.nr {
+            lock.nr_fdd_bands = request.nr_fdd_bands.clone();
+            lock.nr_tdd_bands = request.nr_tdd_bands.clone();
+        }
+        Ok(change)
+    }
+
+    async fn cell_lock_status(&self) -> ModemResult<CellLockStatusResponse> {
+        let state = self.state.lock().unwrap();
+        let rat_status: Vec<CellLockRatStatus> = [CellRat::Nr, CellRat::Lte]
+            .into_iter()
+            .map(|rat| {
+                let lock = state.cell_locks.get(&rat.code());
+                CellLockRatStatus {
+                    rat: rat.code(),
+                    rat_name: rat.name().to_string(),
+                    enabled: lock.is_some(),
+                    lock_type: if lock.is_some() { 3 } else { 0 },
+                    arfcn: lock.map(|(arfcn, _)| *arfcn),
+                    pci: lock.map(|(_, pci)| *pci),
+                }
+            })
+            .collect();
+
+        let any_locked = rat_status.iter().any(|s| s.enabled);
+        Ok(CellLockStatusResponse { rat_status, any_locked })
+    }
+
+    async fn lock_cell(&self, rat: CellRat, arfcn: u32, pci: u16) -> ModemResult<()> {
+        // 与实际模组一致：设置新锁定前先清空所有制式的锁定
+        let mut state = self.state.lock().unwrap();
+        state.cell_locks.clear();
+        state.cell_locks.insert(rat.code(), (arfcn, pci));
+        Ok(())
+    }
+
+    async fn unlock_cell(&self, rat: CellRat) -> ModemResult<()> {
+        self.state.lock().unwrap().cell_locks.remove(&rat.code());
+        Ok(())
+    }
+
+    async fn unlock_all_cells(&self) -> ModemResult<Vec<String>> {
+        self.state.lock().unwrap().cell_locks.clear();
+        Ok(vec!["清空 NR 锁定".to_string(), "清空 LTE 锁定".to_string()])
+    }
+
+    async fn radio_mode(&self) -> ModemResult<RadioModeResponse> {
+        let mode = self.state.lock().unwrap().radio_mode.clone();
+        let name = match mode {
+            RadioMode::Auto => "auto",
+            RadioMode::LteOnly => "lte",
+            RadioMode::NrOnly => "nr",
+        };
+        Ok(RadioModeResponse {
+            mode: name.to_string(),
+            technology_preference: mode.to_ofono_value().to_string(),
+        })
+    }
+
+    async fn set_radio_mode(&self, mode: RadioMode) -> ModemResult<()> {
+        self.state.lock().unwrap().radio_mode = mode;
+        Ok(())
+    }
+
+    async fn send_sms(&self, phone_number: &str, content: &str) -> ModemResult<String> {
+        if phone_number.is_empty() {
+            return Err(ModemError::InvalidArgument("Phone number is empty".to_string()));
+        }
+        if content.is_empty() {
+            return Err(ModemError::InvalidArgument("Message content is empty".to_string()));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let path = format!("/sim_0/message_{:02}", state.next_message_id);
+        state.next_message_id += 1;
+        Ok(path)
+    }
+
+    async fn calls(&self) -> ModemResult<Vec<CallInfo>> {
+        Ok(self.state.lock().unwrap().calls.values().cloned().collect())
+    }
+
+    async fn dial(&self, phone_number: &str) -> ModemResult<CallInfo> {
+        if phone_number.is_empty() {
+            return Err(ModemError::InvalidArgument("Phone number is empty".to_string()));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let call = CallInfo {
+            path: format!("/sim_0/voicecall{:02}", state.next_call_id),
+            phone_number: phone_number.to_string(),
+            state: "dialing".to_string(),
+            direction: "outgoing".to_string(),
+            start_time: None,
+            held: false,
+            multiparty: false,
+        };
+        state.next_call_id += 1;
+        state.calls.insert(call.path.clone(), call.clone());
+        Ok(call)
+    }
+
+    async fn hangup(&self, call_path: &str) -> ModemResult<()> {
+        match self.state.lock().unwrap().calls.remove(call_path) {
+            Some(_) => Ok(()),
+            None => Err(ModemError::InvalidArgument(format!("Unknown call: {}", call_path))),
+        }
+    }
+
+    async fn hangup_all(&self) -> ModemResult<usize> {
+        let mut state = self.state.lock().unwrap();
+        let count = state.calls.len();
+        state.calls.clear();
+        Ok(count)
+    }
+
+    async fn answer(&self, call_path: &str) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let call = state
+            .calls
+            .get_mut(call_path)
+            .ok_or_else(|| ModemError::InvalidArgument(format!("Unknown call: {}", call_path)))?;
+        if call.state != "incoming" {
+            return Err(ModemError::Failed(format!("Call is not incoming: {}", call.state)));
+        }
+        call.state = "active".to_string();
+        Ok(())
+    }
+
+    async fn swap_calls(&self) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let held = state.calls_in(&["held"]);
+        if held.is_empty() {
+            return Err(ModemError::Failed("No held call".to_string()));
+        }
+        for path in state.calls_in(&["active"]) {
+            state.set_call_state(&path, "held");
+        }
+        for path in held {
+            state.set_call_state(&path, "active");
+        }
+        Ok(())
+    }
+
+    async fn release_and_answer(&self) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let active = state.calls_in(&["active"]);
+        let waiting = state.calls_in(&["waiting", "incoming"]);
+        if active.is_empty() && waiting.is_empty() {
+            return Err(ModemError::Failed("No active or waiting call".to_string()));
+        }
+        for path in active {
+            state.calls.remove(&path);
+        }
+        // 没有等待中的来电时恢复保持的通话
+        let next = if waiting.is_empty() { state.calls_in(&["held"]) } else { waiting };
+        for path in next {
+            state.set_call_state(&path, "active");
+        }
+        Ok(())
+    }
+
+    async fn hold_and_answer(&self) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let waiting = state.calls_in(&["waiting", "incoming"]);
+        if waiting.is_empty() {
+            return Err(ModemError::Failed("No waiting call".to_string()));
+        }
+        for path in state.calls_in(&["active"]) {
+            state.set_call_state(&path, "held");
+        }
+        for path in waiting {
+            state.set_call_state(&path, "active");
+        }
+        Ok(())
+    }
+
+    async fn create_multiparty(&self) -> ModemResult<Vec<String>> {
+        let mut state = self.state.lock().unwrap();
+        if state.calls_in(&["active"]).is_empty() || state.calls_in(&["held"]).is_empty() {
+            return Err(ModemError::Failed("Multiparty needs an active and a held call".to_string()));
+        }
+        let members = state.calls_in(&["active", "held"]);
+        for path in &members {
+            state.set_call_state(path, "active");
+            state.calls.get_mut(path).unwrap().multiparty = true;
+        }
+        Ok(members)
+    }
+
+    async fn private_chat(&self, call_path: &str) -> ModemResult<Vec<String>> {
+        let mut state = self.state.lock().unwrap();
+        match state.calls.get(call_path) {
+            Some(call) if call.multiparty => {}
+            Some(_) => return Err(ModemError::Failed(format!("Call is not in multiparty: {}", call_path))),
+            None => return Err(ModemError::InvalidArgument(format!("Unknown call: {}", call_path))),
+        }
+
+        state.calls.get_mut(call_path).unwrap().multiparty = false;
+        state.set_call_state(call_path, "active");
+        let mut members: Vec<String> =
+            state.calls.values().filter(|call| call.multiparty).map(|call| call.path.clone()).collect();
+        for path in &members {
+            state.set_call_state(path, "held");
+        }
+        // 只剩一路时多方通话结束
+        if members.len() == 1 {
+            state.calls.get_mut(&members[0]).unwrap().multiparty = false;
+            members.clear();
+        }
+        Ok(members)
+    }
+
+    async fn hangup_multiparty(&self) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let count = state.calls.len();
+        state.calls.retain(|_, call| !call.multiparty);
+        if state.calls.len() == count {
+            return Err(ModemError::Failed("No multiparty call".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn send_tones(&self, _tones: &str) -> ModemResult<()> {
+        if self.state.lock().unwrap().calls_in(&["active"]).is_empty() {
+            return Err(ModemError::Failed("No active call".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn deflect(&self, call_path: &str, phone_number: &str) -> ModemResult<()> {
+        if phone_number.is_empty() {
+            return Err(ModemError::InvalidArgument("Phone number is empty".to_string()));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let call = state
+            .calls
+            .get(call_path)
+            .ok_or_else(|| ModemError::InvalidArgument(format!("Unknown call: {}", call_path)))?;
+        if call.state != "incoming" && call.state != "waiting" {
+            return Err(ModemError::Failed(format!("Call is not incoming: {}", call.state)));
+        }
+        state.calls.remove(call_path);
+        Ok(())
+    }
+
+    async fn apn_contexts(&self) -> ModemResult<Vec<ApnContext>> {
+        Ok(self.state.lock().unwrap().apn_contexts.clone())
+    }
+
+    async fn set_apn(&self, request: &SetApnRequest) -> ModemResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let context = state
+            .apn_contexts
+            .iter_mut()
+            .find(|c| c.path == request.context_path)
+            .ok_or_else(|| ModemError::InvalidArgument(format!("Unknown context: {}", request.context_path)))?;
+
+        if let Some(apn) = &request.apn {
+            context.apn = apn.clone();
+        }
+        if let Some(protocol) = &request.protocol {
+            context.protocol = protocol.clone();
+        }
+        if let Some(username) = &request.username {
+            context.username = username.clone();
+        }
+        if let Some(password) = &request.password {
+            context.password = password.clone();
+        }
+        if let Some(auth_method) = &request.auth_method {
+            context.auth_method = auth_method.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn band_and_cell_lock_round_trip() {
+        let modem = SimulatedBackend::new();
+        let request = BandLockRequest {
+            lte_fdd_bands: vec![1, 3],
+            lte_tdd_bands: vec![],
+            nr_fdd_bands: vec![],
+            nr_tdd_bands: vec![78],
+        };
+        let change = modem.set_band_lock(&request).await.unwrap();
+        assert_eq!(change, BandLockChange { lte: true, nr: true });
+        let status = modem.band_lock_status().await.unwrap();
+        assert!(status.locked);
+        assert_eq!(status.lte_fdd_bands, vec![1, 3]);
+
+        let unlock = BandLockRequest {
+            lte_fdd_bands: vec![],
+            lte_tdd_bands: vec![],
+            nr_fdd_bands: vec![],
+            nr_tdd_bands: vec![],
+        };
+        assert_eq!(modem.set_band_lock(&unlock).await.unwrap(), BandLockChange { lte: true, nr: true });
+        assert_eq!(modem.set_band_lock(&unlock).await.unwrap(), BandLockChange::default());
+
+        modem.lock_cell(CellRat::Nr, 633984, 597).await.unwrap();
+        let status = modem.cell_lock_status().await.unwrap();
+        assert!(status.any_locked);
+        assert_eq!(status.rat_status[0].pci, Some(597));
+        modem.unlock_all_cells().await.unwrap();
+        assert!(!modem.cell_lock_status().await.unwrap().any_locked);
+    }
+
+    #[tokio::test]
+    async fn radio_mode_drives_serving_cell() {
+        let modem = SimulatedBackend::new();
+        assert_eq!(modem.serving_cell().await.unwrap().tech, "nr");
+
+        modem.set_radio_mode(RadioMode::LteOnly).await.unwrap();
+        let serving = modem.serving_cell().await.unwrap();
+        assert_eq!(serving.tech, "lte");
+        let cells = modem.cell_list(&serving).await.unwrap();
+        assert!(cells[0].is_serving);
+        assert_eq!(modem.radio_mode().await.unwrap().mode, "lte");
+
+        let unknown = ServingCell { tech: "gsm".to_string(), cell_id: 1, tac: 1 };
+        assert!(matches!(modem.cell_list(&unknown).await, Err(ModemError::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn hold_swap_and_conference() {
+        let modem = SimulatedBackend::new();
+        let first = modem.dial("10010").await.unwrap().path;
+        modem.state.lock().unwrap().set_call_state(&first, "active");
+        let second = modem.dial("10086").await.unwrap().path;
+        modem.state.lock().unwrap().set_call_state(&second, "waiting");
+
+        modem.hold_and_answer().await.unwrap();
+        let state = |path: &str| modem.state.lock().unwrap().calls[path].clone();
+        assert!(state(&first).held);
+        assert_eq!(state(&second).state, "active");
+        modem.swap_calls().await.unwrap();
+        assert_eq!(state(&first).state, "active");
+        assert!(state(&second).held);
+
+        let members = modem.create_multiparty().await.unwrap();
+        assert_eq!(members.len(), 2);
+        assert!(state(&first).multiparty && !state(&second).held);
+        modem.send_tones("1#").await.unwrap();
+
+        // 两方会议私聊后多方通话结束
+        assert!(modem.private_chat(&first).await.unwrap().is_empty());
+        assert_eq!(state(&first).state, "active");
+        assert!(state(&second).held && !state(&second).multiparty);
+        assert!(modem.hangup_multiparty().await.is_err());
+
+        modem.release_and_answer().await.unwrap();
+        let calls = modem.calls().await.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].path, second);
+        assert_eq!(calls[0].state, "active");
+    }
+}
@@ -1,12 +1,42 @@
-use crate::models::{OtaMeta, OtaStatusResponse, OtaUploadResponse, OtaValidation};
+use crate::archive::{self, ExtractLimits, ExtractedFile};
+use crate::config::OtaConfig;
+use crate::minisign;
+use crate::modem::ModemBackend;
+use crate::models::{
+    OtaMeta, OtaSigner, OtaSlotStatus, OtaStatusResponse, OtaUploadResponse, OtaValidation,
+};
+use futures_util::StreamExt;
+use ring::digest;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::Path;
 use std::process::Command;
-
-const OTA_STAGING_DIR: &str = "/tmp/ota_staging";
-const OTA_BINARY_PATH: &str = "/home/root/udx710";
-const OTA_WWW_PATH: &str = "/home/root/www";
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub(crate) const OTA_STAGING_DIR: &str = "/tmp/ota_staging";
+/// 暂存目录的互斥锁：手动上传、更新通道下载，以及应用/取消待安装的更新都会改写暂存目录
+pub(crate) static STAGING_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// A/B 分区状态文件（由 loader.sh 在启动时读取和更新）
+const OTA_STATE_FILE: &str = "/home/root/ota/state.env";
+/// 新版本启动后等待健康检查通过的最长时间，超时后退出进程由 loader.sh 计为一次失败启动
+const BOOT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(300);
+const BOOT_CONFIRM_INTERVAL: Duration = Duration::from_secs(10);
+/// 上传更新包大小上限
+pub const OTA_MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+/// 更新包解压限制（设备 /tmp 为容量很小的 tmpfs）
+const OTA_EXTRACT_LIMITS: ExtractLimits = ExtractLimits {
+    max_total_bytes: 96 * 1024 * 1024,
+    max_entries: 4096,
+};
+/// 计算文件哈希时每次读取的块大小
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+/// meta.json 的 minisign 签名（meta.json 中的哈希覆盖二进制和前端文件）
+const OTA_SIGNATURE_FILE: &str = "meta.json.minisig";
+
+/// 编译时内置的 minisign 公钥，多个公钥以逗号或空白分隔
+const BUILTIN_PUBLIC_KEYS: Option<&str> = option_env!("OTA_PUBLIC_KEYS");
 
 pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -17,14 +47,171 @@ pub fn get_current_commit() -> String {
 pub fn get_ota_status() -> OtaStatusResponse {
     let pending_meta = read_pending_meta();
 
+    let state = read_slot_state();
+
     OtaStatusResponse {
         current_version: CURRENT_VERSION.to_string(),
         current_commit: get_current_commit(),
         pending_update: pending_meta.is_some(),
         pending_meta,
+        slot: OtaSlotStatus {
+            active_slot: state.active.name().to_string(),
+            previous_slot: state.previous.map(|slot| slot.name().to_string()),
+            boot_confirmed: state.confirmed,
+            boot_attempts: state.boot_attempts,
+            max_boot_attempts: state.max_boot_attempts,
+            rolled_back_from: state.rolled_back_from.map(|slot| slot.name().to_string()),
+        },
+    }
+}
+
+/// 安装槽位：base 为原有安装目录 /home/root，a/b 为 OTA 写入的分区
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Base,
+    A,
+    B,
+}
+
+impl Slot {
+    fn name(self) -> &'static str {
+        match self {
+            Slot::Base => "base",
+            Slot::A => "a",
+            Slot::B => "b",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "base" => Some(Slot::Base),
+            "a" => Some(Slot::A),
+            "b" => Some(Slot::B),
+            _ => None,
+        }
+    }
+
+    fn dir(self) -> &'static str {
+        match self {
+            Slot::Base => "/home/root",
+            Slot::A => "/home/root/ota/slot_a",
+            Slot::B => "/home/root/ota/slot_b",
+        }
+    }
+}
+
+/// state.env 的内容，字段与 loader.sh 中的 shell 变量一一对应
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SlotState {
+    active: Slot,
+    previous: Option<Slot>,
+    confirmed: bool,
+    boot_attempts: u32,
+    max_boot_attempts: u32,
+    rolled_back_from: Option<Slot>,
+}
+
+impl Default for SlotState {
+    fn default() -> Self {
+        Self {
+            active: Slot::Base,
+            previous: None,
+            confirmed: true,
+            boot_attempts: 0,
+            max_boot_attempts: 3,
+            rolled_back_from: None,
+        }
     }
 }
 
+impl SlotState {
+    fn parse(content: &str) -> Self {
+        let mut state = Self::default();
+        for line in content.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "ACTIVE_SLOT" => state.active = Slot::parse(value).unwrap_or(Slot::Base),
+                "PREVIOUS_SLOT" => state.previous = Slot::parse(value),
+                "CONFIRMED" => state.confirmed = value == "1",
+                "BOOT_ATTEMPTS" => state.boot_attempts = value.parse().unwrap_or(0),
+                "MAX_BOOT_ATTEMPTS" => {
+                    state.max_boot_attempts = value.parse().unwrap_or(state.max_boot_attempts)
+                }
+                "ROLLED_BACK_FROM" => state.rolled_back_from = Slot::parse(value),
+                _ => {}
+            }
+        }
+        state
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "ACTIVE_SLOT={}\nPREVIOUS_SLOT={}\nCONFIRMED={}\nBOOT_ATTEMPTS={}\nMAX_BOOT_ATTEMPTS={}\nROLLED_BACK_FROM={}\n",
+            self.active.name(),
+            self.previous.map(Slot::name).unwrap_or(""),
+            if self.confirmed { 1 } else { 0 },
+            self.boot_attempts,
+            self.max_boot_attempts,
+            self.rolled_back_from.map(Slot::name).unwrap_or(""),
+        )
+    }
+
+    /// 下一次更新写入的槽位（从不覆盖当前运行的槽位和 base）
+    fn inactive_slot(&self) -> Slot {
+        match self.active {
+            Slot::A => Slot::B,
+            Slot::B | Slot::Base => Slot::A,
+        }
+    }
+
+    /// 切换到新安装的槽位，等待启动确认
+    fn install(&mut self, target: Slot) {
+        self.previous = Some(self.active);
+        self.active = target;
+        self.confirmed = false;
+        self.boot_attempts = 0;
+        self.rolled_back_from = None;
+    }
+
+    /// 手动回滚到上一个槽位（上一个槽位视为已确认可用）
+    fn rollback(&mut self) -> Result<(), String> {
+        let previous = self
+            .previous
+            .ok_or_else(|| "No previous slot to roll back to".to_string())?;
+        self.previous = Some(self.active);
+        self.active = previous;
+        self.confirmed = true;
+        self.boot_attempts = 0;
+        self.rolled_back_from = None;
+        Ok(())
+    }
+
+    fn confirm(&mut self) {
+        self.confirmed = true;
+        self.boot_attempts = 0;
+    }
+}
+
+fn read_slot_state() -> SlotState {
+    fs::read_to_string(OTA_STATE_FILE)
+        .map(|content| SlotState::parse(&content))
+        .unwrap_or_default()
+}
+
+fn write_slot_state(state: &SlotState) -> Result<(), String> {
+    crate::config::write_file_atomic(Path::new(OTA_STATE_FILE), &state.serialize())
+}
+
+fn schedule_reboot() {
+    std::thread::spawn(|| {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        let _ = Command::new("reboot").spawn();
+    });
+}
+
 fn read_pending_meta() -> Option<OtaMeta> {
     let meta_path = Path::new(OTA_STAGING_DIR).join("meta.json");
     fs::read_to_string(meta_path)
@@ -32,56 +219,62 @@ fn read_pending_meta() -> Option<OtaMeta> {
         .and_then(|content| serde_json::from_str(&content).ok())
 }
 
-pub fn handle_ota_upload(data: &[u8]) -> Result<OtaUploadResponse, String> {
+/// 接收上传的更新包：请求体边接收边写入暂存目录，不在内存中缓存整个更新包
+pub async fn handle_ota_upload(body: axum::body::Body, config: &OtaConfig) -> Result<OtaUploadResponse, String> {
+    let _staging = STAGING_LOCK
+        .try_lock()
+        .map_err(|_| "Another OTA package is being staged".to_string())?;
     let _ = fs::remove_dir_all(OTA_STAGING_DIR);
     fs::create_dir_all(OTA_STAGING_DIR)
         .map_err(|e| format!("Failed to create staging dir: {}", e))?;
 
-    let is_zip = detect_zip_format(data);
-
-    if is_zip {
-        let zip_path = Path::new(OTA_STAGING_DIR).join("update.zip");
-        let mut file = fs::File::create(&zip_path)
-            .map_err(|e| format!("Failed to create zip file: {}", e))?;
-        file.write_all(data)
-            .map_err(|e| format!("Failed to write zip file: {}", e))?;
-
-        let output = Command::new("unzip")
-            .args(["-o", zip_path.to_str().unwrap_or(""), "-d", OTA_STAGING_DIR])
-            .output()
-            .map_err(|e| format!("Failed to extract zip: {}. Make sure 'unzip' is installed.", e))?;
-
-        if !output.status.success() {
-            return Err(format!(
-                "Failed to extract zip: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    let archive_path = Path::new(OTA_STAGING_DIR).join("update.archive");
+    let mut file = fs::File::create(&archive_path)
+        .map_err(|e| format!("Failed to create archive file: {}", e))?;
+    let mut received = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to receive upload: {}", e))?;
+        received += chunk.len() as u64;
+        if received > OTA_MAX_UPLOAD_BYTES {
+            drop(file);
+            let _ = fs::remove_dir_all(OTA_STAGING_DIR);
+            return Err(format!("Upload exceeds {} bytes", OTA_MAX_UPLOAD_BYTES));
         }
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write archive file: {}", e))?;
+    }
+    drop(file);
 
-        let _ = fs::remove_file(&zip_path);
-    } else {
-        let tar_path = Path::new(OTA_STAGING_DIR).join("update.tar.gz");
-        let mut file = fs::File::create(&tar_path)
-            .map_err(|e| format!("Failed to create tar file: {}", e))?;
-        file.write_all(data)
-            .map_err(|e| format!("Failed to write tar file: {}", e))?;
-
-        let output = Command::new("tar")
-            .args(["-xzf", tar_path.to_str().unwrap_or(""), "-C", OTA_STAGING_DIR])
-            .output()
-            .map_err(|e| format!("Failed to extract tar: {}", e))?;
-
-        if !output.status.success() {
-            return Err(format!(
-                "Failed to extract tar: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || stage_ota_archive(&archive_path, &config))
+        .await
+        .map_err(|e| format!("OTA processing task failed: {}", e))?
+}
 
-        let _ = fs::remove_file(&tar_path);
+/// 解压暂存目录中的更新包（上传或下载得到的 zip / tar.gz）并验证
+///
+/// 暂存目录中除 `archive` 以外的旧文件会先被清理，解压完成后删除 `archive`
+pub(crate) fn stage_ota_archive(archive: &Path, config: &OtaConfig) -> Result<OtaUploadResponse, String> {
+    let entries = fs::read_dir(OTA_STAGING_DIR)
+        .map_err(|e| format!("Failed to read staging dir: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path == archive {
+            continue;
+        }
+        let _ = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
     }
 
-    fix_file_permissions(OTA_STAGING_DIR)?;
+    let extracted = archive::extract(archive, Path::new(OTA_STAGING_DIR), &OTA_EXTRACT_LIMITS);
+    let _ = fs::remove_file(archive);
+    let files = extracted.inspect_err(|_| {
+        let _ = fs::remove_dir_all(OTA_STAGING_DIR);
+    })?;
 
     let meta_path = Path::new(OTA_STAGING_DIR).join("meta.json");
     let meta_content = fs::read_to_string(&meta_path)
@@ -90,70 +283,145 @@ pub fn handle_ota_upload(data: &[u8]) -> Result<OtaUploadResponse, String> {
     let meta: OtaMeta = serde_json::from_str(&meta_content)
         .map_err(|e| format!("Invalid meta.json: {}", e))?;
 
-    let validation = validate_ota_package(&meta)?;
+    let hashes = PackageHashes::from_extracted(&files);
+    let validation = validate_package(Path::new(OTA_STAGING_DIR), &meta, config, Some(&hashes))?;
 
     Ok(OtaUploadResponse { meta, validation })
 }
 
-fn validate_ota_package(meta: &OtaMeta) -> Result<OtaValidation, String> {
-    let binary_path = Path::new(OTA_STAGING_DIR).join("udx710");
-    let www_path = Path::new(OTA_STAGING_DIR).join("www");
+/// 更新包内容的哈希
+struct PackageHashes {
+    binary_md5: String,
+    binary_sha256: String,
+    frontend_md5: String,
+    frontend_sha256: String,
+}
+
+impl PackageHashes {
+    /// 使用解压时计算的哈希，无需重新读取文件
+    fn from_extracted(files: &[ExtractedFile]) -> Self {
+        let binary = files.iter().find(|file| file.path == "udx710");
+        let frontend = || files.iter().filter(|file| file.path.starts_with("www/"));
+        let md5_payload = hash_payload(frontend().map(|file| file.md5.clone()).collect());
+        let sha256_payload = hash_payload(frontend().map(|file| file.sha256.clone()).collect());
+
+        Self {
+            binary_md5: binary.map(|file| file.md5.clone()).unwrap_or_default(),
+            binary_sha256: binary.map(|file| file.sha256.clone()).unwrap_or_default(),
+            frontend_md5: format!("{:x}", md5::compute(md5_payload.as_bytes())),
+            frontend_sha256: to_hex(digest::digest(&digest::SHA256, sha256_payload.as_bytes()).as_ref()),
+        }
+    }
+
+    fn from_dir(dir: &Path) -> Result<Self, String> {
+        let binary_path = dir.join("udx710");
+        let www_path = dir.join("www");
+        Ok(Self {
+            binary_md5: calculate_file_md5(&binary_path)?,
+            binary_sha256: calculate_file_sha256(&binary_path)?,
+            frontend_md5: calculate_directory_md5(&www_path)?,
+            frontend_sha256: calculate_directory_sha256(&www_path)?,
+        })
+    }
+}
+
+fn validate_ota_package(meta: &OtaMeta, config: &OtaConfig) -> Result<OtaValidation, String> {
+    validate_package_dir(Path::new(OTA_STAGING_DIR), meta, config)
+}
+
+fn validate_package_dir(dir: &Path, meta: &OtaMeta, config: &OtaConfig) -> Result<OtaValidation, String> {
+    validate_package(dir, meta, config, None)
+}
+
+/// 验证更新包，`hashes` 为 None 时从目录重新计算哈希
+fn validate_package(
+    dir: &Path,
+    meta: &OtaMeta,
+    config: &OtaConfig,
+    hashes: Option<&PackageHashes>,
+) -> Result<OtaValidation, String> {
+    let binary_path = dir.join("udx710");
+    let www_path = dir.join("www");
 
     if !binary_path.exists() {
         return Ok(OtaValidation {
-            valid: false,
-            is_newer: false,
-            binary_md5_match: false,
-            frontend_md5_match: false,
-            arch_match: false,
             error: Some("Binary file not found in package".to_string()),
+            ..Default::default()
         });
     }
 
     if !www_path.exists() {
         return Ok(OtaValidation {
-            valid: false,
-            is_newer: false,
-            binary_md5_match: false,
-            frontend_md5_match: false,
-            arch_match: false,
             error: Some("Frontend directory not found in package".to_string()),
+            ..Default::default()
         });
     }
 
-    let binary_md5 = calculate_file_md5(&binary_path)?;
-    let binary_md5_match = binary_md5 == meta.binary_md5;
-    let frontend_md5 = calculate_directory_md5(&www_path)?;
-    let frontend_md5_match = frontend_md5 == meta.frontend_md5;
+    let computed;
+    let hashes = match hashes {
+        Some(hashes) => hashes,
+        None => {
+            computed = PackageHashes::from_dir(dir)?;
+            &computed
+        }
+    };
+    let binary_md5 = &hashes.binary_md5;
+    let binary_md5_match = *binary_md5 == meta.binary_md5;
+    let frontend_md5 = &hashes.frontend_md5;
+    let frontend_md5_match = *frontend_md5 == meta.frontend_md5;
     let arch_match = meta.arch == "aarch64-unknown-linux-musl";
     let is_newer = compare_versions(&meta.version, CURRENT_VERSION);
+    let signature = check_signature(dir, config);
+
+    let mut errors = Vec::new();
+    if !binary_md5_match {
+        errors.push(format!(
+            "Binary MD5 mismatch: expected={}, actual={}",
+            meta.binary_md5, binary_md5
+        ));
+    }
+    if !frontend_md5_match {
+        errors.push(format!(
+            "Frontend MD5 mismatch: expected={}, actual={}",
+            meta.frontend_md5, frontend_md5
+        ));
+    }
+    if !arch_match {
+        errors.push(format!(
+            "Arch mismatch: expected=aarch64-unknown-linux-musl, actual={}",
+            meta.arch
+        ));
+    }
+    if let Some(error) = &signature.error {
+        errors.push(error.clone());
+    }
 
-    let valid = binary_md5_match && frontend_md5_match && arch_match;
-
-    let error = if !valid {
-        let mut errors = Vec::new();
-        if !binary_md5_match {
-            errors.push(format!(
-                "Binary MD5 mismatch: expected={}, actual={}",
-                meta.binary_md5, binary_md5
-            ));
+    // MD5 可被碰撞，签名只有在覆盖 SHA-256 时才有意义
+    match (&meta.binary_sha256, &meta.frontend_sha256) {
+        (Some(binary_sha256), Some(frontend_sha256)) => {
+            let actual = &hashes.binary_sha256;
+            if !actual.eq_ignore_ascii_case(binary_sha256) {
+                errors.push(format!(
+                    "Binary SHA-256 mismatch: expected={}, actual={}",
+                    binary_sha256, actual
+                ));
+            }
+            let actual = &hashes.frontend_sha256;
+            if !actual.eq_ignore_ascii_case(frontend_sha256) {
+                errors.push(format!(
+                    "Frontend SHA-256 mismatch: expected={}, actual={}",
+                    frontend_sha256, actual
+                ));
+            }
         }
-        if !frontend_md5_match {
-            errors.push(format!(
-                "Frontend MD5 mismatch: expected={}, actual={}",
-                meta.frontend_md5, frontend_md5
-            ));
+        _ if signature.signer.is_some() => {
+            errors.push("Signed package must include binary_sha256 and frontend_sha256".to_string());
         }
-        if !arch_match {
-            errors.push(format!(
-                "Arch mismatch: expected=aarch64-unknown-linux-musl, actual={}",
-                meta.arch
-            ));
-        }
-        Some(errors.join("; "))
-    } else {
-        None
-    };
+        _ => {}
+    }
+
+    let valid = errors.is_empty();
+    let error = if !valid { Some(errors.join("; ")) } else { None };
 
     Ok(OtaValidation {
         valid,
@@ -161,22 +429,134 @@ fn validate_ota_package(meta: &OtaMeta) -> Result<OtaValidation, String> {
         binary_md5_match,
         frontend_md5_match,
         arch_match,
+        signed: signature.signed,
+        signature_valid: signature.signer.is_some(),
+        signer: signature.signer,
         error,
     })
 }
 
-fn calculate_file_md5(path: &Path) -> Result<String, String> {
+/// 签名检查结果
+struct SignatureCheck {
+    signed: bool,
+    signer: Option<OtaSigner>,
+    /// 导致更新包被拒绝的原因
+    error: Option<String>,
+}
+
+/// 内置公钥和配置中固定的公钥（名称, 公钥）
+fn trusted_keys(config: &OtaConfig) -> Vec<(String, minisign::PublicKey)> {
+    let builtin = BUILTIN_PUBLIC_KEYS
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|key| !key.is_empty())
+        .filter_map(|key| match minisign::PublicKey::parse(key) {
+            Ok(key) => Some(("builtin".to_string(), key)),
+            Err(e) => {
+                warn!(error = %e, "Ignoring invalid built-in OTA public key");
+                None
+            }
+        });
+    let pinned = config.trusted_keys.iter().filter_map(|key| match minisign::PublicKey::parse(&key.public_key) {
+        Ok(public_key) => Some((key.name.clone(), public_key)),
+        Err(e) => {
+            warn!(name = %key.name, error = %e, "Ignoring invalid OTA public key");
+            None
+        }
+    });
+    builtin.chain(pinned).collect()
+}
+
+/// 验证 meta.json 的签名
+///
+/// 未签名的更新包只在开发者模式下允许；签名无效或公钥不受信任时始终拒绝
+fn check_signature(dir: &Path, config: &OtaConfig) -> SignatureCheck {
+    let Ok(signature_text) = fs::read_to_string(dir.join(OTA_SIGNATURE_FILE)) else {
+        return SignatureCheck {
+            signed: false,
+            signer: None,
+            error: (!config.developer_mode)
+                .then(|| "Package is not signed (enable developer mode to allow unsigned packages)".to_string()),
+        };
+    };
+
+    let result = minisign::Signature::parse(&signature_text).and_then(|signature| {
+        let meta = fs::read(dir.join("meta.json")).map_err(|e| format!("Failed to read meta.json: {}", e))?;
+        let (name, public_key) = trusted_keys(config)
+            .into_iter()
+            .find(|(_, key)| key.key_id() == signature.key_id())
+            .ok_or_else(|| format!("Package signed by untrusted key {}", signature.key_id()))?;
+        signature.verify(&public_key, &meta)?;
+        Ok(OtaSigner {
+            name,
+            key_id: public_key.key_id(),
+            trusted_comment: signature.trusted_comment().to_string(),
+        })
+    });
+
+    match result {
+        Ok(signer) => SignatureCheck {
+            signed: true,
+            signer: Some(signer),
+            error: None,
+        },
+        Err(e) => SignatureCheck {
+            signed: true,
+            signer: None,
+            error: Some(format!("Invalid package signature: {}", e)),
+        },
+    }
+}
+
+/// 校验配置中的公钥格式
+pub fn validate_trusted_keys(config: &OtaConfig) -> Result<(), String> {
+    for key in &config.trusted_keys {
+        if key.name.trim().is_empty() {
+            return Err("Public key name is required".to_string());
+        }
+        minisign::PublicKey::parse(&key.public_key).map_err(|e| format!("{}: {}", key.name, e))?;
+    }
+    Ok(())
+}
+
+/// 按固定大小分块读取文件交给 `consume`，不把整个文件读入内存
+fn read_file_chunks(path: &Path, mut consume: impl FnMut(&[u8])) -> Result<(), String> {
     let mut file = fs::File::open(path)
         .map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
 
-    let mut contents = Vec::new();
-    file.read_to_end(&mut contents)
-        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        if n == 0 {
+            return Ok(());
+        }
+        consume(&buffer[..n]);
+    }
+}
+
+fn calculate_file_md5(path: &Path) -> Result<String, String> {
+    let mut context = md5::Context::new();
+    read_file_chunks(path, |chunk| context.consume(chunk))?;
+    Ok(format!("{:x}", context.compute()))
+}
 
-    Ok(format!("{:x}", md5::compute(&contents)))
+pub(crate) fn calculate_file_sha256(path: &Path) -> Result<String, String> {
+    let mut context = digest::Context::new(&digest::SHA256);
+    read_file_chunks(path, |chunk| context.update(chunk))?;
+    Ok(to_hex(context.finish().as_ref()))
 }
 
-fn collect_directory_hashes(path: &Path, hashes: &mut Vec<String>) -> Result<(), String> {
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn collect_directory_hashes(
+    path: &Path,
+    hash_file: fn(&Path) -> Result<String, String>,
+    hashes: &mut Vec<String>,
+) -> Result<(), String> {
     let entries = fs::read_dir(path)
         .map_err(|e| format!("Failed to read directory {}: {}", path.display(), e))?;
 
@@ -185,29 +565,43 @@ fn collect_directory_hashes(path: &Path, hashes: &mut Vec<String>) -> Result<(),
         let entry_path = entry.path();
 
         if entry_path.is_dir() {
-            collect_directory_hashes(&entry_path, hashes)?;
+            collect_directory_hashes(&entry_path, hash_file, hashes)?;
         } else {
-            hashes.push(calculate_file_md5(&entry_path)?);
+            hashes.push(hash_file(&entry_path)?);
         }
     }
 
     Ok(())
 }
 
-fn calculate_directory_md5(path: &Path) -> Result<String, String> {
+/// 目录中所有文件的哈希排序后逐行拼接（每行以换行结尾）
+fn directory_hash_payload(path: &Path, hash_file: fn(&Path) -> Result<String, String>) -> Result<String, String> {
     let mut hashes = Vec::new();
-    collect_directory_hashes(path, &mut hashes)?;
+    collect_directory_hashes(path, hash_file, &mut hashes)?;
+    Ok(hash_payload(hashes))
+}
+
+fn hash_payload(mut hashes: Vec<String>) -> String {
     hashes.sort();
 
     let mut payload = hashes.join("\n");
     if !payload.is_empty() {
         payload.push('\n');
     }
+    payload
+}
 
+fn calculate_directory_md5(path: &Path) -> Result<String, String> {
+    let payload = directory_hash_payload(path, calculate_file_md5)?;
     Ok(format!("{:x}", md5::compute(payload.as_bytes())))
 }
 
-fn compare_versions(v1: &str, v2: &str) -> bool {
+fn calculate_directory_sha256(path: &Path) -> Result<String, String> {
+    let payload = directory_hash_payload(path, calculate_file_sha256)?;
+    Ok(to_hex(digest::digest(&digest::SHA256, payload.as_bytes()).as_ref()))
+}
+
+pub(crate) fn compare_versions(v1: &str, v2: &str) -> bool {
     let parse = |v: &str| -> Vec<u32> {
         v.split('.')
             .filter_map(|s| s.parse().ok())
@@ -229,42 +623,131 @@ fn compare_versions(v1: &str, v2: &str) -> bool {
     false
 }
 
-pub fn apply_ota_update(restart_now: bool) -> Result<String, String> {
+pub fn apply_ota_update(restart_now: bool, config: &OtaConfig) -> Result<String, String> {
     let meta = read_pending_meta()
         .ok_or_else(|| "No pending update".to_string())?;
-    let validation = validate_ota_package(&meta)?;
+    let validation = validate_ota_package(&meta, config)?;
     if !validation.valid {
         return Err(validation
             .error
             .unwrap_or_else(|| "OTA package validation failed".to_string()));
     }
 
+    let mut state = read_slot_state();
+    let target = state.inactive_slot();
+    let slot_dir = target.dir();
+
     let staging_binary = Path::new(OTA_STAGING_DIR).join("udx710");
     let staging_www = Path::new(OTA_STAGING_DIR).join("www");
+    let slot_binary = Path::new(slot_dir).join("udx710");
+    let slot_www = Path::new(slot_dir).join("www");
 
-    fs::copy(&staging_binary, OTA_BINARY_PATH)
+    let _ = fs::remove_dir_all(slot_dir);
+    fs::create_dir_all(slot_dir)
+        .map_err(|e| format!("Failed to create slot dir {}: {}", slot_dir, e))?;
+    fs::copy(&staging_binary, &slot_binary)
         .map_err(|e| format!("Failed to copy binary: {}", e))?;
+    copy_dir_recursive(
+        staging_www.to_str().unwrap_or(""),
+        slot_www.to_str().unwrap_or(""),
+    )?;
+    fix_file_permissions(slot_dir)?;
+    let _ = Command::new("sync").output();
+
+    // 先确保 loader.sh 能识别 A/B 分区，再切换状态文件
+    crate::config::install_ab_loader()?;
+    state.install(target);
+    write_slot_state(&state)?;
+
+    let _ = fs::remove_dir_all(OTA_STAGING_DIR);
+
+    if restart_now {
+        schedule_reboot();
+    }
 
-    Command::new("chmod")
-        .args(["755", OTA_BINARY_PATH])
-        .output()
-        .map_err(|e| format!("Failed to chmod binary: {}", e))?;
+    Ok(format!(
+        "Update to version {} installed to slot {}",
+        meta.version,
+        target.name()
+    ))
+}
 
-    let _ = fs::remove_dir_all(OTA_WWW_PATH);
-    copy_dir_recursive(staging_www.to_str().unwrap_or(""), OTA_WWW_PATH)?;
-    fix_file_permissions("/home/root")?;
-    crate::config::ensure_loader_hooks_init()?;
+/// 回滚到上一个槽位
+pub fn rollback_ota(restart_now: bool) -> Result<String, String> {
+    let mut state = read_slot_state();
+    state.rollback()?;
 
-    let _ = fs::remove_dir_all(OTA_STAGING_DIR);
+    let binary = Path::new(state.active.dir()).join("udx710");
+    if !binary.exists() {
+        return Err(format!("Slot {} has no installed binary", state.active.name()));
+    }
+
+    crate::config::install_ab_loader()?;
+    write_slot_state(&state)?;
 
     if restart_now {
-        std::thread::spawn(|| {
-            std::thread::sleep(std::time::Duration::from_secs(1));
-            let _ = Command::new("reboot").spawn();
-        });
+        schedule_reboot();
+    }
+
+    Ok(format!("Rolled back to slot {}", state.active.name()))
+}
+
+/// 新槽位首次启动后确认启动成功
+///
+/// 本地 HTTP 健康检查和调制解调器查询都成功后写入 CONFIRMED=1；
+/// 超时仍未通过则退出进程，由 loader.sh 重启并累计失败次数，达到上限后自动回滚。
+pub async fn confirm_boot_when_healthy(health_url: String, modem: Arc<dyn ModemBackend>) {
+    let state = read_slot_state();
+    if let Some(from) = state.rolled_back_from {
+        warn!(
+            from = from.name(),
+            active = state.active.name(),
+            "OTA slot failed to boot and was rolled back"
+        );
+    }
+    if state.confirmed {
+        return;
+    }
+
+    info!(
+        slot = state.active.name(),
+        attempt = state.boot_attempts,
+        max = state.max_boot_attempts,
+        "Waiting for OTA boot confirmation"
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap_or_default();
+    let deadline = tokio::time::Instant::now() + BOOT_CONFIRM_TIMEOUT;
+
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(BOOT_CONFIRM_INTERVAL).await;
+
+        let http_ok = matches!(
+            client.get(&health_url).send().await,
+            Ok(response) if response.status().is_success()
+        );
+        if !http_ok {
+            continue;
+        }
+        if let Err(e) = modem.device_info().await {
+            warn!(error = %e, "Modem not ready during OTA boot confirmation");
+            continue;
+        }
+
+        let mut state = read_slot_state();
+        state.confirm();
+        match write_slot_state(&state) {
+            Ok(()) => info!(slot = state.active.name(), "OTA boot confirmed"),
+            Err(e) => warn!(error = %e, "Failed to write OTA boot confirmation"),
+        }
+        return;
     }
 
-    Ok(format!("Update to version {} applied successfully", meta.version))
+    warn!("OTA boot confirmation timed out, exiting for loader to retry");
+    std::process::exit(1);
 }
 
 fn copy_dir_recursive(src: &str, dst: &str) -> Result<(), String> {
@@ -301,34 +784,224 @@ pub fn cancel_pending_update() -> Result<(), String> {
     Ok(())
 }
 
-fn detect_zip_format(data: &[u8]) -> bool {
-    if data.len() < 4 {
-        return false;
+fn fix_file_permissions(root: &str) -> Result<(), String> {
+    let binary_path = Path::new(root).join("udx710");
+    let www_path = Path::new(root).join("www");
+
+    if binary_path.exists() {
+        set_mode(&binary_path, 0o755)?;
+    }
+    if www_path.is_dir() {
+        set_tree_modes(&www_path)?;
     }
 
-    data[0] == 0x50 && data[1] == 0x4B && data[2] == 0x03 && data[3] == 0x04
+    Ok(())
 }
 
-fn fix_file_permissions(root: &str) -> Result<(), String> {
-    let binary_path = format!("{}/udx710", root);
-    let www_path = format!("{}/www", root);
+/// 目录 755，文件 644
+fn set_tree_modes(dir: &Path) -> Result<(), String> {
+    set_mode(dir, 0o755)?;
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read dir {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            set_tree_modes(&path)?;
+        } else {
+            set_mode(&path, 0o644)?;
+        }
+    }
+    Ok(())
+}
 
-    if Path::new(&binary_path).exists() {
-        Command::new("chmod")
-            .args(["755", &binary_path])
-            .output()
-            .map_err(|e| format!("Failed to chmod binary {}: {}", binary_path, e))?;
+fn set_mode(path: &Path, mode: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .map_err(|e| format!("Failed to set permissions for {}: {}", path.display(), e))?;
     }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
 
-    if Path::new(&www_path).exists() {
-        let _ = Command::new("find")
-            .args([&www_path, "-type", "d", "-exec", "chmod", "755", "{}", "+"])
-            .output();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OtaTrustedKey;
+    use crate::minisign::tests::{sign, test_keypair};
+
+    /// 在临时目录中生成更新包内容，返回目录和 meta.json 原文
+    fn staged_package(name: &str, with_sha256: bool) -> (std::path::PathBuf, OtaMeta, String) {
+        let dir = std::env::temp_dir().join(format!("ota-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("www/assets")).unwrap();
+        fs::write(dir.join("udx710"), b"\x7fELF binary").unwrap();
+        fs::write(dir.join("www/index.html"), b"<html></html>").unwrap();
+        fs::write(dir.join("www/assets/app.js"), b"console.log(1)").unwrap();
+
+        let meta = OtaMeta {
+            version: "99.0.0".to_string(),
+            commit: "abc1234".to_string(),
+            build_time: "2025-12-20T00:00:00Z".to_string(),
+            binary_md5: calculate_file_md5(&dir.join("udx710")).unwrap(),
+            frontend_md5: calculate_directory_md5(&dir.join("www")).unwrap(),
+            binary_sha256: with_sha256.then(|| calculate_file_sha256(&dir.join("udx710")).unwrap()),
+            frontend_sha256: with_sha256.then(|| calculate_directory_sha256(&dir.join("www")).unwrap()),
+            arch: "aarch64-unknown-linux-musl".to_string(),
+            min_version: None,
+        };
+        let meta_json = serde_json::to_string_pretty(&meta).unwrap();
+        fs::write(dir.join("meta.json"), &meta_json).unwrap();
+        (dir, meta, meta_json)
+    }
 
-        let _ = Command::new("find")
-            .args([&www_path, "-type", "f", "-exec", "chmod", "644", "{}", "+"])
-            .output();
+    #[test]
+    fn slot_state_round_trips_loader_format() {
+        let state = SlotState::parse(
+            "ACTIVE_SLOT=b\nPREVIOUS_SLOT=a\nCONFIRMED=0\nBOOT_ATTEMPTS=2\nMAX_BOOT_ATTEMPTS=3\nROLLED_BACK_FROM=\n",
+        );
+        assert_eq!(state.active, Slot::B);
+        assert_eq!(state.previous, Some(Slot::A));
+        assert!(!state.confirmed);
+        assert_eq!(state.boot_attempts, 2);
+        assert_eq!(SlotState::parse(&state.serialize()), state);
+
+        assert_eq!(SlotState::parse(""), SlotState::default());
     }
 
-    Ok(())
+    #[test]
+    fn slot_state_install_confirm_and_rollback() {
+        let mut state = SlotState::default();
+        assert!(state.rollback().is_err());
+
+        let target = state.inactive_slot();
+        assert_eq!(target, Slot::A);
+        state.install(target);
+        assert_eq!((state.active, state.previous, state.confirmed), (Slot::A, Some(Slot::Base), false));
+
+        state.confirm();
+        assert!(state.confirmed);
+        assert_eq!(state.inactive_slot(), Slot::B);
+        state.install(Slot::B);
+        assert_eq!(state.previous, Some(Slot::A));
+
+        state.rollback().unwrap();
+        assert_eq!((state.active, state.previous, state.confirmed), (Slot::A, Some(Slot::B), true));
+    }
+
+    fn config_with_key(seed: u8, developer_mode: bool) -> OtaConfig {
+        OtaConfig {
+            trusted_keys: vec![OtaTrustedKey {
+                name: "release".to_string(),
+                public_key: test_keypair(seed).0,
+            }],
+            developer_mode,
+        }
+    }
+
+    #[test]
+    fn extracted_hashes_match_directory_hashes() {
+        let (dir, meta, _) = staged_package("extract", true);
+        let archive_path = dir.with_extension("tar.gz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            fs::File::create(&archive_path).unwrap(),
+            flate2::Compression::default(),
+        ));
+        builder.append_dir_all(".", &dir).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = dir.with_extension("out");
+        let _ = fs::remove_dir_all(&dest);
+        fs::create_dir_all(&dest).unwrap();
+        let files = archive::extract(&archive_path, &dest, &OTA_EXTRACT_LIMITS).unwrap();
+        let hashes = PackageHashes::from_extracted(&files);
+
+        assert_eq!(hashes.binary_md5, meta.binary_md5);
+        assert_eq!(hashes.frontend_md5, meta.frontend_md5);
+        assert_eq!(Some(hashes.binary_sha256), meta.binary_sha256);
+        assert_eq!(Some(hashes.frontend_sha256), meta.frontend_sha256);
+
+        for path in [dir, dest, archive_path] {
+            let _ = fs::remove_dir_all(&path);
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn trusted_signature_is_accepted_with_signer() {
+        let (dir, meta, meta_json) = staged_package("trusted", true);
+        let (_, keypair) = test_keypair(1);
+        fs::write(dir.join(OTA_SIGNATURE_FILE), sign(&keypair, 1, meta_json.as_bytes(), "udx710 99.0.0")).unwrap();
+
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, false)).unwrap();
+        assert!(validation.valid, "{:?}", validation.error);
+        assert!(validation.signed && validation.signature_valid);
+        let signer = validation.signer.unwrap();
+        assert_eq!((signer.name.as_str(), signer.trusted_comment.as_str()), ("release", "udx710 99.0.0"));
+
+        // 签名后替换二进制：SHA-256 不匹配
+        fs::write(dir.join("udx710"), b"\x7fELF evil").unwrap();
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, false)).unwrap();
+        assert!(!validation.valid);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsigned_package_requires_developer_mode() {
+        let (dir, meta, _) = staged_package("unsigned", false);
+
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, false)).unwrap();
+        assert!(!validation.valid && !validation.signed);
+        assert!(validation.error.unwrap().contains("not signed"));
+
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, true)).unwrap();
+        assert!(validation.valid, "{:?}", validation.error);
+        assert!(validation.signer.is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bad_signatures_are_rejected_even_in_developer_mode() {
+        let (dir, meta, meta_json) = staged_package("untrusted", true);
+        let (_, other) = test_keypair(2);
+        fs::write(dir.join(OTA_SIGNATURE_FILE), sign(&other, 2, meta_json.as_bytes(), "rogue")).unwrap();
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, true)).unwrap();
+        assert!(!validation.valid && validation.signed && !validation.signature_valid);
+        assert!(validation.error.unwrap().contains("untrusted key"));
+
+        // 签名后修改 meta.json
+        let (_, keypair) = test_keypair(1);
+        fs::write(dir.join(OTA_SIGNATURE_FILE), sign(&keypair, 1, meta_json.as_bytes(), "release")).unwrap();
+        fs::write(dir.join("meta.json"), meta_json.replace("99.0.0", "99.0.1")).unwrap();
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, true)).unwrap();
+        assert!(!validation.valid && !validation.signature_valid);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn signed_package_must_carry_sha256() {
+        let (dir, meta, meta_json) = staged_package("md5-only", false);
+        let (_, keypair) = test_keypair(1);
+        fs::write(dir.join(OTA_SIGNATURE_FILE), sign(&keypair, 1, meta_json.as_bytes(), "release")).unwrap();
+
+        let validation = validate_package_dir(&dir, &meta, &config_with_key(1, false)).unwrap();
+        assert!(validation.signature_valid && !validation.valid);
+        assert!(validation.error.unwrap().contains("binary_sha256"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_trusted_keys_are_reported() {
+        assert!(validate_trusted_keys(&config_with_key(1, false)).is_ok());
+        let config = OtaConfig {
+            trusted_keys: vec![OtaTrustedKey {
+                name: "broken".to_string(),
+                public_key: "RWQ".to_string(),
+            }],
+            developer_mode: false,
+        };
+        assert!(validate_trusted_keys(&config).unwrap_err().starts_with("broken"));
+    }
 }
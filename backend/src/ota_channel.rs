@@ -0,0 +1,514 @@
+//! OTA 更新通道
+//!
+//! 按 `OtaChannelConfig.check_interval_secs` 定期拉取更新清单，清单格式：
+//!
+//! ```json
+//! { "channels": { "stable": { "version": "3.2.0", "url": "udx710-3.2.0.tar.gz", "sha256": "...", "size": 123 } } }
+//! ```
+//!
+//! 订阅通道中的版本比当前新时，把更新包断点续传下载到 OTA 暂存目录，
+//! 解压后沿用上传更新包的签名和哈希验证。`Notify` 策略只推送事件，
+//! `AutoApply` 策略在维护窗口内自动安装到 A/B 槽位并重启。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use reqwest::header::RANGE;
+use reqwest::{Client, StatusCode, Url};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::config::{ConfigManager, OtaChannelConfig, OtaUpdatePolicy};
+use crate::events::{DeviceEvent, EventBus};
+use crate::models::{OtaCheckResult, OtaRelease};
+use crate::ota;
+use crate::utils::{in_time_window, parse_time_of_day};
+
+/// 未完成的下载（断点续传）
+const DOWNLOAD_PART_FILE: &str = "download.part";
+/// 记录未完成下载对应的 URL 和 SHA-256，变化时重新下载
+const DOWNLOAD_SOURCE_FILE: &str = "download.source";
+
+/// 更新清单的大小上限
+const MAX_MANIFEST_BYTES: usize = 256 * 1024;
+
+/// 后台任务的轮询间隔（检查是否到期、是否进入维护窗口）
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 同一时间只允许一个检查/下载任务
+static CHECK_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 最近一次检查结果
+static LAST_CHECK: Mutex<Option<OtaCheckResult>> = Mutex::new(None);
+
+#[derive(Debug, Deserialize)]
+struct OtaManifest {
+    channels: HashMap<String, OtaRelease>,
+}
+
+pub fn last_check() -> Option<OtaCheckResult> {
+    LAST_CHECK.lock().unwrap().clone()
+}
+
+/// 校验通道配置中的 URL 和维护窗口格式
+pub fn validate_channel_config(config: &OtaChannelConfig) -> Result<(), String> {
+    if !config.manifest_url.is_empty() {
+        let url = Url::parse(&config.manifest_url)
+            .map_err(|e| format!("Invalid manifest URL: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Manifest URL must use http or https".to_string());
+        }
+    } else if config.enabled {
+        return Err("Manifest URL is required".to_string());
+    }
+
+    for time in [&config.maintenance_window_start, &config.maintenance_window_end] {
+        parse_time_of_day(time).ok_or_else(|| format!("Invalid maintenance window time: {}", time))?;
+    }
+    Ok(())
+}
+
+/// 从清单中取出订阅通道的发布信息，并把下载地址解析为绝对 URL
+fn select_release(manifest: &str, channel: &str, manifest_url: &Url) -> Result<(OtaRelease, Url), String> {
+    let manifest: OtaManifest = serde_json::from_str(manifest)
+        .map_err(|e| format!("Invalid update manifest: {}", e))?;
+    let release = manifest
+        .channels
+        .get(channel)
+        .cloned()
+        .ok_or_else(|| format!("Channel {} not found in update manifest", channel))?;
+    let url = manifest_url
+        .join(&release.url)
+        .map_err(|e| format!("Invalid release URL {}: {}", release.url, e))?;
+    Ok((release, url))
+}
+
+/// 断点续传下载更新包到 `dir`，返回下载完成的文件路径
+///
+/// 无论清单是否提供 `size`，写入的文件都不超过 `max_bytes`
+async fn download_release(
+    client: &Client,
+    url: &Url,
+    release: &OtaRelease,
+    dir: &Path,
+    max_bytes: u64,
+) -> Result<PathBuf, String> {
+    if let Some(size) = release.size.filter(|size| *size > max_bytes) {
+        return Err(format!("Update size {} exceeds {} bytes", size, max_bytes));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create download dir: {}", e))?;
+
+    let part_path = dir.join(DOWNLOAD_PART_FILE);
+    let source_path = dir.join(DOWNLOAD_SOURCE_FILE);
+    let source = format!("{}\n{}\n", url, release.sha256.as_deref().unwrap_or(""));
+    if fs::read_to_string(&source_path).ok().as_deref() != Some(source.as_str()) {
+        let _ = fs::remove_file(&part_path);
+        fs::write(&source_path, &source)
+            .map_err(|e| format!("Failed to write download marker: {}", e))?;
+    }
+
+    let mut offset = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    if offset > max_bytes {
+        let _ = fs::remove_file(&part_path);
+        offset = 0;
+    }
+    let complete = release.size.is_some_and(|size| offset >= size);
+
+    if !complete {
+        let mut request = client.get(url.clone());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download update: {}", e))?;
+
+        let append = match response.status() {
+            StatusCode::PARTIAL_CONTENT => Some(true),
+            StatusCode::OK => Some(false),
+            // 已下载完整，但清单未提供大小
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => None,
+            status => return Err(format!("Update download returned status {}", status)),
+        };
+
+        if let Some(append) = append {
+            let mut written = if append { offset } else { 0 };
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&part_path)
+                .map_err(|e| format!("Failed to open download file: {}", e))?;
+
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| format!("Update download interrupted: {}", e))?
+            {
+                written += chunk.len() as u64;
+                if written > max_bytes {
+                    drop(file);
+                    let _ = fs::remove_file(&part_path);
+                    return Err(format!("Update download exceeds {} bytes", max_bytes));
+                }
+                file.write_all(&chunk)
+                    .map_err(|e| format!("Failed to write download file: {}", e))?;
+            }
+            file.sync_all()
+                .map_err(|e| format!("Failed to sync download file: {}", e))?;
+        }
+    }
+
+    let size = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    if let Some(expected) = release.size {
+        if size != expected {
+            let _ = fs::remove_file(&part_path);
+            return Err(format!("Update size mismatch: expected={}, actual={}", expected, size));
+        }
+    }
+    if let Some(expected) = &release.sha256 {
+        let hash_path = part_path.clone();
+        let actual = tokio::task::spawn_blocking(move || ota::calculate_file_sha256(&hash_path))
+            .await
+            .map_err(|e| format!("OTA hashing task failed: {}", e))??;
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = fs::remove_file(&part_path);
+            return Err(format!("Update SHA-256 mismatch: expected={}, actual={}", expected, actual));
+        }
+    }
+
+    Ok(part_path)
+}
+
+/// 读取更新清单，超过 `max_bytes` 时中止
+async fn read_manifest(mut response: reqwest::Response, max_bytes: usize) -> Result<String, String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read update manifest: {}", e))?
+    {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(format!("Update manifest exceeds {} bytes", max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8(body).map_err(|_| "Update manifest is not valid UTF-8".to_string())
+}
+
+/// 检查更新通道，有新版本时下载并暂存
+pub async fn check_for_update(config_manager: &ConfigManager) -> Result<OtaCheckResult, String> {
+    let _guard = CHECK_LOCK.lock().await;
+    let config = config_manager.get_ota_channel();
+
+    let mut result = OtaCheckResult {
+        channel: config.channel.clone(),
+        current_version: ota::CURRENT_VERSION.to_string(),
+        checked_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        update_available: false,
+        release: None,
+        staged: false,
+        error: None,
+    };
+
+    let outcome = fetch_and_stage(config_manager, &config, &mut result).await;
+    if let Err(e) = &outcome {
+        result.error = Some(e.clone());
+    }
+    *LAST_CHECK.lock().unwrap() = Some(result.clone());
+    outcome.map(|_| result)
+}
+
+async fn fetch_and_stage(
+    config_manager: &ConfigManager,
+    config: &OtaChannelConfig,
+    result: &mut OtaCheckResult,
+) -> Result<(), String> {
+    let manifest_url = Url::parse(&config.manifest_url)
+        .map_err(|e| format!("Invalid manifest URL: {}", e))?;
+    let client = Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .read_timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .get(manifest_url.clone())
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch update manifest: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Update manifest returned status {}", response.status()));
+    }
+    let manifest = read_manifest(response, MAX_MANIFEST_BYTES).await?;
+
+    let (release, url) = select_release(&manifest, &config.channel, &manifest_url)?;
+    result.update_available = ota::compare_versions(&release.version, ota::CURRENT_VERSION);
+    result.release = Some(release.clone());
+    if !result.update_available {
+        return Ok(());
+    }
+
+    // 同一版本已暂存则不重复下载
+    if ota::get_ota_status()
+        .pending_meta
+        .is_some_and(|meta| meta.version == release.version)
+    {
+        result.staged = true;
+        return Ok(());
+    }
+
+    // 与手动上传共用暂存目录，整个下载和解压过程持有暂存锁
+    let _staging = ota::STAGING_LOCK.lock().await;
+    info!(version = %release.version, url = %url, "Downloading OTA update");
+    let archive = download_release(
+        &client,
+        &url,
+        &release,
+        Path::new(ota::OTA_STAGING_DIR),
+        ota::OTA_MAX_UPLOAD_BYTES,
+    )
+    .await?;
+    // 解压和哈希校验是同步的文件操作，不在异步运行时线程上执行
+    let ota_config = config_manager.get_ota();
+    let upload = tokio::task::spawn_blocking(move || ota::stage_ota_archive(&archive, &ota_config))
+        .await
+        .map_err(|e| format!("OTA processing task failed: {}", e))??;
+    if upload.meta.version != release.version {
+        let _ = ota::cancel_pending_update();
+        return Err(format!(
+            "Downloaded package version {} does not match manifest version {}",
+            upload.meta.version, release.version
+        ));
+    }
+    if !upload.validation.valid {
+        let _ = ota::cancel_pending_update();
+        return Err(upload
+            .validation
+            .error
+            .unwrap_or_else(|| "OTA package validation failed".to_string()));
+    }
+
+    result.staged = true;
+    Ok(())
+}
+
+/// 在维护窗口内应用已由更新通道暂存的版本
+async fn auto_apply_if_due(config_manager: &ConfigManager, config: &OtaChannelConfig) {
+    let (Some(start), Some(end)) = (
+        parse_time_of_day(&config.maintenance_window_start),
+        parse_time_of_day(&config.maintenance_window_end),
+    ) else {
+        return;
+    };
+    if !in_time_window(Local::now().time(), start, end) {
+        return;
+    }
+
+    let Some(release) = last_check().filter(|r| r.staged).and_then(|r| r.release) else {
+        return;
+    };
+    let status = ota::get_ota_status();
+    if status
+        .pending_meta
+        .is_none_or(|meta| meta.version != release.version)
+    {
+        return;
+    }
+    // 新版本启动失败被自动回滚后不再自动安装，需要手动处理
+    if let Some(from) = status.slot.rolled_back_from {
+        warn!(slot = %from, "Skipping OTA auto-apply after automatic rollback");
+        return;
+    }
+
+    info!(version = %release.version, "Auto-applying OTA update in maintenance window");
+    let _staging = ota::STAGING_LOCK.lock().await;
+    let ota_config = config_manager.get_ota();
+    match tokio::task::spawn_blocking(move || ota::apply_ota_update(true, &ota_config)).await {
+        Ok(Ok(message)) => info!(message = %message, "OTA update auto-applied"),
+        Ok(Err(e)) => warn!(error = %e, "Failed to auto-apply OTA update"),
+        Err(e) => warn!(error = %e, "OTA apply task failed"),
+    }
+}
+
+/// 更新通道后台任务
+pub async fn start_ota_channel_poller(config_manager: Arc<ConfigManager>, events: Arc<EventBus>) {
+    let mut last_run: Option<Instant> = None;
+    let mut announced: Option<String> = None;
+
+    loop {
+        let config = config_manager.get_ota_channel();
+
+        if config.enabled && !config.manifest_url.is_empty() {
+            let interval = Duration::from_secs(config.check_interval_secs);
+            if last_run.is_none_or(|t| t.elapsed() >= interval) {
+                last_run = Some(Instant::now());
+                match check_for_update(&config_manager).await {
+                    Ok(OtaCheckResult { staged: true, release: Some(release), channel, .. }) => {
+                        if announced.as_deref() != Some(release.version.as_str()) {
+                            info!(version = %release.version, channel = %channel, "OTA update available");
+                            events.publish(DeviceEvent::OtaUpdateAvailable {
+                                version: release.version.clone(),
+                                channel,
+                                notes: release.notes,
+                            });
+                            announced = Some(release.version);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!(error = %e, "OTA update check failed"),
+                }
+            }
+
+            if config.policy == OtaUpdatePolicy::AutoApply {
+                auto_apply_if_due(&config_manager, &config).await;
+            }
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::http::HeaderMap;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+
+    /// 本地 HTTP 服务，支持 Range 请求，记录收到的 Range 头
+    async fn serve_archive(body: Vec<u8>) -> (Url, Arc<Mutex<Vec<Option<String>>>>) {
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&ranges);
+        let app = Router::new().route(
+            "/update.tar.gz",
+            get(move |headers: HeaderMap| {
+                let body = body.clone();
+                let seen = Arc::clone(&seen);
+                async move {
+                    let range = headers.get("range").and_then(|v| v.to_str().ok()).map(str::to_string);
+                    seen.lock().unwrap().push(range.clone());
+                    let start = range
+                        .as_deref()
+                        .and_then(|r| r.strip_prefix("bytes="))
+                        .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+                    match start {
+                        Some(start) => (StatusCode::PARTIAL_CONTENT, Bytes::from(body[start..].to_vec())).into_response(),
+                        None => (StatusCode::OK, Bytes::from(body)).into_response(),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (Url::parse(&format!("http://{}/update.tar.gz", addr)).unwrap(), ranges)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ota-channel-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        ring::digest::digest(&ring::digest::SHA256, data)
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn maintenance_window_handles_midnight() {
+        let t = |s: &str| parse_time_of_day(s).unwrap();
+        assert!(in_time_window(t("03:30"), t("03:00"), t("05:00")));
+        assert!(!in_time_window(t("05:00"), t("03:00"), t("05:00")));
+        assert!(in_time_window(t("23:30"), t("23:00"), t("02:00")));
+        assert!(in_time_window(t("01:59"), t("23:00"), t("02:00")));
+        assert!(!in_time_window(t("12:00"), t("23:00"), t("02:00")));
+        assert!(in_time_window(t("12:00"), t("00:00"), t("00:00")));
+        assert!(parse_time_of_day("25:00").is_none());
+    }
+
+    #[test]
+    fn release_url_is_resolved_against_manifest() {
+        let manifest = r#"{"channels":{"stable":{"version":"9.0.0","url":"builds/udx710.tar.gz"},"beta":{"version":"9.1.0","url":"https://cdn.example.com/beta.tar.gz"}}}"#;
+        let base = Url::parse("https://updates.example.com/cpe/manifest.json").unwrap();
+
+        let (release, url) = select_release(manifest, "stable", &base).unwrap();
+        assert_eq!(release.version, "9.0.0");
+        assert_eq!(url.as_str(), "https://updates.example.com/cpe/builds/udx710.tar.gz");
+
+        let (_, url) = select_release(manifest, "beta", &base).unwrap();
+        assert_eq!(url.as_str(), "https://cdn.example.com/beta.tar.gz");
+
+        assert!(select_release(manifest, "nightly", &base).is_err());
+    }
+
+    #[tokio::test]
+    async fn manifest_read_is_capped() {
+        let (url, _) = serve_archive(vec![b' '; 10_000]).await;
+
+        let response = Client::new().get(url.clone()).send().await.unwrap();
+        assert_eq!(read_manifest(response, 10_000).await.unwrap().len(), 10_000);
+
+        let response = Client::new().get(url).send().await.unwrap();
+        let err = read_manifest(response, 8_000).await.unwrap_err();
+        assert!(err.contains("exceeds 8000 bytes"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn download_resumes_partial_file() {
+        let body: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let (url, ranges) = serve_archive(body.clone()).await;
+        let release = OtaRelease {
+            version: "9.0.0".to_string(),
+            url: url.to_string(),
+            sha256: Some(sha256_hex(&body)),
+            size: Some(body.len() as u64),
+            notes: None,
+        };
+
+        // 模拟上次下载中断在 4000 字节处
+        let dir = temp_dir("resume");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(DOWNLOAD_SOURCE_FILE), format!("{}\n{}\n", url, release.sha256.as_deref().unwrap())).unwrap();
+        fs::write(dir.join(DOWNLOAD_PART_FILE), &body[..4000]).unwrap();
+
+        let path = download_release(&Client::new(), &url, &release, &dir, ota::OTA_MAX_UPLOAD_BYTES).await.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+        assert_eq!(ranges.lock().unwrap().as_slice(), &[Some("bytes=4000-".to_string())]);
+
+        // 清单中的 SHA-256 变化后放弃旧的部分文件重新下载
+        let mut changed = release.clone();
+        changed.sha256 = Some("00".repeat(32));
+        let err = download_release(&Client::new(), &url, &changed, &dir, ota::OTA_MAX_UPLOAD_BYTES).await.unwrap_err();
+        assert!(err.contains("SHA-256 mismatch"), "{}", err);
+        assert_eq!(ranges.lock().unwrap().last().unwrap(), &None);
+        assert!(!dir.join(DOWNLOAD_PART_FILE).exists());
+
+        // 清单未提供大小时，下载过程中同样限制文件大小
+        let mut unsized_release = release.clone();
+        unsized_release.size = None;
+        let err = download_release(&Client::new(), &url, &unsized_release, &dir, 8_000).await.unwrap_err();
+        assert!(err.contains("exceeds 8000 bytes"), "{}", err);
+        assert!(!dir.join(DOWNLOAD_PART_FILE).exists());
+        let err = download_release(&Client::new(), &url, &release, &dir, 8_000).await.unwrap_err();
+        assert!(err.contains("exceeds"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -19,6 +19,9 @@ use tokio::sync::Mutex;
 /// Global mutex to serialize DBus/AT operations
 static DBUS_LOCK: Mutex<()> = Mutex::const_new(());
 
+/// Separate mutex for USSD round trips, which wait on the network
+static USSD_LOCK: Mutex<()> = Mutex::const_new(());
+
 /// Execute a future while holding the global DBus lock
 ///
 /// This ensures that only one DBus/AT operation can be in progress at a time,
@@ -38,3 +41,14 @@ where
     f.await
 }
 
+/// Execute a USSD request while holding the USSD lock
+///
+/// USSD initiate/respond block until the network answers (up to the D-Bus timeout),
+/// so they only serialize against each other and never hold the global DBus lock.
+pub async fn with_ussd_serial<T, F>(f: F) -> T
+where
+    F: Future<Output = T>,
+{
+    let _guard = USSD_LOCK.lock().await;
+    f.await
+}
@@ -0,0 +1,282 @@
+//! 信号质量历史记录模块
+//!
+//! 后台按 `SignalHistoryConfig.interval_secs` 采样服务小区的 RSRP/RSRQ/SINR、
+//! 小区标识、网络制式和注册状态写入 SQLite。超过 `raw_retention_hours` 的原始采样
+//! 按小时降采样，超过 `retention_days` 的数据被删除。
+//! `/api/history/signal` 按请求的分辨率从这些数据中聚合出曲线。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tracing::{debug, warn};
+use zbus::Connection;
+
+use crate::config::ConfigManager;
+use crate::db::{Database, SignalHistoryPoint, SignalSample};
+use crate::dbus::{get_network_info_data, get_serving_cell_info, send_at_command};
+use crate::utils::{get_cell_command_config, parse_at_response_to_2d_vec, parse_primary_cell};
+
+/// 降采样/过期清理的执行间隔
+const COMPACT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// `resolution=auto` 时单次返回的目标点数
+const AUTO_TARGET_POINTS: i64 = 500;
+
+/// 将 AT 返回的 ×100 原始值转换为 dBm/dB，无效值返回 None
+fn parse_scaled(raw: &str) -> Option<f64> {
+    raw.trim().parse::<f64>().ok().map(|v| v / 100.0)
+}
+
+/// 采集一次信号质量
+///
+/// 任一步骤失败都不会中断采样：注册状态始终记录，
+/// 无服务小区时信号字段为空，便于在曲线上看出掉网区间。
+async fn collect_sample(conn: &Connection) -> SignalSample {
+    let mut sample = SignalSample {
+        timestamp: Utc::now().timestamp(),
+        tech: "unknown".to_string(),
+        registration_status: "unknown".to_string(),
+        ..Default::default()
+    };
+
+    if let Ok(network) = get_network_info_data(conn).await {
+        sample.registration_status = network.registration_status;
+    }
+
+    let serving = match get_serving_cell_info(conn).await {
+        Ok(serving) => serving,
+        Err(e) => {
+            debug!(error = %e, "Signal sampler: serving cell unavailable");
+            return sample;
+        }
+    };
+
+    sample.tech = serving.tech.clone();
+    sample.cell_id = Some(serving.cell_id).filter(|id| *id != 0);
+    sample.tac = Some(serving.tac).filter(|tac| *tac != 0);
+
+    let Some(cmd_config) = get_cell_command_config(&serving.tech) else {
+        return sample;
+    };
+
+    match send_at_command(conn, cmd_config.primary).await {
+        Ok(response) => {
+            let cell = parse_primary_cell(&serving.tech, &parse_at_response_to_2d_vec(&response));
+            sample.rsrp = parse_scaled(&cell.rsrp);
+            sample.rsrq = parse_scaled(&cell.rsrq);
+            sample.sinr = parse_scaled(&cell.sinr);
+            sample.band = cell.band;
+            sample.arfcn = cell.arfcn;
+            sample.pci = cell.pci;
+        }
+        Err(e) => debug!(error = %e, "Signal sampler: primary cell AT command failed"),
+    }
+
+    sample
+}
+
+/// 信号质量采样任务
+pub async fn start_signal_sampler(conn: Arc<Connection>, db: Arc<Database>, config_manager: Arc<ConfigManager>) {
+    let mut last_compact: Option<Instant> = None;
+
+    loop {
+        let config = config_manager.get_signal_history();
+
+        if config.enabled {
+            let sample = collect_sample(&conn).await;
+            if let Err(e) = db.insert_signal_sample(&sample) {
+                warn!(error = %e, "Failed to store signal sample");
+            }
+        }
+
+        if last_compact.is_none_or(|t| t.elapsed() >= COMPACT_INTERVAL) {
+            let now = Utc::now().timestamp();
+            let raw_cutoff = now - (config.raw_retention_hours * 3600) as i64;
+            let purge_cutoff = now - (config.retention_days * 86_400) as i64;
+
+            match db.compact_signal_samples(raw_cutoff) {
+                Ok(n) if n > 0 => debug!(samples = n, "Signal history downsampled"),
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "Failed to downsample signal history"),
+            }
+            if let Err(e) = db.purge_signal_history(purge_cutoff) {
+                warn!(error = %e, "Failed to purge signal history");
+            }
+            last_compact = Some(Instant::now());
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+    }
+}
+
+/// 解析 `resolution` 参数，返回时间桶长度（秒），0 表示返回原始数据点
+///
+/// 支持 `raw`、`auto`（默认，约 500 个点）或秒数
+pub fn resolve_resolution(resolution: Option<&str>, from: i64, to: i64, interval_secs: u64) -> Result<i64, String> {
+    match resolution.map(str::trim) {
+        Some("raw") => Ok(0),
+        None | Some("") | Some("auto") => {
+            let bucket = (to - from).max(0) / AUTO_TARGET_POINTS;
+            if bucket <= interval_secs as i64 {
+                Ok(0)
+            } else {
+                // 向上取整到分钟，便于前端对齐坐标轴
+                Ok((bucket + 59) / 60 * 60)
+            }
+        }
+        Some(value) => match value.parse::<i64>() {
+            Ok(secs) if secs > 0 => Ok(secs),
+            _ => Err(format!("Invalid resolution: {}", value)),
+        },
+    }
+}
+
+/// 加权合并两个平均值（忽略缺失值）
+fn merge_avg(acc: &mut Option<(f64, i64)>, value: Option<f64>, weight: i64) {
+    if let Some(value) = value {
+        let (sum, total) = acc.get_or_insert((0.0, 0));
+        *sum += value * weight as f64;
+        *total += weight;
+    }
+}
+
+fn merge_min(acc: Option<f64>, value: Option<f64>) -> Option<f64> {
+    match (acc, value) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn merge_max(acc: Option<f64>, value: Option<f64>) -> Option<f64> {
+    match (acc, value) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 按固定时间桶聚合数据点（输入须按时间升序）
+///
+/// 平均值按采样数加权，最小/最大值取桶内极值，
+/// 服务小区、制式和注册状态取桶内最后一个数据点。
+pub fn bucket_points(points: &[SignalHistoryPoint], resolution: i64) -> Vec<SignalHistoryPoint> {
+    if resolution <= 0 {
+        return points.to_vec();
+    }
+
+    let mut result: Vec<SignalHistoryPoint> = Vec::new();
+    let mut averages: [Option<(f64, i64)>; 3] = [None; 3];
+
+    let finish = |point: &mut SignalHistoryPoint, averages: &mut [Option<(f64, i64)>; 3]| {
+        let avg = |acc: Option<(f64, i64)>| acc.filter(|(_, n)| *n > 0).map(|(sum, n)| sum / n as f64);
+        point.rsrp_avg = avg(averages[0]);
+        point.rsrq_avg = avg(averages[1]);
+        point.sinr_avg = avg(averages[2]);
+        *averages = [None; 3];
+    };
+
+    for point in points {
+        let bucket = point.timestamp - point.timestamp.rem_euclid(resolution);
+
+        let same_bucket = result.last().is_some_and(|last| last.timestamp == bucket);
+        if !same_bucket {
+            if let Some(last) = result.last_mut() {
+                finish(last, &mut averages);
+            }
+            result.push(SignalHistoryPoint {
+                timestamp: bucket,
+                ..Default::default()
+            });
+        }
+
+        let current = result.last_mut().unwrap();
+        current.samples += point.samples;
+        merge_avg(&mut averages[0], point.rsrp_avg, point.samples);
+        merge_avg(&mut averages[1], point.rsrq_avg, point.samples);
+        merge_avg(&mut averages[2], point.sinr_avg, point.samples);
+        current.rsrp_min = merge_min(current.rsrp_min, point.rsrp_min);
+        current.rsrp_max = merge_max(current.rsrp_max, point.rsrp_max);
+        current.rsrq_min = merge_min(current.rsrq_min, point.rsrq_min);
+        current.rsrq_max = merge_max(current.rsrq_max, point.rsrq_max);
+        current.sinr_min = merge_min(current.sinr_min, point.sinr_min);
+        current.sinr_max = merge_max(current.sinr_max, point.sinr_max);
+        current.tech = point.tech.clone();
+        current.registration_status = point.registration_status.clone();
+        current.band = point.band.clone();
+        current.arfcn = point.arfcn.clone();
+        current.pci = point.pci.clone();
+        current.cell_id = point.cell_id;
+        current.tac = point.tac;
+    }
+
+    if let Some(last) = result.last_mut() {
+        finish(last, &mut averages);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, rsrp: Option<f64>, pci: &str) -> SignalHistoryPoint {
+        SignalHistoryPoint::from(SignalSample {
+            timestamp,
+            tech: "nr".to_string(),
+            registration_status: "registered".to_string(),
+            rsrp,
+            pci: pci.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn bucket_points_aggregates_per_bucket() {
+        let points = vec![
+            sample(3600, Some(-90.0), "1"),
+            sample(3660, Some(-100.0), "2"),
+            sample(3720, None, "2"),
+            sample(7200, Some(-80.0), "3"),
+        ];
+
+        let buckets = bucket_points(&points, 3600);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].timestamp, 3600);
+        assert_eq!(buckets[0].samples, 3);
+        assert_eq!(buckets[0].rsrp_avg, Some(-95.0));
+        assert_eq!(buckets[0].rsrp_min, Some(-100.0));
+        assert_eq!(buckets[0].rsrp_max, Some(-90.0));
+        assert_eq!(buckets[0].pci, "2");
+        assert_eq!(buckets[1].rsrp_avg, Some(-80.0));
+        assert_eq!(buckets[1].sinr_avg, None);
+    }
+
+    #[test]
+    fn bucket_points_weights_by_sample_count() {
+        let mut hourly = sample(0, Some(-100.0), "1");
+        hourly.samples = 3;
+        let points = vec![hourly, sample(3600, Some(-80.0), "1")];
+
+        let buckets = bucket_points(&points, 86_400);
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].samples, 4);
+        assert_eq!(buckets[0].rsrp_avg, Some(-95.0));
+    }
+
+    #[test]
+    fn resolution_parsing() {
+        assert_eq!(resolve_resolution(Some("raw"), 0, 86_400, 60), Ok(0));
+        assert_eq!(resolve_resolution(None, 0, 3600, 60), Ok(0));
+        assert_eq!(resolve_resolution(Some("auto"), 0, 30 * 86_400, 60), Ok(5220));
+        assert_eq!(resolve_resolution(Some("300"), 0, 86_400, 60), Ok(300));
+        assert!(resolve_resolution(Some("0"), 0, 86_400, 60).is_err());
+        assert!(resolve_resolution(Some("fast"), 0, 86_400, 60).is_err());
+    }
+
+    #[test]
+    fn scaled_values() {
+        assert_eq!(parse_scaled("-9550"), Some(-95.5));
+        assert_eq!(parse_scaled(""), None);
+    }
+}
@@ -10,26 +10,39 @@
  */
 //! SMS Listener Module
 //!
-//! Listens for incoming SMS via D-Bus signals and stores them in the database.
+//! Listens for incoming SMS via D-Bus signals and stores them in the database,
+//! runs the SMS rules engine on them, and tracks the delivery state of outgoing
+//! SMS (ofono Message `State` changes and SMS-STATUS-REPORT PDUs).
 //!
 //! Copyright (c) 2025 1orz
 //! https://github.com/1orz/project-cpe
 
-use crate::db::{Database, SmsMessage, CallRecord};
+use crate::call_filter;
+use crate::call_policy::{self, IncomingAction};
+use crate::config::{CallPushEvent, ConfigManager, SmsIngestMode, SmsRulesConfig};
+use crate::db::{Database, SmsMessage, SmsFragment, CallRecord};
+use crate::dbus::{answer_call, hangup_call};
+use crate::events::{DeviceEvent, EventBus};
+use crate::sms_pdu;
 use crate::sms_push::SmsPushSender;
+use crate::sms_rules::{self, RuleMatch, SmsRuleEngine};
+use crate::utils::same_number;
 use crate::webhook::WebhookSender;
 use std::sync::Arc;
 use zbus::{Connection, MessageStream, Proxy};
 use zbus::zvariant::OwnedValue;
 use futures_util::StreamExt;
+use tracing::{info, warn};
 
 /// PDU decode result
 #[allow(dead_code)]
 pub struct PduDecodeResult {
     pub sender: String,
     pub content: String,
+    /// Service centre timestamp (RFC 3339)
+    pub timestamp: String,
     pub is_multipart: bool,
-    pub reference: u8,
+    pub reference: u16,
     pub total_parts: u8,
     pub part_number: u8,
 }
@@ -43,229 +56,341 @@ pub fn decode_pdu_simple(pdu_hex: &str) -> Option<(String, String)> {
 }
 
 /// Full PDU decode (includes multipart SMS info)
+///
+/// Only SMS-DELIVER is accepted; see `sms_pdu` for the full codec.
 pub fn decode_pdu_full(pdu_hex: &str) -> Option<PduDecodeResult> {
-    let pdu = pdu_hex.trim().to_uppercase();
-    if pdu.len() < 20 {
-        return None;
-    }
-    
-    // 1. Skip SMSC (SMS center)
-    let smsc_len = u8::from_str_radix(&pdu[0..2], 16).ok()? as usize;
-    let mut pos = 2 + smsc_len * 2;
-    
-    if pos + 2 > pdu.len() {
-        return None;
-    }
-    
-    // 2. PDU type (first byte)
-    let pdu_type = u8::from_str_radix(&pdu[pos..pos+2], 16).ok()?;
-    let has_udh = (pdu_type & 0x40) != 0; // Check UDHI bit
-    pos += 2;
-    
-    if pos + 2 > pdu.len() {
-        return None;
-    }
-    
-    // 3. Sender address length
-    let sender_len = u8::from_str_radix(&pdu[pos..pos+2], 16).ok()? as usize;
-    pos += 2;
-    
-    // 4. Sender type
-    if pos + 2 > pdu.len() {
-        return None;
-    }
-    pos += 2;
-    
-    // 5. Sender number (BCD encoded)
-    let sender_digits_len = if sender_len % 2 == 0 { sender_len } else { sender_len + 1 };
-    if pos + sender_digits_len > pdu.len() {
+    let sms_pdu::Pdu::Deliver(deliver) = sms_pdu::decode(pdu_hex).ok()? else {
         return None;
+    };
+
+    if deliver.user_data.unsupported_language_shift() {
+        warn!(sender = %deliver.originator.number, "SMS uses an unsupported national language shift table, text may be garbled");
     }
-    
-    let sender_hex = &pdu[pos..pos+sender_digits_len];
-    let sender = decode_bcd_number(sender_hex);
-    pos += sender_digits_len;
-    
-    // 6. PID (1 byte)
-    if pos + 2 > pdu.len() {
-        return None;
+
+    let concat = deliver.user_data.concat();
+    Some(PduDecodeResult {
+        sender: deliver.originator.number,
+        content: deliver.user_data.text_lossy(),
+        timestamp: deliver.timestamp.to_rfc3339(),
+        is_multipart: concat.is_some(),
+        reference: concat.map_or(0, |c| c.reference),
+        total_parts: concat.map_or(1, |c| c.total),
+        part_number: concat.map_or(1, |c| c.sequence),
+    })
+}
+
+/// How often incomplete multipart fragments are checked for timeout
+const FRAGMENT_FLUSH_INTERVAL_SECS: u64 = 30;
+
+/// A stored incoming SMS and the SMS rules it matched
+pub struct IncomingSms {
+    pub sms: SmsMessage,
+    pub matches: Vec<RuleMatch>,
+    /// Matched a device command rule: the PIN was masked before storing and the
+    /// message is not published or forwarded
+    pub device_command: bool,
+}
+
+/// Match SMS rules against the original text, then store the incoming SMS
+///
+/// Device command messages are stored with the PIN masked and without the raw PDU.
+fn store_incoming_sms(
+    db: &Database,
+    rules: &SmsRulesConfig,
+    sender: &str,
+    content: &str,
+    status: &str,
+    pdu: Option<&str>,
+) -> Option<IncomingSms> {
+    let matches = sms_rules::evaluate(rules, sender, content, Local::now().time());
+    let device_command = sms_rules::has_device_command(&matches);
+    let (content, pdu) = if device_command {
+        (sms_rules::redact_pin(content, &rules.pin), None)
+    } else {
+        (content.to_string(), pdu)
+    };
+    let id = db.insert_sms("incoming", sender, &content, status, pdu).ok()?;
+    let sms = db.get_sms(id).ok().flatten()?;
+    Some(IncomingSms { sms, matches, device_command })
+}
+
+/// Run SMS rules, then publish to the event stream and forward to webhook / SMS push
+///
+/// Tags are applied before publishing so that every consumer sees them; the other
+/// rule actions run in their own task and do not wait for the forwarders.
+fn forward_incoming_sms(
+    incoming: IncomingSms,
+    rules: &Arc<SmsRuleEngine>,
+    config_manager: &ConfigManager,
+    webhook: &Arc<WebhookSender>,
+    sms_push: &Arc<SmsPushSender>,
+    events: &EventBus,
+) {
+    let IncomingSms { mut sms, matches, device_command } = incoming;
+    rules.apply_tags(&mut sms, &matches);
+    if !matches.is_empty() {
+        let rules = Arc::clone(rules);
+        let sms = sms.clone();
+        tokio::spawn(async move { rules.run(&sms, matches).await });
     }
-    pos += 2;
-    
-    // 7. DCS (1 byte) - Data Coding Scheme
-    if pos + 2 > pdu.len() {
-        return None;
+
+    if device_command {
+        info!(sender = %sms.phone_number, "SMS device command received, not forwarding");
+        return;
     }
-    let dcs = u8::from_str_radix(&pdu[pos..pos+2], 16).ok()?;
-    pos += 2;
-    
-    // 8. Timestamp (7 bytes = 14 hex chars)
-    if pos + 14 > pdu.len() {
-        return None;
+
+    events.publish(DeviceEvent::SmsReceived { message: sms.clone() });
+    if call_filter::suppress_sms(&config_manager.get_call_filter(), &sms.phone_number) {
+        info!(sender = %sms.phone_number, "SMS sender is on the block list, not forwarding");
+        return;
     }
-    pos += 14;
-    
-    // 9. User data length
-    if pos + 2 > pdu.len() {
-        return None;
+    let webhook_clone = Arc::clone(webhook);
+    let sms_push_clone = Arc::clone(sms_push);
+    tokio::spawn(async move {
+        let _ = webhook_clone.forward_sms(&sms).await;
+        let _ = sms_push_clone.forward_sms(&sms).await;
+    });
+}
+
+/// Join fragments in sequence order into one stored message
+fn store_assembled_sms(
+    db: &Database,
+    rules: &SmsRulesConfig,
+    fragments: &[SmsFragment],
+    status: &str,
+) -> Option<IncomingSms> {
+    let first = fragments.first()?;
+    let content: String = fragments.iter().map(|f| f.content.as_str()).collect();
+    let pdu = fragments.iter().map(|f| f.pdu.as_str()).collect::<Vec<_>>().join("\n");
+    store_incoming_sms(db, rules, &first.sender, &content, status, Some(&pdu))
+}
+
+/// Ingest one raw SMS-DELIVER PDU
+///
+/// Single-part messages are stored immediately. Multipart fragments are staged
+/// until every part has arrived, then stored as one message. Returns the stored
+/// message, or None while fragments are still pending.
+pub fn ingest_sms_pdu(db: &Database, rules: &SmsRulesConfig, pdu_hex: &str, now: i64) -> Option<IncomingSms> {
+    let decoded = decode_pdu_full(pdu_hex)?;
+    if !decoded.is_multipart || decoded.total_parts <= 1 {
+        return store_incoming_sms(db, rules, &decoded.sender,&decoded.content, "received", Some(pdu_hex.trim()));
     }
-    let _ud_len = u8::from_str_radix(&pdu[pos..pos+2], 16).ok()? as usize;
-    pos += 2;
-    
-    // 10. Process user data
-    let mut is_multipart = false;
-    let mut reference: u8 = 0;
-    let mut total_parts: u8 = 1;
-    let mut part_number: u8 = 1;
-    
-    // If UDH (User Data Header) exists, parse it first
-    if has_udh && pos + 2 <= pdu.len() {
-        let udh_len = u8::from_str_radix(&pdu[pos..pos+2], 16).ok()? as usize;
-        pos += 2;
-        
-        // Parse UDH content
-        if udh_len >= 5 && pos + udh_len * 2 <= pdu.len() {
-            let udh_data = &pdu[pos..pos + udh_len * 2];
-            
-            // Check if concatenated SMS (IEI = 0x00)
-            if udh_data.len() >= 10 && &udh_data[0..2] == "00" && &udh_data[2..4] == "03" {
-                is_multipart = true;
-                reference = u8::from_str_radix(&udh_data[4..6], 16).ok()?;
-                total_parts = u8::from_str_radix(&udh_data[6..8], 16).ok()?;
-                part_number = u8::from_str_radix(&udh_data[8..10], 16).ok()?;
-            }
+
+    let fragment = SmsFragment {
+        sender: decoded.sender,
+        reference: i64::from(decoded.reference),
+        total: i64::from(decoded.total_parts),
+        sequence: i64::from(decoded.part_number),
+        content: decoded.content,
+        pdu: pdu_hex.trim().to_string(),
+        received_at: now,
+    };
+    db.insert_sms_fragment(&fragment).ok()?;
+
+    let fragments = db.take_complete_sms_fragments(&fragment.sender, fragment.reference).ok()??;
+    store_assembled_sms(db, rules, &fragments, "received")
+}
+
+/// Store multipart messages whose fragments did not all arrive within `timeout_secs`
+/// with the "partial" status
+pub fn flush_expired_fragments(
+    db: &Database,
+    rules: &SmsRulesConfig,
+    timeout_secs: u64,
+    now: i64,
+) -> Vec<IncomingSms> {
+    let before = now - timeout_secs as i64;
+    match db.take_expired_sms_fragments(before) {
+        Ok(groups) => groups
+            .iter()
+            .filter_map(|fragments| {
+                let first = fragments.first()?;
+                warn!(
+                    sender = %first.sender,
+                    reference = first.reference,
+                    received = fragments.len(),
+                    total = first.total,
+                    "Multipart SMS incomplete, storing partial message"
+                );
+                store_assembled_sms(db, rules, fragments, "partial")
+            })
+            .collect(),
+        Err(e) => {
+            warn!(error = %e, "Failed to flush expired SMS fragments");
+            Vec::new()
         }
-        
-        // Skip entire UDH
-        pos += udh_len * 2;
-    }
-    
-    // 11. Decode message content
-    if pos >= pdu.len() {
-        return None;
     }
-    
-    let user_data = &pdu[pos..];
-    
-    // Determine encoding based on DCS
-    let content = if (dcs & 0x08) != 0 || dcs == 0x08 {
-        // UCS2 encoding (UTF-16BE)
-        decode_ucs2(user_data).ok()?
-    } else if dcs == 0x00 || (dcs & 0xF0) == 0x00 {
-        // GSM 7-bit encoding (simplified: try as ASCII)
-        decode_ucs2(user_data).unwrap_or_else(|_| {
-            // Fallback: treat as hex string
-            user_data.to_string()
-        })
-    } else {
-        // Other encodings, try UCS2
-        decode_ucs2(user_data).ok()?
+}
+
+/// How many outgoing messages awaiting a report are considered when matching a status report
+const STATUS_REPORT_CANDIDATES: i64 = 50;
+
+/// Apply an ofono Message `State` change to the outgoing SMS sent through that object
+///
+/// Returns the updated message when its status actually changed.
+pub fn apply_message_state(db: &Database, message_path: &str, state: &str, at: &str) -> Option<SmsMessage> {
+    let status = match state {
+        "sent" | "delivered" | "failed" => state,
+        _ => return None,
     };
-    
-    Some(PduDecodeResult {
-        sender,
-        content,
-        is_multipart,
-        reference,
-        total_parts,
-        part_number,
-    })
+    let sms = db.find_sms_by_message_path(message_path).ok()??;
+    db.update_sms_delivery(sms.id, status, at).ok()?
 }
 
-/// Decode BCD encoded phone number
-fn decode_bcd_number(hex: &str) -> String {
-    let mut result = String::new();
-    for i in (0..hex.len()).step_by(2) {
-        if i + 1 < hex.len() {
-            let second = &hex[i+1..i+2];
-            let first = &hex[i..i+1];
-            
-            if second != "F" && second != "f" {
-                result.push_str(second);
-            }
-            if first != "F" && first != "f" {
-                result.push_str(first);
-            }
-        }
+/// Apply an SMS-STATUS-REPORT to the latest outgoing SMS sent to the same recipient
+///
+/// ofono does not expose the TP-MR it used, so reports are matched by recipient.
+/// Temporary errors (the SMSC is still retrying) leave the message untouched.
+pub fn apply_status_report(db: &Database, report: &sms_pdu::SmsStatusReport) -> Option<SmsMessage> {
+    let status = match report.delivery_status() {
+        sms_pdu::DeliveryStatus::Delivered => "delivered",
+        sms_pdu::DeliveryStatus::Failed => "failed",
+        sms_pdu::DeliveryStatus::Pending => return None,
+    };
+    let sms = db
+        .get_sms_awaiting_report(STATUS_REPORT_CANDIDATES)
+        .ok()?
+        .into_iter()
+        .find(|m| same_number(&m.phone_number, &report.recipient.number))?;
+    db.update_sms_delivery(sms.id, status, &report.discharge_time.to_rfc3339()).ok()?
+}
+
+/// Publish a delivery state change and forward final results (delivered/failed) to the webhook
+fn forward_sms_status(sms: SmsMessage, webhook: &Arc<WebhookSender>, events: &EventBus) {
+    events.publish(DeviceEvent::SmsStatusChanged { message: sms.clone() });
+    if sms.status == "sent" {
+        return;
     }
-    result
+    let webhook_clone = Arc::clone(webhook);
+    tokio::spawn(async move {
+        let _ = webhook_clone.forward_sms_status(&sms).await;
+    });
 }
 
-/// Decode UCS2 (UTF-16BE) encoding
-fn decode_ucs2(hex: &str) -> Result<String, String> {
-    let bytes: Vec<u8> = (0..hex.len())
-        .step_by(2)
-        .filter_map(|i| u8::from_str_radix(&hex[i..i+2], 16).ok())
-        .collect();
-    
-    // UTF-16BE decode
-    let utf16_values: Vec<u16> = bytes
-        .chunks_exact(2)
-        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
-        .collect();
-    
-    String::from_utf16(&utf16_values)
-        .map_err(|e| format!("UTF-16 decode error: {}", e))
+/// Extract the PDU hex string from a MessagePDU signal body
+fn message_pdu_hex(msg: &zbus::Message) -> Option<String> {
+    let body = msg.body();
+    body.deserialize::<(String, i32)>()
+        .map(|(pdu, _)| pdu)
+        .or_else(|_| body.deserialize::<(String, std::collections::HashMap<String, OwnedValue>)>().map(|(pdu, _)| pdu))
+        .or_else(|_| body.deserialize::<(String,)>().map(|(pdu,)| pdu))
+        .ok()
 }
 
 /// Start SMS listener with webhook and SMS push support
+///
+/// Both `IncomingMessage` (text assembled by ofono) and `MessagePDU` (raw PDU) are
+/// subscribed; which one is stored depends on the configured ingest mode, so the mode
+/// can be switched at runtime without duplicate messages. Status report PDUs and
+/// `org.ofono.Message` `PropertyChanged` signals update outgoing messages in either mode.
 pub async fn start_sms_listener(
     conn: Connection,
     db: Arc<Database>,
+    config_manager: Arc<ConfigManager>,
     webhook: Arc<WebhookSender>,
     sms_push: Arc<SmsPushSender>,
+    events: Arc<EventBus>,
 ) -> zbus::Result<()> {
     // Subscribe to D-Bus signals via proxy
     let dbus_proxy = Proxy::new(&conn, "org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus").await?;
     
-    // Only listen to IncomingMessage signal (ofono auto-assembles long SMS)
-    // Note: MessagePDU is not monitored to avoid duplicate SMS notifications
-    let rule = "type='signal',sender='org.ofono',interface='org.ofono.MessageManager',member='IncomingMessage'";
+    for member in ["IncomingMessage", "MessagePDU"] {
+        let rule = format!(
+            "type='signal',sender='org.ofono',interface='org.ofono.MessageManager',member='{}'",
+            member
+        );
+        dbus_proxy.call::<_, _, ()>("AddMatch", &(rule,)).await?;
+    }
+    
+    // Outgoing message state (pending → sent / failed, delivered on some ofono builds)
+    let rule = "type='signal',sender='org.ofono',interface='org.ofono.Message',member='PropertyChanged'";
     dbus_proxy.call::<_, _, ()>("AddMatch", &(rule,)).await?;
     
+    let rules = Arc::new(SmsRuleEngine::new(
+        conn.clone(),
+        Arc::clone(&db),
+        Arc::clone(&config_manager),
+        Arc::clone(&webhook),
+    ));
+
     // Create message stream
     let mut stream = MessageStream::from(&conn);
+    let mut flush_timer = tokio::time::interval(std::time::Duration::from_secs(FRAGMENT_FLUSH_INTERVAL_SECS));
     
     // Listen for signals
     loop {
-        let msg = match stream.next().await {
-            Some(Ok(msg)) => msg,
-            Some(Err(_)) => continue,
-            None => continue,
+        let msg = tokio::select! {
+            msg = stream.next() => match msg {
+                Some(Ok(msg)) => msg,
+                Some(Err(_)) => continue,
+                None => continue,
+            },
+            _ = flush_timer.tick() => {
+                let timeout = config_manager.get_sms_ingest().fragment_timeout_secs;
+                let rules_config = config_manager.get_sms_rules();
+                for sms in flush_expired_fragments(&db, &rules_config, timeout, Utc::now().timestamp()) {
+                    forward_incoming_sms(sms, &rules, &config_manager, &webhook, &sms_push, &events);
+                }
+                continue;
+            }
         };
         
-        // Check if it's a signal message
-        if let Some(member) = msg.header().member() {
-            if member.as_str() == "IncomingMessage" {
-                // Parse IncomingMessage format (text format)
-                if let Ok((content, props)) = msg.body().deserialize::<(String, std::collections::HashMap<String, OwnedValue>)>() {
-                    // Extract sender from properties
-                    let sender = props.get("Sender")
-                        .and_then(|v| v.downcast_ref::<zbus::zvariant::Str>().ok())
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Unknown".to_string());
-                    
-                    // Store to database
-                    if let Ok(id) = db.insert_sms("incoming", &sender, &content, "received", None) {
-                        // Forward to webhook / SMS push
-                        let sms = SmsMessage {
-                            id,
-                            direction: "incoming".to_string(),
-                            phone_number: sender,
-                            content,
-                            timestamp: chrono::Utc::now().to_rfc3339(),
-                            status: "received".to_string(),
-                            pdu: None,
-                        };
-                        let webhook_clone = Arc::clone(&webhook);
-                        let sms_push_clone = Arc::clone(&sms_push);
-                        tokio::spawn(async move {
-                            let _ = webhook_clone.forward_sms(&sms).await;
-                            let _ = sms_push_clone.forward_sms(&sms).await;
-                        });
+        let Some(member) = msg.header().member().map(|m| m.to_string()) else {
+            continue;
+        };
+        let mode = config_manager.get_sms_ingest().mode;
+        
+        let stored = match (member.as_str(), mode) {
+            ("PropertyChanged", _) => {
+                let header = msg.header();
+                let (Some(path), Some("org.ofono.Message")) = (header.path(), header.interface().map(|i| i.as_str())) else {
+                    continue;
+                };
+                let Ok((name, value)) = msg.body().deserialize::<(String, OwnedValue)>() else {
+                    continue;
+                };
+                let Ok(state) = value.downcast_ref::<zbus::zvariant::Str>() else {
+                    continue;
+                };
+                if name == "State" {
+                    if let Some(sms) = apply_message_state(&db, path.as_str(), state.as_str(), &Utc::now().to_rfc3339()) {
+                        forward_sms_status(sms, &webhook, &events);
                     }
                 }
+                continue;
+            }
+            // Parse IncomingMessage format (text format)
+            ("IncomingMessage", SmsIngestMode::Text) => {
+                let Ok((content, props)) = msg.body().deserialize::<(String, std::collections::HashMap<String, OwnedValue>)>() else {
+                    continue;
+                };
+                // Extract sender from properties
+                let sender = props.get("Sender")
+                    .and_then(|v| v.downcast_ref::<zbus::zvariant::Str>().ok())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                store_incoming_sms(&db, &config_manager.get_sms_rules(), &sender, &content, "received", None)
             }
+            ("MessagePDU", _) => {
+                let Some(pdu) = message_pdu_hex(&msg) else {
+                    continue;
+                };
+                match sms_pdu::decode(&pdu) {
+                    Ok(sms_pdu::Pdu::StatusReport(report)) => {
+                        if let Some(sms) = apply_status_report(&db, &report) {
+                            forward_sms_status(sms, &webhook, &events);
+                        }
+                        continue;
+                    }
+                    _ if mode == SmsIngestMode::Pdu => ingest_sms_pdu(&db, &config_manager.get_sms_rules(), &pdu, Utc::now().timestamp()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        
+        if let Some(sms) = stored {
+            forward_incoming_sms(sms, &rules, &config_manager, &webhook, &sms_push, &events);
         }
     }
 }
@@ -273,7 +398,7 @@ pub async fn start_sms_listener(
 /// 活跃通话追踪
 use std::collections::HashMap;
 use std::sync::Mutex as StdMutex;
-use chrono::Utc;
+use chrono::{Local, Utc};
 
 /// 通话追踪信息
 struct ActiveCall {
@@ -282,14 +407,71 @@ struct ActiveCall {
     direction: String,
     start_time: chrono::DateTime<Utc>,
     answered: bool,
+    /// (最长通话秒数, 策略名称)，接通时开始计时
+    max_duration: Option<(u64, String)>,
+    /// 自动处理原因，多次操作以 "; " 连接
+    reason: Option<String>,
 }
 
 lazy_static::lazy_static! {
     static ref ACTIVE_CALLS: StdMutex<HashMap<String, ActiveCall>> = StdMutex::new(HashMap::new());
 }
 
-/// Start call status listener with call history recording and webhook support
-pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: Arc<WebhookSender>) -> zbus::Result<()> {
+/// 记录通话的自动处理原因（内存中的活动通话和通话记录）
+fn record_call_reason(db: &Database, path: &str, reason: &str) {
+    let mut active_calls = ACTIVE_CALLS.lock().unwrap();
+    let Some(call) = active_calls.get_mut(path) else {
+        return;
+    };
+    let combined = match call.reason.take() {
+        Some(existing) => format!("{}; {}", existing, reason),
+        None => reason.to_string(),
+    };
+    let _ = db.set_call_reason(call.db_id, &combined);
+    call.reason = Some(combined);
+}
+
+/// 响铃 `delay_secs` 秒后自动接听，期间已接听或挂断则跳过
+fn schedule_auto_answer(conn: Connection, db: Arc<Database>, path: String, delay_secs: u64, policy: String) {
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+        if ACTIVE_CALLS.lock().unwrap().get(&path).is_none_or(|call| call.answered) {
+            return;
+        }
+        match answer_call(&conn, &path).await {
+            Ok(()) => {
+                info!(path = %path, policy = %policy, "Call auto-answered by policy");
+                record_call_reason(&db, &path, &format!("auto_answer: {}", policy));
+            }
+            Err(e) => warn!(error = %e, path = %path, "Failed to auto-answer call"),
+        }
+    });
+}
+
+/// 接通 `max_secs` 秒后自动挂断，期间通话已结束则跳过
+fn schedule_max_duration(conn: Connection, db: Arc<Database>, path: String, db_id: i64, max_secs: u64, policy: String) {
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(max_secs)).await;
+        if ACTIVE_CALLS.lock().unwrap().get(&path).is_none_or(|call| call.db_id != db_id) {
+            return;
+        }
+        record_call_reason(&db, &path, &format!("max_duration: {}", policy));
+        match hangup_call(&conn, &path).await {
+            Ok(()) => info!(path = %path, policy = %policy, "Call hung up after max duration"),
+            Err(e) => warn!(error = %e, path = %path, "Failed to hang up call after max duration"),
+        }
+    });
+}
+
+/// Start call status listener with call history recording, webhook and SMS push support
+pub async fn start_call_listener(
+    conn: Connection,
+    db: Arc<Database>,
+    config_manager: Arc<ConfigManager>,
+    webhook: Arc<WebhookSender>,
+    sms_push: Arc<SmsPushSender>,
+    events: Arc<EventBus>,
+) -> zbus::Result<()> {
     // Subscribe to D-Bus signals via proxy
     let dbus_proxy = Proxy::new(&conn, "org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus").await?;
     
@@ -333,24 +515,99 @@ pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: A
                             .unwrap_or_default();
                         
                         // Determine direction based on state
-                        let direction = if state == "incoming" || state == "alerting" {
+                        let direction = if state == "incoming" || state == "waiting" || state == "alerting" {
                             "incoming"
                         } else {
                             "outgoing"
                         };
+
+                        // Reject calls matching the block/allow lists before they ring for long
+                        if direction == "incoming"
+                            && call_filter::is_blocked(&config_manager.get_call_filter(), &phone_number)
+                        {
+                            if let Err(e) = hangup_call(&conn, &path_str).await {
+                                warn!(error = %e, path = %path_str, "Failed to reject blocked call");
+                            }
+                            info!(phone_number = %phone_number, "Blocked incoming call");
+                            let _ = db.insert_blocked_call(&phone_number);
+                            events.publish(DeviceEvent::CallBlocked {
+                                path: path_str,
+                                phone_number,
+                            });
+                            continue;
+                        }
                         
+                        events.publish(DeviceEvent::CallAdded {
+                            path: path_str.clone(),
+                            phone_number: phone_number.clone(),
+                            state: state.clone(),
+                            direction: direction.to_string(),
+                        });
+                        
+                        let decision = call_policy::evaluate(
+                            &config_manager.get_call_policy(),
+                            &phone_number,
+                            direction == "incoming",
+                            Local::now().naive_local(),
+                        );
+
                         // Insert call record into database
                         let answered = state == "active";
-                        if let Ok(db_id) = db.insert_call(direction, &phone_number, answered) {
-                            let mut active_calls = ACTIVE_CALLS.lock().unwrap();
-                            active_calls.insert(path_str, ActiveCall {
-                                db_id,
-                                phone_number,
+                        let Ok(db_id) = db.insert_call(direction, &phone_number, answered) else {
+                            continue;
+                        };
+                        let start_time = Utc::now();
+
+                        // Notify while the call is still ringing (calls rejected by policy are
+                        // only reported as missed)
+                        let rejected = matches!(decision.incoming, Some(IncomingAction::Reject { .. }));
+                        if direction == "incoming" && !answered && !rejected {
+                            let ringing = CallRecord {
+                                id: db_id,
                                 direction: direction.to_string(),
-                                start_time: Utc::now(),
-                                answered,
+                                contact_name: db.find_contact_name(&phone_number).ok().flatten(),
+                                phone_number: phone_number.clone(),
+                                duration: 0,
+                                start_time: start_time.to_rfc3339(),
+                                end_time: None,
+                                answered: false,
+                                reason: None,
+                            };
+                            let webhook_clone = Arc::clone(&webhook);
+                            let sms_push_clone = Arc::clone(&sms_push);
+                            tokio::spawn(async move {
+                                let _ = webhook_clone.forward_call_ringing(&ringing).await;
+                                let _ = sms_push_clone.forward_call(CallPushEvent::Ringing, &ringing).await;
                             });
                         }
+
+                        ACTIVE_CALLS.lock().unwrap().insert(path_str.clone(), ActiveCall {
+                            db_id,
+                            phone_number,
+                            direction: direction.to_string(),
+                            start_time,
+                            answered,
+                            max_duration: decision.max_duration.clone(),
+                            reason: None,
+                        });
+
+                        match decision.incoming {
+                            Some(IncomingAction::Reject { policy }) => {
+                                record_call_reason(&db, &path_str, &format!("auto_reject: {}", policy));
+                                match hangup_call(&conn, &path_str).await {
+                                    Ok(()) => info!(path = %path_str, policy = %policy, "Call rejected by policy"),
+                                    Err(e) => warn!(error = %e, path = %path_str, "Failed to reject call"),
+                                }
+                            }
+                            Some(IncomingAction::Answer { delay_secs, policy }) => {
+                                schedule_auto_answer(conn.clone(), Arc::clone(&db), path_str, delay_secs, policy);
+                            }
+                            None => {
+                                if let (true, Some((max_secs, policy))) = (answered, decision.max_duration) {
+                                    schedule_max_duration(conn.clone(), Arc::clone(&db), path_str, db_id, max_secs, policy);
+                                }
+                            }
+                        }
                     }
                 }
                 "CallRemoved" => {
@@ -358,8 +615,8 @@ pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: A
                     if let Ok(path) = msg.body().deserialize::<zbus::zvariant::ObjectPath>() {
                         let path_str = path.to_string();
                         
-                        let mut active_calls = ACTIVE_CALLS.lock().unwrap();
-                        if let Some(call) = active_calls.remove(&path_str) {
+                        let removed = ACTIVE_CALLS.lock().unwrap().remove(&path_str);
+                        if let Some(call) = removed {
                             // Calculate duration
                             let duration = (Utc::now() - call.start_time).num_seconds();
                             let end_time = Utc::now().to_rfc3339();
@@ -374,19 +631,39 @@ pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: A
                                 call.direction.clone()
                             };
                             
-                            // Forward to webhook
+                            events.publish(DeviceEvent::CallRemoved {
+                                path: path_str,
+                                phone_number: call.phone_number.clone(),
+                                direction: final_direction.clone(),
+                                duration,
+                            });
+                            
+                            // Forward to webhook and SMS push
+                            let push_event = if final_direction == "missed" {
+                                Some(CallPushEvent::Missed)
+                            } else if call.answered {
+                                Some(CallPushEvent::Completed)
+                            } else {
+                                None
+                            };
                             let call_record = CallRecord {
                                 id: call.db_id,
                                 direction: final_direction,
+                                contact_name: db.find_contact_name(&call.phone_number).ok().flatten(),
                                 phone_number: call.phone_number,
                                 duration,
                                 start_time: call.start_time.to_rfc3339(),
                                 end_time: Some(end_time),
                                 answered: call.answered,
+                                reason: call.reason,
                             };
                             let webhook_clone = Arc::clone(&webhook);
+                            let sms_push_clone = Arc::clone(&sms_push);
                             tokio::spawn(async move {
                                 let _ = webhook_clone.forward_call(&call_record).await;
+                                if let Some(event) = push_event {
+                                    let _ = sms_push_clone.forward_call(event, &call_record).await;
+                                }
                             });
                         }
                     }
@@ -402,11 +679,26 @@ pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: A
                                 if let Some(path) = msg.header().path() {
                                     let path_str = path.to_string();
                                     
-                                    // Update answered status if call becomes active
+                                    events.publish(DeviceEvent::CallStateChanged {
+                                        path: path_str.clone(),
+                                        state: state_str.clone(),
+                                    });
+                                    
+                                    // Update answered status if call becomes active, and start
+                                    // the max duration timer on the first transition
                                     if state_str == "active" {
-                                        let mut active_calls = ACTIVE_CALLS.lock().unwrap();
-                                        if let Some(call) = active_calls.get_mut(&path_str) {
-                                            call.answered = true;
+                                        let limit = {
+                                            let mut active_calls = ACTIVE_CALLS.lock().unwrap();
+                                            match active_calls.get_mut(&path_str) {
+                                                Some(call) if !call.answered => {
+                                                    call.answered = true;
+                                                    call.max_duration.clone().map(|limit| (call.db_id, limit))
+                                                }
+                                                _ => None,
+                                            }
+                                        };
+                                        if let Some((db_id, (max_secs, policy))) = limit {
+                                            schedule_max_duration(conn.clone(), Arc::clone(&db), path_str, db_id, max_secs, policy);
                                         }
                                     }
                                 }
@@ -419,3 +711,183 @@ pub async fn start_call_listener(conn: Connection, db: Arc<Database>, webhook: A
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SmsDeviceCommand, SmsRule, SmsRuleAction};
+    use crate::sms_pdu::{Address, InformationElement, Pdu, SmsDeliver, SmsStatusReport, UserData};
+
+    fn deliver_pdu(sender: &str, text: &str, concat: Option<(u8, u8, u8)>) -> String {
+        let mut user_data = UserData::text(text);
+        if let Some((reference, total, sequence)) = concat {
+            user_data.header.push(InformationElement { id: 0x00, data: vec![reference, total, sequence] });
+        }
+        Pdu::Deliver(SmsDeliver {
+            smsc: None,
+            originator: Address::new(sender),
+            more_messages: false,
+            reply_path: false,
+            status_report_indication: false,
+            protocol_id: 0,
+            dcs: 0,
+            timestamp: chrono::DateTime::parse_from_rfc3339("2025-12-08T10:00:00+08:00").unwrap(),
+            user_data,
+        })
+        .encode()
+        .unwrap()
+    }
+
+    fn no_rules() -> SmsRulesConfig {
+        SmsRulesConfig::default()
+    }
+
+    fn memory_db() -> Database {
+        Database::new(std::path::PathBuf::from(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn decode_pdu_full_reports_concat_info() {
+        let decoded = decode_pdu_full(&deliver_pdu("+8613800138000", "Your code", Some((9, 2, 1)))).unwrap();
+        assert_eq!(decoded.sender, "+8613800138000");
+        assert_eq!(decoded.content, "Your code");
+        assert!(decoded.is_multipart);
+        assert_eq!((decoded.reference, decoded.total_parts, decoded.part_number), (9, 2, 1));
+    }
+
+    #[test]
+    fn multipart_fragments_are_reassembled_in_order() {
+        let db = memory_db();
+        let single = ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "Hello", None), 100).unwrap().sms;
+        assert_eq!((single.phone_number.as_str(), single.content.as_str()), ("BANK", "Hello"));
+
+        // 乱序到达，且第 3 段重复收到
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "456", Some((7, 3, 3))), 100).is_none());
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "Code: ", Some((7, 3, 1))), 101).is_none());
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "456", Some((7, 3, 3))), 101).is_none());
+        // 其他发送方的相同参考号互不影响
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("10086", "other", Some((7, 2, 1))), 101).is_none());
+
+        let sms = ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "123", Some((7, 3, 2))), 102).unwrap().sms;
+        assert_eq!(sms.content, "Code: 123456");
+        assert_eq!(sms.status, "received");
+        assert_eq!(sms.pdu.unwrap().lines().count(), 3);
+        assert!(db.take_complete_sms_fragments("BANK", 7).unwrap().is_none());
+    }
+
+    #[test]
+    fn incomplete_fragments_flush_as_partial() {
+        let db = memory_db();
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "Part one ", Some((1, 3, 1))), 1_000).is_none());
+        assert!(ingest_sms_pdu(&db, &no_rules(), &deliver_pdu("BANK", "part three", Some((1, 3, 3))), 1_200).is_none());
+
+        // 最早分段未超时
+        assert!(flush_expired_fragments(&db, &no_rules(), 300, 1_250).is_empty());
+
+        let flushed = flush_expired_fragments(&db, &no_rules(), 300, 1_301);
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].sms.status, "partial");
+        assert_eq!(flushed[0].sms.content, "Part one part three");
+        assert!(flush_expired_fragments(&db, &no_rules(), 300, 10_000).is_empty());
+        assert_eq!(db.get_sms_stats().unwrap().incoming, 1);
+    }
+
+    #[test]
+    fn device_commands_are_stored_without_pin() {
+        let db = memory_db();
+        let rules = SmsRulesConfig {
+            enabled: true,
+            pin: "246813".to_string(),
+            authorized_senders: vec!["+8613800138000".to_string()],
+            rules: vec![SmsRule {
+                name: "status".to_string(),
+                enabled: true,
+                sender: String::new(),
+                content_regex: "(?i)^status$".to_string(),
+                time_window_start: String::new(),
+                time_window_end: String::new(),
+                stop: false,
+                actions: vec![SmsRuleAction::Device { command: SmsDeviceCommand::Status }],
+            }],
+        };
+
+        let incoming = ingest_sms_pdu(&db, &rules, &deliver_pdu("+8613800138000", "246813 status", None), 100).unwrap();
+        assert!(incoming.device_command);
+        assert_eq!(incoming.matches.len(), 1);
+        assert_eq!(incoming.sms.content, "**** status");
+        assert!(incoming.sms.pdu.is_none());
+        assert!(db.get_sms_messages(10, 0).unwrap().iter().all(|m| !m.content.contains("246813")));
+
+        // 未授权的发送方不会命中，内容原样保存
+        let other = ingest_sms_pdu(&db, &rules, &deliver_pdu("+8613900139000", "246813 status", None), 101).unwrap();
+        assert!(!other.device_command);
+        assert_eq!(other.sms.content, "246813 status");
+    }
+
+    fn status_report(recipient: &str, status: u8) -> SmsStatusReport {
+        let time = chrono::DateTime::parse_from_rfc3339("2025-12-08T10:00:05+08:00").unwrap();
+        SmsStatusReport {
+            smsc: None,
+            more_messages: false,
+            status_report_qualifier: false,
+            message_reference: 1,
+            recipient: Address::new(recipient),
+            service_centre_timestamp: time,
+            discharge_time: time,
+            status,
+            protocol_id: None,
+            dcs: None,
+            user_data: None,
+        }
+    }
+
+    fn outgoing(db: &Database, phone_number: &str, message_path: &str) -> i64 {
+        let id = db.insert_sms("outgoing", phone_number, "alert", "pending", None).unwrap();
+        db.set_sms_message_path(id, message_path).unwrap();
+        id
+    }
+
+    #[test]
+    fn message_state_changes_only_move_forward() {
+        let db = memory_db();
+        let id = outgoing(&db, "10086", "/ril_0/message_01");
+
+        assert!(apply_message_state(&db, "/ril_0/message_01", "pending", "t0").is_none());
+        let sms = apply_message_state(&db, "/ril_0/message_01", "sent", "t1").unwrap();
+        assert_eq!((sms.id, sms.status.as_str(), sms.sent_at.as_deref()), (id, "sent", Some("t1")));
+        assert!(apply_message_state(&db, "/ril_0/message_01", "sent", "t2").is_none());
+
+        let sms = apply_message_state(&db, "/ril_0/message_01", "delivered", "t3").unwrap();
+        assert_eq!((sms.sent_at.as_deref(), sms.delivered_at.as_deref()), (Some("t1"), Some("t3")));
+        // 已送达后迟到的失败通知不再生效
+        assert!(apply_message_state(&db, "/ril_0/message_01", "failed", "t4").is_none());
+        assert!(apply_message_state(&db, "/ril_0/message_02", "sent", "t4").is_none());
+
+        outgoing(&db, "10010", "/ril_0/message_02");
+        let sms = apply_message_state(&db, "/ril_0/message_02", "failed", "t5").unwrap();
+        assert_eq!((sms.status.as_str(), sms.failed_at.as_deref(), sms.sent_at), ("failed", Some("t5"), None));
+    }
+
+    #[test]
+    fn status_reports_match_latest_message_to_recipient() {
+        let db = memory_db();
+        let older = outgoing(&db, "13800138000", "/ril_0/message_01");
+        let newer = outgoing(&db, "13800138000", "/ril_0/message_02");
+        let other = outgoing(&db, "10086", "/ril_0/message_03");
+
+        // 临时错误：服务中心仍在重试
+        assert!(apply_status_report(&db, &status_report("+8613800138000", 0x30)).is_none());
+
+        let sms = apply_status_report(&db, &status_report("+8613800138000", 0x00)).unwrap();
+        assert_eq!((sms.id, sms.status.as_str()), (newer, "delivered"));
+        assert_eq!(sms.delivered_at.as_deref(), Some("2025-12-08T10:00:05+08:00"));
+
+        let sms = apply_status_report(&db, &status_report("+8613800138000", 0x41)).unwrap();
+        assert_eq!((sms.id, sms.status.as_str()), (older, "failed"));
+        assert!(apply_status_report(&db, &status_report("+8613800138000", 0x00)).is_none());
+
+        // 短号不做后缀匹配
+        assert!(apply_status_report(&db, &status_report("+8613910086", 0x00)).is_none());
+        assert_eq!(apply_status_report(&db, &status_report("10086", 0x00)).unwrap().id, other);
+    }
+}
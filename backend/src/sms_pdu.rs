@@ -0,0 +1,1446 @@
+//! 3GPP TS 23.040 短信 PDU 编解码
+//!
+//! 支持 SMS-DELIVER、SMS-SUBMIT、SMS-STATUS-REPORT 三种消息类型：
+//! - 用户数据编码：GSM 7-bit 默认字母表（含扩展表）、8-bit 数据、UCS2
+//! - 地址：国际/国内号码、字母数字发送方（如银行短信 "BANK"）
+//! - 服务中心时间戳（SCTS）及时区
+//! - 用户数据头：8 位（IEI 0x00）和 16 位（IEI 0x08）长短信拼接参考号
+//!
+//! - 国家语言锁定移位表（IEI 0x25）和单移表（IEI 0x24）：土耳其语、西班牙语、葡萄牙语
+//!
+//! 印度语系（语言标识 4–13）的移位表未实现，这类短信按默认字母表解码，
+//! 非拉丁字符会显示错误，可通过 [`UserData::unsupported_language_shift`] 判断。
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Timelike};
+
+/// GSM 7-bit 默认字母表（0x1B 为扩展表转义符）
+const GSM7_BASIC: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å', //
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\u{1B}', 'Æ', 'æ', 'ß', 'É', //
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', //
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', //
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', //
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§', //
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', //
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à', //
+];
+
+/// GSM 7-bit 扩展表（0x1B 之后的字符）
+const GSM7_EXTENSION: [(u8, char); 10] = [
+    (0x0A, '\u{0C}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// 土耳其语锁定移位表（TS 23.038 A.3.1）
+const GSM7_TURKISH_LOCKING: [char; 128] = [
+    '@', '£', '$', '¥', '€', 'é', 'ù', 'ı', 'ò', 'Ç', '\n', 'Ğ', 'ğ', '\r', 'Å', 'å', //
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\u{1B}', 'Ş', 'ş', 'ß', 'É', //
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', //
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', //
+    'İ', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', //
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§', //
+    'ç', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', //
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à', //
+];
+
+/// 葡萄牙语锁定移位表（TS 23.038 A.3.3）
+const GSM7_PORTUGUESE_LOCKING: [char; 128] = [
+    '@', '£', '$', '¥', 'ê', 'é', 'ú', 'í', 'ó', 'ç', '\n', 'Ô', 'ô', '\r', 'Á', 'á', //
+    'Δ', '_', 'ª', 'Ç', 'À', '∞', '^', '\\', '€', 'Ó', '|', '\u{1B}', 'Â', 'â', 'Ê', 'É', //
+    ' ', '!', '"', '#', 'º', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', //
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', //
+    'Í', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', //
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ã', 'Õ', 'Ú', 'Ü', '§', //
+    '~', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', //
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ã', 'õ', '`', 'ü', 'à', //
+];
+
+/// 土耳其语单移表（TS 23.038 A.2.1）
+const GSM7_TURKISH_SINGLE_SHIFT: [(u8, char); 17] = [
+    (0x0A, '\u{0C}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x47, 'Ğ'),
+    (0x49, 'İ'),
+    (0x53, 'Ş'),
+    (0x63, 'ç'),
+    (0x65, '€'),
+    (0x67, 'ğ'),
+    (0x69, 'ı'),
+    (0x73, 'ş'),
+];
+
+/// 西班牙语单移表（TS 23.038 A.2.2）
+const GSM7_SPANISH_SINGLE_SHIFT: [(u8, char); 19] = [
+    (0x09, 'ç'),
+    (0x0A, '\u{0C}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x41, 'Á'),
+    (0x49, 'Í'),
+    (0x4F, 'Ó'),
+    (0x55, 'Ú'),
+    (0x61, 'á'),
+    (0x65, '€'),
+    (0x69, 'í'),
+    (0x6F, 'ó'),
+    (0x75, 'ú'),
+];
+
+/// 葡萄牙语单移表（TS 23.038 A.2.3）
+const GSM7_PORTUGUESE_SINGLE_SHIFT: [(u8, char); 37] = [
+    (0x05, 'ê'),
+    (0x09, 'ç'),
+    (0x0A, '\u{0C}'),
+    (0x0B, 'Ô'),
+    (0x0C, 'ô'),
+    (0x0E, 'Á'),
+    (0x0F, 'á'),
+    (0x12, 'Φ'),
+    (0x13, 'Γ'),
+    (0x14, '^'),
+    (0x15, 'Ω'),
+    (0x16, 'Π'),
+    (0x17, 'Ψ'),
+    (0x18, 'Σ'),
+    (0x19, 'Θ'),
+    (0x1F, 'Ê'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x41, 'À'),
+    (0x49, 'Í'),
+    (0x4F, 'Ó'),
+    (0x55, 'Ú'),
+    (0x5B, 'Ã'),
+    (0x5C, 'Õ'),
+    (0x61, 'Â'),
+    (0x65, '€'),
+    (0x69, 'í'),
+    (0x6F, 'ó'),
+    (0x75, 'ú'),
+    (0x7B, 'ã'),
+    (0x7C, 'õ'),
+    (0x7F, 'â'),
+];
+
+const GSM7_ESCAPE: u8 = 0x1B;
+
+/// 国家语言标识（TS 23.038 6.2.1.2.4）
+const LANGUAGE_TURKISH: u8 = 1;
+const LANGUAGE_SPANISH: u8 = 2;
+const LANGUAGE_PORTUGUESE: u8 = 3;
+
+/// 拼接短信信息单元标识
+const IEI_CONCAT_8BIT: u8 = 0x00;
+const IEI_CONCAT_16BIT: u8 = 0x08;
+/// 国家语言单移表 / 锁定移位表信息单元标识
+const IEI_NATIONAL_SINGLE_SHIFT: u8 = 0x24;
+const IEI_NATIONAL_LOCKING_SHIFT: u8 = 0x25;
+
+/// 单条短信用户数据最大长度（字节）
+const MAX_USER_DATA_OCTETS: usize = 140;
+/// 单条短信 GSM 7-bit 最大字符数（septet）
+const MAX_USER_DATA_SEPTETS: usize = 160;
+
+// ========== GSM 7-bit 字母表 ==========
+
+/// 编解码使用的字母表：锁定移位表替换默认字母表，单移表替换扩展表
+#[derive(Debug, Clone, Copy)]
+struct Gsm7Tables {
+    basic: &'static [char; 128],
+    extension: &'static [(u8, char)],
+}
+
+impl Gsm7Tables {
+    const DEFAULT: Self = Self {
+        basic: &GSM7_BASIC,
+        extension: &GSM7_EXTENSION,
+    };
+
+    /// 按用户数据头中的国家语言信息单元选择，未实现的语言保留默认表
+    fn from_header(header: &[InformationElement]) -> Self {
+        let mut tables = Self::DEFAULT;
+        for ie in header {
+            match (ie.id, ie.data.as_slice()) {
+                (IEI_NATIONAL_SINGLE_SHIFT, &[language]) => {
+                    if let Some(extension) = single_shift_table(language) {
+                        tables.extension = extension;
+                    }
+                }
+                (IEI_NATIONAL_LOCKING_SHIFT, &[language]) => {
+                    if let Some(basic) = locking_shift_table(language) {
+                        tables.basic = basic;
+                    }
+                }
+                _ => {}
+            }
+        }
+        tables
+    }
+
+    /// 单个字符的 septet 编码（单移表字符为两个 septet）
+    fn char_septets(&self, c: char) -> Option<Vec<u8>> {
+        if c != '\u{1B}' {
+            if let Some(pos) = self.basic.iter().position(|&b| b == c) {
+                return Some(vec![pos as u8]);
+            }
+        }
+        self.extension
+            .iter()
+            .find(|(_, e)| *e == c)
+            .map(|(code, _)| vec![GSM7_ESCAPE, *code])
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let mut septets = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            septets.extend(self.char_septets(c)?);
+        }
+        Some(septets)
+    }
+
+    fn decode(&self, septets: &[u8]) -> String {
+        let mut text = String::with_capacity(septets.len());
+        let mut iter = septets.iter();
+        while let Some(&s) = iter.next() {
+            if s == GSM7_ESCAPE {
+                // 转义符后跟单移表字符；未定义的字符按锁定移位表显示
+                if let Some(&code) = iter.next() {
+                    let c = self
+                        .extension
+                        .iter()
+                        .find(|(e, _)| *e == code)
+                        .map(|(_, c)| *c)
+                        .unwrap_or(self.basic[(code & 0x7F) as usize]);
+                    text.push(c);
+                }
+            } else {
+                text.push(self.basic[(s & 0x7F) as usize]);
+            }
+        }
+        text
+    }
+}
+
+/// 国家语言单移表，None 表示未实现
+fn single_shift_table(language: u8) -> Option<&'static [(u8, char)]> {
+    match language {
+        LANGUAGE_TURKISH => Some(&GSM7_TURKISH_SINGLE_SHIFT),
+        LANGUAGE_SPANISH => Some(&GSM7_SPANISH_SINGLE_SHIFT),
+        LANGUAGE_PORTUGUESE => Some(&GSM7_PORTUGUESE_SINGLE_SHIFT),
+        _ => None,
+    }
+}
+
+/// 国家语言锁定移位表，None 表示未实现（规范未定义西班牙语锁定移位表）
+fn locking_shift_table(language: u8) -> Option<&'static [char; 128]> {
+    match language {
+        LANGUAGE_TURKISH => Some(&GSM7_TURKISH_LOCKING),
+        LANGUAGE_PORTUGUESE => Some(&GSM7_PORTUGUESE_LOCKING),
+        _ => None,
+    }
+}
+
+/// 文本按默认字母表编码为 GSM 7-bit septet 序列，包含无法表示的字符时返回 None
+pub fn gsm7_encode(text: &str) -> Option<Vec<u8>> {
+    Gsm7Tables::DEFAULT.encode(text)
+}
+
+/// GSM 7-bit septet 序列按默认字母表解码为文本
+pub fn gsm7_decode(septets: &[u8]) -> String {
+    Gsm7Tables::DEFAULT.decode(septets)
+}
+
+/// 按位打包 septet，`skip_bits` 为起始填充位数（用户数据头之后对齐到 septet 边界）
+fn pack_septets(septets: &[u8], skip_bits: usize) -> Vec<u8> {
+    let total_bits = skip_bits + septets.len() * 7;
+    let mut out = vec![0u8; total_bits.div_ceil(8)];
+    for (i, &s) in septets.iter().enumerate() {
+        let bit = skip_bits + i * 7;
+        let value = ((s & 0x7F) as u16) << (bit % 8);
+        out[bit / 8] |= value as u8;
+        if value > 0xFF {
+            out[bit / 8 + 1] |= (value >> 8) as u8;
+        }
+    }
+    out
+}
+
+/// 按位解包 septet
+fn unpack_septets(bytes: &[u8], count: usize, skip_bits: usize) -> Vec<u8> {
+    let mut septets = Vec::with_capacity(count);
+    for i in 0..count {
+        let bit = skip_bits + i * 7;
+        let Some(&low) = bytes.get(bit / 8) else {
+            break;
+        };
+        let high = bytes.get(bit / 8 + 1).copied().unwrap_or(0);
+        let value = (u16::from(high) << 8 | u16::from(low)) >> (bit % 8);
+        septets.push((value & 0x7F) as u8);
+    }
+    septets
+}
+
+// ========== 基础类型 ==========
+
+/// 用户数据编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    Gsm7,
+    Data8,
+    Ucs2,
+}
+
+impl Alphabet {
+    /// 从数据编码方案（DCS）解析
+    pub fn from_dcs(dcs: u8) -> Self {
+        match dcs >> 4 {
+            // 通用数据编码组（含自动删除组），压缩数据按 8-bit 处理
+            0x0..=0x7 => {
+                if dcs & 0x20 != 0 {
+                    return Alphabet::Data8;
+                }
+                match (dcs >> 2) & 0x03 {
+                    0x01 => Alphabet::Data8,
+                    0x02 => Alphabet::Ucs2,
+                    _ => Alphabet::Gsm7,
+                }
+            }
+            // 消息等待指示组
+            0xC | 0xD => Alphabet::Gsm7,
+            0xE => Alphabet::Ucs2,
+            // 数据编码/消息类别组
+            0xF => {
+                if dcs & 0x04 != 0 {
+                    Alphabet::Data8
+                } else {
+                    Alphabet::Gsm7
+                }
+            }
+            // 保留编码组按默认字母表处理
+            _ => Alphabet::Gsm7,
+        }
+    }
+
+    /// 对应的 DCS 值（无消息类别）
+    pub fn dcs(self) -> u8 {
+        match self {
+            Alphabet::Gsm7 => 0x00,
+            Alphabet::Data8 => 0x04,
+            Alphabet::Ucs2 => 0x08,
+        }
+    }
+}
+
+/// 号码/地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    /// 号码（国际号码带 "+" 前缀）或字母数字名称
+    pub number: String,
+    /// 地址类型（TON/NPI），如 0x91 国际、0x81 未知、0xD0 字母数字
+    pub type_of_address: u8,
+}
+
+impl Address {
+    /// 根据号码内容自动选择地址类型
+    pub fn new(number: &str) -> Self {
+        if let Some(digits) = number.strip_prefix('+') {
+            Self {
+                number: format!("+{}", digits),
+                type_of_address: 0x91,
+            }
+        } else if number.chars().all(|c| c.is_ascii_digit() || c == '*' || c == '#') {
+            Self {
+                number: number.to_string(),
+                type_of_address: 0x81,
+            }
+        } else {
+            Self {
+                number: number.to_string(),
+                type_of_address: 0xD0,
+            }
+        }
+    }
+
+    /// 是否为字母数字地址（TON = 101）
+    pub fn is_alphanumeric(&self) -> bool {
+        self.type_of_address & 0x70 == 0x50
+    }
+
+    fn is_international(&self) -> bool {
+        self.type_of_address & 0x70 == 0x10
+    }
+
+    /// 解码 TP 地址（长度为有效半字节数）
+    fn decode(r: &mut Reader) -> Result<Self, String> {
+        let len = r.u8()? as usize;
+        let type_of_address = r.u8()?;
+        let bytes = r.take(len.div_ceil(2))?;
+
+        let number = if type_of_address & 0x70 == 0x50 {
+            gsm7_decode(&unpack_septets(bytes, len * 4 / 7, 0))
+        } else {
+            let digits = decode_semi_octets(bytes, len);
+            if type_of_address & 0x70 == 0x10 {
+                format!("+{}", digits)
+            } else {
+                digits
+            }
+        };
+
+        Ok(Self { number, type_of_address })
+    }
+
+    /// 解码服务中心地址（长度为字节数，包含类型字节）
+    fn decode_smsc(r: &mut Reader) -> Result<Option<Self>, String> {
+        let len = r.u8()? as usize;
+        if len == 0 {
+            return Ok(None);
+        }
+        let type_of_address = r.u8()?;
+        let bytes = r.take(len - 1)?;
+        let digits = decode_semi_octets(bytes, bytes.len() * 2);
+        let number = if type_of_address & 0x70 == 0x10 {
+            format!("+{}", digits)
+        } else {
+            digits
+        };
+        Ok(Some(Self { number, type_of_address }))
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) -> Result<(), String> {
+        if self.is_alphanumeric() {
+            let septets =
+                gsm7_encode(&self.number).ok_or_else(|| format!("Address not GSM 7-bit: {}", self.number))?;
+            if septets.len() > 11 {
+                return Err(format!("Alphanumeric address too long: {}", self.number));
+            }
+            out.push((septets.len() * 7).div_ceil(4) as u8);
+            out.push(self.type_of_address);
+            out.extend(pack_septets(&septets, 0));
+        } else {
+            let digits = self.digits();
+            if digits.len() > 20 {
+                return Err(format!("Address too long: {}", self.number));
+            }
+            out.push(digits.len() as u8);
+            out.push(self.type_of_address);
+            out.extend(encode_semi_octets(digits)?);
+        }
+        Ok(())
+    }
+
+    fn encode_smsc(smsc: Option<&Self>, out: &mut Vec<u8>) -> Result<(), String> {
+        match smsc {
+            None => out.push(0),
+            Some(addr) => {
+                let bytes = encode_semi_octets(addr.digits())?;
+                out.push(bytes.len() as u8 + 1);
+                out.push(addr.type_of_address);
+                out.extend(bytes);
+            }
+        }
+        Ok(())
+    }
+
+    fn digits(&self) -> &str {
+        if self.is_international() {
+            self.number.trim_start_matches('+')
+        } else {
+            &self.number
+        }
+    }
+}
+
+fn decode_semi_octets(bytes: &[u8], len: usize) -> String {
+    bytes
+        .iter()
+        .flat_map(|b| [b & 0x0F, b >> 4])
+        .take(len)
+        .filter_map(|n| match n {
+            0..=9 => Some((b'0' + n) as char),
+            0x0A => Some('*'),
+            0x0B => Some('#'),
+            0x0C => Some('a'),
+            0x0D => Some('b'),
+            0x0E => Some('c'),
+            _ => None,
+        })
+        .collect()
+}
+
+fn encode_semi_octets(digits: &str) -> Result<Vec<u8>, String> {
+    let nibbles = digits
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => Ok(c as u8 - b'0'),
+            '*' => Ok(0x0A),
+            '#' => Ok(0x0B),
+            'a' | 'A' => Ok(0x0C),
+            'b' | 'B' => Ok(0x0D),
+            'c' | 'C' => Ok(0x0E),
+            _ => Err(format!("Invalid address digit: {}", c)),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(nibbles
+        .chunks(2)
+        .map(|pair| pair[0] | (pair.get(1).copied().unwrap_or(0x0F) << 4))
+        .collect())
+}
+
+/// 半字节交换的 BCD 值
+fn swapped_bcd(b: u8) -> Result<u32, String> {
+    let (tens, units) = (b & 0x0F, b >> 4);
+    if tens > 9 || units > 9 {
+        return Err(format!("Invalid BCD octet: {:02X}", b));
+    }
+    Ok(u32::from(tens) * 10 + u32::from(units))
+}
+
+fn to_swapped_bcd(value: u32) -> u8 {
+    (((value % 10) << 4) | ((value / 10) % 10)) as u8
+}
+
+/// 解码 7 字节服务中心时间戳
+fn decode_timestamp(r: &mut Reader) -> Result<DateTime<FixedOffset>, String> {
+    let b = r.take(7)?;
+    let yy = swapped_bcd(b[0])? as i32;
+    let year = if yy >= 90 { 1900 + yy } else { 2000 + yy };
+
+    // 时区：以 15 分钟为单位，低半字节的 bit3 为符号位
+    let quarters = i32::from(b[6] & 0x07) * 10 + i32::from(b[6] >> 4);
+    let offset_secs = quarters * 15 * 60 * if b[6] & 0x08 != 0 { -1 } else { 1 };
+    let offset = FixedOffset::east_opt(offset_secs).ok_or("Invalid timezone")?;
+
+    let (hour, minute, second) = (swapped_bcd(b[3])?, swapped_bcd(b[4])?, swapped_bcd(b[5])?);
+    NaiveDate::from_ymd_opt(year, swapped_bcd(b[1])?, swapped_bcd(b[2])?)
+        .and_then(|d| d.and_hms_opt(hour, minute, second))
+        .and_then(|dt| dt.and_local_timezone(offset).single())
+        .ok_or_else(|| "Invalid timestamp".to_string())
+}
+
+fn encode_timestamp(ts: &DateTime<FixedOffset>, out: &mut Vec<u8>) {
+    out.push(to_swapped_bcd(ts.year().rem_euclid(100) as u32));
+    out.push(to_swapped_bcd(ts.month()));
+    out.push(to_swapped_bcd(ts.day()));
+    out.push(to_swapped_bcd(ts.hour()));
+    out.push(to_swapped_bcd(ts.minute()));
+    out.push(to_swapped_bcd(ts.second()));
+    let offset = ts.offset().local_minus_utc();
+    let tz = to_swapped_bcd((offset.unsigned_abs() / 900) % 80);
+    out.push(if offset < 0 { tz | 0x08 } else { tz });
+}
+
+// ========== 用户数据 ==========
+
+/// 用户数据头中的信息单元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InformationElement {
+    pub id: u8,
+    pub data: Vec<u8>,
+}
+
+/// 长短信拼接信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Concat {
+    /// 拼接参考号（同一条长短信的各部分相同）
+    pub reference: u16,
+    /// 总分段数
+    pub total: u8,
+    /// 当前分段序号（从 1 开始）
+    pub sequence: u8,
+    /// 是否使用 16 位参考号（IEI 0x08）
+    pub wide_reference: bool,
+}
+
+impl Concat {
+    fn from_ie(ie: &InformationElement) -> Option<Self> {
+        let concat = match (ie.id, ie.data.as_slice()) {
+            (IEI_CONCAT_8BIT, &[reference, total, sequence]) => Concat {
+                reference: u16::from(reference),
+                total,
+                sequence,
+                wide_reference: false,
+            },
+            (IEI_CONCAT_16BIT, &[hi, lo, total, sequence]) => Concat {
+                reference: u16::from_be_bytes([hi, lo]),
+                total,
+                sequence,
+                wide_reference: true,
+            },
+            _ => return None,
+        };
+        // 序号为 0 或超出总数的信息单元按规范忽略
+        (concat.sequence >= 1 && concat.sequence <= concat.total).then_some(concat)
+    }
+
+    fn to_ie(self) -> InformationElement {
+        if self.wide_reference {
+            let [hi, lo] = self.reference.to_be_bytes();
+            InformationElement {
+                id: IEI_CONCAT_16BIT,
+                data: vec![hi, lo, self.total, self.sequence],
+            }
+        } else {
+            InformationElement {
+                id: IEI_CONCAT_8BIT,
+                data: vec![self.reference as u8, self.total, self.sequence],
+            }
+        }
+    }
+}
+
+/// 用户数据正文
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// 用户数据（用户数据头 + 正文）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserData {
+    pub header: Vec<InformationElement>,
+    pub body: Body,
+}
+
+impl UserData {
+    pub fn text(text: &str) -> Self {
+        Self {
+            header: Vec::new(),
+            body: Body::Text(text.to_string()),
+        }
+    }
+
+    /// 拼接信息（IEI 0x00 或 0x08）
+    pub fn concat(&self) -> Option<Concat> {
+        self.header.iter().find_map(Concat::from_ie)
+    }
+
+    /// 正文文本，8-bit 数据以十六进制显示
+    pub fn text_lossy(&self) -> String {
+        match &self.body {
+            Body::Text(text) => text.clone(),
+            Body::Binary(data) => bytes_to_hex(data),
+        }
+    }
+
+    /// 是否引用了未实现的国家语言移位表（IEI 0x24/0x25）
+    ///
+    /// 此时 GSM 7-bit 正文按默认字母表解码，结果不可靠。
+    pub fn unsupported_language_shift(&self) -> bool {
+        self.header.iter().any(|ie| match (ie.id, ie.data.as_slice()) {
+            (IEI_NATIONAL_SINGLE_SHIFT, &[language]) => single_shift_table(language).is_none(),
+            (IEI_NATIONAL_LOCKING_SHIFT, &[language]) => locking_shift_table(language).is_none(),
+            _ => false,
+        })
+    }
+
+    fn header_bytes(&self) -> Vec<u8> {
+        if self.header.is_empty() {
+            return Vec::new();
+        }
+        let mut out = vec![0];
+        for ie in &self.header {
+            out.push(ie.id);
+            out.push(ie.data.len() as u8);
+            out.extend(&ie.data);
+        }
+        out[0] = (out.len() - 1) as u8;
+        out
+    }
+
+    fn decode(r: &mut Reader, alphabet: Alphabet, has_header: bool) -> Result<Self, String> {
+        let udl = r.u8()? as usize;
+        let octets = match alphabet {
+            Alphabet::Gsm7 => (udl * 7).div_ceil(8),
+            _ => udl,
+        };
+        // 部分网络会截断末尾填充字节，按实际剩余长度读取
+        let bytes = r.take(octets.min(r.remaining()))?;
+
+        let mut header = Vec::new();
+        let mut header_len = 0;
+        if has_header && !bytes.is_empty() {
+            header_len = 1 + bytes[0] as usize;
+            let mut ies = bytes
+                .get(1..header_len)
+                .ok_or("User data header exceeds user data")?;
+            while ies.len() >= 2 {
+                let len = ies[1] as usize;
+                let data = ies.get(2..2 + len).ok_or("Truncated information element")?;
+                header.push(InformationElement { id: ies[0], data: data.to_vec() });
+                ies = &ies[2 + len..];
+            }
+        }
+
+        let payload = &bytes[header_len..];
+        let body = match alphabet {
+            Alphabet::Gsm7 => {
+                let header_septets = (header_len * 8).div_ceil(7);
+                let fill_bits = header_septets * 7 - header_len * 8;
+                let count = udl.saturating_sub(header_septets);
+                let tables = Gsm7Tables::from_header(&header);
+                Body::Text(tables.decode(&unpack_septets(payload, count, fill_bits)))
+            }
+            Alphabet::Data8 => Body::Binary(payload.to_vec()),
+            Alphabet::Ucs2 => {
+                let units: Vec<u16> = payload
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Body::Text(String::from_utf16_lossy(&units))
+            }
+        };
+
+        Ok(Self { header, body })
+    }
+
+    fn encode(&self, alphabet: Alphabet, out: &mut Vec<u8>) -> Result<(), String> {
+        let header = self.header_bytes();
+        match alphabet {
+            Alphabet::Gsm7 => {
+                let Body::Text(text) = &self.body else {
+                    return Err("Binary user data requires 8-bit encoding".to_string());
+                };
+                let septets = Gsm7Tables::from_header(&self.header)
+                    .encode(text)
+                    .ok_or("Text contains characters outside GSM 7-bit alphabet")?;
+                let header_septets = (header.len() * 8).div_ceil(7);
+                let udl = header_septets + septets.len();
+                if udl > MAX_USER_DATA_SEPTETS {
+                    return Err(format!("User data too long: {} septets", udl));
+                }
+                out.push(udl as u8);
+                out.extend(&header);
+                out.extend(pack_septets(&septets, header_septets * 7 - header.len() * 8));
+            }
+            Alphabet::Data8 | Alphabet::Ucs2 => {
+                let payload = match (&self.body, alphabet) {
+                    (Body::Binary(data), _) => data.clone(),
+                    (Body::Text(text), Alphabet::Ucs2) => {
+                        text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+                    }
+                    (Body::Text(text), _) => text.as_bytes().to_vec(),
+                };
+                let udl = header.len() + payload.len();
+                if udl > MAX_USER_DATA_OCTETS {
+                    return Err(format!("User data too long: {} octets", udl));
+                }
+                out.push(udl as u8);
+                out.extend(&header);
+                out.extend(payload);
+            }
+        }
+        Ok(())
+    }
+}
+
+// ========== 消息类型 ==========
+
+/// 有效期
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidityPeriod {
+    /// 相对格式（TP-VP 原始值，如 0xAA = 4 天）
+    Relative(u8),
+    /// 绝对时间
+    Absolute(DateTime<FixedOffset>),
+    /// 增强格式（原样保留）
+    Enhanced([u8; 7]),
+}
+
+/// SMS-DELIVER（网络下发给手机的短信）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsDeliver {
+    pub smsc: Option<Address>,
+    pub originator: Address,
+    /// 服务中心是否还有更多消息待下发
+    pub more_messages: bool,
+    pub reply_path: bool,
+    /// 发送方是否请求了状态报告
+    pub status_report_indication: bool,
+    pub protocol_id: u8,
+    pub dcs: u8,
+    /// 服务中心时间戳
+    pub timestamp: DateTime<FixedOffset>,
+    pub user_data: UserData,
+}
+
+/// SMS-SUBMIT（手机提交给网络的短信）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsSubmit {
+    pub smsc: Option<Address>,
+    pub reject_duplicates: bool,
+    /// 是否请求状态报告
+    pub status_report_request: bool,
+    pub message_reference: u8,
+    pub destination: Address,
+    pub protocol_id: u8,
+    pub dcs: u8,
+    pub validity_period: Option<ValidityPeriod>,
+    pub user_data: UserData,
+}
+
+/// SMS-STATUS-REPORT（投递状态报告）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsStatusReport {
+    pub smsc: Option<Address>,
+    pub more_messages: bool,
+    /// false 表示对应 SMS-SUBMIT，true 表示对应 SMS-COMMAND
+    pub status_report_qualifier: bool,
+    /// 对应 SMS-SUBMIT 的消息参考号
+    pub message_reference: u8,
+    pub recipient: Address,
+    pub service_centre_timestamp: DateTime<FixedOffset>,
+    /// 投递完成（或最后一次尝试）时间
+    pub discharge_time: DateTime<FixedOffset>,
+    /// TP-Status 原始值
+    pub status: u8,
+    pub protocol_id: Option<u8>,
+    pub dcs: Option<u8>,
+    pub user_data: Option<UserData>,
+}
+
+/// 状态报告结果分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// 已送达
+    Delivered,
+    /// 临时错误，服务中心仍在重试
+    Pending,
+    /// 永久错误或已停止重试
+    Failed,
+}
+
+impl SmsStatusReport {
+    pub fn delivery_status(&self) -> DeliveryStatus {
+        match self.status {
+            0x00..=0x1F => DeliveryStatus::Delivered,
+            0x20..=0x3F => DeliveryStatus::Pending,
+            _ => DeliveryStatus::Failed,
+        }
+    }
+}
+
+/// 解码后的短信 PDU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pdu {
+    Deliver(SmsDeliver),
+    Submit(SmsSubmit),
+    StatusReport(SmsStatusReport),
+}
+
+impl Pdu {
+    /// 编码为十六进制字符串（包含服务中心地址字段）
+    pub fn encode(&self) -> Result<String, String> {
+        let mut out = Vec::new();
+        match self {
+            Pdu::Deliver(m) => {
+                Address::encode_smsc(m.smsc.as_ref(), &mut out)?;
+                let mut first = 0x00;
+                if !m.more_messages {
+                    first |= 0x04;
+                }
+                if m.status_report_indication {
+                    first |= 0x20;
+                }
+                if !m.user_data.header.is_empty() {
+                    first |= 0x40;
+                }
+                if m.reply_path {
+                    first |= 0x80;
+                }
+                out.push(first);
+                m.originator.encode(&mut out)?;
+                out.push(m.protocol_id);
+                out.push(m.dcs);
+                encode_timestamp(&m.timestamp, &mut out);
+                m.user_data.encode(Alphabet::from_dcs(m.dcs), &mut out)?;
+            }
+            Pdu::Submit(m) => {
+                Address::encode_smsc(m.smsc.as_ref(), &mut out)?;
+                let mut first = 0x01;
+                if m.reject_duplicates {
+                    first |= 0x04;
+                }
+                first |= match m.validity_period {
+                    None => 0x00,
+                    Some(ValidityPeriod::Relative(_)) => 0x10,
+                    Some(ValidityPeriod::Enhanced(_)) => 0x08,
+                    Some(ValidityPeriod::Absolute(_)) => 0x18,
+                };
+                if m.status_report_request {
+                    first |= 0x20;
+                }
+                if !m.user_data.header.is_empty() {
+                    first |= 0x40;
+                }
+                out.push(first);
+                out.push(m.message_reference);
+                m.destination.encode(&mut out)?;
+                out.push(m.protocol_id);
+                out.push(m.dcs);
+                match &m.validity_period {
+                    None => {}
+                    Some(ValidityPeriod::Relative(vp)) => out.push(*vp),
+                    Some(ValidityPeriod::Enhanced(vp)) => out.extend(vp),
+                    Some(ValidityPeriod::Absolute(ts)) => encode_timestamp(ts, &mut out),
+                }
+                m.user_data.encode(Alphabet::from_dcs(m.dcs), &mut out)?;
+            }
+            Pdu::StatusReport(m) => {
+                Address::encode_smsc(m.smsc.as_ref(), &mut out)?;
+                let mut first = 0x02;
+                if !m.more_messages {
+                    first |= 0x04;
+                }
+                if m.status_report_qualifier {
+                    first |= 0x20;
+                }
+                if m.user_data.as_ref().is_some_and(|ud| !ud.header.is_empty()) {
+                    first |= 0x40;
+                }
+                out.push(first);
+                out.push(m.message_reference);
+                m.recipient.encode(&mut out)?;
+                encode_timestamp(&m.service_centre_timestamp, &mut out);
+                encode_timestamp(&m.discharge_time, &mut out);
+                out.push(m.status);
+
+                let mut indicator = 0;
+                if m.protocol_id.is_some() {
+                    indicator |= 0x01;
+                }
+                if m.dcs.is_some() {
+                    indicator |= 0x02;
+                }
+                if m.user_data.is_some() {
+                    indicator |= 0x04;
+                }
+                if indicator != 0 {
+                    out.push(indicator);
+                    if let Some(pid) = m.protocol_id {
+                        out.push(pid);
+                    }
+                    if let Some(dcs) = m.dcs {
+                        out.push(dcs);
+                    }
+                    if let Some(ud) = &m.user_data {
+                        ud.encode(Alphabet::from_dcs(m.dcs.unwrap_or(0)), &mut out)?;
+                    }
+                }
+            }
+        }
+        Ok(bytes_to_hex(&out))
+    }
+}
+
+/// 解码包含服务中心地址字段的 PDU（ofono / AT+CMGL 的常见格式）
+pub fn decode(pdu_hex: &str) -> Result<Pdu, String> {
+    let bytes = hex_to_bytes(pdu_hex)?;
+    let mut r = Reader::new(&bytes);
+    let smsc = Address::decode_smsc(&mut r)?;
+    decode_tpdu_inner(&mut r, smsc)
+}
+
+fn decode_tpdu_inner(r: &mut Reader, smsc: Option<Address>) -> Result<Pdu, String> {
+    let first = r.u8()?;
+    let has_header = first & 0x40 != 0;
+
+    match first & 0x03 {
+        0x00 => {
+            let originator = Address::decode(r)?;
+            let protocol_id = r.u8()?;
+            let dcs = r.u8()?;
+            let timestamp = decode_timestamp(r)?;
+            let user_data = UserData::decode(r, Alphabet::from_dcs(dcs), has_header)?;
+            Ok(Pdu::Deliver(SmsDeliver {
+                smsc,
+                originator,
+                more_messages: first & 0x04 == 0,
+                reply_path: first & 0x80 != 0,
+                status_report_indication: first & 0x20 != 0,
+                protocol_id,
+                dcs,
+                timestamp,
+                user_data,
+            }))
+        }
+        0x01 => {
+            let message_reference = r.u8()?;
+            let destination = Address::decode(r)?;
+            let protocol_id = r.u8()?;
+            let dcs = r.u8()?;
+            let validity_period = match (first >> 3) & 0x03 {
+                0x00 => None,
+                0x02 => Some(ValidityPeriod::Relative(r.u8()?)),
+                0x01 => {
+                    let mut vp = [0u8; 7];
+                    vp.copy_from_slice(r.take(7)?);
+                    Some(ValidityPeriod::Enhanced(vp))
+                }
+                _ => Some(ValidityPeriod::Absolute(decode_timestamp(r)?)),
+            };
+            let user_data = UserData::decode(r, Alphabet::from_dcs(dcs), has_header)?;
+            Ok(Pdu::Submit(SmsSubmit {
+                smsc,
+                reject_duplicates: first & 0x04 != 0,
+                status_report_request: first & 0x20 != 0,
+                message_reference,
+                destination,
+                protocol_id,
+                dcs,
+                validity_period,
+                user_data,
+            }))
+        }
+        0x02 => {
+            let message_reference = r.u8()?;
+            let recipient = Address::decode(r)?;
+            let service_centre_timestamp = decode_timestamp(r)?;
+            let discharge_time = decode_timestamp(r)?;
+            let status = r.u8()?;
+
+            // 可选参数：TP-PI 指示后续是否有 PID/DCS/UDL
+            let indicator = if r.remaining() > 0 { r.u8()? } else { 0 };
+            let protocol_id = if indicator & 0x01 != 0 { Some(r.u8()?) } else { None };
+            let dcs = if indicator & 0x02 != 0 { Some(r.u8()?) } else { None };
+            let user_data = if indicator & 0x04 != 0 {
+                Some(UserData::decode(r, Alphabet::from_dcs(dcs.unwrap_or(0)), has_header)?)
+            } else {
+                None
+            };
+
+            Ok(Pdu::StatusReport(SmsStatusReport {
+                smsc,
+                more_messages: first & 0x04 == 0,
+                status_report_qualifier: first & 0x20 != 0,
+                message_reference,
+                recipient,
+                service_centre_timestamp,
+                discharge_time,
+                status,
+                protocol_id,
+                dcs,
+                user_data,
+            }))
+        }
+        mti => Err(format!("Unsupported message type indicator: {}", mti)),
+    }
+}
+
+// ========== 长短信分段 ==========
+
+/// 选择能表示文本的最紧凑编码，并按单条短信容量分段
+///
+/// 需要分段时每段预留 6 字节拼接用户数据头（IEI 0x00），
+/// GSM 7-bit 不拆开扩展字符的转义序列，UCS2 不拆开代理对。
+pub fn segment_text(text: &str) -> (Alphabet, Vec<String>) {
+    let (alphabet, units): (Alphabet, Vec<(char, usize)>) = if gsm7_encode(text).is_some() {
+        let units = text
+            .chars()
+            .map(|c| (c, Gsm7Tables::DEFAULT.char_septets(c).map_or(1, |s| s.len())))
+            .collect();
+        (Alphabet::Gsm7, units)
+    } else {
+        (Alphabet::Ucs2, text.chars().map(|c| (c, c.len_utf16() * 2)).collect())
+    };
+
+    let (single, multi) = match alphabet {
+        Alphabet::Gsm7 => (MAX_USER_DATA_SEPTETS, 153),
+        _ => (MAX_USER_DATA_OCTETS, 134),
+    };
+
+    let total: usize = units.iter().map(|(_, n)| n).sum();
+    if total <= single {
+        return (alphabet, vec![text.to_string()]);
+    }
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut used = 0;
+    for (c, n) in units {
+        if used + n > multi {
+            parts.push(std::mem::take(&mut current));
+            used = 0;
+        }
+        current.push(c);
+        used += n;
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    (alphabet, parts)
+}
+
+/// 构造发送用的 SMS-SUBMIT 列表，超长文本自动分段并添加拼接信息
+pub fn build_submit(destination: &str, text: &str, reference: u8, status_report_request: bool) -> Vec<SmsSubmit> {
+    let (alphabet, parts) = segment_text(text);
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let mut user_data = UserData::text(&part);
+            if total > 1 {
+                user_data.header.push(
+                    Concat {
+                        reference: u16::from(reference),
+                        total: total as u8,
+                        sequence: i as u8 + 1,
+                        wide_reference: false,
+                    }
+                    .to_ie(),
+                );
+            }
+            SmsSubmit {
+                smsc: None,
+                reject_duplicates: false,
+                status_report_request,
+                message_reference: 0,
+                destination: Address::new(destination),
+                protocol_id: 0,
+                dcs: alphabet.dcs(),
+                validity_period: Some(ValidityPeriod::Relative(0xA7)),
+                user_data,
+            }
+        })
+        .collect()
+}
+
+// ========== 工具 ==========
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| format!("PDU truncated at offset {}", self.pos))?;
+        self.pos += n;
+        Ok(slice)
+    }
+}
+
+fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return Err("PDU hex has odd length".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| format!("Invalid hex at offset {}", i))
+        })
+        .collect()
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    fn deliver(hex: &str) -> SmsDeliver {
+        match decode(hex).unwrap() {
+            Pdu::Deliver(m) => m,
+            other => panic!("expected SMS-DELIVER, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deliver_gsm7_golden() {
+        // SMSC +27381000015，发送方 27838890001，1999-03-29 15:16:59 +02:00，"hellohello"
+        let hex = "07917283010010F5040BC87238880900F10000993092516195800AE8329BFD4697D9EC37";
+        let m = deliver(hex);
+        assert_eq!(m.smsc.as_ref().unwrap().number, "+27381000015");
+        assert_eq!(m.originator.number, "27838890001");
+        assert_eq!(m.timestamp, ts("1999-03-29T15:16:59+02:00"));
+        assert_eq!(m.user_data.body, Body::Text("hellohello".to_string()));
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn submit_gsm7_golden() {
+        // 发往 +46708251358，相对有效期 0xAA，"hellohello"
+        let hex = "0011000B916407281553F80000AA0AE8329BFD4697D9EC37";
+        let submit = SmsSubmit {
+            smsc: None,
+            reject_duplicates: false,
+            status_report_request: false,
+            message_reference: 0,
+            destination: Address::new("+46708251358"),
+            protocol_id: 0,
+            dcs: 0,
+            validity_period: Some(ValidityPeriod::Relative(0xAA)),
+            user_data: UserData::text("hellohello"),
+        };
+        assert_eq!(Pdu::Submit(submit.clone()).encode().unwrap(), hex);
+        assert_eq!(decode(hex).unwrap(), Pdu::Submit(submit));
+    }
+
+    #[test]
+    fn deliver_alphanumeric_sender_with_extension_chars() {
+        // 发送方 "Bank"（D0，7 个半字节），2024-05-01 08:30:00 +08:00，"Pay {5}€"
+        let hex = "0004".to_string()
+            + "07D0C2B07B0D" // Bank
+            + "0000"
+            + "42501080030023"
+            + "0B" // 11 septets: P a y space ESC{ 5 ESC} ESC€
+            + "D0701EB441D536A94D19";
+        let m = deliver(&hex);
+        assert!(m.smsc.is_none());
+        assert!(m.originator.is_alphanumeric());
+        assert_eq!(m.originator.number, "Bank");
+        assert_eq!(m.timestamp, ts("2024-05-01T08:30:00+08:00"));
+        assert_eq!(m.user_data.text_lossy(), "Pay {5}€");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn deliver_ucs2_concat_16bit_reference() {
+        // UDHI + UCS2，IEI 0x08 参考号 0x1234，第 2/3 段，"你好"，时区 -05:00
+        let hex = "0044".to_string()
+            + "0B913148801215F1" // +13840821511
+            + "0008"
+            + "4210312143650A" // 2024-01-13 12:34:56 -05:00
+            + "0B"
+            + "06080412340302"
+            + "4F60597D";
+        let m = deliver(&hex);
+        assert_eq!(m.originator.number, "+13840821511");
+        assert_eq!(m.timestamp, ts("2024-01-13T12:34:56-05:00"));
+        assert_eq!(m.user_data.text_lossy(), "你好");
+        assert_eq!(
+            m.user_data.concat(),
+            Some(Concat { reference: 0x1234, total: 3, sequence: 2, wide_reference: true })
+        );
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn deliver_gsm7_concat_8bit_reference_fill_bits() {
+        // UDHI + GSM 7-bit，IEI 0x00 参考号 0x2A，第 1/2 段，用户数据头后 1 位填充，"Hi"
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "09" + "0500032A0201" + "9069";
+        let m = deliver(&hex);
+        assert_eq!(m.originator.number, "0123456789");
+        assert_eq!(m.user_data.text_lossy(), "Hi");
+        assert_eq!(
+            m.user_data.concat(),
+            Some(Concat { reference: 0x2A, total: 2, sequence: 1, wide_reference: false })
+        );
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn deliver_8bit_data() {
+        let hex = "0004".to_string() + "0A8110325476980004" + "42501080030023" + "03" + "C0FFEE";
+        let m = deliver(&hex);
+        assert_eq!(m.user_data.body, Body::Binary(vec![0xC0, 0xFF, 0xEE]));
+        assert_eq!(m.user_data.text_lossy(), "C0FFEE");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn status_report_golden() {
+        // MR 0x2A，接收方 +8613800138000，已送达
+        let hex = "0006".to_string() + "2A0D91683108108300F0" + "42501080030023" + "42501080031023" + "00";
+        let Pdu::StatusReport(m) = decode(&hex).unwrap() else {
+            panic!("expected SMS-STATUS-REPORT");
+        };
+        assert_eq!(m.message_reference, 0x2A);
+        assert_eq!(m.recipient.number, "+8613800138000");
+        assert_eq!(m.discharge_time, ts("2024-05-01T08:30:01+08:00"));
+        assert_eq!(m.delivery_status(), DeliveryStatus::Delivered);
+        assert!(m.user_data.is_none());
+        assert_eq!(Pdu::StatusReport(m.clone()).encode().unwrap(), hex);
+
+        let failed = SmsStatusReport { status: 0x41, ..m.clone() };
+        assert_eq!(failed.delivery_status(), DeliveryStatus::Failed);
+        let pending = SmsStatusReport { status: 0x30, ..m };
+        assert_eq!(pending.delivery_status(), DeliveryStatus::Pending);
+    }
+
+    #[test]
+    fn segment_and_build_submit() {
+        assert_eq!(segment_text("hello").1.len(), 1);
+        assert_eq!(segment_text(&"a".repeat(160)).1.len(), 1);
+
+        let (alphabet, parts) = segment_text(&"a".repeat(161));
+        assert_eq!(alphabet, Alphabet::Gsm7);
+        assert_eq!(parts.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![153, 8]);
+
+        // 扩展字符占 2 个 septet，不会被拆开
+        let (_, parts) = segment_text(&("a".repeat(152) + "€" + &"b".repeat(10)));
+        assert_eq!(parts[0], "a".repeat(152));
+
+        let (alphabet, parts) = segment_text(&"中".repeat(71));
+        assert_eq!(alphabet, Alphabet::Ucs2);
+        assert_eq!(parts.iter().map(|p| p.chars().count()).collect::<Vec<_>>(), vec![67, 4]);
+
+        let text = "短信".repeat(40);
+        let submits = build_submit("10086", &text, 7, true);
+        assert_eq!(submits.len(), 2);
+        let mut decoded = String::new();
+        for (i, submit) in submits.iter().enumerate() {
+            let Pdu::Submit(m) = decode(&Pdu::Submit(submit.clone()).encode().unwrap()).unwrap() else {
+                panic!("expected SMS-SUBMIT");
+            };
+            assert!(m.status_report_request);
+            assert_eq!(m.destination.number, "10086");
+            let concat = m.user_data.concat().unwrap();
+            assert_eq!((concat.reference, concat.total, concat.sequence), (7, 2, i as u8 + 1));
+            decoded.push_str(&m.user_data.text_lossy());
+        }
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn gsm7_round_trip_covers_both_tables() {
+        let text: String = GSM7_BASIC
+            .iter()
+            .filter(|&&c| c != '\u{1B}')
+            .chain(GSM7_EXTENSION.iter().map(|(_, c)| c))
+            .collect();
+        let septets = gsm7_encode(&text).unwrap();
+        assert_eq!(septets.len(), 127 + 2 * GSM7_EXTENSION.len());
+        assert_eq!(gsm7_decode(&septets), text);
+        assert!(gsm7_encode("中").is_none());
+
+        // 任意 fill bit 下打包再解包都应还原
+        for skip in 0..7 {
+            let packed = pack_septets(&septets, skip);
+            assert_eq!(unpack_septets(&packed, septets.len(), skip), septets);
+        }
+    }
+
+    #[test]
+    fn submit_ucs2_golden() {
+        // 发往 +8613800138000，UCS2，相对有效期 0xA7，"你好"
+        let hex = "0011000D91683108108300F00008A7044F60597D";
+        let submits = build_submit("+8613800138000", "你好", 0, false);
+        assert_eq!(submits.len(), 1);
+        assert_eq!(Pdu::Submit(submits[0].clone()).encode().unwrap(), hex);
+        assert_eq!(decode(hex).unwrap(), Pdu::Submit(submits[0].clone()));
+    }
+
+    #[test]
+    fn alphanumeric_address_round_trip() {
+        let address = Address::new("BANK");
+        assert!(address.is_alphanumeric());
+        let mut out = Vec::new();
+        address.encode(&mut out).unwrap();
+        // 4 个字符 = 28 bit = 7 个半字节
+        assert_eq!(bytes_to_hex(&out), "07D0C2A07309");
+        assert_eq!(Address::decode(&mut Reader::new(&out)).unwrap(), address);
+
+        // 超过 11 个字符无法编码
+        assert!(Address::new("ABCDEFGHIJKL").encode(&mut Vec::new()).is_err());
+        assert_eq!(Address::new("+8613800138000").type_of_address, 0x91);
+        assert_eq!(Address::new("10086").type_of_address, 0x81);
+    }
+
+    #[test]
+    fn national_language_shift_tables() {
+        // 土耳其语锁定移位表：0x07 ı，0x0C ğ
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "07" + "03250101" + "383000";
+        let m = deliver(&hex);
+        assert!(!m.user_data.unsupported_language_shift());
+        assert_eq!(m.user_data.text_lossy(), "ığ");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+
+        // 土耳其语单移表：ESC 0x53 Ş，ESC 0x63 ç
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "09" + "03240101" + "D84C3763";
+        let m = deliver(&hex);
+        assert_eq!(m.user_data.text_lossy(), "Şç");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+
+        // 西班牙语单移表：ESC 0x41 Á
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "0B" + "03240102" + "D804E5E2371B";
+        let m = deliver(&hex);
+        assert_eq!(m.user_data.text_lossy(), "Árbol");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+
+        // 葡萄牙语锁定 + 单移表同时出现，用户数据头 7 字节无需填充：0x7B ã，0x1E Ê
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "0D" + "06250103240103" + "CEFD1BE401";
+        let m = deliver(&hex);
+        assert_eq!(m.user_data.text_lossy(), "Não Ê");
+        assert_eq!(Pdu::Deliver(m).encode().unwrap(), hex);
+    }
+
+    #[test]
+    fn unsupported_language_shift_is_flagged() {
+        // IEI 0x25 语言 4（印度语系）未实现，"Hi" 按默认字母表解码
+        let hex = "0044".to_string() + "0A8110325476980000" + "42501080030023" + "07" + "03250104" + "40A601";
+        let m = deliver(&hex);
+        assert!(m.user_data.unsupported_language_shift());
+        assert_eq!(m.user_data.text_lossy(), "Hi");
+
+        // 规范未定义西班牙语锁定移位表
+        let spanish = UserData {
+            header: vec![InformationElement { id: IEI_NATIONAL_LOCKING_SHIFT, data: vec![LANGUAGE_SPANISH] }],
+            body: Body::Text(String::new()),
+        };
+        assert!(spanish.unsupported_language_shift());
+
+        let plain = deliver("07917283010010F5040BC87238880900F10000993092516195800AE8329BFD4697D9EC37");
+        assert!(!plain.user_data.unsupported_language_shift());
+    }
+
+    #[test]
+    fn rejects_malformed_pdu() {
+        assert!(decode("").is_err());
+        assert!(decode("0").is_err());
+        assert!(decode("0004ZZ").is_err());
+        assert!(decode("07917283010010F504").is_err());
+    }
+}
@@ -9,8 +9,8 @@ use chrono::Utc;
 use reqwest::{Client, RequestBuilder, StatusCode};
 use serde_json::{json, Value};
 
-use crate::config::{ConfigManager, SmsPushConfig, SmsPushProvider};
-use crate::db::SmsMessage;
+use crate::config::{CallPushEvent, ConfigManager, SmsPushConfig, SmsPushProvider};
+use crate::db::{CallRecord, SmsMessage};
 
 pub struct SmsPushSender {
     client: Client,
@@ -45,6 +45,20 @@ impl SmsPushSender {
         self.send_with_config(&config, &title, &body).await.map(|_| ())
     }
 
+    /// 推送通话事件（响铃、未接、通话结束），未在 `call_events` 中启用的事件直接跳过
+    pub async fn forward_call(&self, event: CallPushEvent, call: &CallRecord) -> Result<(), String> {
+        let config = self.get_config();
+
+        if !config.enabled || !config.call_events.contains(&event) {
+            return Ok(());
+        }
+
+        let title = render_call_push_template(&config.call_title_template, event, call);
+        let body = render_call_push_template(&config.call_body_template, event, call);
+
+        self.send_with_config(&config, &title, &body).await.map(|_| ())
+    }
+
     pub async fn test_sms_push(&self) -> Result<String, String> {
         let config = self.get_config();
 
@@ -60,6 +74,14 @@ impl SmsPushSender {
             timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             status: "received".to_string(),
             pdu: None,
+            message_path: None,
+            sent_at: None,
+            delivered_at: None,
+            failed_at: None,
+            tags: Vec::new(),
+            contact_name: None,
+            thread_id: None,
+            read: false,
         };
 
         let title = render_sms_push_template(&config.title_template, &test_message);
@@ -262,6 +284,23 @@ fn render_sms_push_template(template: &str, message: &SmsMessage) -> String {
         .replace("{{time}}", &message.timestamp)
 }
 
+/// 支持的变量：{{event}}, {{event_cn}}, {{id}}, {{phone_number}}, {{contact_name}}, {{direction}},
+/// {{duration}}, {{start_time}}, {{end_time}}, {{answered}}, {{reason}}
+fn render_call_push_template(template: &str, event: CallPushEvent, call: &CallRecord) -> String {
+    template
+        .replace("{{event}}", event.as_str())
+        .replace("{{event_cn}}", event.label())
+        .replace("{{id}}", &call.id.to_string())
+        .replace("{{phone_number}}", &call.phone_number)
+        .replace("{{contact_name}}", call.contact_name.as_deref().unwrap_or_default())
+        .replace("{{direction}}", &call.direction)
+        .replace("{{duration}}", &call.duration.to_string())
+        .replace("{{start_time}}", &call.start_time)
+        .replace("{{end_time}}", call.end_time.as_deref().unwrap_or_default())
+        .replace("{{answered}}", if call.answered { "是" } else { "否" })
+        .replace("{{reason}}", call.reason.as_deref().unwrap_or_default())
+}
+
 fn resolve_endpoint(input: &str, default: &str) -> String {
     let trimmed = input.trim();
     if trimmed.is_empty() {
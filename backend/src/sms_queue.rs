@@ -0,0 +1,346 @@
+//! 短信发送队列
+//!
+//! 队列任务保存在 SQLite 的 `sms_queue` 表中，后台每 10 秒发送到期（`send_at`）的任务：
+//! - 带 `schedule`（cron 表达式，本地时间）的任务发送后按表达式计算下次发送时间；
+//! - 模组返回错误（如未注册网络）时按 `retry_base_secs` 指数退避重试，
+//!   超过 `max_attempts` 后一次性任务标记为 failed，周期任务跳到下一次；
+//! - 同一号码一小时内的发送数超过 `max_per_hour_per_number` 时推迟发送，不计入失败次数。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone, Utc};
+use tracing::{info, warn};
+
+use crate::config::{ConfigManager, SmsQueueConfig};
+use crate::cron::CronSchedule;
+use crate::db::{Database, SmsQueueItem};
+use crate::modem::{ModemBackend, ModemError, ModemResult};
+use crate::sms_pdu::{self, Pdu};
+
+const STATUS_SENT: &str = "sent";
+const STATUS_FAILED: &str = "failed";
+
+/// 队列检查间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// 单次最多处理的任务数
+const BATCH_SIZE: i64 = 20;
+/// 触发限速时推迟的时间（秒）
+const RATE_LIMIT_DELAY_SECS: i64 = 300;
+/// 重试间隔上限（秒）
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+/// 已发送短信
+#[derive(Debug, Clone)]
+pub struct SentSms {
+    pub message_path: String,
+    /// 数据库记录 ID，保存失败时为 None
+    pub db_id: Option<i64>,
+    /// "pending"（等待 ofono 投递状态）或 "sent"
+    pub status: &'static str,
+}
+
+/// 发送短信并写入短信记录，投递状态由短信监听任务根据 ofono 信号更新
+pub async fn send_and_record(
+    modem: &dyn ModemBackend,
+    db: &Database,
+    phone_number: &str,
+    content: &str,
+) -> ModemResult<SentSms> {
+    let message_path = modem.send_sms(phone_number, content).await?;
+
+    let pdu = submit_pdus(phone_number, content, Utc::now().timestamp() as u8, modem.tracks_sms_status());
+    let stored = db.insert_sms("outgoing", phone_number, content, "pending", pdu.as_deref()).and_then(|id| {
+        db.set_sms_message_path(id, &message_path)?;
+        if !modem.tracks_sms_status() {
+            db.update_sms_delivery(id, "sent", &Utc::now().to_rfc3339())?;
+        }
+        Ok(id)
+    });
+    if let Err(e) = &stored {
+        warn!(error = %e, "Failed to save sent SMS");
+    }
+
+    Ok(SentSms {
+        message_path,
+        db_id: stored.ok(),
+        status: if modem.tracks_sms_status() { "pending" } else { "sent" },
+    })
+}
+
+/// 与发送内容等价的 SMS-SUBMIT PDU，长短信每段一行（与收到的长短信格式一致）
+///
+/// ofono 按文本发送并自行编码，这里只用于记录编码方式和分段数；
+/// 号码无法编码（如含字母）时返回 None。
+fn submit_pdus(phone_number: &str, content: &str, reference: u8, status_report_request: bool) -> Option<String> {
+    sms_pdu::build_submit(phone_number, content, reference, status_report_request)
+        .into_iter()
+        .map(|submit| Pdu::Submit(submit).encode())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .map(|pdus| pdus.join("\n"))
+}
+
+/// cron 表达式在 `after`（Unix 时间戳）之后的下一次触发时间，按本地时区计算
+pub fn next_occurrence(schedule: &CronSchedule, after: i64) -> Option<i64> {
+    let mut local = Local.timestamp_opt(after, 0).single()?.naive_local();
+    // 夏令时跳过的本地时间不存在，继续找下一个
+    for _ in 0..4 {
+        local = schedule.next_after(local)?;
+        if let Some(time) = Local.from_local_datetime(&local).earliest() {
+            return Some(time.timestamp());
+        }
+    }
+    None
+}
+
+/// 第 `attempts` 次失败后的重试等待时间：基础间隔逐次翻倍，最长 1 小时
+fn retry_delay(config: &SmsQueueConfig, attempts: u32) -> i64 {
+    let base = config.retry_base_secs as i64;
+    base.saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY_SECS)
+}
+
+/// 只有模组侧错误（未注册网络、短信中心无响应等）值得重试，参数错误重试也不会成功
+fn is_transient(error: &ModemError) -> bool {
+    matches!(error, ModemError::Failed(_))
+}
+
+/// 根据一次发送结果更新任务状态
+fn apply_outcome(
+    item: &mut SmsQueueItem,
+    result: Result<Option<i64>, &ModemError>,
+    config: &SmsQueueConfig,
+    now: i64,
+) {
+    let next = || {
+        item.schedule
+            .as_deref()
+            .and_then(|expr| CronSchedule::parse(expr).ok())
+            .and_then(|schedule| next_occurrence(&schedule, now))
+    };
+
+    match result {
+        Ok(sms_id) => {
+            let next = next();
+            item.last_sent_at = Some(now);
+            item.last_sms_id = sms_id;
+            item.last_error = None;
+            item.attempts = 0;
+            match next {
+                Some(send_at) => item.send_at = send_at,
+                None => item.status = STATUS_SENT.to_string(),
+            }
+        }
+        Err(error) => {
+            item.last_error = Some(error.to_string());
+            let attempts = item.attempts + 1;
+            if is_transient(error) && attempts < item.max_attempts {
+                item.attempts = attempts;
+                item.send_at = now + retry_delay(config, attempts);
+                return;
+            }
+            // 重试次数用尽：周期任务放弃本次，等待下次触发
+            match next() {
+                Some(send_at) => {
+                    item.attempts = 0;
+                    item.send_at = send_at;
+                }
+                None => {
+                    item.attempts = attempts;
+                    item.status = STATUS_FAILED.to_string();
+                }
+            }
+        }
+    }
+}
+
+/// 号码在最近一小时内的发送数是否已达上限
+fn is_rate_limited(db: &Database, config: &SmsQueueConfig, phone_number: &str, now: i64) -> bool {
+    if config.max_per_hour_per_number == 0 {
+        return false;
+    }
+    let Some(since) = Utc.timestamp_opt(now - 3600, 0).single() else {
+        return false;
+    };
+    match db.count_outgoing_sms_since(phone_number, &since.to_rfc3339()) {
+        Ok(count) => count >= config.max_per_hour_per_number as i64,
+        Err(e) => {
+            warn!(error = %e, "Failed to count recent SMS");
+            false
+        }
+    }
+}
+
+/// 发送所有到期任务，返回实际尝试发送的任务数
+pub async fn process_due(db: &Database, modem: &dyn ModemBackend, config: &SmsQueueConfig, now: i64) -> usize {
+    let due = match db.get_due_sms_queue(now, BATCH_SIZE) {
+        Ok(due) => due,
+        Err(e) => {
+            warn!(error = %e, "Failed to load SMS queue");
+            return 0;
+        }
+    };
+
+    let mut attempted = 0;
+    for mut item in due {
+        if is_rate_limited(db, config, &item.phone_number, now) {
+            item.send_at = now + RATE_LIMIT_DELAY_SECS;
+            item.last_error = Some("Rate limited".to_string());
+        } else {
+            attempted += 1;
+            match send_and_record(modem, db, &item.phone_number, &item.content).await {
+                Ok(sent) => {
+                    info!(id = item.id, phone = %item.phone_number, "Queued SMS sent");
+                    apply_outcome(&mut item, Ok(sent.db_id), config, now);
+                }
+                Err(e) => {
+                    warn!(id = item.id, phone = %item.phone_number, error = %e, "Queued SMS failed");
+                    apply_outcome(&mut item, Err(&e), config, now);
+                }
+            }
+        }
+
+        if let Err(e) = db.save_sms_queue_progress(&item) {
+            warn!(id = item.id, error = %e, "Failed to update SMS queue item");
+        }
+    }
+    attempted
+}
+
+/// 短信发送队列后台任务
+pub async fn start_sms_queue_worker(
+    db: Arc<Database>,
+    modem: Arc<dyn ModemBackend>,
+    config_manager: Arc<ConfigManager>,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let config = config_manager.get_sms_queue();
+        process_due(&db, modem.as_ref(), &config, Utc::now().timestamp()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem::simulated::SimulatedBackend;
+    use std::path::PathBuf;
+
+    fn item(schedule: Option<&str>) -> SmsQueueItem {
+        SmsQueueItem {
+            id: 1,
+            phone_number: "10086".to_string(),
+            content: "hello".to_string(),
+            schedule: schedule.map(str::to_string),
+            send_at: 0,
+            status: "scheduled".to_string(),
+            attempts: 0,
+            max_attempts: 3,
+            last_error: None,
+            last_sent_at: None,
+            last_sms_id: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn outcome_transitions() {
+        let config = SmsQueueConfig::default();
+        let now = 1_000_000;
+        let not_registered = ModemError::Failed("Not registered".to_string());
+
+        // 一次性任务：重试两次后失败
+        let mut once = item(None);
+        apply_outcome(&mut once, Err(&not_registered), &config, now);
+        assert_eq!((once.status.as_str(), once.attempts, once.send_at), ("scheduled", 1, now + 60));
+        apply_outcome(&mut once, Err(&not_registered), &config, now);
+        assert_eq!((once.attempts, once.send_at), (2, now + 120));
+        apply_outcome(&mut once, Err(&not_registered), &config, now);
+        assert_eq!((once.status.as_str(), once.attempts), (STATUS_FAILED, 3));
+
+        // 参数错误不重试
+        let mut invalid = item(None);
+        apply_outcome(&mut invalid, Err(&ModemError::InvalidArgument("bad".to_string())), &config, now);
+        assert_eq!(invalid.status, STATUS_FAILED);
+
+        // 成功后一次性任务结束，周期任务排到下一次
+        let mut sent = item(None);
+        apply_outcome(&mut sent, Ok(Some(7)), &config, now);
+        assert_eq!((sent.status.as_str(), sent.last_sms_id, sent.last_sent_at), (STATUS_SENT, Some(7), Some(now)));
+
+        let mut recurring = item(Some("@hourly"));
+        recurring.attempts = 2;
+        apply_outcome(&mut recurring, Ok(None), &config, now);
+        assert_eq!((recurring.status.as_str(), recurring.attempts), ("scheduled", 0));
+        assert!(recurring.send_at > now && recurring.send_at <= now + 3600);
+
+        // 周期任务重试用尽后跳到下一次
+        let mut skipped = item(Some("@daily"));
+        skipped.attempts = 2;
+        apply_outcome(&mut skipped, Err(&not_registered), &config, now);
+        assert_eq!((skipped.status.as_str(), skipped.attempts), ("scheduled", 0));
+        assert!(skipped.send_at > now && skipped.last_error.is_some());
+
+        assert_eq!(retry_delay(&config, 20), MAX_RETRY_DELAY_SECS);
+    }
+
+    #[tokio::test]
+    async fn process_due_sends_and_rate_limits() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let modem = SimulatedBackend::new();
+        let config = SmsQueueConfig {
+            max_per_hour_per_number: 2,
+            ..Default::default()
+        };
+        let now = Utc::now().timestamp();
+
+        for _ in 0..3 {
+            db.insert_sms_queue("10086", "hello", None, now - 1, 3).unwrap();
+        }
+        let later = db.insert_sms_queue("10010", "later", None, now + 600, 3).unwrap();
+
+        assert_eq!(process_due(&db, &modem, &config, now).await, 2);
+
+        let items = db.list_sms_queue().unwrap();
+        let sent: Vec<_> = items.iter().filter(|item| item.status == STATUS_SENT).collect();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|item| item.last_sms_id.is_some()));
+
+        let limited = items.iter().find(|item| item.status == "scheduled" && item.id != later).unwrap();
+        assert_eq!(limited.send_at, now + RATE_LIMIT_DELAY_SECS);
+        assert_eq!(limited.attempts, 0);
+
+        assert_eq!(db.get_sms_queue_item(later).unwrap().unwrap().send_at, now + 600);
+        assert_eq!(process_due(&db, &modem, &config, now).await, 0);
+    }
+
+    #[tokio::test]
+    async fn sent_sms_records_submit_pdus() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let modem = SimulatedBackend::new();
+
+        let text = "验证码".repeat(30);
+        let sent = send_and_record(&modem, &db, "+8613800138000", &text).await.unwrap();
+        let sms = db.get_sms(sent.db_id.unwrap()).unwrap().unwrap();
+        let pdus: Vec<_> = sms.pdu.as_deref().unwrap().lines().collect();
+        assert_eq!(pdus.len(), 2);
+        let decoded: String = pdus
+            .iter()
+            .map(|pdu| match sms_pdu::decode(pdu).unwrap() {
+                Pdu::Submit(m) => {
+                    assert_eq!(m.destination.number, "+8613800138000");
+                    m.user_data.text_lossy()
+                }
+                other => panic!("expected SMS-SUBMIT, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(decoded, text);
+
+        // 号码无法编码时不记录 PDU，短信照常发送
+        let sent = send_and_record(&modem, &db, "+86 138", "hello").await.unwrap();
+        assert!(db.get_sms(sent.db_id.unwrap()).unwrap().unwrap().pdu.is_none());
+    }
+}
@@ -0,0 +1,479 @@
+//! 短信规则引擎
+//!
+//! 收到短信后按顺序匹配 `SmsRulesConfig.rules`（发件人、内容正则、生效时段），执行命中规则的动作：
+//! 自动回复、转发到其他号码、打标签、发送 Webhook，以及设备指令（重启、开关数据连接、查询状态）。
+//! 数据链路中断时，现场人员可以通过短信指令远程恢复设备。
+//!
+//! 设备指令只接受 `authorized_senders` 中的号码，且短信需以 PIN 开头（发件号码可被伪造，
+//! 包含设备指令的规则必须配置 PIN），正则匹配的是去掉 PIN 之后的内容。命中设备指令的短信入库前会遮盖 PIN，
+//! 且不会推送到事件流、Webhook 和短信推送。回复和转发写入短信发送队列，
+//! 受队列的按号码限速保护，不会与对方的自动回复形成循环。
+
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveTime, Utc};
+use regex::Regex;
+use tracing::{info, warn};
+use zbus::Connection;
+
+use crate::config::{ConfigManager, SmsDeviceCommand, SmsRuleAction, SmsRulesConfig};
+use crate::db::{Database, SmsMessage};
+use crate::dbus::{get_data_connection_status, get_network_info_data, set_data_connection, set_user_suspended};
+use crate::utils::{format_uptime, in_time_window, parse_time_of_day, read_uptime, same_number};
+use crate::webhook::WebhookSender;
+
+/// 转发模板为空时使用的默认模板
+const DEFAULT_FORWARD_TEMPLATE: &str = "[{{sender}}] {{content}}";
+/// 收到重启指令后延迟重启，留出发送回复的时间
+const REBOOT_DELAY_SECS: u64 = 30;
+/// 入库时替换 PIN 的占位符
+const PIN_MASK: &str = "****";
+/// 设备指令 PIN 最短长度
+pub const MIN_PIN_LEN: usize = 6;
+
+/// 校验规则配置
+pub fn validate_config(config: &SmsRulesConfig) -> Result<(), String> {
+    if !config.pin.is_empty() && (config.pin.chars().count() < MIN_PIN_LEN || config.pin.contains(char::is_whitespace)) {
+        return Err(format!(
+            "PIN must be at least {} characters without spaces",
+            MIN_PIN_LEN
+        ));
+    }
+    for rule in &config.rules {
+        if rule.name.trim().is_empty() {
+            return Err("Rule name is required".to_string());
+        }
+        if rule.actions.is_empty() {
+            return Err(format!("Rule '{}' has no actions", rule.name));
+        }
+        if !rule.content_regex.is_empty() {
+            Regex::new(&rule.content_regex)
+                .map_err(|e| format!("Rule '{}' has an invalid regex: {}", rule.name, e))?;
+        }
+        match (rule.time_window_start.trim(), rule.time_window_end.trim()) {
+            ("", "") => {}
+            (start, end) => {
+                for time in [start, end] {
+                    parse_time_of_day(time)
+                        .ok_or_else(|| format!("Rule '{}' has an invalid time: '{}'", rule.name, time))?;
+                }
+            }
+        }
+        for action in &rule.actions {
+            match action {
+                SmsRuleAction::Reply { template } if template.trim().is_empty() => {
+                    return Err(format!("Rule '{}': reply template is empty", rule.name));
+                }
+                SmsRuleAction::Forward { to, .. } if to.trim().is_empty() => {
+                    return Err(format!("Rule '{}': forward number is empty", rule.name));
+                }
+                SmsRuleAction::Tag { tag } if tag.trim().is_empty() || tag.contains(',') => {
+                    return Err(format!("Rule '{}': invalid tag '{}'", rule.name, tag));
+                }
+                _ => {}
+            }
+        }
+        if rule.requires_authorization() && config.authorized_senders.is_empty() {
+            return Err(format!(
+                "Rule '{}' runs device commands but no authorized senders are configured",
+                rule.name
+            ));
+        }
+        if rule.requires_authorization() && config.pin.is_empty() {
+            return Err(format!("Rule '{}' runs device commands but no PIN is configured", rule.name));
+        }
+    }
+    Ok(())
+}
+
+/// 命中的规则
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub actions: Vec<SmsRuleAction>,
+    /// 正则分组，下标 0 为整个匹配
+    captures: Vec<String>,
+}
+
+/// 发件人是否匹配规则中的号码列表
+fn sender_matches(pattern: &str, sender: &str) -> bool {
+    let mut patterns = pattern.split(',').map(str::trim).filter(|p| !p.is_empty()).peekable();
+    if patterns.peek().is_none() {
+        return true;
+    }
+    patterns.any(|p| match p.strip_suffix('*') {
+        Some(prefix) => sender.starts_with(prefix),
+        None => same_number(p, sender),
+    })
+}
+
+/// 常量时间比较，避免通过响应时间逐位猜测 PIN
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// 去掉短信开头的 PIN，未配置 PIN 或 PIN 不匹配时返回 None
+fn strip_pin<'a>(content: &'a str, pin: &str) -> Option<&'a str> {
+    let content = content.trim();
+    if pin.is_empty() {
+        return None;
+    }
+    let head = content.get(..pin.len().min(content.len()))?;
+    if !constant_time_eq(head.as_bytes(), pin.as_bytes()) {
+        return None;
+    }
+    let rest = &content[pin.len()..];
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim_start())
+}
+
+/// 遮盖设备指令短信开头的 PIN，不以 PIN 开头时原样返回
+pub fn redact_pin(content: &str, pin: &str) -> String {
+    match strip_pin(content, pin) {
+        Some(rest) => format!("{} {}", PIN_MASK, rest).trim_end().to_string(),
+        None => content.to_string(),
+    }
+}
+
+/// 命中的规则中是否包含设备指令
+pub fn has_device_command(matches: &[RuleMatch]) -> bool {
+    matches
+        .iter()
+        .flat_map(|matched| &matched.actions)
+        .any(|action| matches!(action, SmsRuleAction::Device { .. }))
+}
+
+/// 按顺序匹配规则
+pub fn evaluate(config: &SmsRulesConfig, sender: &str, content: &str, now: NaiveTime) -> Vec<RuleMatch> {
+    let mut matches = Vec::new();
+    if !config.enabled {
+        return matches;
+    }
+
+    for rule in config.rules.iter().filter(|rule| rule.enabled) {
+        if !sender_matches(&rule.sender, sender) {
+            continue;
+        }
+        if let (Some(start), Some(end)) = (
+            parse_time_of_day(&rule.time_window_start),
+            parse_time_of_day(&rule.time_window_end),
+        ) {
+            if !in_time_window(now, start, end) {
+                continue;
+            }
+        }
+
+        let text = if rule.requires_authorization() {
+            let authorized = config.authorized_senders.iter().any(|number| same_number(number, sender));
+            match strip_pin(content, &config.pin) {
+                Some(text) if authorized => text,
+                _ => {
+                    warn!(rule = %rule.name, sender, "Unauthorized SMS device command ignored");
+                    continue;
+                }
+            }
+        } else {
+            content
+        };
+
+        let captures = if rule.content_regex.is_empty() {
+            vec![text.to_string()]
+        } else {
+            let Ok(regex) = Regex::new(&rule.content_regex) else {
+                continue;
+            };
+            let Some(captures) = regex.captures(text) else {
+                continue;
+            };
+            captures
+                .iter()
+                .map(|group| group.map_or(String::new(), |m| m.as_str().to_string()))
+                .collect()
+        };
+
+        matches.push(RuleMatch {
+            rule: rule.name.clone(),
+            actions: rule.actions.clone(),
+            captures,
+        });
+        if rule.stop {
+            break;
+        }
+    }
+    matches
+}
+
+/// 渲染回复/转发模板
+fn render_template(template: &str, sms: &SmsMessage, matched: &RuleMatch) -> String {
+    let mut text = template
+        .replace("{{sender}}", &sms.phone_number)
+        .replace("{{content}}", &sms.content)
+        .replace("{{time}}", &sms.timestamp)
+        .replace("{{rule}}", &matched.rule);
+    for index in 0..10 {
+        let value = matched.captures.get(index).map(String::as_str).unwrap_or_default();
+        text = text.replace(&format!("{{{{{}}}}}", index), value);
+    }
+    text
+}
+
+/// 规则动作执行器（在短信监听任务中使用）
+pub struct SmsRuleEngine {
+    conn: Connection,
+    db: Arc<Database>,
+    config_manager: Arc<ConfigManager>,
+    webhook: Arc<WebhookSender>,
+}
+
+impl SmsRuleEngine {
+    pub fn new(
+        conn: Connection,
+        db: Arc<Database>,
+        config_manager: Arc<ConfigManager>,
+        webhook: Arc<WebhookSender>,
+    ) -> Self {
+        Self {
+            conn,
+            db,
+            config_manager,
+            webhook,
+        }
+    }
+
+    /// 立即添加命中规则的标签，使推送出去的短信带上标签；其余动作由 [`Self::run`] 执行
+    ///
+    /// 规则在入库前按原始内容匹配（见 [`evaluate`]），入库内容中的 PIN 已被遮盖。
+    pub fn apply_tags(&self, sms: &mut SmsMessage, matches: &[RuleMatch]) {
+        for action in matches.iter().flat_map(|matched| &matched.actions) {
+            if let SmsRuleAction::Tag { tag } = action {
+                match self.db.add_sms_tag(sms.id, tag.trim()) {
+                    Ok(tags) => sms.tags = tags,
+                    Err(e) => warn!(id = sms.id, error = %e, "Failed to tag SMS"),
+                }
+            }
+        }
+    }
+
+    /// 执行命中规则的动作
+    pub async fn run(&self, sms: &SmsMessage, matches: Vec<RuleMatch>) {
+        for matched in matches {
+            info!(rule = %matched.rule, sender = %sms.phone_number, "SMS rule matched");
+            for action in &matched.actions {
+                match action {
+                    SmsRuleAction::Reply { template } => {
+                        self.queue_sms(&sms.phone_number, &render_template(template, sms, &matched));
+                    }
+                    SmsRuleAction::Forward { to, template } => {
+                        let template = if template.is_empty() { DEFAULT_FORWARD_TEMPLATE } else { template };
+                        self.queue_sms(to.trim(), &render_template(template, sms, &matched));
+                    }
+                    SmsRuleAction::Tag { .. } => {}
+                    SmsRuleAction::Webhook { template } => {
+                        if let Err(e) = self.webhook.forward_sms_rule(&matched.rule, sms, template).await {
+                            warn!(rule = %matched.rule, error = %e, "SMS rule webhook failed");
+                        }
+                    }
+                    SmsRuleAction::Device { command } => {
+                        let result = self.run_device_command(*command).await;
+                        info!(rule = %matched.rule, command = ?command, result = %result, "SMS device command");
+                        self.queue_sms(&sms.phone_number, &result);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 通过短信发送队列发送（立即到期）
+    fn queue_sms(&self, phone_number: &str, content: &str) {
+        let max_attempts = self.config_manager.get_sms_queue().max_attempts;
+        if let Err(e) = self
+            .db
+            .insert_sms_queue(phone_number, content, None, Utc::now().timestamp(), max_attempts)
+        {
+            warn!(phone = %phone_number, error = %e, "Failed to queue SMS rule reply");
+        }
+    }
+
+    async fn run_device_command(&self, command: SmsDeviceCommand) -> String {
+        match command {
+            SmsDeviceCommand::Reboot => {
+                tokio::spawn(async {
+                    tokio::time::sleep(Duration::from_secs(REBOOT_DELAY_SECS)).await;
+                    let _ = Command::new("reboot").output();
+                });
+                format!("Rebooting in {}s", REBOOT_DELAY_SECS)
+            }
+            SmsDeviceCommand::DataOn | SmsDeviceCommand::DataOff => {
+                let active = command == SmsDeviceCommand::DataOn;
+                // 关闭前先置位，避免 Watchdog 在两者之间重新激活；失败时清除，交给 Watchdog 处理
+                if !active {
+                    set_user_suspended(true);
+                }
+                match set_data_connection(&self.conn, active).await {
+                    Ok(()) => {
+                        set_user_suspended(!active);
+                        format!("Data connection {}", if active { "on" } else { "off" })
+                    }
+                    Err(e) => {
+                        set_user_suspended(false);
+                        format!("Failed to set data connection: {}", e)
+                    }
+                }
+            }
+            SmsDeviceCommand::Status => self.status_report().await,
+        }
+    }
+
+    async fn status_report(&self) -> String {
+        let mut parts = Vec::new();
+        match get_network_info_data(&self.conn).await {
+            Ok(network) => parts.push(format!(
+                "{} {} signal {}%",
+                network.operator_name, network.registration_status, network.signal_strength
+            )),
+            Err(_) => parts.push("network unknown".to_string()),
+        }
+        match get_data_connection_status(&self.conn).await {
+            Ok(active) => parts.push(format!("data {}", if active { "on" } else { "off" })),
+            Err(_) => parts.push("data unknown".to_string()),
+        }
+        if let Ok((uptime, _)) = read_uptime() {
+            parts.push(format!("up {}", format_uptime(uptime)));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SmsRule;
+
+    fn rule(name: &str, sender: &str, regex: &str, actions: Vec<SmsRuleAction>) -> SmsRule {
+        SmsRule {
+            name: name.to_string(),
+            enabled: true,
+            sender: sender.to_string(),
+            content_regex: regex.to_string(),
+            time_window_start: String::new(),
+            time_window_end: String::new(),
+            stop: false,
+            actions,
+        }
+    }
+
+    fn names(matches: &[RuleMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.rule.as_str()).collect()
+    }
+
+    fn noon() -> NaiveTime {
+        parse_time_of_day("12:00").unwrap()
+    }
+
+    #[test]
+    fn rules_match_sender_content_and_time() {
+        let tag = |tag: &str| vec![SmsRuleAction::Tag { tag: tag.to_string() }];
+        let mut night = rule("night", "", "", tag("night"));
+        night.time_window_start = "22:00".to_string();
+        night.time_window_end = "07:00".to_string();
+        let mut config = SmsRulesConfig {
+            enabled: true,
+            rules: vec![
+                rule("bank", "95588,1069*", r"验证码\D*(\d{4,6})", tag("otp")),
+                rule("any", "", "", tag("all")),
+                night,
+            ],
+            ..Default::default()
+        };
+
+        let matches = evaluate(&config, "95588", "您的验证码是 123456", noon());
+        assert_eq!(names(&matches), ["bank", "any"]);
+        assert_eq!(matches[0].captures[1], "123456");
+        assert_eq!(names(&evaluate(&config, "10690001", "验证码 8888", noon())), ["bank", "any"]);
+        assert_eq!(names(&evaluate(&config, "10086", "验证码 8888", noon())), ["any"]);
+        let late = parse_time_of_day("23:30").unwrap();
+        assert_eq!(names(&evaluate(&config, "10086", "hi", late)), ["any", "night"]);
+
+        config.rules[0].stop = true;
+        assert_eq!(names(&evaluate(&config, "95588", "验证码 1234", noon())), ["bank"]);
+
+        config.enabled = false;
+        assert!(evaluate(&config, "95588", "验证码 1234", noon()).is_empty());
+    }
+
+    #[test]
+    fn device_commands_require_authorized_sender_and_pin() {
+        let reboot = vec![SmsRuleAction::Device { command: SmsDeviceCommand::Reboot }];
+        let config = SmsRulesConfig {
+            enabled: true,
+            pin: "246813".to_string(),
+            authorized_senders: vec!["13800138000".to_string()],
+            rules: vec![rule("reboot", "", "(?i)^reboot$", reboot)],
+        };
+
+        assert_eq!(names(&evaluate(&config, "+8613800138000", "246813 REBOOT", noon())), ["reboot"]);
+        assert!(evaluate(&config, "+8613800138000", "REBOOT", noon()).is_empty());
+        assert!(evaluate(&config, "+8613800138000", "2468130 reboot", noon()).is_empty());
+        assert!(evaluate(&config, "+8613800138000", "246814 reboot", noon()).is_empty());
+        assert!(evaluate(&config, "+8613800138000", "2468", noon()).is_empty());
+        assert!(evaluate(&config, "13900139000", "246813 reboot", noon()).is_empty());
+        // 授权号码按完整号码比较，后缀和短号码不算授权
+        for spoofed in ["800138000", "38000", "+8513800138000"] {
+            assert!(evaluate(&config, spoofed, "246813 reboot", noon()).is_empty(), "{}", spoofed);
+        }
+        assert_eq!(redact_pin("246813 reboot", &config.pin), "**** reboot");
+        assert_eq!(redact_pin("hello", &config.pin), "hello");
+
+        // 已保存的旧配置没有 PIN 时不执行设备指令
+        let no_pin = SmsRulesConfig { pin: String::new(), ..config.clone() };
+        assert!(evaluate(&no_pin, "+8613800138000", "reboot", noon()).is_empty());
+        assert!(validate_config(&no_pin).is_err());
+        for short in ["2468", "2468 13"] {
+            let invalid = SmsRulesConfig { pin: short.to_string(), ..config.clone() };
+            assert!(validate_config(&invalid).is_err(), "{}", short);
+        }
+
+        assert!(validate_config(&config).is_ok());
+        let unauthorized = SmsRulesConfig {
+            authorized_senders: Vec::new(),
+            ..config.clone()
+        };
+        assert!(validate_config(&unauthorized).is_err());
+        let mut bad_regex = config.clone();
+        bad_regex.rules[0].content_regex = "(".to_string();
+        assert!(validate_config(&bad_regex).is_err());
+    }
+
+    #[test]
+    fn templates_use_message_fields_and_captures() {
+        let config = SmsRulesConfig {
+            enabled: true,
+            rules: vec![rule("otp", "", r"code (\d+)", vec![SmsRuleAction::Tag { tag: "otp".to_string() }])],
+            ..Default::default()
+        };
+        let sms = SmsMessage {
+            id: 1,
+            direction: "incoming".to_string(),
+            phone_number: "10086".to_string(),
+            content: "your code 4321".to_string(),
+            timestamp: "2025-12-20T10:00:00+00:00".to_string(),
+            status: "received".to_string(),
+            pdu: None,
+            message_path: None,
+            sent_at: None,
+            delivered_at: None,
+            failed_at: None,
+            tags: Vec::new(),
+            contact_name: None,
+            thread_id: None,
+            read: false,
+        };
+        let matched = &evaluate(&config, &sms.phone_number, &sms.content, noon())[0];
+        assert_eq!(
+            render_template("{{rule}} from {{sender}}: {{1}}{{2}}", &sms, matched),
+            "otp from 10086: 4321"
+        );
+        assert_eq!(render_template(DEFAULT_FORWARD_TEMPLATE, &sms, matched), "[10086] your code 4321");
+    }
+}
@@ -15,8 +15,11 @@ use std::time::{Duration, Instant};
 use axum::extract::FromRef;
 use zbus::Connection;
 
+use crate::auth::AuthManager;
 use crate::config::ConfigManager;
 use crate::db::Database;
+use crate::events::EventBus;
+use crate::modem::ModemBackend;
 use crate::sms_push::SmsPushSender;
 use crate::webhook::WebhookSender;
 
@@ -51,9 +54,13 @@ pub struct AppState {
     pub webhook_sender: Arc<WebhookSender>,
     pub sms_push_sender: Arc<SmsPushSender>,
     pub frontend_runtime: Arc<FrontendRuntime>,
+    pub auth_manager: Arc<AuthManager>,
+    pub events: Arc<EventBus>,
+    pub modem: Arc<dyn ModemBackend>,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         dbus_conn: Arc<Connection>,
         database: Arc<Database>,
@@ -61,6 +68,9 @@ impl AppState {
         webhook_sender: Arc<WebhookSender>,
         sms_push_sender: Arc<SmsPushSender>,
         frontend_runtime: Arc<FrontendRuntime>,
+        auth_manager: Arc<AuthManager>,
+        events: Arc<EventBus>,
+        modem: Arc<dyn ModemBackend>,
     ) -> Self {
         Self {
             dbus_conn,
@@ -69,6 +79,9 @@ impl AppState {
             webhook_sender,
             sms_push_sender,
             frontend_runtime,
+            auth_manager,
+            events,
+            modem,
         }
     }
 }
@@ -109,8 +122,20 @@ impl FromRef<AppState> for Arc<FrontendRuntime> {
     }
 }
 
-impl FromRef<AppState> for (Arc<Connection>, Arc<Database>) {
+impl FromRef<AppState> for Arc<AuthManager> {
     fn from_ref(state: &AppState) -> Self {
-        (state.dbus_conn.clone(), state.database.clone())
+        state.auth_manager.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<EventBus> {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn ModemBackend> {
+    fn from_ref(state: &AppState) -> Self {
+        state.modem.clone()
     }
 }
@@ -0,0 +1,1118 @@
+//! HTTP 接口集成测试（完整路由 + 模拟 ofono）
+
+use std::sync::Arc;
+
+use reqwest::Method;
+use serde_json::json;
+use zbus::zvariant::Value;
+
+use super::{capture_server, wait_for, TestApp, TEST_PASSWORD, TEST_UI_ORIGIN};
+use crate::events::DeviceEvent;
+use crate::{dbus, sms_listener, ussd};
+
+#[tokio::test]
+async fn auth_routes_and_api_key_scopes() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 未携带令牌
+    let response = app.http.get(app.url("/api/device")).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    // CORS 只放行配置的前端地址
+    for (origin, allowed) in [(TEST_UI_ORIGIN, true), ("http://evil.test", false)] {
+        let response = app
+            .http
+            .request(Method::OPTIONS, app.url("/api/device"))
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "GET")
+            .send()
+            .await
+            .unwrap();
+        let allow_origin = response.headers().get("access-control-allow-origin");
+        assert_eq!(allow_origin.and_then(|v| v.to_str().ok()), allowed.then_some(origin), "{}", origin);
+    }
+
+    let status = app.get_ok("/api/auth/status").await;
+    assert_eq!(status["authenticated"], true);
+    assert_eq!(status["password_set"], true);
+
+    // 新密码可以登录，默认密码失效
+    let body = app.post("/api/auth/login", json!({ "password": TEST_PASSWORD })).await;
+    assert_eq!(body["data"]["must_change_password"], false);
+    let (status, body) = app.request(Method::POST, "/api/auth/login", Some(json!({ "password": "admin" }))).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["status"], "error");
+
+    // 只能发短信的 API 密钥
+    let created = app
+        .post_ok("/api/auth/keys", json!({ "name": "sms-bot", "scopes": ["sms:send"] }))
+        .await;
+    let key = created["key"].as_str().unwrap().to_string();
+    let key_id = created["info"]["id"].as_str().unwrap().to_string();
+    let keys = app.get_ok("/api/auth/keys").await;
+    assert!(keys.as_array().unwrap().iter().any(|k| k["id"] == key_id.as_str()));
+
+    let send = app
+        .http
+        .post(app.url("/api/sms/send"))
+        .header("X-API-Key", &key)
+        .json(&json!({ "phone_number": "10086", "content": "hello" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(send.status(), 200);
+
+    // 危险或依赖设备环境的接口：只验证路由和权限，不实际执行
+    let denied = [
+        (Method::POST, "/api/system/reboot"),
+        (Method::GET, "/api/usb-mode"),
+        (Method::POST, "/api/usb-mode"),
+        (Method::POST, "/api/usb-advance"),
+        (Method::POST, "/api/init-script"),
+        (Method::POST, "/api/ota/upload"),
+        (Method::POST, "/api/ota/apply"),
+        (Method::POST, "/api/ota/cancel"),
+        (Method::GET, "/api/connectivity"),
+        (Method::POST, "/api/data"),
+        (Method::POST, "/api/at"),
+        (Method::POST, "/api/band-lock"),
+    ];
+    for (method, path) in denied {
+        let response = app
+            .http
+            .request(method.clone(), app.url(path))
+            .bearer_auth(&key)
+            .json(&json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{} {}", method, path);
+    }
+
+    // 只读密钥不能读取短信规则；管理员读取时也只返回是否设置了设备指令 PIN
+    app.post_ok(
+        "/api/sms/rules",
+        json!({ "enabled": true, "pin": "432198", "authorized_senders": ["13800138000"], "rules": [] }),
+    )
+    .await;
+    let reader = app.post_ok("/api/auth/keys", json!({ "name": "dashboard", "scopes": ["read"] })).await;
+    let reader_key = reader["key"].as_str().unwrap().to_string();
+    let response = app.http.get(app.url("/api/sms/rules")).header("X-API-Key", &reader_key).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    assert!(!response.text().await.unwrap().contains("432198"));
+    let rules = app.get_ok("/api/sms/rules").await;
+    assert_eq!(rules["pin_set"], true);
+    assert!(rules.get("pin").is_none());
+    // 不传 pin 时保留已保存的 PIN
+    let rules = app.post_ok("/api/sms/rules", json!({ "enabled": true, "authorized_senders": ["13800138000"] })).await;
+    assert_eq!(rules["pin_set"], true);
+    assert_eq!(app.state.config_manager.get_sms_rules().pin, "432198");
+    // 默认密码重新生效（如 config.json 被重置）时，API 密钥同样只能等待改密
+    let mut auth_config = app.state.config_manager.get_auth();
+    let password_hash = std::mem::take(&mut auth_config.password_hash);
+    app.state.config_manager.set_auth(auth_config.clone()).unwrap();
+    let response = app.http.get(app.url("/api/stats")).header("X-API-Key", &reader_key).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    auth_config.password_hash = password_hash;
+    app.state.config_manager.set_auth(auth_config).unwrap();
+    let response = app.http.get(app.url("/api/stats")).header("X-API-Key", &reader_key).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    app.delete_ok(&format!("/api/auth/keys/{}", reader["info"]["id"].as_str().unwrap())).await;
+
+    app.delete_ok(&format!("/api/auth/keys/{}", key_id)).await;
+    let revoked = app
+        .http
+        .get(app.url("/api/sms/list"))
+        .header("X-API-Key", &key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoked.status(), 401);
+
+    app.post_ok("/api/auth/logout", json!({})).await;
+    let (status, _) = app.request(Method::GET, "/api/device", None).await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn device_sim_and_network_routes() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let body = app.get("/api/health").await;
+    assert_eq!(body["status"], "ok");
+
+    let device = app.get_ok("/api/device").await;
+    assert_eq!(device["imei"], "861234567890123");
+    assert_eq!(app.get_ok("/api/device/imeisv").await["software_version_number"], "01");
+
+    let sim = app.get_ok("/api/sim").await;
+    assert_eq!(sim["iccid"], "89860012345678901234");
+    assert_eq!(app.get_ok("/api/sim/slot").await["active_slot"], 1);
+    app.post_ok("/api/sim/slot/switch", json!({ "slot": 2 })).await;
+    assert!(app.mock.at_log().contains(&"AT+SPCONFIGSIMSLOT=66306".to_string()));
+
+    let network = app.get_ok("/api/network").await;
+    assert_eq!(network["operator_name"], "CHINA MOBILE");
+    assert_eq!(network["registration_status"], "registered");
+    app.get_ok("/api/network/interfaces").await;
+    assert_eq!(app.get_ok("/api/network/signal-strength").await["strength"], -95);
+    assert_eq!(app.get_ok("/api/network/nitz").await["available"], true);
+    let operators = app.get_ok("/api/network/operators").await;
+    assert_eq!(operators["operators"][0]["mcc"], "460");
+    app.get_ok("/api/network/operators/scan").await;
+    app.post_ok("/api/network/register-manual", json!({ "mccmnc": "46000" })).await;
+    app.post_ok("/api/network/register-auto", json!({})).await;
+
+    let cells = app.get_ok("/api/cells").await;
+    assert_eq!(cells["serving_cell"]["tech"], "lte");
+    assert_eq!(cells["serving_cell"]["cell_id"], 0x1A2B3C);
+    assert_eq!(cells["cells"][0]["pci"], "123");
+    app.get_ok("/api/location/cell-info").await;
+
+    let qos = app.get_ok("/api/qos").await;
+    assert_eq!(qos["qci"], 9);
+
+    app.get_ok("/api/ims/status").await;
+    app.get_ok("/api/voicemail/status").await;
+    app.get_ok("/api/stats").await;
+    app.get_ok("/api/stats/cpu").await;
+    app.get_ok("/api/ota/status").await;
+    app.get("/api/init-script").await;
+
+    // 原始 AT 指令返回纯文本
+    let (status, body) = app.request(Method::POST, "/api/at", Some(json!({ "cmd": "AT+CGSN" }))).await;
+    assert_eq!(status, 200);
+    assert!(body.as_str().unwrap().contains("861234567890123"));
+}
+
+#[tokio::test]
+async fn band_and_cell_lock_send_expected_at_commands() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let status = app.get_ok("/api/band-lock").await;
+    assert_eq!(status["locked"], true);
+    assert_eq!(status["lte_fdd_bands"], json!([1, 3, 5, 8]));
+
+    app.post_ok("/api/band-lock", json!({ "lte_fdd_bands": [1, 3], "nr_tdd_bands": [78] }))
+        .await;
+    let log = app.mock.at_log();
+    assert!(log.contains(&"AT+SPLBAND=1,0,0,0,5,0".to_string()), "{:?}", log);
+    assert!(log.iter().any(|cmd| cmd.starts_with("AT+SPLBAND=2,")), "{:?}", log);
+
+    // 空列表表示解除锁定：当前有锁定，应下发解锁指令
+    app.post_ok("/api/band-lock", json!({})).await;
+    let log = app.mock.at_log();
+    assert!(log.contains(&"AT+SPLBAND=1,0,0,0,0,0".to_string()));
+    assert!(log.contains(&"AT+SPLBAND=2,0,0,0,0".to_string()));
+
+    // 当前没有锁定时不下发解锁指令
+    app.mock.set_at_reply("AT+SPLBAND=0", "+SPLBAND: 0,0,0,0,0\r\nOK");
+    app.mock.set_at_reply("AT+SPLBAND=3", "+SPLBAND: 0,0,0,0\r\nOK");
+    let before = app.mock.at_log().len();
+    app.post_ok("/api/band-lock", json!({})).await;
+    assert_eq!(&app.mock.at_log()[before..], ["AT+SPLBAND=0", "AT+SPLBAND=3"]);
+    assert_eq!(app.get_ok("/api/band-lock").await["locked"], false);
+
+    let cell_lock = app.get_ok("/api/cell-lock").await;
+    assert!(cell_lock.is_object());
+
+    app.post_ok(
+        "/api/cell-lock",
+        json!({ "rat": 12, "enable": true, "arfcn": 1650, "pci": 123 }),
+    )
+    .await;
+    let log = app.mock.at_log();
+    let lock_sequence = [
+        "AT+SFUN=5",
+        "AT+SPFORCEFRQ=16,0",
+        "AT+SPFORCEFRQ=12,0",
+        "AT+SPFORCEFRQ=12,2,1650,123",
+        "AT+SFUN=4",
+    ];
+    let start = log.iter().position(|cmd| cmd == "AT+SFUN=5").unwrap();
+    assert_eq!(&log[start..start + lock_sequence.len()], lock_sequence);
+
+    // 缺少 arfcn/pci 时不下发任何指令
+    let before = app.mock.at_log().len();
+    let body = app.post("/api/cell-lock", json!({ "rat": 16, "enable": true })).await;
+    assert_eq!(body["status"], "error");
+    assert_eq!(app.mock.at_log().len(), before);
+
+    app.post_ok("/api/cell-lock", json!({ "rat": 12, "enable": false })).await;
+    app.post_ok("/api/cell-lock/unlock-all", json!({})).await;
+    assert_eq!(app.mock.at_log().last().unwrap(), "AT+SFUN=4");
+}
+
+#[tokio::test]
+async fn apn_data_and_radio_routes_update_modem_state() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let apns = app.get_ok("/api/apn").await;
+    let contexts = apns["contexts"].as_array().unwrap();
+    assert_eq!(contexts.len(), 1, "only internet contexts are listed");
+    assert_eq!(contexts[0]["apn"], "cmnet");
+
+    let updated = app
+        .post_ok(
+            "/api/apn",
+            json!({ "context_path": "/ril_0/context2", "apn": "cbnet", "protocol": "dual", "auth_method": "pap" }),
+        )
+        .await;
+    assert_eq!(updated["updated_context"]["apn"], "cbnet");
+    let apn: String = app.mock.property("/ril_0/context2", "AccessPointName").unwrap().try_into().unwrap();
+    assert_eq!(apn, "cbnet");
+    let active: bool = app.mock.property("/ril_0/context2", "Active").unwrap().try_into().unwrap();
+    assert!(active, "context should be reactivated after APN change");
+
+    let (status, body) = app.request(Method::POST, "/api/apn", Some(json!({ "context_path": "" }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["status"], "error");
+
+    // POST /api/data 会清空 iptables，这里直接调用 D-Bus 层切换数据连接
+    assert_eq!(app.get_ok("/api/data").await["active"], true);
+    crate::dbus::set_data_connection(&app.state.dbus_conn, false).await.unwrap();
+    assert_eq!(app.get_ok("/api/data").await["active"], false);
+
+    assert_eq!(app.get_ok("/api/roaming").await["roaming_allowed"], false);
+    app.post_ok("/api/roaming", json!({ "allowed": true })).await;
+    assert_eq!(app.get_ok("/api/roaming").await["roaming_allowed"], true);
+
+    assert_eq!(app.get_ok("/api/airplane-mode").await["enabled"], false);
+    app.post_ok("/api/airplane-mode", json!({ "enabled": true })).await;
+    let online: bool = app.mock.property("org.ofono.Modem", "Online").unwrap().try_into().unwrap();
+    assert!(!online);
+    app.post_ok("/api/airplane-mode", json!({ "enabled": false })).await;
+
+    assert_eq!(app.get_ok("/api/radio-mode").await["mode"], "auto");
+    app.post_ok("/api/radio-mode", json!({ "mode": "lte" })).await;
+    assert_eq!(app.get_ok("/api/radio-mode").await["technology_preference"], "LTE only");
+}
+
+#[tokio::test]
+async fn sms_send_receive_and_history() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 与生产环境一样，在独立连接上运行短信监听
+    {
+        let conn = app.mock.client().await;
+        let state = app.state.clone();
+        tokio::spawn(async move {
+            let _ = sms_listener::start_sms_listener(
+                conn,
+                state.database,
+                state.config_manager,
+                state.webhook_sender,
+                state.sms_push_sender,
+                state.events,
+            )
+            .await;
+        });
+    }
+
+    let sent = app
+        .post_ok("/api/sms/send", json!({ "phone_number": "10086", "content": "查询余额" }))
+        .await;
+    assert_eq!(sent["message_path"], "/ril_0/message_01");
+    assert_eq!(sent["status"], "pending");
+    let reports: bool = app.mock.property("org.ofono.MessageManager", "UseDeliveryReports").unwrap().try_into().unwrap();
+    assert!(reports);
+    assert_eq!(
+        app.mock.state.lock().unwrap().sent_messages,
+        vec![("10086".to_string(), "查询余额".to_string())]
+    );
+
+    // 等待监听任务注册完匹配规则后再发信号
+    let db = Arc::clone(&app.state.database);
+    let mut delivered = false;
+    for _ in 0..20 {
+        app.mock.incoming_message("10086", "您的余额为 10 元").await;
+        if wait_for(|| db.get_sms_stats().map(|s| s.incoming > 0).unwrap_or(false)).await {
+            delivered = true;
+            break;
+        }
+    }
+    assert!(delivered, "incoming SMS was not stored");
+
+    // 监听任务已就绪，此时发出的状态变化不会丢失
+    app.mock.message_state_changed("/ril_0/message_01", "sent").await;
+    assert!(wait_for(|| db.get_sms_messages(10, 0).unwrap().iter().any(|m| m.status == "sent")).await);
+    app.mock.message_state_changed("/ril_0/message_01", "delivered").await;
+    assert!(wait_for(|| db.get_sms_messages(10, 0).unwrap().iter().any(|m| m.delivered_at.is_some())).await);
+
+    let messages = app.get_ok("/api/sms/list?limit=10").await;
+    let messages = messages.as_array().unwrap();
+    assert!(messages.iter().any(|m| m["direction"] == "outgoing" && m["content"] == "查询余额" && m["status"] == "delivered"));
+    assert!(messages.iter().any(|m| m["direction"] == "incoming" && m["phone_number"] == "10086"));
+
+    let conversation = app.get_ok("/api/sms/conversation?phone_number=10086").await;
+    assert!(conversation.as_array().unwrap().len() >= 2);
+    let stats = app.get_ok("/api/sms/stats").await;
+    assert_eq!(stats["outgoing"], 1);
+
+    app.post_ok("/api/sms/clear", json!({})).await;
+    assert_eq!(app.get_ok("/api/sms/stats").await["total"], 0);
+}
+
+#[tokio::test]
+async fn sms_device_command_pin_is_not_exposed() {
+    let Some(app) = TestApp::start().await else { return };
+    {
+        let conn = app.mock.client().await;
+        let state = app.state.clone();
+        tokio::spawn(async move {
+            let _ = sms_listener::start_sms_listener(
+                conn,
+                state.database,
+                state.config_manager,
+                state.webhook_sender,
+                state.sms_push_sender,
+                state.events,
+            )
+            .await;
+        });
+    }
+
+    let (receiver, received) = capture_server().await;
+    let mut webhook = app.get_ok("/api/webhook/config").await;
+    webhook["enabled"] = json!(true);
+    webhook["url"] = json!(format!("{}/webhook", receiver));
+    app.post_ok("/api/webhook/config", webhook).await;
+    app.post_ok(
+        "/api/sms/rules",
+        json!({
+            "enabled": true,
+            "pin": "864213",
+            "authorized_senders": ["+8613800138000"],
+            "rules": [{ "name": "status", "content_regex": "(?i)^status$",
+                        "actions": [{ "type": "device", "command": "status" }] }]
+        }),
+    )
+    .await;
+
+    // 普通短信照常转发，确认监听任务和 Webhook 已就绪
+    let has = |needle: &str| received.lock().unwrap().iter().any(|(_, body)| body.contains(needle));
+    let mut ready = false;
+    for _ in 0..20 {
+        app.mock.incoming_message("+8613800138000", "hello").await;
+        if wait_for(|| has("hello")).await {
+            ready = true;
+            break;
+        }
+    }
+    assert!(ready, "incoming SMS was not forwarded");
+
+    app.mock.incoming_message("+8613800138000", "864213 status").await;
+    let db = Arc::clone(&app.state.database);
+    assert!(wait_for(|| db.get_sms_messages(50, 0).unwrap().iter().any(|m| m.content == "**** status")).await);
+    // 设备指令的结果回复写入发送队列，说明规则已执行
+    assert!(wait_for(|| db.list_sms_queue().map(|q| !q.is_empty()).unwrap_or(false)).await);
+
+    let list = app.get_ok("/api/sms/list?limit=50").await;
+    assert!(!list.to_string().contains("864213"));
+    let search = app.get_ok("/api/sms/search?q=status").await;
+    assert!(!search.as_array().unwrap().is_empty());
+    assert!(!search.to_string().contains("864213"));
+    assert!(!has("864213"));
+    assert!(!has("**** status"), "device commands are not forwarded");
+}
+
+#[tokio::test]
+async fn sms_data_off_command_is_not_undone_by_watchdog() {
+    let Some(app) = TestApp::start().await else { return };
+    {
+        let conn = app.mock.client().await;
+        let state = app.state.clone();
+        tokio::spawn(async move {
+            let _ = sms_listener::start_sms_listener(
+                conn,
+                state.database,
+                state.config_manager,
+                state.webhook_sender,
+                state.sms_push_sender,
+                state.events,
+            )
+            .await;
+        });
+    }
+    app.post_ok(
+        "/api/sms/rules",
+        json!({
+            "enabled": true,
+            "pin": "864213",
+            "authorized_senders": ["+8613800138000"],
+            "rules": [
+                { "name": "off", "content_regex": "(?i)^data off$", "actions": [{ "type": "device", "command": "data_off" }] },
+                { "name": "on", "content_regex": "(?i)^data on$", "actions": [{ "type": "device", "command": "data_on" }] }
+            ]
+        }),
+    )
+    .await;
+
+    let context_active = || -> bool { app.mock.property("/ril_0/context2", "Active").unwrap().try_into().unwrap() };
+    assert!(context_active());
+    let mut suspended = false;
+    for _ in 0..20 {
+        app.mock.incoming_message("+8613800138000", "864213 data off").await;
+        if wait_for(|| !context_active()).await {
+            suspended = true;
+            break;
+        }
+    }
+    assert!(suspended, "data_off command was not run");
+    assert!(wait_for(dbus::is_user_suspended).await);
+
+    // Watchdog 下一轮检查不会重新激活
+    let status = dbus::check_and_restore_data_connection(&app.state.dbus_conn).await;
+    assert_eq!(status, "Suspended by SMS command");
+    assert!(!context_active());
+
+    app.mock.incoming_message("+8613800138000", "864213 data on").await;
+    assert!(wait_for(context_active).await);
+    assert!(wait_for(|| !dbus::is_user_suspended()).await);
+    let status = dbus::check_and_restore_data_connection(&app.state.dbus_conn).await;
+    assert!(status.starts_with("Connected"), "{}", status);
+}
+
+#[tokio::test]
+async fn call_control_and_history() {
+    let Some(app) = TestApp::start().await else { return };
+
+    {
+        let conn = app.mock.client().await;
+        let state = app.state.clone();
+        tokio::spawn(async move {
+            let _ = sms_listener::start_call_listener(
+                conn,
+                state.database,
+                state.config_manager,
+                state.webhook_sender,
+                state.sms_push_sender,
+                state.events,
+            )
+            .await;
+        });
+    }
+
+    // 等待监听任务就绪：未接来电应写入通话记录
+    let db = Arc::clone(&app.state.database);
+    let mut listening = false;
+    for _ in 0..20 {
+        let path = app.mock.incoming_call("10000").await;
+        app.mock.remote_hangup(&path).await;
+        if wait_for(|| db.get_call_history(10, 0).map(|c| !c.is_empty()).unwrap_or(false)).await {
+            listening = true;
+            break;
+        }
+    }
+    assert!(listening, "call listener did not record the missed call");
+    assert!(!db.get_call_history(10, 0).unwrap()[0].answered);
+    app.post_ok("/api/call/history/clear", json!({})).await;
+
+    let call = app.post_ok("/api/call/dial", json!({ "phone_number": "10010" })).await;
+    let path = call["path"].as_str().unwrap().to_string();
+    let calls = app.get_ok("/api/calls").await;
+    assert_eq!(calls["calls"][0]["phone_number"], "10010");
+    app.post_ok("/api/call/hangup", json!({ "path": path })).await;
+    assert!(app.get_ok("/api/calls").await["calls"].as_array().unwrap().is_empty());
+
+    let body = app.post("/api/call/hangup", json!({ "path": path })).await;
+    assert_eq!(body["status"], "error");
+
+    // 来电 -> 接听 -> 全部挂断
+    let incoming = app.mock.incoming_call("13800138000").await;
+    app.post_ok("/api/call/answer", json!({ "path": incoming })).await;
+    assert_eq!(app.get_ok("/api/calls").await["calls"][0]["state"], "active");
+    let hung_up = app.post_ok("/api/call/hangup-all", json!({})).await;
+    assert!(hung_up.is_object());
+    assert!(app.get_ok("/api/calls").await["calls"].as_array().unwrap().is_empty());
+
+    assert!(wait_for(|| db.get_call_history(10, 0).map(|c| c.len() >= 2).unwrap_or(false)).await);
+    let history = app.get_ok("/api/call/history?limit=10").await;
+    let records = history["records"].as_array().unwrap();
+    let answered = records.iter().find(|r| r["phone_number"] == "13800138000").unwrap();
+    assert_eq!(answered["answered"], true);
+
+    let id = records[0]["id"].as_i64().unwrap();
+    app.delete_ok(&format!("/api/call/history/{}", id)).await;
+    app.post_ok("/api/call/history/clear", json!({})).await;
+
+    // 来电拦截：黑名单号码和隐藏号码被自动挂断，白名单优先
+    let body = app
+        .post("/api/call/filter", json!({ "enabled": true, "block_list": [{ "type": "regex", "pattern": "(" }] }))
+        .await;
+    assert_eq!(body["status"], "error");
+    let filter = app
+        .post_ok(
+            "/api/call/filter",
+            json!({
+                "enabled": true,
+                "block_list": [{ "type": "prefix", "pattern": " 400 " }, { "type": "withheld", "pattern": "x" }],
+                "allow_list": [{ "type": "exact", "pattern": "4001234567" }],
+            }),
+        )
+        .await;
+    assert_eq!(filter["mode"], "blocklist");
+    assert_eq!(filter["block_list"][0]["pattern"], "400");
+    assert_eq!(filter["block_list"][1]["pattern"], "");
+
+    app.mock.incoming_call("4008123123").await;
+    app.mock.incoming_call("withheld").await;
+    let allowed = app.mock.incoming_call("4001234567").await;
+    let mock = Arc::clone(&app.mock.state);
+    assert!(wait_for(|| mock.lock().unwrap().calls.len() == 1).await);
+    assert!(mock.lock().unwrap().calls.contains_key(&allowed));
+    let history = db.get_call_history(10, 0).unwrap();
+    assert_eq!(history.iter().filter(|c| c.direction == "blocked").count(), 2);
+    app.mock.remote_hangup(&allowed).await;
+    app.post_ok("/api/call/filter", json!({})).await;
+    app.post_ok("/api/call/history/clear", json!({})).await;
+
+    // 通话策略：拒接、自动接听和限时挂断，原因写入通话记录
+    let body = app
+        .post(
+            "/api/call/policy",
+            json!({ "enabled": true, "policies": [{ "name": "x", "weekdays": [8], "action": { "type": "reject" } }] }),
+        )
+        .await;
+    assert_eq!(body["status"], "error");
+    app.post_ok(
+        "/api/call/policy",
+        json!({
+            "enabled": true,
+            "policies": [
+                { "name": "noc", "numbers": [{ "type": "exact", "pattern": "13700137000" }],
+                  "action": { "type": "auto_answer", "delay_secs": 0 } },
+                { "name": "spam", "numbers": [{ "type": "prefix", "pattern": "10010" }], "action": { "type": "reject" } },
+                { "name": "limit", "action": { "type": "max_duration", "max_secs": 1 } },
+            ],
+        }),
+    )
+    .await;
+
+    app.mock.incoming_call("10010").await;
+    assert!(wait_for(|| mock.lock().unwrap().calls.is_empty()).await);
+    assert!(wait_for(|| db.get_call_history(10, 0).map(|c| c.len() == 1 && c[0].end_time.is_some()).unwrap_or(false)).await);
+    let rejected = &db.get_call_history(10, 0).unwrap()[0];
+    assert_eq!(rejected.direction, "missed");
+    assert_eq!(rejected.reason.as_deref(), Some("auto_reject: spam"));
+
+    let answered = app.mock.incoming_call("+8613700137000").await;
+    let is_active = |path: &str| {
+        mock.lock().unwrap().calls.get(path).and_then(|c| c.get("State").cloned()).map(|s| String::try_from(s).unwrap())
+            == Some("active".to_string())
+    };
+    assert!(wait_for(|| is_active(&answered)).await);
+    assert!(wait_for(|| mock.lock().unwrap().calls.is_empty()).await);
+    assert!(wait_for(|| db.get_call_history(10, 0).map(|c| c.len() == 2 && c[0].end_time.is_some()).unwrap_or(false)).await);
+    let limited = &db.get_call_history(10, 0).unwrap()[0];
+    assert!(limited.answered);
+    assert_eq!(limited.reason.as_deref(), Some("auto_answer: noc; max_duration: limit"));
+    assert_eq!(app.get_ok("/api/call/history").await["records"][0]["reason"], "auto_answer: noc; max_duration: limit");
+    app.post_ok("/api/call/policy", json!({})).await;
+    app.post_ok("/api/call/history/clear", json!({})).await;
+
+    // 通话通知：响铃时立即发送 Webhook，未接来电推送到短信推送服务
+    let (receiver, received) = capture_server().await;
+    let mut webhook = app.get_ok("/api/webhook/config").await;
+    webhook["enabled"] = json!(true);
+    webhook["url"] = json!(format!("{}/webhook", receiver));
+    webhook["call_ringing_template"] = json!(r#"{"event":"ringing","number":"{{phone_number}}"}"#);
+    webhook["call_template"] = json!(r#"{"event":"{{direction}}","number":"{{phone_number}}"}"#);
+    app.post_ok("/api/webhook/config", webhook).await;
+    let mut sms_push = app.get_ok("/api/sms-push/config").await;
+    assert_eq!(sms_push["call_events"], json!(["missed"]));
+    sms_push["enabled"] = json!(true);
+    sms_push["provider"] = json!("ntfy");
+    sms_push["server_url"] = json!(format!("{}/ntfy", receiver));
+    sms_push["topic"] = json!("calls");
+    app.post_ok("/api/sms-push/config", sms_push).await;
+
+    let ringing = app.mock.incoming_call("13900139000").await;
+    let has = |path: &str, needle: &str| received.lock().unwrap().iter().any(|(p, body)| p == path && body.contains(needle));
+    assert!(wait_for(|| has("/webhook", r#""event":"ringing""#)).await);
+    assert!(mock.lock().unwrap().calls.contains_key(&ringing), "ringing webhook is sent before the call ends");
+    assert!(!has("/ntfy/", "13900139000"), "ringing is not in the default push events");
+    app.mock.remote_hangup(&ringing).await;
+    assert!(wait_for(|| has("/webhook", r#""event":"missed""#)).await);
+    assert!(wait_for(|| has("/ntfy/", "未接来电 · 13900139000")).await);
+    app.post_ok("/api/webhook/config", json!({ "enabled": false, "url": "", "forward_sms": true, "forward_calls": true })).await;
+    app.post_ok("/api/sms-push/config", json!({ "enabled": false })).await;
+    app.post_ok("/api/call/history/clear", json!({})).await;
+
+    app.get_ok("/api/call/volume").await;
+    app.post_ok("/api/call/volume", json!({ "speaker_volume": 80, "muted": true })).await;
+    let muted: bool = app.mock.property("org.ofono.CallVolume", "Muted").unwrap().try_into().unwrap();
+    assert!(muted);
+
+    app.get_ok("/api/call/forwarding").await;
+    app.post_ok(
+        "/api/call/forwarding",
+        json!({ "forward_type": "noreply", "number": "10086", "timeout": 30 }),
+    )
+    .await;
+    let timeout: u16 = app
+        .mock
+        .property("org.ofono.CallForwarding", "VoiceNoReplyTimeout")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(timeout, 30);
+
+    assert_eq!(app.get_ok("/api/call/settings").await["voice_call_waiting"], "enabled");
+    app.post_ok("/api/call/settings", json!({ "property": "VoiceCallWaiting", "value": "disabled" }))
+        .await;
+    assert_eq!(app.get_ok("/api/call/settings").await["voice_call_waiting"], "disabled");
+}
+
+#[tokio::test]
+async fn supplementary_call_control() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let state_of = |calls: &serde_json::Value, path: &str| {
+        calls["calls"].as_array().unwrap().iter().find(|c| c["path"] == path).cloned().unwrap()
+    };
+
+    let first = app.mock.incoming_call("13800138000").await;
+    app.post_ok("/api/call/answer", json!({ "path": first })).await;
+    let second = app.mock.waiting_call("13900139000").await;
+    let calls = app.get_ok("/api/calls").await;
+    assert_eq!(state_of(&calls, &second)["direction"], "incoming");
+
+    // 保持并接听，然后交换
+    app.post_ok("/api/call/hold-and-answer", json!({})).await;
+    let calls = app.get_ok("/api/calls").await;
+    assert_eq!(state_of(&calls, &first)["held"], true);
+    assert_eq!(state_of(&calls, &second)["state"], "active");
+    app.post_ok("/api/call/swap", json!({})).await;
+    let calls = app.get_ok("/api/calls").await;
+    assert_eq!(state_of(&calls, &first)["state"], "active");
+    assert_eq!(state_of(&calls, &second)["held"], true);
+
+    // DTMF：规范化后发送，非法字符在调用前拒绝
+    let sent = app.post_ok("/api/call/tones", json!({ "tones": "1 2 3 # p a" })).await;
+    assert_eq!(sent["tones"], "123#pA");
+    let body = app.post("/api/call/tones", json!({ "tones": "12x" })).await;
+    assert_eq!(body["status"], "error");
+    assert_eq!(app.mock.state.lock().unwrap().sent_tones, vec!["123#pA"]);
+
+    // 多方通话与私聊
+    let conference = app.post_ok("/api/call/multiparty", json!({})).await;
+    assert_eq!(conference["calls"].as_array().unwrap().len(), 2);
+    let calls = app.get_ok("/api/calls").await;
+    assert!(calls["calls"].as_array().unwrap().iter().all(|c| c["multiparty"] == true && c["held"] == false));
+    let remaining = app.post_ok("/api/call/multiparty/private-chat", json!({ "path": second })).await;
+    assert!(remaining["calls"].as_array().unwrap().is_empty());
+    let body = app.post("/api/call/multiparty/hangup", json!({})).await;
+    assert_eq!(body["status"], "error");
+    app.post_ok("/api/call/multiparty", json!({})).await;
+    app.post_ok("/api/call/multiparty/hangup", json!({})).await;
+    assert!(app.get_ok("/api/calls").await["calls"].as_array().unwrap().is_empty());
+
+    // 挂断当前通话并接听等待中的来电
+    let active = app.mock.incoming_call("10086").await;
+    app.post_ok("/api/call/answer", json!({ "path": active })).await;
+    let waiting = app.mock.waiting_call("10010").await;
+    app.post_ok("/api/call/release-and-answer", json!({})).await;
+    let calls = app.get_ok("/api/calls").await;
+    assert_eq!(calls["calls"].as_array().unwrap().len(), 1);
+    assert_eq!(state_of(&calls, &waiting)["state"], "active");
+
+    // 转接来电
+    let incoming = app.mock.waiting_call("10000").await;
+    let body = app.post("/api/call/deflect", json!({ "path": incoming, "phone_number": " " })).await;
+    assert_eq!(body["status"], "error");
+    app.post_ok("/api/call/deflect", json!({ "path": incoming, "phone_number": "13700137000" })).await;
+    assert_eq!(app.mock.state.lock().unwrap().deflected, vec![(incoming, "13700137000".to_string())]);
+    let body = app.post("/api/call/deflect", json!({ "path": waiting, "phone_number": "13700137000" })).await;
+    assert_eq!(body["status"], "error");
+    app.post_ok("/api/call/hangup-all", json!({})).await;
+}
+
+#[tokio::test]
+async fn sms_threads_read_state_archive_and_search() {
+    let Some(app) = TestApp::start().await else { return };
+
+    // 同一号码的不同格式归入一个会话
+    let db = Arc::clone(&app.state.database);
+    let first = db.insert_sms("incoming", "+8613800138000", "您的验证码是 482913，5 分钟内有效", "received", None).unwrap();
+    db.insert_sms("outgoing", "13800138000", "收到，谢谢", "sent", None).unwrap();
+    db.insert_sms("incoming", "013800138000", "Meeting moved to 3pm", "received", None).unwrap();
+    db.insert_sms("incoming", "10086", "话费余额 12.5 元", "received", None).unwrap();
+
+    let threads = app.get_ok("/api/sms/threads").await;
+    let threads = threads.as_array().unwrap();
+    assert_eq!(threads.len(), 2);
+    let thread = threads.iter().find(|t| t["phone_number"] == "+8613800138000").unwrap();
+    let thread_id = thread["id"].as_i64().unwrap();
+    assert_eq!(thread["message_count"], 3);
+    assert_eq!(thread["unread_count"], 2);
+    assert_eq!(thread["last_message"]["content"], "Meeting moved to 3pm");
+    assert_eq!(app.get_ok("/api/sms/stats").await["unread"], 3);
+
+    let detail = app.get_ok(&format!("/api/sms/threads/{}", thread_id)).await;
+    assert_eq!(detail["messages"].as_array().unwrap().len(), 3);
+    assert_eq!(detail["messages"][1]["read"], true);
+
+    // 已读 / 未读
+    let thread = app.post_ok(&format!("/api/sms/threads/{}/read", thread_id), json!({})).await;
+    assert_eq!(thread["unread_count"], 0);
+    let message = app.post_ok(&format!("/api/sms/messages/{}/read", first), json!({ "read": false })).await;
+    assert_eq!(message["read"], false);
+    assert_eq!(app.get_ok("/api/sms/stats").await["unread"], 2);
+
+    // 全文搜索：中文子串、英文大小写、短关键词
+    let found = app.get_ok("/api/sms/search?q=%E9%AA%8C%E8%AF%81%E7%A0%81").await;
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["id"], first);
+    let found = app.get_ok("/api/sms/search?q=meeting").await;
+    assert_eq!(found[0]["content"], "Meeting moved to 3pm");
+    let found = app.get_ok("/api/sms/search?q=12").await;
+    assert_eq!(found[0]["phone_number"], "10086");
+    let found = app.get_ok(&format!("/api/sms/search?q=12&thread_id={}", thread_id)).await;
+    assert!(found.as_array().unwrap().is_empty());
+    let body = app.get("/api/sms/search?q=%20").await;
+    assert_eq!(body["status"], "error");
+
+    // 归档后收到新短信重新出现在收件箱
+    app.post_ok(&format!("/api/sms/threads/{}/archive", thread_id), json!({})).await;
+    assert_eq!(app.get_ok("/api/sms/threads").await.as_array().unwrap().len(), 1);
+    assert_eq!(app.get_ok("/api/sms/threads?archived=true").await[0]["id"], thread_id);
+    db.insert_sms("incoming", "13800138000", "Are you there?", "received", None).unwrap();
+    assert_eq!(app.get_ok("/api/sms/threads").await.as_array().unwrap().len(), 2);
+
+    // 删除单条短信和整个会话，搜索索引同步更新
+    app.delete_ok(&format!("/api/sms/messages/{}", first)).await;
+    assert!(app.get_ok("/api/sms/search?q=482913").await.as_array().unwrap().is_empty());
+    app.delete_ok(&format!("/api/sms/threads/{}", thread_id)).await;
+    let body = app.get(&format!("/api/sms/threads/{}", thread_id)).await;
+    assert_eq!(body["status"], "error");
+    assert_eq!(app.get_ok("/api/sms/stats").await["total"], 1);
+}
+
+#[tokio::test]
+async fn contacts_crud_import_export_and_name_resolution() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let body = app.post("/api/contacts", json!({ "name": " ", "phone_number": "10086" })).await;
+    assert_eq!(body["status"], "error");
+    let contact = app
+        .post_ok("/api/contacts", json!({ "name": "Zhang San", "phone_number": "+86 138 0013 8000" }))
+        .await;
+    let id = contact["id"].as_i64().unwrap();
+    assert_eq!(contact["source"], "manual");
+
+    // 本地格式的号码解析为联系人名称
+    let db = Arc::clone(&app.state.database);
+    db.insert_sms("incoming", "13800138000", "hi", "received", None).unwrap();
+    db.insert_sms("outgoing", "+8613800138000", "hello", "sent", None).unwrap();
+    db.insert_sms("incoming", "10010", "ad", "received", None).unwrap();
+    db.insert_call("incoming", "013800138000", true).unwrap();
+
+    let list = app.get_ok("/api/sms/list").await;
+    let names: Vec<_> = list.as_array().unwrap().iter().map(|m| m["contact_name"].clone()).collect();
+    assert_eq!(names.iter().filter(|n| **n == "Zhang San").count(), 2);
+    assert!(names.contains(&serde_json::Value::Null));
+    let conversation = app.get_ok("/api/sms/conversation?phone_number=%2B8613800138000").await;
+    assert_eq!(conversation.as_array().unwrap().len(), 2);
+    let history = app.get_ok("/api/call/history").await;
+    assert_eq!(history["records"][0]["contact_name"], "Zhang San");
+
+    app.put_ok(&format!("/api/contacts/{}", id), json!({ "name": "Boss", "phone_number": "13800138000" }))
+        .await;
+    assert_eq!(db.find_contact_name("+86-138-0013-8000").unwrap().as_deref(), Some("Boss"));
+
+    // SIM 电话本导入，重复导入时跳过
+    let summary = app.post_ok("/api/contacts/import/sim", json!({})).await;
+    assert_eq!(summary, json!({ "imported": 2, "skipped": 0 }));
+    let summary = app.post_ok("/api/contacts/import/sim", json!({})).await;
+    assert_eq!(summary, json!({ "imported": 0, "skipped": 2 }));
+
+    let csv = "name,phone_number,email\nLi Si,13700137000,ls@example.com\nBroken,abc\n";
+    let summary = app.post_ok("/api/contacts/import", json!({ "format": "csv", "data": csv })).await;
+    assert_eq!(summary, json!({ "imported": 1, "skipped": 1 }));
+    let vcard = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Wang Wu\r\nTEL:13600136000\r\nEND:VCARD\r\n";
+    app.post_ok("/api/contacts/import", json!({ "format": "vcard", "data": vcard })).await;
+    let body = app.post("/api/contacts/import", json!({ "format": "xml", "data": "" })).await;
+    assert_eq!(body["status"], "error");
+
+    let found = app.get_ok("/api/contacts?search=1370").await;
+    assert_eq!(found[0]["email"], "ls@example.com");
+    assert_eq!(app.get_ok("/api/contacts").await.as_array().unwrap().len(), 5);
+
+    let exported = app.get("/api/contacts/export?format=csv").await;
+    let exported = exported.as_str().unwrap();
+    assert!(exported.starts_with("name,phone_number,email,note"));
+    assert!(exported.contains("Li Si,13700137000,ls@example.com,"));
+    let exported = app.get("/api/contacts/export").await;
+    assert_eq!(exported.as_str().unwrap().matches("BEGIN:VCARD").count(), 5);
+
+    app.delete_ok(&format!("/api/contacts/{}", id)).await;
+    let body = app.get(&format!("/api/contacts/{}", id)).await;
+    assert_eq!(body["status"], "error");
+    assert_eq!(db.find_contact_name("13800138000").unwrap(), None);
+}
+
+#[tokio::test]
+async fn sim_pin_management_and_auto_entry() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let sim = app.get_ok("/api/sim").await;
+    assert_eq!(sim["retries"]["pin"], 3);
+    assert!(sim["locked_pins"].as_array().unwrap().is_empty());
+
+    let body = app.post("/api/sim/pin/lock", json!({ "pin": "12" })).await;
+    assert_eq!(body["status"], "error");
+    let sim = app.post_ok("/api/sim/pin/lock", json!({ "pin": "1234" })).await;
+    assert_eq!(sim["locked_pins"], json!(["pin"]));
+
+    // 错误的 PIN 会消耗次数，错误信息中带剩余次数
+    let body = app.post("/api/sim/pin/change", json!({ "old_pin": "0000", "new_pin": "4321" })).await;
+    assert_eq!(body["status"], "error");
+    assert!(body["message"].as_str().unwrap().contains("pin=2"));
+    let sim = app.post_ok("/api/sim/pin/change", json!({ "old_pin": "1234", "new_pin": "4321" })).await;
+    assert_eq!(sim["retries"]["pin"], 3);
+
+    let body = app.post("/api/sim/pin/config", json!({ "auto_enter": true })).await;
+    assert_eq!(body["status"], "error");
+    let config = app.post_ok("/api/sim/pin/config", json!({ "auto_enter": true, "pin": "4321" })).await;
+    assert_eq!(config, json!({ "auto_enter": true, "pin_set": true }));
+
+    // 重启后 SIM 等待 PIN：Watchdog 自动输入配置的 PIN
+    let conn = app.mock.client().await;
+    let mut rejected = None;
+    app.mock.require_sim_pin();
+    assert!(dbus::auto_enter_sim_pin(&conn, "4321", &mut rejected).await);
+    assert_eq!(app.get_ok("/api/sim").await["pin_required"], "none");
+    assert!(!dbus::auto_enter_sim_pin(&conn, "4321", &mut rejected).await);
+
+    // 被拒绝的 PIN 不再重试
+    app.mock.require_sim_pin();
+    assert!(dbus::auto_enter_sim_pin(&conn, "9999", &mut rejected).await);
+    assert!(!dbus::auto_enter_sim_pin(&conn, "9999", &mut rejected).await);
+    assert_eq!(app.get_ok("/api/sim").await["retries"]["pin"], 2);
+
+    // PIN 次数用完后需要 PUK，解锁后同步保存的 PIN
+    app.post("/api/sim/pin/enter", json!({ "pin": "0000" })).await;
+    assert!(!dbus::auto_enter_sim_pin(&conn, "4321", &mut rejected).await);
+    app.post("/api/sim/pin/enter", json!({ "pin": "0000" })).await;
+    assert_eq!(app.get_ok("/api/sim").await["pin_required"], "puk");
+    let body = app.post("/api/sim/pin/reset", json!({ "puk": "1234", "new_pin": "1111" })).await;
+    assert_eq!(body["status"], "error");
+    let sim = app
+        .post_ok("/api/sim/pin/reset", json!({ "puk": "12345678", "new_pin": "1111" }))
+        .await;
+    assert_eq!(sim["pin_required"], "none");
+    assert_eq!(app.state.config_manager.get_sim_pin().pin, "1111");
+
+    let sim = app.post_ok("/api/sim/pin/unlock", json!({ "pin": "1111" })).await;
+    assert!(sim["locked_pins"].as_array().unwrap().is_empty());
+    assert_eq!(app.get_ok("/api/sim/pin/config").await["pin_set"], true);
+}
+
+#[tokio::test]
+async fn ussd_session_and_history() {
+    let Some(app) = TestApp::start().await else { return };
+
+    assert_eq!(app.get_ok("/api/ussd/status").await["state"], "idle");
+    let body = app.post("/api/ussd/initiate", json!({ "command": "*100#; ls" })).await;
+    assert_eq!(body["status"], "error");
+
+    // 余额菜单 -> 重复发起被拒绝 -> 回复选项后会话结束
+    let menu = app.post_ok("/api/ussd/initiate", json!({ "command": "*100#" })).await;
+    assert_eq!(menu["state"], "user-response");
+    assert_eq!(menu["message"], "Balance 10.00\n1. Buy 1GB");
+    let body = app.post("/api/ussd/initiate", json!({ "command": "*101#" })).await;
+    assert_eq!(body["status"], "error");
+    let done = app.post_ok("/api/ussd/respond", json!({ "response": "1" })).await;
+    assert_eq!(done["state"], "idle");
+    assert_eq!(done["message"], "1GB pack purchased");
+    let body = app.post("/api/ussd/respond", json!({ "response": "1" })).await;
+    assert_eq!(body["status"], "error");
+
+    app.post_ok("/api/ussd/initiate", json!({ "command": "*100#" })).await;
+    assert_eq!(app.post_ok("/api/ussd/cancel", json!({})).await["state"], "idle");
+
+    let history = app.get_ok("/api/ussd/history?limit=10").await;
+    let records = history.as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1]["request"], "1");
+    assert_eq!(records[1]["state"], "idle");
+    assert_eq!(records[2]["request"], "*100#");
+    assert_eq!(records[2]["state"], "user-response");
+
+    // 网络主动下发的菜单写入记录
+    {
+        let conn = app.mock.client().await;
+        let state = app.state.clone();
+        tokio::spawn(async move {
+            let _ = ussd::start_ussd_listener(conn, state.database, state.events).await;
+        });
+    }
+    let db = Arc::clone(&app.state.database);
+    let mut listening = false;
+    for _ in 0..20 {
+        app.mock.ussd_request("Reply 1 to renew").await;
+        if wait_for(|| db.get_ussd_history(10, 0).map(|r| r.len() > 3).unwrap_or(false)).await {
+            listening = true;
+            break;
+        }
+    }
+    assert!(listening, "USSD listener did not record the network request");
+    let latest = &db.get_ussd_history(1, 0).unwrap()[0];
+    assert_eq!(latest.direction, "incoming");
+    assert_eq!(latest.state, "user-response");
+
+    app.post_ok("/api/ussd/history/clear", json!({})).await;
+    assert!(app.get_ok("/api/ussd/history").await.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn config_history_and_event_routes() {
+    let Some(app) = TestApp::start().await else { return };
+
+    let webhook = app.get_ok("/api/webhook/config").await;
+    app.post_ok("/api/webhook/config", webhook).await;
+    let test = app.post_ok("/api/webhook/test", json!({})).await;
+    assert_eq!(test["success"], false, "webhook URL is not configured");
+
+    let sms_push = app.get_ok("/api/sms-push/config").await;
+    app.post_ok("/api/sms-push/config", sms_push).await;
+    app.post("/api/sms-push/test", json!({})).await;
+
+    app.get_ok("/api/refresh/config").await;
+    app.post_ok("/api/refresh/config", json!({ "interval_ms": 3000 })).await;
+    app.post_ok("/api/refresh/heartbeat", json!({})).await;
+
+    let signal_config = app.get_ok("/api/history/signal/config").await;
+    app.post_ok("/api/history/signal/config", signal_config).await;
+    let history = app.get_ok("/api/history/signal?resolution=raw").await;
+    assert!(history["points"].as_array().unwrap().is_empty());
+    let body = app.get("/api/history/signal?resolution=fast").await;
+    assert_eq!(body["status"], "error");
+
+    let usage_config = app.get_ok("/api/data-usage/config").await;
+    app.post_ok("/api/data-usage/config", usage_config).await;
+    app.get_ok("/api/data-usage").await;
+    app.get_ok("/api/data-usage/history?period=month").await;
+
+    // SSE：浏览器先换取一次性票据，长期令牌不能出现在 URL 中
+    let response = app
+        .http
+        .get(app.url(&format!("/api/events?access_token={}", app.token)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    let ticket = app.post_ok("/api/auth/sse-ticket", json!({})).await;
+    assert_eq!(ticket["expires_in_secs"], 30);
+    let events_url = app.url(&format!("/api/events?ticket={}", ticket["ticket"].as_str().unwrap()));
+    let response = app.http.get(&events_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/event-stream"));
+    drop(response);
+    // 票据只能使用一次
+    assert_eq!(app.http.get(&events_url).send().await.unwrap().status(), 401);
+
+    // ofono 属性变化会推送到事件流
+    {
+        let conn = app.mock.client().await;
+        let events = Arc::clone(&app.state.events);
+        tokio::spawn(async move {
+            let _ = crate::events::start_property_listener(conn, events).await;
+        });
+    }
+    let mut receiver = app.state.events.subscribe();
+    let mut received = false;
+    for _ in 0..20 {
+        app.mock.network_property_changed("Strength", Value::U8(60)).await;
+        if let Ok(Ok(_)) = tokio::time::timeout(std::time::Duration::from_millis(200), receiver.recv()).await {
+            received = true;
+            break;
+        }
+    }
+    assert!(received, "property change was not published");
+}
+
+#[tokio::test]
+async fn webhook_endpoints_route_events() {
+    let Some(app) = TestApp::start().await else { return };
+    let (receiver, received) = capture_server().await;
+
+    let body = app
+        .post(
+            "/api/webhook/config",
+            json!({ "enabled": true, "url": "", "forward_sms": true, "forward_calls": true,
+                    "endpoints": [{ "name": "x", "url": format!("{}/x", receiver), "content_regex": "(" }] }),
+        )
+        .await;
+    assert_eq!(body["status"], "error");
+
+    app.post_ok(
+        "/api/webhook/config",
+        json!({
+            "enabled": true,
+            "url": format!("{}/default", receiver),
+            "forward_sms": true,
+            "forward_calls": true,
+            "sms_template": r#"{"from":"{{phone_number}}"}"#,
+            "endpoints": [
+                { "name": "finance", "url": format!(" {}/finance ", receiver), "events": ["sms"],
+                  "numbers": [{ "type": "prefix", "pattern": "955" }] },
+                { "name": "ops", "url": format!("{}/ops", receiver),
+                  "events": ["data_down", "signal_alert", "reboot", "ota"],
+                  "template": r#"{"event":"{{event}}","level":"{{level}}"}"# },
+                { "name": "outages", "url": format!("{}/outages", receiver), "content_regex": "故障|outage" },
+                { "name": "muted", "enabled": false, "url": format!("{}/muted", receiver) },
+                { "name": "broken", "url": "http://127.0.0.1:1/broken", "events": ["ota"] },
+            ],
+        }),
+    )
+    .await;
+    let config = app.get_ok("/api/webhook/config").await;
+    assert_eq!(config["endpoints"][0]["url"], format!("{}/finance", receiver));
+
+    let count = |path: &str, needle: &str| {
+        received.lock().unwrap().iter().filter(|(p, body)| p == path && body.contains(needle)).count()
+    };
+
+    // 设备通知只发送到订阅的目标
+    {
+        let webhook = Arc::clone(&app.state.webhook_sender);
+        let events = Arc::clone(&app.state.events);
+        tokio::spawn(crate::webhook::start_event_forwarder(webhook, events));
+    }
+    assert!(wait_for(|| count("/ops", r#""event":"reboot""#) == 1).await);
+
+    // 短信按号码和内容分发，未订阅或已禁用的目标不接收
+    let db = &app.state.database;
+    let bank = db.insert_sms("incoming", "95588", "工资到账 8000 元", "received", None).unwrap();
+    let bank = db.get_sms(bank).unwrap().unwrap();
+    app.state.webhook_sender.forward_sms(&bank).await.unwrap();
+    let outage = db.insert_sms("incoming", "10086", "基站故障维护通知", "received", None).unwrap();
+    let outage = db.get_sms(outage).unwrap().unwrap();
+    app.state.webhook_sender.forward_sms(&outage).await.unwrap();
+
+    assert_eq!(count("/default", r#""from":"95588""#), 1);
+    assert_eq!(count("/default", r#""from":"10086""#), 1);
+    assert_eq!(count("/finance", "95588"), 1);
+    assert_eq!(count("/finance", "10086"), 0);
+    assert_eq!(count("/outages", "10086"), 1);
+    assert_eq!(count("/outages", "95588"), 0);
+    assert_eq!(count("/muted", ""), 0);
+
+    app.state.events.publish(DeviceEvent::DataConnectionChanged { active: true, status: "connected".to_string() });
+    app.state.events.publish(DeviceEvent::DataConnectionChanged { active: false, status: "disconnected".to_string() });
+    app.state.events.publish(DeviceEvent::NetworkRegistrationChanged { property: "Status".to_string(), value: json!("searching") });
+    app.state.events.publish(DeviceEvent::NetworkRegistrationChanged { property: "Status".to_string(), value: json!("registered") });
+    assert!(wait_for(|| count("/ops", r#""event":"data_down""#) == 1).await);
+    assert!(wait_for(|| count("/ops", r#""level":"lost""#) == 1 && count("/ops", r#""level":"restored""#) == 1).await);
+    assert_eq!(count("/default", "data_down"), 0);
+
+    // 测试接口返回各目标的结果
+    let test = app.post_ok("/api/webhook/test", json!({})).await;
+    assert_eq!(test["success"], false);
+    let results = test["results"].as_array().unwrap();
+    assert_eq!(results.len(), 6);
+    assert!(results.iter().all(|r| r["success"] == (r["name"] != "broken")), "{}", test);
+    assert_eq!(count("/finance", "+8613800138000"), 1);
+}
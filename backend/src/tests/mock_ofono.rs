@@ -0,0 +1,1116 @@
+//! 测试用 ofono 模拟服务
+//!
+//! 启动一个私有的 dbus-daemon（session 配置），在上面以 `org.ofono` 名称导出
+//! `/ril_0` 上的 Modem、SimManager、NetworkRegistration、NetworkMonitor、RadioSettings、
+//! ConnectionManager、MessageManager、VoiceCallManager、SupplementaryServices、Phonebook 等接口，以及
+//! `/ril_0/contextN`、`/ril_0/voicecallNN` 对象。所有状态保存在 [`MockState`] 中，
+//! 测试可以预置 AT 应答、读取 AT 指令记录，或主动发出来电/短信信号。
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+use zbus::object_server::{ObjectServer, SignalEmitter};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{connection, fdo, interface, Connection};
+
+type Properties = HashMap<String, OwnedValue>;
+
+/// 把任意 D-Bus 值转换为 OwnedValue
+pub fn ov<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    value.into().try_into_owned().expect("value without fds")
+}
+
+fn props<const N: usize>(entries: [(&str, OwnedValue); N]) -> Properties {
+    entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+/// 模拟服务的全部状态
+pub struct MockState {
+    /// 各接口的属性，键为 ofono 接口名（/ril_0）或 context 路径
+    pub properties: HashMap<String, Properties>,
+    /// 预置的 AT 应答（完整指令 → 应答），未预置的指令返回 "OK"
+    pub at_replies: HashMap<String, String>,
+    /// 收到的 AT 指令（按顺序）
+    pub at_log: Vec<String>,
+    /// 通过 SendMessage 发出的短信 (号码, 内容)
+    pub sent_messages: Vec<(String, String)>,
+    /// 当前通话，键为通话对象路径
+    pub calls: BTreeMap<String, Properties>,
+    /// 通过 SendTones 发出的 DTMF 按键音
+    pub sent_tones: Vec<String>,
+    /// 通过 Deflect 转接的来电 (通话路径, 目标号码)
+    pub deflected: Vec<(String, String)>,
+    /// SIM 卡的 PIN 和 PUK
+    pub sim_pin: String,
+    pub sim_puk: String,
+    next_call_id: u32,
+}
+
+/// 默认 AT 应答，覆盖频段锁定、小区锁定、小区信息、QoS 和 SIM 卡槽查询
+fn default_at_replies() -> HashMap<String, String> {
+    // LTE 主小区: [0] band, [1] arfcn, [2] pci, [3] rsrp, [4] rsrq, [33] sinr（×100）
+    let lte_primary = (0..34)
+        .map(|i| match i {
+            0 => "3",
+            1 => "1650",
+            2 => "123",
+            3 => "-9550",
+            4 => "-1050",
+            33 => "1500",
+            _ => "0",
+        })
+        .collect::<Vec<_>>()
+        .join("-");
+
+    [
+        ("AT+SPLBAND=0", "+SPLBAND: 0,320,0,149,0\r\nOK".to_string()),
+        ("AT+SPLBAND=3", "+SPLBAND: 1,0,256,0\r\nOK".to_string()),
+        ("AT+SPFORCEFRQ=16,3", "+SPFORCEFRQ: 16,3\r\nOK".to_string()),
+        ("AT+SPFORCEFRQ=12,3", "+SPFORCEFRQ: 12,3,1650,123\r\nOK".to_string()),
+        ("AT+CGEQOSRDP", "+CGEQOSRDP: 11,9,0,0,0,0,300000,100000\r\nOK".to_string()),
+        ("AT+SPCONFIGSIMSLOT?", "+SPCONFIGSIMSLOT: 66051\r\nOK".to_string()),
+        ("AT+SPENGMD=0,6,0", format!("{}\r\nOK", lte_primary)),
+        ("AT+CGSN", "861234567890123\r\nOK".to_string()),
+    ]
+    .into_iter()
+    .map(|(cmd, reply)| (cmd.to_string(), reply))
+    .collect()
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "org.ofono.Modem".to_string(),
+            props([
+                ("Powered", ov(true)),
+                ("Online", ov(true)),
+                ("Manufacturer", ov("UNISOC")),
+                ("Model", ov("UDX710")),
+                ("Revision", ov("MOCK_1.0")),
+                ("Serial", ov("861234567890123")),
+            ]),
+        );
+        properties.insert(
+            "org.ofono.SimManager".to_string(),
+            props([
+                ("Present", ov(true)),
+                ("CardIdentifier", ov("89860012345678901234")),
+                ("SubscriberIdentity", ov("460001234567890")),
+                ("SubscriberNumbers", ov(vec!["+8613800138000"])),
+                ("MobileCountryCode", ov("460")),
+                ("MobileNetworkCode", ov("00")),
+                ("PinRequired", ov("none")),
+                ("LockedPins", ov(Vec::<String>::new())),
+                ("Retries", ov(HashMap::from([("pin".to_string(), 3u8), ("puk".to_string(), 10u8)]))),
+                ("PreferredLanguages", ov(vec!["zh"])),
+            ]),
+        );
+        properties.insert(
+            "org.ofono.NetworkRegistration".to_string(),
+            props([
+                ("Status", ov("registered")),
+                ("Name", ov("CHINA MOBILE")),
+                ("Strength", ov(80u8)),
+                ("MobileCountryCode", ov("460")),
+                ("MobileNetworkCode", ov("00")),
+                ("Technology", ov("lte")),
+            ]),
+        );
+        properties.insert(
+            "org.ofono.RadioSettings".to_string(),
+            props([("TechnologyPreference", ov("NR 5G/LTE auto"))]),
+        );
+        properties.insert(
+            "org.ofono.ConnectionManager".to_string(),
+            props([("Attached", ov(true)), ("RoamingAllowed", ov(false)), ("Powered", ov(true))]),
+        );
+        properties.insert(
+            "org.ofono.MessageManager".to_string(),
+            props([("ServiceCenterAddress", ov("+8613800100500")), ("UseDeliveryReports", ov(false))]),
+        );
+        properties.insert(
+            "org.ofono.IpMultimediaSystem".to_string(),
+            props([("Registered", ov(true)), ("VoiceCapable", ov(true)), ("SmsCapable", ov(true))]),
+        );
+        properties.insert(
+            "org.ofono.CallVolume".to_string(),
+            props([("SpeakerVolume", ov(50u8)), ("MicrophoneVolume", ov(50u8)), ("Muted", ov(false))]),
+        );
+        properties.insert(
+            "org.ofono.MessageWaiting".to_string(),
+            props([("VoicemailWaiting", ov(false)), ("VoicemailMessageCount", ov(0u8)), ("VoicemailMailboxNumber", ov(""))]),
+        );
+        properties.insert(
+            "org.ofono.CallForwarding".to_string(),
+            props([
+                ("VoiceUnconditional", ov("")),
+                ("VoiceBusy", ov("")),
+                ("VoiceNoReply", ov("")),
+                ("VoiceNoReplyTimeout", ov(20u16)),
+                ("VoiceNotReachable", ov("")),
+            ]),
+        );
+        properties.insert(
+            "org.ofono.CallSettings".to_string(),
+            props([
+                ("CallingLinePresentation", ov("enabled")),
+                ("HideCallerId", ov("default")),
+                ("VoiceCallWaiting", ov("enabled")),
+            ]),
+        );
+        properties.insert(
+            "org.ofono.SupplementaryServices".to_string(),
+            props([("State", ov("idle"))]),
+        );
+        properties.insert(
+            "/ril_0/context1".to_string(),
+            props([
+                ("Name", ov("IMS")),
+                ("Type", ov("ims")),
+                ("Active", ov(false)),
+                ("AccessPointName", ov("ims")),
+                ("Protocol", ov("ipv4v6")),
+            ]),
+        );
+        properties.insert(
+            "/ril_0/context2".to_string(),
+            props([
+                ("Name", ov("Internet")),
+                ("Type", ov("internet")),
+                ("Active", ov(true)),
+                ("AccessPointName", ov("cmnet")),
+                ("Protocol", ov("ip")),
+                ("Username", ov("")),
+                ("Password", ov("")),
+                ("AuthenticationMethod", ov("chap")),
+                ("Settings", ov(HashMap::from([("Interface", ov("sipa_eth0"))]))),
+            ]),
+        );
+
+        Self {
+            properties,
+            at_replies: default_at_replies(),
+            at_log: Vec::new(),
+            sent_messages: Vec::new(),
+            calls: BTreeMap::new(),
+            sent_tones: Vec::new(),
+            deflected: Vec::new(),
+            sim_pin: "1234".to_string(),
+            sim_puk: "12345678".to_string(),
+            next_call_id: 1,
+        }
+    }
+}
+
+type SharedState = Arc<Mutex<MockState>>;
+
+fn get_properties(state: &SharedState, key: &str) -> Properties {
+    state.lock().unwrap().properties.get(key).cloned().unwrap_or_default()
+}
+
+fn set_property(state: &SharedState, key: &str, name: &str, value: &OwnedValue) -> fdo::Result<()> {
+    let mut state = state.lock().unwrap();
+    let properties = state
+        .properties
+        .get_mut(key)
+        .ok_or_else(|| fdo::Error::UnknownObject(key.to_string()))?;
+    if !properties.contains_key(name) {
+        return Err(fdo::Error::InvalidArgs(format!("Unknown property {}", name)));
+    }
+    properties.insert(name.to_string(), value.clone());
+    Ok(())
+}
+
+/// 只有 GetProperties / SetProperty / PropertyChanged 的 ofono 接口
+macro_rules! property_interface {
+    ($ty:ident, $name:literal) => {
+        struct $ty {
+            state: SharedState,
+        }
+
+        #[interface(name = $name)]
+        impl $ty {
+            fn get_properties(&self) -> Properties {
+                get_properties(&self.state, $name)
+            }
+
+            async fn set_property(
+                &self,
+                name: String,
+                value: OwnedValue,
+                #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+            ) -> fdo::Result<()> {
+                set_property(&self.state, $name, &name, &value)?;
+                let _ = Self::property_changed(&emitter, &name, &value).await;
+                Ok(())
+            }
+
+            #[zbus(signal)]
+            async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+        }
+    };
+}
+
+property_interface!(MockRadioSettings, "org.ofono.RadioSettings");
+property_interface!(MockIms, "org.ofono.IpMultimediaSystem");
+property_interface!(MockCallVolume, "org.ofono.CallVolume");
+property_interface!(MockMessageWaiting, "org.ofono.MessageWaiting");
+property_interface!(MockCallForwarding, "org.ofono.CallForwarding");
+property_interface!(MockCallSettings, "org.ofono.CallSettings");
+
+/// SimManager 模拟：校验 PIN/PUK 并维护剩余次数，PIN 次数用完后要求 PUK
+struct MockSimManager {
+    state: SharedState,
+}
+
+impl MockSimManager {
+    fn update(&self, f: impl FnOnce(&mut MockState)) {
+        f(&mut self.state.lock().unwrap())
+    }
+
+    fn set(state: &mut MockState, name: &str, value: OwnedValue) {
+        state.properties.get_mut("org.ofono.SimManager").unwrap().insert(name.to_string(), value);
+    }
+
+    fn sim_property<T: TryFrom<OwnedValue>>(state: &MockState, name: &str) -> Option<T> {
+        state.properties["org.ofono.SimManager"].get(name).cloned()?.try_into().ok()
+    }
+
+    /// 校验 PIN 或 PUK，错误时减少剩余次数
+    fn check(&self, code_type: &str, code: &str) -> fdo::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let (expected, full) = if code_type == "puk" {
+            (state.sim_puk.clone(), 10u8)
+        } else {
+            (state.sim_pin.clone(), 3u8)
+        };
+        let mut retries: HashMap<String, u8> = Self::sim_property(&state, "Retries").unwrap_or_default();
+        let left = retries.entry(code_type.to_string()).or_insert(full);
+        let result = if code == expected {
+            *left = full;
+            Ok(())
+        } else {
+            *left = left.saturating_sub(1);
+            if code_type == "pin" && *left == 0 {
+                Self::set(&mut state, "PinRequired", ov("puk"));
+            }
+            Err(fdo::Error::Failed("Operation failed".to_string()))
+        };
+        Self::set(&mut state, "Retries", ov(retries));
+        result
+    }
+}
+
+#[interface(name = "org.ofono.SimManager")]
+impl MockSimManager {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.SimManager")
+    }
+
+    fn enter_pin(&self, pin_type: String, pin: String) -> fdo::Result<()> {
+        let required: String =
+            Self::sim_property(&self.state.lock().unwrap(), "PinRequired").unwrap_or_default();
+        if required != pin_type {
+            return Err(fdo::Error::Failed("Operation not permitted".to_string()));
+        }
+        self.check(&pin_type, &pin)?;
+        self.update(|state| Self::set(state, "PinRequired", ov("none")));
+        Ok(())
+    }
+
+    fn reset_pin(&self, puk_type: String, puk: String, new_pin: String) -> fdo::Result<()> {
+        if puk_type != "puk" {
+            return Err(fdo::Error::InvalidArgs(format!("Unsupported PUK type {}", puk_type)));
+        }
+        self.check("puk", &puk)?;
+        self.update(|state| {
+            state.sim_pin = new_pin;
+            Self::set(state, "PinRequired", ov("none"));
+            let mut retries: HashMap<String, u8> = Self::sim_property(state, "Retries").unwrap_or_default();
+            retries.insert("pin".to_string(), 3);
+            Self::set(state, "Retries", ov(retries));
+        });
+        Ok(())
+    }
+
+    fn change_pin(&self, pin_type: String, old_pin: String, new_pin: String) -> fdo::Result<()> {
+        self.check(&pin_type, &old_pin)?;
+        self.update(|state| state.sim_pin = new_pin);
+        Ok(())
+    }
+
+    fn lock_pin(&self, pin_type: String, pin: String) -> fdo::Result<()> {
+        self.check(&pin_type, &pin)?;
+        self.update(|state| {
+            let mut locked: Vec<String> = Self::sim_property(state, "LockedPins").unwrap_or_default();
+            if !locked.contains(&pin_type) {
+                locked.push(pin_type);
+            }
+            Self::set(state, "LockedPins", ov(locked));
+        });
+        Ok(())
+    }
+
+    fn unlock_pin(&self, pin_type: String, pin: String) -> fdo::Result<()> {
+        self.check(&pin_type, &pin)?;
+        self.update(|state| {
+            let mut locked: Vec<String> = Self::sim_property(state, "LockedPins").unwrap_or_default();
+            locked.retain(|t| *t != pin_type);
+            Self::set(state, "LockedPins", ov(locked));
+        });
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+struct MockModem {
+    state: SharedState,
+}
+
+#[interface(name = "org.ofono.Modem")]
+impl MockModem {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.Modem")
+    }
+
+    async fn set_property(
+        &self,
+        name: String,
+        value: OwnedValue,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        set_property(&self.state, "org.ofono.Modem", &name, &value)?;
+        let _ = Self::property_changed(&emitter, &name, &value).await;
+        Ok(())
+    }
+
+    /// 厂商扩展：透传 AT 指令
+    fn send_atcmd(&self, cmd: String) -> String {
+        let mut state = self.state.lock().unwrap();
+        state.at_log.push(cmd.clone());
+        state.at_replies.get(&cmd).cloned().unwrap_or_else(|| "OK".to_string())
+    }
+
+    fn get_imeisv(&self) -> Properties {
+        props([("SoftwareVersionNumber", ov("01"))])
+    }
+
+    #[zbus(name = "GetNITZ")]
+    fn get_nitz(&self) -> String {
+        "2025/12/08,13:03:48+32".to_string()
+    }
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+struct MockNetworkRegistration {
+    state: SharedState,
+}
+
+impl MockNetworkRegistration {
+    fn operators(&self) -> Vec<(OwnedObjectPath, Properties)> {
+        let path = OwnedObjectPath::try_from("/ril_0/operator/46000").unwrap();
+        vec![(
+            path,
+            props([
+                ("Name", ov("CHINA MOBILE")),
+                ("Status", ov("current")),
+                ("MobileCountryCode", ov("460")),
+                ("MobileNetworkCode", ov("00")),
+                ("Technologies", ov(vec!["lte", "nr"])),
+            ]),
+        )]
+    }
+}
+
+#[interface(name = "org.ofono.NetworkRegistration")]
+impl MockNetworkRegistration {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.NetworkRegistration")
+    }
+
+    fn get_signal_strength(&self) -> Properties {
+        props([("Strength", ov(-95i32))])
+    }
+
+    fn get_operators(&self) -> Vec<(OwnedObjectPath, Properties)> {
+        self.operators()
+    }
+
+    fn scan(&self) -> Vec<(OwnedObjectPath, Properties)> {
+        self.operators()
+    }
+
+    fn register(&self) {}
+
+    fn register_manually(&self, mccmnc: String, _technology: String) -> fdo::Result<()> {
+        if mccmnc.len() < 5 {
+            return Err(fdo::Error::InvalidArgs(format!("Invalid MCCMNC {}", mccmnc)));
+        }
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    pub async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+struct MockNetworkMonitor;
+
+#[interface(name = "org.ofono.NetworkMonitor")]
+impl MockNetworkMonitor {
+    fn get_serving_cell_information(&self) -> Properties {
+        props([
+            ("Technology", ov("lte")),
+            ("CellId", ov(0x1A2B3Cu32)),
+            ("TrackingAreaCode", ov(100u32)),
+        ])
+    }
+}
+
+struct MockConnectionManager {
+    state: SharedState,
+}
+
+#[interface(name = "org.ofono.ConnectionManager")]
+impl MockConnectionManager {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.ConnectionManager")
+    }
+
+    async fn set_property(
+        &self,
+        name: String,
+        value: OwnedValue,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        set_property(&self.state, "org.ofono.ConnectionManager", &name, &value)?;
+        let _ = Self::property_changed(&emitter, &name, &value).await;
+        Ok(())
+    }
+
+    fn get_contexts(&self) -> Vec<(OwnedObjectPath, Properties)> {
+        let state = self.state.lock().unwrap();
+        let mut contexts: Vec<_> = state
+            .properties
+            .iter()
+            .filter(|(key, _)| key.starts_with("/ril_0/context"))
+            .map(|(key, props)| (OwnedObjectPath::try_from(key.as_str()).unwrap(), props.clone()))
+            .collect();
+        contexts.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        contexts
+    }
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+struct MockConnectionContext {
+    state: SharedState,
+    path: String,
+}
+
+#[interface(name = "org.ofono.ConnectionContext")]
+impl MockConnectionContext {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, &self.path)
+    }
+
+    async fn set_property(
+        &self,
+        name: String,
+        value: OwnedValue,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        set_property(&self.state, &self.path, &name, &value)?;
+        let _ = Self::property_changed(&emitter, &name, &value).await;
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+struct MockMessageManager {
+    state: SharedState,
+}
+
+#[interface(name = "org.ofono.MessageManager")]
+impl MockMessageManager {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.MessageManager")
+    }
+
+    async fn set_property(
+        &self,
+        name: String,
+        value: OwnedValue,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        set_property(&self.state, "org.ofono.MessageManager", &name, &value)?;
+        let _ = Self::property_changed(&emitter, &name, &value).await;
+        Ok(())
+    }
+
+    fn send_message(&self, to: String, text: String) -> OwnedObjectPath {
+        let mut state = self.state.lock().unwrap();
+        state.sent_messages.push((to, text));
+        OwnedObjectPath::try_from(format!("/ril_0/message_{:02}", state.sent_messages.len())).unwrap()
+    }
+
+    #[zbus(signal)]
+    pub async fn incoming_message(emitter: &SignalEmitter<'_>, message: &str, info: Properties) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+/// `/ril_0/message_NN` 对象，只用于发出 State 变化信号
+struct MockMessage;
+
+#[interface(name = "org.ofono.Message")]
+impl MockMessage {
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+/// SIM 电话本模拟，返回两条 vCard
+struct MockPhonebook;
+
+#[interface(name = "org.ofono.Phonebook")]
+impl MockPhonebook {
+    fn import(&self) -> String {
+        "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Mom\r\nTEL;TYPE=VOICE:+8613900139000\r\nEND:VCARD\r\n\
+         BEGIN:VCARD\r\nVERSION:3.0\r\nFN:China Mobile\r\nTEL;TYPE=VOICE:10086\r\nEND:VCARD\r\n"
+            .to_string()
+    }
+}
+
+/// USSD 模拟：`*100#` 返回余额菜单并等待回复，回复 `1` 后会话结束，其他指令直接返回通知
+struct MockSupplementaryServices {
+    state: SharedState,
+}
+
+impl MockSupplementaryServices {
+    fn set_state(&self, value: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(properties) = state.properties.get_mut("org.ofono.SupplementaryServices") {
+            properties.insert("State".to_string(), ov(value));
+        }
+    }
+
+    fn current_state(&self) -> String {
+        get_properties(&self.state, "org.ofono.SupplementaryServices")
+            .get("State")
+            .and_then(|v| String::try_from(v.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[interface(name = "org.ofono.SupplementaryServices")]
+impl MockSupplementaryServices {
+    fn get_properties(&self) -> Properties {
+        get_properties(&self.state, "org.ofono.SupplementaryServices")
+    }
+
+    fn initiate(&self, command: String) -> fdo::Result<(String, OwnedValue)> {
+        if self.current_state() != "idle" {
+            return Err(fdo::Error::Failed("Operation already in progress".to_string()));
+        }
+        if command == "*100#" {
+            self.set_state("user-response");
+            Ok(("USSD".to_string(), ov("Balance 10.00\n1. Buy 1GB")))
+        } else {
+            Ok(("USSD".to_string(), ov(format!("Unknown code {}", command))))
+        }
+    }
+
+    fn respond(&self, reply: String) -> fdo::Result<String> {
+        if self.current_state() != "user-response" {
+            return Err(fdo::Error::Failed("Operation not permitted".to_string()));
+        }
+        self.set_state("idle");
+        if reply == "1" {
+            Ok("1GB pack purchased".to_string())
+        } else {
+            Ok("Invalid option".to_string())
+        }
+    }
+
+    fn cancel(&self) {
+        self.set_state("idle");
+    }
+
+    #[zbus(signal)]
+    pub async fn request_received(emitter: &SignalEmitter<'_>, message: &str) -> zbus::Result<()>;
+}
+
+struct MockVoiceCallManager {
+    state: SharedState,
+}
+
+impl MockVoiceCallManager {
+    /// 创建通话对象并发出 CallAdded 信号
+    async fn add_call(
+        state: &SharedState,
+        server: &ObjectServer,
+        conn: &Connection,
+        number: &str,
+        call_state: &str,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let (path, properties) = {
+            let mut state = state.lock().unwrap();
+            let path = format!("/ril_0/voicecall{:02}", state.next_call_id);
+            state.next_call_id += 1;
+            let properties = props([
+                ("LineIdentification", ov(number)),
+                ("State", ov(call_state)),
+                ("StartTime", ov(chrono::Utc::now().to_rfc3339())),
+                ("Multiparty", ov(false)),
+            ]);
+            state.calls.insert(path.clone(), properties.clone());
+            (path, properties)
+        };
+
+        server
+            .at(
+                path.as_str(),
+                MockVoiceCall {
+                    state: Arc::clone(state),
+                    path: path.clone(),
+                },
+            )
+            .await?;
+
+        let object_path = OwnedObjectPath::try_from(path).unwrap();
+        let emitter = SignalEmitter::new(conn, "/ril_0")?;
+        Self::call_added(&emitter, object_path.as_ref(), properties).await?;
+        Ok(object_path)
+    }
+
+    /// 指定状态的通话路径
+    fn calls_in(state: &MockState, states: &[&str]) -> Vec<String> {
+        state
+            .calls
+            .iter()
+            .filter(|(_, props)| {
+                props
+                    .get("State")
+                    .and_then(|v| String::try_from(v.clone()).ok())
+                    .is_some_and(|s| states.contains(&s.as_str()))
+            })
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    fn set_call(state: &mut MockState, path: &str, name: &str, value: OwnedValue) {
+        if let Some(call) = state.calls.get_mut(path) {
+            call.insert(name.to_string(), value);
+        }
+    }
+
+    fn is_multiparty(state: &MockState, path: &str) -> bool {
+        state.calls[path].get("Multiparty").and_then(|v| bool::try_from(v).ok()).unwrap_or(false)
+    }
+
+    /// 删除通话对象并发出 CallRemoved 信号
+    async fn remove_call(state: &SharedState, server: &ObjectServer, conn: &Connection, path: &str) -> fdo::Result<()> {
+        if state.lock().unwrap().calls.remove(path).is_none() {
+            return Err(fdo::Error::UnknownObject(path.to_string()));
+        }
+        server.remove::<MockVoiceCall, _>(path).await?;
+        let emitter = SignalEmitter::new(conn, "/ril_0")?;
+        Self::call_removed(&emitter, ObjectPath::try_from(path).unwrap()).await?;
+        Ok(())
+    }
+}
+
+#[interface(name = "org.ofono.VoiceCallManager")]
+impl MockVoiceCallManager {
+    fn get_calls(&self) -> Vec<(OwnedObjectPath, Properties)> {
+        let state = self.state.lock().unwrap();
+        state
+            .calls
+            .iter()
+            .map(|(path, props)| (OwnedObjectPath::try_from(path.as_str()).unwrap(), props.clone()))
+            .collect()
+    }
+
+    async fn dial(
+        &self,
+        number: String,
+        _hide_callerid: String,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<OwnedObjectPath> {
+        if number.is_empty() {
+            return Err(fdo::Error::InvalidArgs("Empty number".to_string()));
+        }
+        Self::add_call(&self.state, server, conn, &number, "dialing").await
+    }
+
+    async fn hangup_all(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<()> {
+        let paths: Vec<String> = self.state.lock().unwrap().calls.keys().cloned().collect();
+        for path in paths {
+            Self::remove_call(&self.state, server, conn, &path).await?;
+        }
+        Ok(())
+    }
+
+    fn swap_calls(&self) -> fdo::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let held = Self::calls_in(&state, &["held"]);
+        if held.is_empty() {
+            return Err(fdo::Error::Failed("No held call".to_string()));
+        }
+        for path in Self::calls_in(&state, &["active"]) {
+            Self::set_call(&mut state, &path, "State", ov("held"));
+        }
+        for path in held {
+            Self::set_call(&mut state, &path, "State", ov("active"));
+        }
+        Ok(())
+    }
+
+    fn hold_and_answer(&self) -> fdo::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let waiting = Self::calls_in(&state, &["waiting"]);
+        if waiting.is_empty() {
+            return Err(fdo::Error::Failed("No waiting call".to_string()));
+        }
+        for path in Self::calls_in(&state, &["active"]) {
+            Self::set_call(&mut state, &path, "State", ov("held"));
+        }
+        for path in waiting {
+            Self::set_call(&mut state, &path, "State", ov("active"));
+        }
+        Ok(())
+    }
+
+    async fn release_and_answer(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<()> {
+        let (active, waiting) = {
+            let state = self.state.lock().unwrap();
+            (Self::calls_in(&state, &["active"]), Self::calls_in(&state, &["waiting"]))
+        };
+        if active.is_empty() && waiting.is_empty() {
+            return Err(fdo::Error::Failed("No active or waiting call".to_string()));
+        }
+        for path in active {
+            Self::remove_call(&self.state, server, conn, &path).await?;
+        }
+        let mut state = self.state.lock().unwrap();
+        let next = if waiting.is_empty() { Self::calls_in(&state, &["held"]) } else { waiting };
+        for path in next {
+            Self::set_call(&mut state, &path, "State", ov("active"));
+        }
+        Ok(())
+    }
+
+    fn create_multiparty(&self) -> fdo::Result<Vec<OwnedObjectPath>> {
+        let mut state = self.state.lock().unwrap();
+        if Self::calls_in(&state, &["active"]).is_empty() || Self::calls_in(&state, &["held"]).is_empty() {
+            return Err(fdo::Error::Failed("Need an active and a held call".to_string()));
+        }
+        let members = Self::calls_in(&state, &["active", "held"]);
+        for path in &members {
+            Self::set_call(&mut state, path, "State", ov("active"));
+            Self::set_call(&mut state, path, "Multiparty", ov(true));
+        }
+        Ok(members.into_iter().map(|p| OwnedObjectPath::try_from(p).unwrap()).collect())
+    }
+
+    fn private_chat(&self, call: ObjectPath<'_>) -> fdo::Result<Vec<OwnedObjectPath>> {
+        let mut state = self.state.lock().unwrap();
+        let path = call.to_string();
+        if !state.calls.contains_key(&path) || !Self::is_multiparty(&state, &path) {
+            return Err(fdo::Error::InvalidArgs(format!("Not a multiparty call: {}", path)));
+        }
+        Self::set_call(&mut state, &path, "Multiparty", ov(false));
+        Self::set_call(&mut state, &path, "State", ov("active"));
+        let mut members: Vec<String> =
+            state.calls.keys().filter(|p| Self::is_multiparty(&state, p)).cloned().collect();
+        for member in &members {
+            Self::set_call(&mut state, member, "State", ov("held"));
+        }
+        if members.len() == 1 {
+            Self::set_call(&mut state, &members[0], "Multiparty", ov(false));
+            members.clear();
+        }
+        Ok(members.into_iter().map(|p| OwnedObjectPath::try_from(p).unwrap()).collect())
+    }
+
+    async fn hangup_multiparty(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<()> {
+        let members: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            state.calls.keys().filter(|p| Self::is_multiparty(&state, p)).cloned().collect()
+        };
+        if members.is_empty() {
+            return Err(fdo::Error::Failed("No multiparty call".to_string()));
+        }
+        for path in members {
+            Self::remove_call(&self.state, server, conn, &path).await?;
+        }
+        Ok(())
+    }
+
+    fn send_tones(&self, tones: String) -> fdo::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if Self::calls_in(&state, &["active"]).is_empty() {
+            return Err(fdo::Error::Failed("No active call".to_string()));
+        }
+        state.sent_tones.push(tones);
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn call_added(emitter: &SignalEmitter<'_>, path: ObjectPath<'_>, properties: Properties) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn call_removed(emitter: &SignalEmitter<'_>, path: ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+struct MockVoiceCall {
+    state: SharedState,
+    path: String,
+}
+
+#[interface(name = "org.ofono.VoiceCall")]
+impl MockVoiceCall {
+    fn get_properties(&self) -> Properties {
+        self.state.lock().unwrap().calls.get(&self.path).cloned().unwrap_or_default()
+    }
+
+    async fn answer(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let call = state
+                .calls
+                .get_mut(&self.path)
+                .ok_or_else(|| fdo::Error::UnknownObject(self.path.clone()))?;
+            if call.get("State").and_then(|v| String::try_from(v.clone()).ok()).as_deref() != Some("incoming") {
+                return Err(fdo::Error::Failed("Call is not incoming".to_string()));
+            }
+            call.insert("State".to_string(), ov("active"));
+        }
+        let _ = Self::property_changed(&emitter, "State", &Value::from("active")).await;
+        Ok(())
+    }
+
+    async fn hangup(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<()> {
+        MockVoiceCallManager::remove_call(&self.state, server, conn, &self.path).await
+    }
+
+    async fn deflect(
+        &self,
+        number: String,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] conn: &Connection,
+    ) -> fdo::Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if !MockVoiceCallManager::calls_in(&state, &["incoming", "waiting"]).contains(&self.path) {
+                return Err(fdo::Error::Failed("Call is not incoming".to_string()));
+            }
+            state.deflected.push((self.path.clone(), number));
+        }
+        MockVoiceCallManager::remove_call(&self.state, server, conn, &self.path).await
+    }
+
+    #[zbus(signal)]
+    async fn property_changed(emitter: &SignalEmitter<'_>, name: &str, value: &Value<'_>) -> zbus::Result<()>;
+}
+
+/// 私有总线上运行的 ofono 模拟服务
+pub struct MockOfono {
+    daemon: Child,
+    address: String,
+    server: Connection,
+    pub state: SharedState,
+}
+
+impl MockOfono {
+    /// 启动私有 dbus-daemon 并导出模拟对象
+    ///
+    /// 系统中没有 dbus-daemon 时返回 None，调用方应跳过测试
+    pub async fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1", "--address=unix:tmpdir=/tmp"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+        let address = address.trim().to_string();
+
+        let state: SharedState = Arc::new(Mutex::new(MockState::default()));
+        let shared = || Arc::clone(&state);
+
+        let server = connection::Builder::address(address.as_str())
+            .and_then(|b| b.name("org.ofono"))
+            .and_then(|b| b.serve_at("/ril_0", MockModem { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockSimManager { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockNetworkRegistration { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockNetworkMonitor))
+            .and_then(|b| b.serve_at("/ril_0", MockRadioSettings { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockConnectionManager { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockMessageManager { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockVoiceCallManager { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockIms { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockCallVolume { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockMessageWaiting { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockCallForwarding { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockCallSettings { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockSupplementaryServices { state: shared() }))
+            .and_then(|b| b.serve_at("/ril_0", MockPhonebook))
+            .and_then(|b| {
+                b.serve_at(
+                    "/ril_0/context1",
+                    MockConnectionContext { state: shared(), path: "/ril_0/context1".to_string() },
+                )
+            })
+            .and_then(|b| {
+                b.serve_at(
+                    "/ril_0/context2",
+                    MockConnectionContext { state: shared(), path: "/ril_0/context2".to_string() },
+                )
+            })
+            .expect("Failed to configure mock ofono")
+            .build()
+            .await
+            .expect("Failed to start mock ofono");
+
+        Some(Self {
+            daemon,
+            address,
+            server,
+            state,
+        })
+    }
+
+    /// 连接到私有总线的新客户端连接（相当于生产环境的 `Connection::system()`）
+    pub async fn client(&self) -> Connection {
+        connection::Builder::address(self.address.as_str())
+            .expect("Invalid bus address")
+            .build()
+            .await
+            .expect("Failed to connect to mock bus")
+    }
+
+    /// 预置 AT 指令应答
+    pub fn set_at_reply(&self, cmd: &str, reply: &str) {
+        self.state.lock().unwrap().at_replies.insert(cmd.to_string(), reply.to_string());
+    }
+
+    /// 收到的 AT 指令
+    pub fn at_log(&self) -> Vec<String> {
+        self.state.lock().unwrap().at_log.clone()
+    }
+
+    /// 读取某个接口（或 context 路径）的属性
+    pub fn property(&self, key: &str, name: &str) -> Option<OwnedValue> {
+        self.state.lock().unwrap().properties.get(key)?.get(name).cloned()
+    }
+
+    /// 模拟收到短信
+    pub async fn incoming_message(&self, sender: &str, text: &str) {
+        let emitter = SignalEmitter::new(&self.server, "/ril_0").unwrap();
+        let info = props([("Sender", ov(sender)), ("LocalSentTime", ov(chrono::Utc::now().to_rfc3339()))]);
+        MockMessageManager::incoming_message(&emitter, text, info).await.unwrap();
+    }
+
+    /// 模拟已发出短信的状态变化（sent / failed / delivered）
+    pub async fn message_state_changed(&self, path: &str, state: &str) {
+        let emitter = SignalEmitter::new(&self.server, path).unwrap();
+        MockMessage::property_changed(&emitter, "State", &Value::from(state)).await.unwrap();
+    }
+
+    /// 模拟 SIM 等待输入 PIN（如重启后）
+    pub fn require_sim_pin(&self) {
+        self.state
+            .lock()
+            .unwrap()
+            .properties
+            .get_mut("org.ofono.SimManager")
+            .unwrap()
+            .insert("PinRequired".to_string(), ov("pin"));
+    }
+
+    /// 模拟网络主动下发需要回复的 USSD 菜单
+    pub async fn ussd_request(&self, message: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .properties
+            .get_mut("org.ofono.SupplementaryServices")
+            .unwrap()
+            .insert("State".to_string(), ov("user-response"));
+        let emitter = SignalEmitter::new(&self.server, "/ril_0").unwrap();
+        MockSupplementaryServices::request_received(&emitter, message).await.unwrap();
+    }
+
+    /// 模拟来电，返回通话对象路径
+    pub async fn incoming_call(&self, number: &str) -> String {
+        let path = MockVoiceCallManager::add_call(&self.state, self.server.object_server(), &self.server, number, "incoming")
+            .await
+            .unwrap();
+        path.to_string()
+    }
+
+    /// 模拟通话中的第二路来电（呼叫等待），返回通话对象路径
+    pub async fn waiting_call(&self, number: &str) -> String {
+        let path = MockVoiceCallManager::add_call(&self.state, self.server.object_server(), &self.server, number, "waiting")
+            .await
+            .unwrap();
+        path.to_string()
+    }
+
+    /// 模拟对方挂断
+    pub async fn remote_hangup(&self, path: &str) {
+        MockVoiceCallManager::remove_call(&self.state, self.server.object_server(), &self.server, path)
+            .await
+            .unwrap();
+    }
+
+    /// 模拟 NetworkRegistration 属性变化
+    pub async fn network_property_changed(&self, name: &str, value: Value<'_>) {
+        self.state
+            .lock()
+            .unwrap()
+            .properties
+            .get_mut("org.ofono.NetworkRegistration")
+            .unwrap()
+            .insert(name.to_string(), ov(value.try_clone().unwrap()));
+        let emitter = SignalEmitter::new(&self.server, "/ril_0").unwrap();
+        MockNetworkRegistration::property_changed(&emitter, name, &value).await.unwrap();
+    }
+}
+
+impl Drop for MockOfono {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
@@ -0,0 +1,225 @@
+//! 集成测试
+//!
+//! 在私有 D-Bus 总线上运行 [`mock_ofono::MockOfono`]，用与生产环境相同的
+//! `build_router` 启动完整的 HTTP 服务，通过真实的 HTTP 请求验证各个接口。
+//! 系统中没有 `dbus-daemon` 时测试直接跳过。
+
+mod api;
+pub mod mock_ofono;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+use crate::auth::AuthManager;
+use crate::config::ConfigManager;
+use crate::db::Database;
+use crate::events::EventBus;
+use crate::modem::ofono::OfonoBackend;
+use crate::sms_push::SmsPushSender;
+use crate::state::{AppState, FrontendRuntime};
+use crate::webhook::WebhookSender;
+use mock_ofono::MockOfono;
+
+/// 测试使用的管理员密码
+pub const TEST_PASSWORD: &str = "test-password";
+
+/// 测试服务允许跨域访问的前端地址
+pub const TEST_UI_ORIGIN: &str = "http://ui.test:5173";
+
+/// 为每个测试生成独立的临时目录
+fn temp_dir() -> PathBuf {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "udx710-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).expect("Failed to create temp dir");
+    dir
+}
+
+/// 运行中的测试服务：模拟 ofono + 完整 HTTP 路由
+pub struct TestApp {
+    pub mock: MockOfono,
+    pub state: AppState,
+    pub base_url: String,
+    pub http: reqwest::Client,
+    pub token: String,
+    dir: PathBuf,
+}
+
+impl TestApp {
+    /// 启动模拟 ofono 和 HTTP 服务，并完成首次登录改密
+    ///
+    /// 没有 dbus-daemon 时返回 None
+    pub async fn start() -> Option<Self> {
+        let Some(mock) = MockOfono::start().await else {
+            eprintln!("dbus-daemon not available, skipping integration test");
+            return None;
+        };
+
+        let dir = temp_dir();
+        let config_manager = Arc::new(ConfigManager::new(dir.join("config.json")));
+        let conn = Arc::new(mock.client().await);
+        let state = AppState::new(
+            Arc::clone(&conn),
+            Arc::new(Database::new(dir.join("data.db")).expect("Failed to open database")),
+            Arc::clone(&config_manager),
+            Arc::new(WebhookSender::new(Arc::clone(&config_manager))),
+            Arc::new(SmsPushSender::new(Arc::clone(&config_manager))),
+            Arc::new(FrontendRuntime::new()),
+            Arc::new(AuthManager::new(Arc::clone(&config_manager))),
+            Arc::new(EventBus::new()),
+            Arc::new(OfonoBackend::new(conn)),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let router = crate::build_router(state.clone(), &[TEST_UI_ORIGIN.to_string()]);
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await;
+        });
+
+        let mut app = Self {
+            mock,
+            state,
+            base_url,
+            http: reqwest::Client::new(),
+            token: String::new(),
+            dir,
+        };
+        app.login().await;
+        Some(app)
+    }
+
+    /// 使用默认密码登录并修改密码，得到可用的会话令牌
+    async fn login(&mut self) {
+        let body = self.post("/api/auth/login", json!({ "password": "admin" })).await;
+        assert_eq!(body["data"]["must_change_password"], true);
+        self.token = body["data"]["token"].as_str().unwrap().to_string();
+
+        // 改密前只能访问修改密码相关接口
+        let (status, _) = self.request(reqwest::Method::GET, "/api/device", None).await;
+        assert_eq!(status, 403);
+        let (status, _) = self.request(reqwest::Method::POST, "/api/auth/sse-ticket", Some(json!({}))).await;
+        assert_eq!(status, 403);
+
+        let body = self
+            .post(
+                "/api/auth/password",
+                json!({ "current_password": "admin", "new_password": TEST_PASSWORD }),
+            )
+            .await;
+        assert_eq!(body["status"], "ok", "{}", body);
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// 发送带会话令牌的请求，返回 (HTTP 状态码, JSON 响应体)
+    pub async fn request(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> (u16, Value) {
+        let mut request = self.http.request(method, self.url(path)).bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.expect("HTTP request failed");
+        let status = response.status().as_u16();
+        let text = response.text().await.unwrap();
+        let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        (status, body)
+    }
+
+    pub async fn get(&self, path: &str) -> Value {
+        let (status, body) = self.request(reqwest::Method::GET, path, None).await;
+        assert_eq!(status, 200, "GET {} -> {}", path, body);
+        body
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> Value {
+        let (status, body) = self.request(reqwest::Method::POST, path, Some(body)).await;
+        assert_eq!(status, 200, "POST {} -> {}", path, body);
+        body
+    }
+
+    pub async fn put(&self, path: &str, body: Value) -> Value {
+        let (status, body) = self.request(reqwest::Method::PUT, path, Some(body)).await;
+        assert_eq!(status, 200, "PUT {} -> {}", path, body);
+        body
+    }
+
+    pub async fn delete(&self, path: &str) -> Value {
+        let (status, body) = self.request(reqwest::Method::DELETE, path, None).await;
+        assert_eq!(status, 200, "DELETE {} -> {}", path, body);
+        body
+    }
+
+    /// GET 并断言 `status` 为 ok，返回 `data`
+    pub async fn get_ok(&self, path: &str) -> Value {
+        let body = self.get(path).await;
+        assert_eq!(body["status"], "ok", "GET {} -> {}", path, body);
+        body["data"].clone()
+    }
+
+    /// POST 并断言 `status` 为 ok，返回 `data`
+    pub async fn post_ok(&self, path: &str, payload: Value) -> Value {
+        let body = self.post(path, payload).await;
+        assert_eq!(body["status"], "ok", "POST {} -> {}", path, body);
+        body["data"].clone()
+    }
+
+    /// PUT 并断言 `status` 为 ok，返回 `data`
+    pub async fn put_ok(&self, path: &str, payload: Value) -> Value {
+        let body = self.put(path, payload).await;
+        assert_eq!(body["status"], "ok", "PUT {} -> {}", path, body);
+        body["data"].clone()
+    }
+
+    /// DELETE 并断言 `status` 为 ok，返回 `data`
+    pub async fn delete_ok(&self, path: &str) -> Value {
+        let body = self.delete(path).await;
+        assert_eq!(body["status"], "ok", "DELETE {} -> {}", path, body);
+        body["data"].clone()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 启动记录请求的 HTTP 接收端（模拟 Webhook / 推送服务），返回 (基础 URL, 收到的 (路径, 请求体))
+pub async fn capture_server() -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&received);
+    let router = axum::Router::new().fallback(move |uri: axum::http::Uri, body: String| {
+        let sink = Arc::clone(&sink);
+        async move {
+            sink.lock().unwrap().push((uri.path().to_string(), body));
+            "{}"
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, router).await;
+    });
+    (base_url, received)
+}
+
+/// 轮询等待条件成立（用于等待 D-Bus 信号被后台任务处理）
+pub async fn wait_for<F: FnMut() -> bool>(mut condition: F) -> bool {
+    for _ in 0..100 {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    false
+}
@@ -0,0 +1,195 @@
+//! USSD 会话
+//!
+//! 通过 ofono SupplementaryServices 发起、回复和取消 USSD 会话（余额查询、购买流量包等运营商菜单），
+//! 每次交互连同交互后的会话状态（idle / active / user-response）记录到 `ussd_history`。
+//! 网络主动下发的通知（NotificationReceived）和菜单（RequestReceived）由后台监听任务记录，
+//! 并推送 `ussd_received` 事件。
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tracing::{info, warn};
+use zbus::{Connection, MessageStream, Proxy};
+
+use crate::db::Database;
+use crate::dbus::{get_ussd_state, ussd_cancel, ussd_initiate, ussd_respond};
+use crate::events::{DeviceEvent, EventBus};
+use crate::models::UssdResponse;
+
+/// USSD 字符串最大长度（GSM 7-bit 编码下 182 个字符）
+const MAX_USSD_LENGTH: usize = 182;
+
+pub const STATE_IDLE: &str = "idle";
+pub const STATE_USER_RESPONSE: &str = "user-response";
+
+/// 校验 USSD 指令：只允许数字、`*`、`#` 和 `+`
+fn validate_command(command: &str) -> Result<&str, String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err("USSD command is empty".to_string());
+    }
+    if command.len() > MAX_USSD_LENGTH {
+        return Err(format!("USSD command exceeds {} characters", MAX_USSD_LENGTH));
+    }
+    if !command.chars().all(|c| c.is_ascii_digit() || matches!(c, '*' | '#' | '+')) {
+        return Err(format!("Invalid USSD command: {}", command));
+    }
+    Ok(command)
+}
+
+fn validate_reply(reply: &str) -> Result<&str, String> {
+    let reply = reply.trim();
+    if reply.is_empty() {
+        return Err("USSD response is empty".to_string());
+    }
+    if reply.chars().count() > MAX_USSD_LENGTH {
+        return Err(format!("USSD response exceeds {} characters", MAX_USSD_LENGTH));
+    }
+    Ok(reply)
+}
+
+/// 当前会话状态
+pub async fn status(conn: &Connection) -> Result<UssdResponse, String> {
+    let state = get_ussd_state(conn)
+        .await
+        .map_err(|e| format!("Failed to get USSD state: {}", e))?;
+    Ok(UssdResponse {
+        state,
+        ..Default::default()
+    })
+}
+
+/// 交互完成后的会话状态，查询失败时按会话已结束处理
+async fn state_after(conn: &Connection) -> String {
+    get_ussd_state(conn).await.unwrap_or_else(|_| STATE_IDLE.to_string())
+}
+
+fn record(
+    db: &Database,
+    direction: &str,
+    request: Option<&str>,
+    response: Option<&str>,
+    result_type: Option<&str>,
+    state: &str,
+    error: Option<&str>,
+) {
+    if let Err(e) = db.insert_ussd(direction, request, response, result_type, state, error) {
+        warn!(error = %e, "Failed to save USSD record");
+    }
+}
+
+/// 发起 USSD 会话，已有会话进行中时返回错误
+pub async fn initiate(conn: &Connection, db: &Database, command: &str) -> Result<UssdResponse, String> {
+    let command = validate_command(command)?;
+    let current = status(conn).await?.state;
+    if current != STATE_IDLE {
+        return Err(format!("A USSD session is already {}, respond or cancel it first", current));
+    }
+
+    let result = ussd_initiate(conn, command).await;
+    let state = state_after(conn).await;
+    match result {
+        Ok((result_type, message)) => {
+            record(db, "outgoing", Some(command), Some(&message), Some(&result_type), &state, None);
+            Ok(UssdResponse {
+                state,
+                result_type: Some(result_type),
+                message: Some(message),
+            })
+        }
+        Err(e) => {
+            let error = format!("USSD request failed: {}", e);
+            record(db, "outgoing", Some(command), None, None, &state, Some(&error));
+            Err(error)
+        }
+    }
+}
+
+/// 回复网络下发的菜单，只有会话处于 user-response 状态时可用
+pub async fn respond(conn: &Connection, db: &Database, reply: &str) -> Result<UssdResponse, String> {
+    let reply = validate_reply(reply)?;
+    if status(conn).await?.state != STATE_USER_RESPONSE {
+        return Err("No USSD session is waiting for a response".to_string());
+    }
+
+    let result = ussd_respond(conn, reply).await;
+    let state = state_after(conn).await;
+    match result {
+        Ok(message) => {
+            record(db, "outgoing", Some(reply), Some(&message), Some("USSD"), &state, None);
+            Ok(UssdResponse {
+                state,
+                result_type: Some("USSD".to_string()),
+                message: Some(message),
+            })
+        }
+        Err(e) => {
+            let error = format!("USSD response failed: {}", e);
+            record(db, "outgoing", Some(reply), None, None, &state, Some(&error));
+            Err(error)
+        }
+    }
+}
+
+/// 取消当前会话（没有会话时直接返回）
+pub async fn cancel(conn: &Connection) -> Result<UssdResponse, String> {
+    if status(conn).await?.state != STATE_IDLE {
+        ussd_cancel(conn)
+            .await
+            .map_err(|e| format!("Failed to cancel USSD session: {}", e))?;
+    }
+    status(conn).await
+}
+
+/// 监听网络主动下发的 USSD 消息
+pub async fn start_ussd_listener(conn: Connection, db: Arc<Database>, events: Arc<EventBus>) -> zbus::Result<()> {
+    let dbus_proxy = Proxy::new(&conn, "org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus").await?;
+    for member in ["NotificationReceived", "RequestReceived"] {
+        let rule = format!(
+            "type='signal',sender='org.ofono',interface='org.ofono.SupplementaryServices',member='{}'",
+            member
+        );
+        dbus_proxy.call::<_, _, ()>("AddMatch", &(rule,)).await?;
+    }
+
+    let mut stream = MessageStream::from(&conn);
+    while let Some(msg) = stream.next().await {
+        let Ok(msg) = msg else {
+            continue;
+        };
+        let header = msg.header();
+        if header.interface().map(|i| i.as_str()) != Some("org.ofono.SupplementaryServices") {
+            continue;
+        }
+        let awaiting_response = match header.member().map(|m| m.as_str()) {
+            Some("RequestReceived") => true,
+            Some("NotificationReceived") => false,
+            _ => continue,
+        };
+        let Ok(message) = msg.body().deserialize::<String>() else {
+            continue;
+        };
+
+        info!(awaiting_response, "USSD message received from network");
+        let state = if awaiting_response { STATE_USER_RESPONSE } else { STATE_IDLE };
+        record(&db, "incoming", None, Some(&message), Some("USSD"), state, None);
+        events.publish(DeviceEvent::UssdReceived { message, awaiting_response });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_commands_and_replies() {
+        assert_eq!(validate_command(" *100# ").unwrap(), "*100#");
+        assert!(validate_command("*#21#").is_ok());
+        assert!(validate_command("").is_err());
+        assert!(validate_command("*100#; reboot").is_err());
+        assert!(validate_command(&"1".repeat(MAX_USSD_LENGTH + 1)).is_err());
+        assert_eq!(validate_reply(" 1 ").unwrap(), "1");
+        assert!(validate_reply("  ").is_err());
+    }
+}
@@ -13,9 +13,79 @@
 //! 包含 AT 指令解析、数据处理等工具函数
 
 use crate::models::{CellInfo, IpAddress, NetworkInterfaceInfo};
+use chrono::NaiveTime;
 use std::collections::HashMap;
 use std::net::IpAddr;
 
+/// 号码归一化：只保留数字，并去掉国际前缀 `00` 和长途前缀 `0`
+///
+/// `+86 138-0013-8000` 和 `008613800138000` 都归一化为 `8613800138000`，
+/// `010-12345678` 归一化为 `1012345678`；号码比较见 [`same_number`]
+pub fn normalize_phone_number(number: &str) -> String {
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    digits.trim_start_matches('0').to_string()
+}
+
+/// E.164 两位国家码（1 和 7 为一位，其余未列出的均为三位；国家码互不为前缀）
+const TWO_DIGIT_COUNTRY_CODES: [&str; 44] = [
+    "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46", "47", "48", "49", "51",
+    "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63", "64", "65", "66", "81", "82", "84", "86", "90",
+    "91", "92", "93", "94", "95", "98",
+];
+
+/// 国际号码（已去掉 `+` / `00`）的国家码长度
+fn country_code_len(digits: &str) -> usize {
+    if digits.starts_with('1') || digits.starts_with('7') {
+        1
+    } else if TWO_DIGIT_COUNTRY_CODES.iter().any(|code| digits.starts_with(code)) {
+        2
+    } else {
+        3
+    }
+}
+
+/// 比较两个号码是否相同（忽略格式和长途前缀，如 "+86 138-0013-8000" 与 "13800138000"）
+///
+/// 两个号码都带国际前缀（`+` 或 `00`）或都不带时要求数字完全一致；只有一个带时，
+/// 去掉它的国家码后与另一个号码完全一致才算相同。不做后缀匹配，
+/// "800138000" 不会被当作 "+8613800138000"。
+pub fn same_number(a: &str, b: &str) -> bool {
+    let international = |number: &str| {
+        let number = number.trim();
+        number.starts_with('+') || number.starts_with("00")
+    };
+    let (digits_a, digits_b) = (normalize_phone_number(a), normalize_phone_number(b));
+    if digits_a.is_empty() || digits_b.is_empty() {
+        return false;
+    }
+    if digits_a == digits_b {
+        return true;
+    }
+    let (full, national) = match (international(a), international(b)) {
+        (true, false) => (digits_a, digits_b),
+        (false, true) => (digits_b, digits_a),
+        _ => return false,
+    };
+    full.len() > country_code_len(&full)
+        && full[country_code_len(&full)..].trim_start_matches('0') == national
+}
+
+/// 解析 `HH:MM` 格式的本地时间
+pub fn parse_time_of_day(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// 判断时间是否在时段内，结束时间早于开始时间表示跨零点，相等表示全天
+pub fn in_time_window(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
 /// 小区信息查询指令配置
 #[derive(Debug, Clone)]
 pub struct CellCommandConfig {
@@ -1395,3 +1465,31 @@ pub fn build_splband_nr_command(fdd_mask: u16, tdd_mask: u16) -> String {
     format!("AT+SPLBAND=2,{},0,{},0", fdd_mask, tdd_mask)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::same_number;
+
+    #[test]
+    fn same_number_ignores_format_and_country_code() {
+        assert!(same_number("+86 138-0013-8000", "13800138000"));
+        assert!(same_number("008613800138000", "+8613800138000"));
+        assert!(same_number("8613800138000", "+8613800138000"));
+        assert!(same_number("+44 7700 900123", "07700 900123"));
+        assert!(same_number("+1 (202) 555-0143", "2025550143"));
+        assert!(same_number("10086", "10086"));
+        assert!(same_number("+8610086", "10086"));
+    }
+
+    #[test]
+    fn same_number_rejects_suffixes_and_short_numbers() {
+        assert!(!same_number("800138000", "+8613800138000"));
+        assert!(!same_number("3800138000", "+8613800138000"));
+        assert!(!same_number("0138000", "13800138000"));
+        assert!(!same_number("10086", "13910086"));
+        assert!(!same_number("10086", "+8613910086"));
+        assert!(!same_number("+8613800138000", "+8513800138000"));
+        assert!(!same_number("+8613800138000", "+86913800138000"));
+        assert!(!same_number("", ""));
+        assert!(!same_number("+", "0"));
+    }
+}
@@ -10,14 +10,53 @@
  */
 //! Webhook 转发模块
 //!
-//! 用于将来电和短信转发到外部 Webhook
+//! 用于将来电、短信、短信投递结果、流量告警和设备通知转发到外部 Webhook
 //! 支持自定义 payload 模板，使用 {{变量名}} 格式替换
+//!
+//! 除主 URL 外可配置多个目标（`endpoints`），每个目标有独立的请求头、签名密钥和模板，
+//! 按事件类型、号码和短信内容正则过滤，事件并行发送到所有接收它的目标。
 
-use crate::config::{ConfigManager, WebhookConfig};
+use crate::call_filter::{self, matches_any};
+use crate::config::{ConfigManager, WebhookConfig, WebhookEndpoint, WebhookEvent};
+use crate::data_usage::DataUsageAlert;
 use crate::db::{CallRecord, SmsMessage};
+use crate::events::{DeviceEvent, EventBus};
+use crate::models::WebhookEndpointResult;
 use chrono::Utc;
+use futures_util::future::join_all;
+use regex::Regex;
 use reqwest::Client;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+/// 事件关联的号码和内容，用于目标的号码/正则过滤
+#[derive(Default)]
+struct Subject<'a> {
+    number: Option<&'a str>,
+    content: Option<&'a str>,
+}
+
+/// 设备通知（数据断开、信号告警、重启、OTA）
+#[derive(Debug, Clone)]
+pub struct DeviceNotice {
+    pub event: WebhookEvent,
+    /// 可读的通知文本，对应模板变量 {{text}}
+    pub text: String,
+    pub timestamp: String,
+    /// 事件专属的模板变量
+    pub vars: Vec<(&'static str, String)>,
+}
+
+impl DeviceNotice {
+    pub fn new(event: WebhookEvent, text: String, vars: Vec<(&'static str, String)>) -> Self {
+        Self {
+            event,
+            text,
+            timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            vars,
+        }
+    }
+}
 
 /// Webhook 发送器
 pub struct WebhookSender {
@@ -45,52 +84,129 @@ impl WebhookSender {
     /// 转发短信
     pub async fn forward_sms(&self, message: &SmsMessage) -> Result<(), String> {
         let config = self.get_config();
+        let subject = Subject { number: Some(&message.phone_number), content: Some(&message.content) };
         
-        if !config.enabled || !config.forward_sms || config.url.is_empty() {
-            return Ok(());
-        }
-        
-        // 使用模板替换变量
-        let payload = render_sms_template(&config.sms_template, message);
+        self.fan_out(&config, WebhookEvent::Sms, subject, config.forward_sms, |template| {
+            render_sms_template(template, message)
+        })
+        .await
+    }
+    
+    /// 转发发出短信的投递结果（已发送/已送达/失败）
+    pub async fn forward_sms_status(&self, message: &SmsMessage) -> Result<(), String> {
+        let config = self.get_config();
+        let subject = Subject { number: Some(&message.phone_number), content: Some(&message.content) };
         
-        self.send_webhook_raw(&config, &payload).await
+        self.fan_out(&config, WebhookEvent::SmsStatus, subject, config.forward_sms_status, |template| {
+            render_sms_template(template, message)
+        })
+        .await
     }
     
+    /// 发送短信规则命中的短信（只要求 Webhook 已启用，不受 `forward_sms` 开关影响）
+    ///
+    /// `template` 为空时使用短信模板，模板中可额外使用 `{{rule}}`
+    pub async fn forward_sms_rule(&self, rule: &str, message: &SmsMessage, template: &str) -> Result<(), String> {
+        let config = self.get_config();
+
+        let Some(endpoint) = primary_endpoint(&config).filter(|_| config.enabled) else {
+            return Err("Webhook is not enabled".to_string());
+        };
+
+        let template = if template.is_empty() { &config.sms_template } else { template };
+        let payload = render_sms_template(template, message).replace("{{rule}}", &escape_json_string(rule));
+
+        self.send_webhook_raw(&endpoint, &payload).await
+    }
+
     /// 转发通话记录
     pub async fn forward_call(&self, call: &CallRecord) -> Result<(), String> {
         let config = self.get_config();
+        let subject = Subject { number: Some(&call.phone_number), content: None };
         
-        if !config.enabled || !config.forward_calls || config.url.is_empty() {
-            return Ok(());
-        }
-        
-        // 使用模板替换变量
-        let payload = render_call_template(&config.call_template, call);
-        
-        self.send_webhook_raw(&config, &payload).await
+        self.fan_out(&config, WebhookEvent::Call, subject, config.forward_calls, |template| {
+            render_call_template(template, call)
+        })
+        .await
     }
     
-    /// 发送原始 JSON 字符串的 Webhook 请求
-    async fn send_webhook_raw(&self, config: &WebhookConfig, payload: &str) -> Result<(), String> {
-        let mut request = self.client.post(&config.url);
+    /// 来电响铃时立即转发（通话结束后仍由 `forward_call` 发送汇总）
+    pub async fn forward_call_ringing(&self, call: &CallRecord) -> Result<(), String> {
+        let config = self.get_config();
+        let subject = Subject { number: Some(&call.phone_number), content: None };
+        let primary = config.forward_calls && config.forward_call_ringing;
+
+        self.fan_out(&config, WebhookEvent::CallRinging, subject, primary, |template| {
+            render_call_template(template, call)
+        })
+        .await
+    }
+    
+    /// 转发流量配额告警
+    pub async fn forward_data_usage(&self, alert: &DataUsageAlert) -> Result<(), String> {
+        let config = self.get_config();
         
-        // 添加自定义请求头
-        for (key, value) in &config.headers {
-            request = request.header(key, value);
+        self.fan_out(&config, WebhookEvent::DataUsage, Subject::default(), config.forward_data_usage, |template| {
+            render_data_usage_template(template, alert)
+        })
+        .await
+    }
+    
+    /// 转发设备通知（只发送到订阅了该事件的目标，不发送到主 URL）
+    pub async fn forward_notice(&self, notice: &DeviceNotice) -> Result<(), String> {
+        let config = self.get_config();
+
+        self.fan_out(&config, notice.event, Subject::default(), false, |template| {
+            render_notice_template(template, notice)
+        })
+        .await
+    }
+
+    /// 将事件并行发送到主 URL（`primary` 为 true 时）和所有接收该事件的目标
+    ///
+    /// 任一目标失败都不影响其他目标，返回的错误中包含所有失败目标的名称
+    async fn fan_out(
+        &self,
+        config: &WebhookConfig,
+        event: WebhookEvent,
+        subject: Subject<'_>,
+        primary: bool,
+        render: impl Fn(&str) -> String,
+    ) -> Result<(), String> {
+        if !config.enabled {
+            return Ok(());
         }
         
-        // 添加 Content-Type
-        request = request.header("Content-Type", "application/json");
-        
-        // 如果有密钥，添加签名头
-        if !config.secret.is_empty() {
-            let signature = compute_hmac(&config.secret, payload);
-            request = request.header("X-Webhook-Signature", signature);
+        let default_template = default_template(config, event);
+        let mut deliveries = Vec::new();
+        if let Some(endpoint) = primary_endpoint(config).filter(|_| primary) {
+            deliveries.push((endpoint, render(default_template)));
+        }
+        for endpoint in config.endpoints.iter().filter(|e| endpoint_accepts(e, event, &subject)) {
+            let template = if endpoint.template.is_empty() { default_template } else { &endpoint.template };
+            deliveries.push((endpoint.clone(), render(template)));
         }
         
-        // 发送请求
-        let response = request
-            .body(payload.to_string())
+        let results = join_all(
+            deliveries.iter().map(|(endpoint, payload)| self.send_webhook_raw(endpoint, payload)),
+        )
+        .await;
+        let errors: Vec<String> = deliveries
+            .iter()
+            .zip(results)
+            .filter_map(|((endpoint, _), result)| result.err().map(|e| format!("{}: {}", endpoint.name, e)))
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+    
+    /// 发送原始 JSON 字符串的 Webhook 请求
+    async fn send_webhook_raw(&self, endpoint: &WebhookEndpoint, payload: &str) -> Result<(), String> {
+        let response = self.build_request(endpoint, payload)
             .send()
             .await
             .map_err(|e| format!("Failed to send webhook: {}", e))?;
@@ -104,14 +220,32 @@ impl WebhookSender {
         }
     }
     
-    /// 测试 Webhook 连接（使用短信模板发送测试数据）
-    pub async fn test_webhook(&self) -> Result<String, String> {
-        let config = self.get_config();
+    fn build_request(&self, endpoint: &WebhookEndpoint, payload: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.post(&endpoint.url);
         
-        if config.url.is_empty() {
-            return Err("Webhook URL is not configured".to_string());
+        // 添加自定义请求头
+        for (key, value) in &endpoint.headers {
+            request = request.header(key, value);
+        }
+        
+        // 添加 Content-Type
+        request = request.header("Content-Type", "application/json");
+        
+        // 如果有密钥，添加签名头
+        if !endpoint.secret.is_empty() {
+            let signature = compute_hmac(&endpoint.secret, payload);
+            request = request.header("X-Webhook-Signature", signature);
         }
         
+        request.body(payload.to_string())
+    }
+    
+    /// 测试 Webhook 连接：向主 URL 和所有目标并行发送测试短信（忽略事件类型和过滤条件）
+    ///
+    /// 没有配置任何 URL 时返回空列表
+    pub async fn test_webhook(&self) -> Vec<WebhookEndpointResult> {
+        let config = self.get_config();
+        
         // 使用模拟数据渲染短信模板进行测试
         let test_message = SmsMessage {
             id: 0,
@@ -121,42 +255,200 @@ impl WebhookSender {
             timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             status: "received".to_string(),
             pdu: None,
+            message_path: None,
+            sent_at: None,
+            delivered_at: None,
+            failed_at: None,
+            tags: Vec::new(),
+            contact_name: None,
+            thread_id: None,
+            read: false,
         };
         
-        let payload = render_sms_template(&config.sms_template, &test_message);
+        let endpoints: Vec<WebhookEndpoint> = primary_endpoint(&config)
+            .into_iter()
+            .chain(config.endpoints.iter().filter(|e| !e.url.is_empty()).cloned())
+            .collect();
+        let tests = endpoints.iter().map(|endpoint| {
+            let template = if endpoint.template.is_empty() { &config.sms_template } else { &endpoint.template };
+            let payload = render_sms_template(template, &test_message);
+            async move {
+                let response = self.build_request(endpoint, &payload).send().await;
+                let (success, message) = match response {
+                    Ok(response) => {
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        if status.is_success() {
+                            (true, format!("Webhook test successful (status: {})", status))
+                        } else {
+                            (false, format!("Webhook test failed (status: {}): {}", status, body))
+                        }
+                    }
+                    Err(e) => (false, format!("Failed to send test webhook: {}", e)),
+                };
+                WebhookEndpointResult {
+                    name: endpoint.name.clone(),
+                    url: endpoint.url.clone(),
+                    success,
+                    message,
+                }
+            }
+        });
         
-        let mut request = self.client.post(&config.url);
-        
-        for (key, value) in &config.headers {
-            request = request.header(key, value);
-        }
+        join_all(tests).await
+    }
+}
         
-        request = request.header("Content-Type", "application/json");
+/// 主 URL 视为名为 `default` 的目标（未配置 URL 时为 None）
+fn primary_endpoint(config: &WebhookConfig) -> Option<WebhookEndpoint> {
+    if config.url.is_empty() {
+        return None;
+    }
+    Some(WebhookEndpoint {
+        name: "default".to_string(),
+        enabled: true,
+        url: config.url.clone(),
+        headers: config.headers.clone(),
+        secret: config.secret.clone(),
+        template: String::new(),
+        events: Vec::new(),
+        numbers: Vec::new(),
+        content_regex: String::new(),
+    })
+}
         
-        if !config.secret.is_empty() {
-            let signature = compute_hmac(&config.secret, &payload);
-            request = request.header("X-Webhook-Signature", signature);
+/// 目标未设置模板时使用的全局模板
+fn default_template(config: &WebhookConfig, event: WebhookEvent) -> &str {
+    match event {
+        WebhookEvent::Sms => &config.sms_template,
+        WebhookEvent::SmsStatus => &config.sms_status_template,
+        WebhookEvent::CallRinging => &config.call_ringing_template,
+        WebhookEvent::Call => &config.call_template,
+        WebhookEvent::DataUsage => &config.data_usage_template,
+        WebhookEvent::DataDown | WebhookEvent::SignalAlert | WebhookEvent::Reboot | WebhookEvent::Ota => {
+            &config.event_template
         }
-        
-        let response = request
-            .body(payload)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to send test webhook: {}", e))?;
-        
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        
-        if status.is_success() {
-            Ok(format!("Webhook test successful (status: {})", status))
-        } else {
-            Err(format!("Webhook test failed (status: {}): {}", status, body))
+    }
+}
+
+/// 目标是否接收该事件
+fn endpoint_accepts(endpoint: &WebhookEndpoint, event: WebhookEvent, subject: &Subject) -> bool {
+    if !endpoint.enabled || endpoint.url.is_empty() {
+        return false;
+    }
+    if !endpoint.events.is_empty() && !endpoint.events.contains(&event) {
+        return false;
+    }
+    if !endpoint.numbers.is_empty() && !subject.number.is_some_and(|number| matches_any(&endpoint.numbers, number)) {
+        return false;
+    }
+    if !endpoint.content_regex.is_empty() {
+        return subject
+            .content
+            .is_some_and(|content| Regex::new(&endpoint.content_regex).is_ok_and(|regex| regex.is_match(content)));
+    }
+    true
+}
+
+/// 校验 Webhook 目标配置
+pub fn validate_config(config: &WebhookConfig) -> Result<(), String> {
+    for endpoint in &config.endpoints {
+        if endpoint.name.is_empty() {
+            return Err("Webhook endpoint name is required".to_string());
+        }
+        if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
+            return Err(format!("Webhook endpoint '{}' has an invalid URL", endpoint.name));
+        }
+        call_filter::validate_patterns(&endpoint.numbers)
+            .map_err(|e| format!("Webhook endpoint '{}': {}", endpoint.name, e))?;
+        if !endpoint.content_regex.is_empty() {
+            Regex::new(&endpoint.content_regex)
+                .map_err(|e| format!("Webhook endpoint '{}': invalid regex: {}", endpoint.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 订阅事件总线，将数据连接断开、网络注册丢失/恢复和 OTA 新版本转发到订阅了对应事件的目标
+///
+/// 启动时先发送一次 `reboot` 通知
+pub async fn start_event_forwarder(webhook: Arc<WebhookSender>, events: Arc<EventBus>) {
+    let mut receiver = events.subscribe();
+
+    let uptime_secs = std::fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|content| content.split_whitespace().next()?.parse::<f64>().ok())
+        .map(|secs| secs as u64)
+        .unwrap_or_default();
+    let version = env!("APP_VERSION").to_string();
+    send_notice(&webhook, DeviceNotice::new(
+        WebhookEvent::Reboot,
+        format!("设备已启动 (版本 {}, 系统已运行 {} 秒)", version, uptime_secs),
+        vec![("uptime_secs", uptime_secs.to_string()), ("version", version)],
+    ));
+
+    // 只在状态变化时通知；启动时网络注册状态未知，按已注册处理
+    let mut data_active: Option<bool> = None;
+    let mut registered = true;
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+
+        let notice = match event {
+            DeviceEvent::DataConnectionChanged { active, status } => {
+                let was_active = data_active.replace(active);
+                (was_active == Some(true) && !active).then(|| {
+                    DeviceNotice::new(WebhookEvent::DataDown, format!("数据连接已断开: {}", status), vec![("status", status)])
+                })
+            }
+            DeviceEvent::NetworkRegistrationChanged { property, value } if property == "Status" => {
+                let status = value.as_str().unwrap_or_default().to_string();
+                let now_registered = matches!(status.as_str(), "registered" | "roaming");
+                let was_registered = std::mem::replace(&mut registered, now_registered);
+                match (was_registered, now_registered) {
+                    (true, false) => Some(DeviceNotice::new(
+                        WebhookEvent::SignalAlert,
+                        format!("网络注册丢失: {}", status),
+                        vec![("level", "lost".to_string()), ("status", status)],
+                    )),
+                    (false, true) => Some(DeviceNotice::new(
+                        WebhookEvent::SignalAlert,
+                        format!("网络已恢复: {}", status),
+                        vec![("level", "restored".to_string()), ("status", status)],
+                    )),
+                    _ => None,
+                }
+            }
+            DeviceEvent::OtaUpdateAvailable { version, channel, notes } => Some(DeviceNotice::new(
+                WebhookEvent::Ota,
+                format!("OTA 新版本可用: {} ({})", version, channel),
+                vec![("version", version), ("channel", channel), ("notes", notes.unwrap_or_default())],
+            )),
+            _ => None,
+        };
+
+        if let Some(notice) = notice {
+            send_notice(&webhook, notice);
         }
     }
 }
 
+fn send_notice(webhook: &Arc<WebhookSender>, notice: DeviceNotice) {
+    let webhook = Arc::clone(webhook);
+    tokio::spawn(async move {
+        if let Err(e) = webhook.forward_notice(&notice).await {
+            tracing::warn!(error = %e, event = notice.event.as_str(), "Failed to forward device notice");
+        }
+    });
+}
+
 /// 渲染短信模板，替换变量
-/// 支持的变量：{{id}}, {{phone_number}}, {{content}}, {{direction}}, {{timestamp}}, {{status}}
+/// 支持的变量：{{id}}, {{phone_number}}, {{content}}, {{direction}}, {{timestamp}}, {{status}},
+/// {{sent_at}}, {{delivered_at}}, {{failed_at}}（未发生时为空）, {{tags}}（逗号分隔）,
+/// {{contact_name}}（联系人名称，不在通讯录中时为空）
 fn render_sms_template(template: &str, message: &SmsMessage) -> String {
     template
         .replace("{{id}}", &message.id.to_string())
@@ -165,6 +457,11 @@ fn render_sms_template(template: &str, message: &SmsMessage) -> String {
         .replace("{{direction}}", &message.direction)
         .replace("{{timestamp}}", &message.timestamp)
         .replace("{{status}}", &message.status)
+        .replace("{{sent_at}}", message.sent_at.as_deref().unwrap_or_default())
+        .replace("{{delivered_at}}", message.delivered_at.as_deref().unwrap_or_default())
+        .replace("{{failed_at}}", message.failed_at.as_deref().unwrap_or_default())
+        .replace("{{tags}}", &escape_json_string(&message.tags.join(",")))
+        .replace("{{contact_name}}", &escape_json_string(message.contact_name.as_deref().unwrap_or_default()))
         // 别名支持
         .replace("{{sender}}", &message.phone_number)
         .replace("{{message}}", &escape_json_string(&message.content))
@@ -172,7 +469,7 @@ fn render_sms_template(template: &str, message: &SmsMessage) -> String {
 }
 
 /// 渲染通话模板，替换变量
-/// 支持的变量：{{id}}, {{phone_number}}, {{direction}}, {{duration}}, {{start_time}}, {{end_time}}, {{answered}}
+/// 支持的变量：{{id}}, {{phone_number}}, {{contact_name}}, {{direction}}, {{duration}}, {{start_time}}, {{end_time}}, {{answered}}, {{reason}}
 fn render_call_template(template: &str, call: &CallRecord) -> String {
     let end_time = call.end_time.clone().unwrap_or_default();
     let answered_str = if call.answered { "是" } else { "否" };
@@ -181,18 +478,56 @@ fn render_call_template(template: &str, call: &CallRecord) -> String {
     template
         .replace("{{id}}", &call.id.to_string())
         .replace("{{phone_number}}", &call.phone_number)
+        .replace("{{contact_name}}", &escape_json_string(call.contact_name.as_deref().unwrap_or_default()))
         .replace("{{direction}}", &call.direction)
         .replace("{{direction_cn}}", direction_cn)
         .replace("{{duration}}", &call.duration.to_string())
         .replace("{{start_time}}", &call.start_time)
         .replace("{{end_time}}", &end_time)
         .replace("{{answered}}", answered_str)
+        .replace("{{reason}}", &escape_json_string(call.reason.as_deref().unwrap_or_default()))
         .replace("{{answered_bool}}", &call.answered.to_string())
         // 别名支持
         .replace("{{caller}}", &call.phone_number)
         .replace("{{time}}", &call.start_time)
 }
 
+/// 渲染流量告警模板，替换变量
+/// 支持的变量：{{level}}, {{level_cn}}, {{used_mb}}, {{quota_mb}}, {{percent}}, {{cycle_start}}, {{cycle_end}}, {{action}}, {{action_cn}}
+fn render_data_usage_template(template: &str, alert: &DataUsageAlert) -> String {
+    let level_cn = if alert.level == "exceeded" { "已超出配额" } else { "即将达到配额" };
+    let action_cn = match alert.action.as_str() {
+        "disconnect" => "已关闭数据连接",
+        "warn" => "仅告警",
+        _ => "无",
+    };
+    
+    template
+        .replace("{{level}}", &alert.level)
+        .replace("{{level_cn}}", level_cn)
+        .replace("{{used_mb}}", &(alert.used_bytes / 1024 / 1024).to_string())
+        .replace("{{quota_mb}}", &(alert.quota_bytes / 1024 / 1024).to_string())
+        .replace("{{percent}}", &alert.percent.to_string())
+        .replace("{{cycle_start}}", &alert.cycle_start)
+        .replace("{{cycle_end}}", &alert.cycle_end)
+        .replace("{{action}}", &alert.action)
+        .replace("{{action_cn}}", action_cn)
+}
+
+/// 渲染设备通知模板，替换变量
+/// 支持的变量：{{event}}, {{text}}, {{timestamp}}，以及各事件的专属变量：
+/// data_down: {{status}}; signal_alert: {{level}}（lost/restored）, {{status}};
+/// reboot: {{version}}, {{uptime_secs}}; ota: {{version}}, {{channel}}, {{notes}}
+fn render_notice_template(template: &str, notice: &DeviceNotice) -> String {
+    let rendered = template
+        .replace("{{event}}", notice.event.as_str())
+        .replace("{{text}}", &escape_json_string(&notice.text))
+        .replace("{{timestamp}}", &notice.timestamp);
+    notice.vars.iter().fold(rendered, |rendered, (name, value)| {
+        rendered.replace(&format!("{{{{{}}}}}", name), &escape_json_string(value))
+    })
+}
+
 /// 转义 JSON 字符串中的特殊字符
 fn escape_json_string(s: &str) -> String {
     s.replace('\\', "\\\\")
@@ -13,8 +13,11 @@ import { BrowserRouter, Routes, Route, Navigate } from 'react-router-dom'
 import { QueryClientProvider } from '@tanstack/react-query'
 import { Box, CircularProgress } from '@mui/material'
 import { ThemeProvider } from './contexts/ThemeContext'
+import { AuthProvider, useAuth } from './contexts/AuthContext'
 import { queryClient } from './lib/queryClient'
 import MainLayout from './components/Layout/MainLayout'
+import ChangePasswordDialog from './components/ChangePasswordDialog'
+import Login from './pages/Login'
 
 // 路由级别代码分割 - 按需加载页面组件
 const Dashboard = lazy(() => import('./pages/Dashboard'))
@@ -66,27 +69,48 @@ function renderLazyPage(PageComponent: LazyPageComponent) {
   )
 }
 
+// 未登录显示登录页；使用默认密码登录后必须先修改密码
+function AuthGate() {
+  const { state, mustChangePassword } = useAuth()
+
+  if (state === 'loading') {
+    return <PageLoading />
+  }
+  if (state === 'anonymous') {
+    return <Login />
+  }
+  if (mustChangePassword) {
+    return <ChangePasswordDialog open forced />
+  }
+
+  return (
+    <BrowserRouter>
+      <Routes>
+        <Route path="/" element={<MainLayout />}>
+          {appRoutes.map((route) => (
+            <Route
+              key={route.path ?? 'index'}
+              index={route.index}
+              path={route.path}
+              element={renderLazyPage(route.component)}
+            />
+          ))}
+          {/* 旧路由重定向到网络状态页面 */}
+          <Route path="network-interfaces" element={<Navigate to="/network" replace />} />
+          <Route path="band-lock" element={<Navigate to="/network" replace />} />
+        </Route>
+      </Routes>
+    </BrowserRouter>
+  )
+}
+
 function App() {
   return (
     <QueryClientProvider client={queryClient}>
       <ThemeProvider>
-        <BrowserRouter>
-          <Routes>
-            <Route path="/" element={<MainLayout />}>
-              {appRoutes.map((route) => (
-                <Route
-                  key={route.path ?? 'index'}
-                  index={route.index}
-                  path={route.path}
-                  element={renderLazyPage(route.component)}
-                />
-              ))}
-              {/* 旧路由重定向到网络状态页面 */}
-              <Route path="network-interfaces" element={<Navigate to="/network" replace />} />
-              <Route path="band-lock" element={<Navigate to="/network" replace />} />
-            </Route>
-          </Routes>
-        </BrowserRouter>
+        <AuthProvider>
+          <AuthGate />
+        </AuthProvider>
       </ThemeProvider>
     </QueryClientProvider>
   )
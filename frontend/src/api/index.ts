@@ -73,28 +73,77 @@ import type {
   RefreshConfigResponse,
   OtaStatusResponse,
   OtaUploadResponse,
+  LoginRequest,
+  LoginResponse,
+  AuthStatusResponse,
+  ChangePasswordRequest,
+  SseTicketResponse,
 } from './types'
 
 // API 基础配置
 const API_BASE = '/api'
 
+// ========== 会话令牌 ==========
+
+const AUTH_TOKEN_KEY = 'auth-token'
+
+// 令牌失效（401）时触发，AuthProvider 监听后回到登录页
+export const AUTH_EXPIRED_EVENT = 'auth:expired'
+// 会话需要先修改密码（403）时触发
+export const PASSWORD_CHANGE_REQUIRED_EVENT = 'auth:password-change-required'
+
+export function getAuthToken(): string | null {
+  return localStorage.getItem(AUTH_TOKEN_KEY)
+}
+
+export function setAuthToken(token: string) {
+  localStorage.setItem(AUTH_TOKEN_KEY, token)
+}
+
+export function clearAuthToken() {
+  localStorage.removeItem(AUTH_TOKEN_KEY)
+}
+
+// 带上会话令牌的请求头
+function authHeaders(): Record<string, string> {
+  const token = getAuthToken()
+  return token ? { Authorization: `Bearer ${token}` } : {}
+}
+
+// 处理认证失败：401 清除令牌，403 且要求改密时通知 AuthProvider
+async function checkAuthResponse(url: string, response: Response) {
+  if (response.status === 401 && url !== '/auth/login') {
+    clearAuthToken()
+    window.dispatchEvent(new Event(AUTH_EXPIRED_EVENT))
+  } else if (response.status === 403) {
+    const body = (await response.clone().json().catch(() => null)) as ApiResponse<unknown> | null
+    if (body?.message.startsWith('Password change required')) {
+      window.dispatchEvent(new Event(PASSWORD_CHANGE_REQUIRED_EVENT))
+    }
+  }
+}
+
 // 通用请求函数
 async function request<T>(
   url: string,
   options: RequestInit & { returnText?: boolean } = {}
 ): Promise<T> {
-  const { returnText, ...fetchOptions } = options
-  
+  const { returnText, headers, ...fetchOptions } = options
+
   const response = await fetch(`${API_BASE}${url}`, {
+    ...fetchOptions,
     headers: {
       'Content-Type': 'application/json',
-      ...fetchOptions.headers,
+      ...authHeaders(),
+      ...headers,
     },
-    ...fetchOptions,
   })
 
   if (!response.ok) {
-    throw new Error(`HTTP error! status: ${response.status}`)
+    await checkAuthResponse(url, response)
+    // 认证接口的错误响应体中带有具体原因（如密码错误、登录被锁定）
+    const body = (await response.json().catch(() => null)) as ApiResponse<unknown> | null
+    throw new Error(body?.message ?? `HTTP error! status: ${response.status}`)
   }
 
   if (returnText) {
@@ -609,8 +658,10 @@ class UDX710API {
       body: file,
       headers: {
         'Content-Type': 'application/octet-stream',
+        ...authHeaders(),
       },
     })
+    await checkAuthResponse('/ota/upload', response)
     return response.json() as Promise<ApiResponse<OtaUploadResponse>>
   }
 
@@ -629,6 +680,53 @@ class UDX710API {
     })
   }
 
+  // ========== 登录认证 ==========
+
+  // 使用管理员密码登录
+  async login(password: string) {
+    const body: LoginRequest = { password }
+    return request<ApiResponse<LoginResponse>>('/auth/login', {
+      method: 'POST',
+      body: JSON.stringify(body),
+    })
+  }
+
+  // 注销当前会话
+  async logout() {
+    return request<ApiResponse<Record<string, unknown>>>('/auth/logout', {
+      method: 'POST',
+    })
+  }
+
+  // 查询认证状态（是否已设置密码、当前令牌是否有效）
+  async getAuthStatus() {
+    return request<ApiResponse<AuthStatusResponse>>('/auth/status')
+  }
+
+  // 修改管理员密码
+  async changePassword(currentPassword: string, newPassword: string) {
+    const body: ChangePasswordRequest = {
+      current_password: currentPassword,
+      new_password: newPassword,
+    }
+    return request<ApiResponse<Record<string, unknown>>>('/auth/password', {
+      method: 'POST',
+      body: JSON.stringify(body),
+    })
+  }
+
+  // 打开实时事件流：EventSource 无法设置请求头，先换取一次性票据
+  // 票据只能使用一次，断线后需关闭旧连接并重新调用，不能依赖浏览器自动重连
+  async openEventStream() {
+    const response = await request<ApiResponse<SseTicketResponse>>('/auth/sse-ticket', {
+      method: 'POST',
+    })
+    if (response.status !== 'ok' || !response.data) {
+      throw new Error(response.message)
+    }
+    return new EventSource(`${API_BASE}/events?ticket=${encodeURIComponent(response.data.ticket)}`)
+  }
+
 }
 
 // 导出单例
@@ -745,3 +745,36 @@ export interface OtaApplyRequest {
   restart_now: boolean
 }
 
+
+// ========== 登录认证 ==========
+
+// 登录请求
+export interface LoginRequest {
+  password: string
+}
+
+// 登录响应
+export interface LoginResponse {
+  token: string // 会话令牌，放在 Authorization: Bearer 中
+  expires_in_secs: number // 会话有效期（秒，每次请求后顺延）
+  must_change_password: boolean // 使用默认密码登录时必须先修改密码
+}
+
+// 认证状态
+export interface AuthStatusResponse {
+  password_set: boolean // 是否已设置管理员密码
+  authenticated: boolean // 当前令牌是否有效
+  must_change_password: boolean // 当前会话是否必须先修改密码
+}
+
+// 修改密码请求
+export interface ChangePasswordRequest {
+  current_password: string
+  new_password: string
+}
+
+// 事件流一次性票据
+export interface SseTicketResponse {
+  ticket: string
+  expires_in_secs: number
+}
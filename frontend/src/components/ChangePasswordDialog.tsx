@@ -0,0 +1,124 @@
+import { useState, type FormEvent } from 'react'
+import {
+  Alert,
+  Button,
+  CircularProgress,
+  Dialog,
+  DialogActions,
+  DialogContent,
+  DialogTitle,
+  Stack,
+  TextField,
+} from '@mui/material'
+import { useAuth } from '../contexts/AuthContext'
+
+// 与后端 MIN_PASSWORD_LEN 保持一致
+const MIN_PASSWORD_LEN = 6
+
+interface ChangePasswordDialogProps {
+  open: boolean
+  // 使用默认密码登录后强制修改：不能关闭，只能改密或退出登录
+  forced?: boolean
+  onClose?: () => void
+}
+
+export default function ChangePasswordDialog({ open, forced = false, onClose }: ChangePasswordDialogProps) {
+  const { changePassword, logout } = useAuth()
+  const [currentPassword, setCurrentPassword] = useState('')
+  const [newPassword, setNewPassword] = useState('')
+  const [confirmPassword, setConfirmPassword] = useState('')
+  const [submitting, setSubmitting] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  const reset = () => {
+    setCurrentPassword('')
+    setNewPassword('')
+    setConfirmPassword('')
+    setError(null)
+  }
+
+  const handleClose = () => {
+    if (forced) return
+    reset()
+    onClose?.()
+  }
+
+  const handleSubmit = async (event: FormEvent) => {
+    event.preventDefault()
+    if (newPassword.length < MIN_PASSWORD_LEN) {
+      setError(`新密码至少 ${MIN_PASSWORD_LEN} 个字符`)
+      return
+    }
+    if (newPassword !== confirmPassword) {
+      setError('两次输入的新密码不一致')
+      return
+    }
+
+    setSubmitting(true)
+    setError(null)
+    try {
+      await changePassword(currentPassword, newPassword)
+      reset()
+      onClose?.()
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    } finally {
+      setSubmitting(false)
+    }
+  }
+
+  return (
+    <Dialog open={open} onClose={handleClose} fullWidth maxWidth="xs">
+      <form onSubmit={(event) => void handleSubmit(event)}>
+        <DialogTitle>{forced ? '首次登录，请修改管理员密码' : '修改管理员密码'}</DialogTitle>
+        <DialogContent>
+          <Stack spacing={2} sx={{ pt: 1 }}>
+            {forced && <Alert severity="warning">当前使用的是默认密码，修改密码后才能使用其他功能。</Alert>}
+            {error && <Alert severity="error">{error}</Alert>}
+            <TextField
+              label="当前密码"
+              type="password"
+              value={currentPassword}
+              onChange={(event) => setCurrentPassword(event.target.value)}
+              autoComplete="current-password"
+              autoFocus
+              fullWidth
+            />
+            <TextField
+              label="新密码"
+              type="password"
+              value={newPassword}
+              onChange={(event) => setNewPassword(event.target.value)}
+              autoComplete="new-password"
+              helperText={`至少 ${MIN_PASSWORD_LEN} 个字符，不能为默认密码`}
+              fullWidth
+            />
+            <TextField
+              label="确认新密码"
+              type="password"
+              value={confirmPassword}
+              onChange={(event) => setConfirmPassword(event.target.value)}
+              autoComplete="new-password"
+              fullWidth
+            />
+          </Stack>
+        </DialogContent>
+        <DialogActions>
+          {forced ? (
+            <Button onClick={() => void logout()}>退出登录</Button>
+          ) : (
+            <Button onClick={handleClose}>取消</Button>
+          )}
+          <Button
+            type="submit"
+            variant="contained"
+            disabled={submitting || !currentPassword || !newPassword}
+            startIcon={submitting ? <CircularProgress size={16} /> : undefined}
+          >
+            修改密码
+          </Button>
+        </DialogActions>
+      </form>
+    </Dialog>
+  )
+}
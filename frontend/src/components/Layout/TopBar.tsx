@@ -38,9 +38,13 @@ import {
   Brightness4 as DarkModeIcon,
   Brightness7 as LightModeIcon,
   Speed as SpeedIcon,
+  Key as KeyIcon,
+  Logout as LogoutIcon,
 } from '@mui/icons-material'
 import { useTheme } from '../../contexts/ThemeContext'
 import { useRefreshInterval } from '../../contexts/RefreshContext'
+import { useAuth } from '../../contexts/AuthContext'
+import ChangePasswordDialog from '../ChangePasswordDialog'
 
 interface TopBarProps {
   drawerWidth: number
@@ -57,8 +61,10 @@ export default function TopBar({
 }: TopBarProps) {
   const { mode, toggleTheme } = useTheme()
   const { triggerRefresh } = useRefreshInterval()
+  const { logout } = useAuth()
   const [anchorEl, setAnchorEl] = useState<null | HTMLElement>(null)
   const [refreshMenuAnchor, setRefreshMenuAnchor] = useState<null | HTMLElement>(null)
+  const [passwordDialogOpen, setPasswordDialogOpen] = useState(false)
 
   const handleMenuOpen = (event: React.MouseEvent<HTMLElement>) => {
     setAnchorEl(event.currentTarget)
@@ -90,6 +96,16 @@ export default function TopBar({
     handleMenuClose()
   }
 
+  const handleChangePassword = () => {
+    setPasswordDialogOpen(true)
+    handleMenuClose()
+  }
+
+  const handleLogout = () => {
+    handleMenuClose()
+    void logout()
+  }
+
   const getRefreshLabel = () => {
     if (refreshInterval === 0) return '手动'
     if (refreshInterval === 1000) return '1秒'
@@ -196,8 +212,26 @@ export default function TopBar({
               secondaryTypographyProps={{ variant: 'caption' }}
             />
           </MenuItem>
+
+          <Divider />
+
+          {/* 账户 */}
+          <MenuItem onClick={handleChangePassword}>
+            <ListItemIcon>
+              <KeyIcon fontSize="small" />
+            </ListItemIcon>
+            <ListItemText>修改密码</ListItemText>
+          </MenuItem>
+          <MenuItem onClick={handleLogout}>
+            <ListItemIcon>
+              <LogoutIcon fontSize="small" />
+            </ListItemIcon>
+            <ListItemText>退出登录</ListItemText>
+          </MenuItem>
         </Menu>
 
+        <ChangePasswordDialog open={passwordDialogOpen} onClose={() => setPasswordDialogOpen(false)} />
+
         {/* 刷新频率子菜单 */}
         <Menu
           anchorEl={refreshMenuAnchor}
@@ -0,0 +1,111 @@
+/* eslint-disable react-refresh/only-export-components */
+import { createContext, useCallback, useContext, useEffect, useState } from 'react'
+import type { ReactNode } from 'react'
+import {
+  api,
+  AUTH_EXPIRED_EVENT,
+  PASSWORD_CHANGE_REQUIRED_EVENT,
+  clearAuthToken,
+  getAuthToken,
+  setAuthToken,
+} from '../api'
+import { queryClient } from '../lib/queryClient'
+
+// loading: 正在校验本地保存的令牌
+type AuthState = 'loading' | 'anonymous' | 'authenticated'
+
+interface AuthContextType {
+  state: AuthState
+  mustChangePassword: boolean
+  login: (password: string) => Promise<void>
+  logout: () => Promise<void>
+  changePassword: (currentPassword: string, newPassword: string) => Promise<void>
+}
+
+const AuthContext = createContext<AuthContextType | undefined>(undefined)
+
+export function useAuth() {
+  const context = useContext(AuthContext)
+  if (!context) {
+    throw new Error('useAuth must be used within AuthProvider')
+  }
+  return context
+}
+
+interface AuthProviderProps {
+  children: ReactNode
+}
+
+export function AuthProvider({ children }: AuthProviderProps) {
+  const [state, setState] = useState<AuthState>(() => (getAuthToken() ? 'loading' : 'anonymous'))
+  const [mustChangePassword, setMustChangePassword] = useState(false)
+
+  // 启动时校验本地保存的令牌（服务重启后内存中的会话会失效）
+  useEffect(() => {
+    if (!getAuthToken()) return
+    api
+      .getAuthStatus()
+      .then((response) => {
+        if (response.data?.authenticated) {
+          setMustChangePassword(response.data.must_change_password)
+          setState('authenticated')
+        } else {
+          clearAuthToken()
+          setState('anonymous')
+        }
+      })
+      .catch(() => setState('anonymous'))
+  }, [])
+
+  // 任意接口返回 401 / 需要改密时切换页面
+  useEffect(() => {
+    const handleExpired = () => {
+      setMustChangePassword(false)
+      setState('anonymous')
+      queryClient.clear()
+    }
+    const handlePasswordChangeRequired = () => setMustChangePassword(true)
+
+    window.addEventListener(AUTH_EXPIRED_EVENT, handleExpired)
+    window.addEventListener(PASSWORD_CHANGE_REQUIRED_EVENT, handlePasswordChangeRequired)
+    return () => {
+      window.removeEventListener(AUTH_EXPIRED_EVENT, handleExpired)
+      window.removeEventListener(PASSWORD_CHANGE_REQUIRED_EVENT, handlePasswordChangeRequired)
+    }
+  }, [])
+
+  const login = useCallback(async (password: string) => {
+    const response = await api.login(password)
+    if (response.status !== 'ok' || !response.data) {
+      throw new Error(response.message)
+    }
+    setAuthToken(response.data.token)
+    setMustChangePassword(response.data.must_change_password)
+    setState('authenticated')
+  }, [])
+
+  const logout = useCallback(async () => {
+    try {
+      await api.logout()
+    } finally {
+      clearAuthToken()
+      queryClient.clear()
+      setMustChangePassword(false)
+      setState('anonymous')
+    }
+  }, [])
+
+  const changePassword = useCallback(async (currentPassword: string, newPassword: string) => {
+    const response = await api.changePassword(currentPassword, newPassword)
+    if (response.status !== 'ok') {
+      throw new Error(response.message)
+    }
+    setMustChangePassword(false)
+  }, [])
+
+  return (
+    <AuthContext.Provider value={{ state, mustChangePassword, login, logout, changePassword }}>
+      {children}
+    </AuthContext.Provider>
+  )
+}
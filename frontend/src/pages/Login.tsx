@@ -0,0 +1,73 @@
+import { useEffect, useState, type FormEvent } from 'react'
+import { Alert, Box, Button, Card, CardContent, CircularProgress, Stack, TextField, Typography } from '@mui/material'
+import { Lock } from '@mui/icons-material'
+import { api } from '../api'
+import { useAuth } from '../contexts/AuthContext'
+
+export default function Login() {
+  const { login } = useAuth()
+  const [password, setPassword] = useState('')
+  const [submitting, setSubmitting] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  // 尚未设置密码时提示使用默认密码
+  const [passwordSet, setPasswordSet] = useState(true)
+
+  useEffect(() => {
+    api
+      .getAuthStatus()
+      .then((response) => setPasswordSet(response.data?.password_set ?? true))
+      .catch(() => setPasswordSet(true))
+  }, [])
+
+  const handleSubmit = async (event: FormEvent) => {
+    event.preventDefault()
+    setSubmitting(true)
+    setError(null)
+    try {
+      await login(password)
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+      setPassword('')
+    } finally {
+      setSubmitting(false)
+    }
+  }
+
+  return (
+    <Box display="flex" justifyContent="center" alignItems="center" minHeight="100vh" p={2}>
+      <Card sx={{ width: '100%', maxWidth: 400 }}>
+        <CardContent>
+          <Box component="form" onSubmit={(event) => void handleSubmit(event)}>
+            <Stack spacing={2}>
+              <Box display="flex" alignItems="center" gap={1}>
+                <Lock color="primary" />
+                <Typography variant="h6">登录控制面板</Typography>
+              </Box>
+              {!passwordSet && (
+                <Alert severity="info">尚未设置管理员密码，请使用默认密码 admin 登录，登录后需立即修改密码。</Alert>
+              )}
+              {error && <Alert severity="error">{error}</Alert>}
+              <TextField
+                label="管理员密码"
+                type="password"
+                value={password}
+                onChange={(event) => setPassword(event.target.value)}
+                autoComplete="current-password"
+                autoFocus
+                fullWidth
+              />
+              <Button
+                type="submit"
+                variant="contained"
+                disabled={submitting || !password}
+                startIcon={submitting ? <CircularProgress size={16} /> : undefined}
+              >
+                登录
+              </Button>
+            </Stack>
+          </Box>
+        </CardContent>
+      </Card>
+    </Box>
+  )
+}
@@ -82,6 +82,14 @@ else
 fi
 echo "   MD5: $BINARY_MD5"
 
+# 计算二进制 SHA-256
+if [[ "$OSTYPE" == "darwin"* ]]; then
+    BINARY_SHA256=$(shasum -a 256 "$OTA_TMP/udx710" | cut -d' ' -f1)
+else
+    BINARY_SHA256=$(sha256sum "$OTA_TMP/udx710" | cut -d' ' -f1)
+fi
+echo "   SHA-256: $BINARY_SHA256"
+
 # 复制前端文件
 echo "📋 复制前端文件..."
 mkdir -p "$OTA_TMP/www"
@@ -99,6 +107,14 @@ else
 fi
 echo "   MD5: $FRONTEND_MD5"
 
+# 计算前端 SHA-256（计算方式同 MD5，签名的更新包必须提供）
+if [[ "$OSTYPE" == "darwin"* ]]; then
+    FRONTEND_SHA256=$(find "$OTA_TMP/www" -type f -exec shasum -a 256 {} \; | cut -d' ' -f1 | sort | shasum -a 256 | cut -d' ' -f1)
+else
+    FRONTEND_SHA256=$(find "$OTA_TMP/www" -type f -exec sha256sum {} \; | cut -d' ' -f1 | sort | sha256sum | cut -d' ' -f1)
+fi
+echo "   SHA-256: $FRONTEND_SHA256"
+
 # 生成 meta.json
 echo "📋 生成 meta.json..."
 cat > "$OTA_TMP/meta.json" << EOF
@@ -108,6 +124,8 @@ cat > "$OTA_TMP/meta.json" << EOF
     "build_time": "$BUILD_TIME",
     "binary_md5": "$BINARY_MD5",
     "frontend_md5": "$FRONTEND_MD5",
+    "binary_sha256": "$BINARY_SHA256",
+    "frontend_sha256": "$FRONTEND_SHA256",
     "arch": "$ARCH"
 }
 EOF
@@ -115,6 +133,14 @@ EOF
 cat "$OTA_TMP/meta.json"
 echo ""
 
+# 签名 meta.json（设备默认拒绝未签名的更新包）
+# MINISIGN_SECRET_KEY 为 minisign 私钥文件路径，对应公钥需在编译时通过 OTA_PUBLIC_KEYS 内置
+if [ -n "$MINISIGN_SECRET_KEY" ]; then
+    minisign -S -s "$MINISIGN_SECRET_KEY" -m "$OTA_TMP/meta.json" -t "udx710 $VERSION ($COMMIT)"
+else
+    echo "⚠️  未设置 MINISIGN_SECRET_KEY，更新包未签名（仅开发者模式可安装）"
+fi
+
 # 创建输出目录
 mkdir -p release
 
@@ -122,7 +148,7 @@ mkdir -p release
 OTA_FILE="release/udx710-ota-${VERSION}.tar.gz"
 echo "📦 打包 OTA 更新包..."
 cd "$OTA_TMP"
-tar -czf - meta.json udx710 www > "$OLDPWD/$OTA_FILE"
+tar -czf - meta.json $(ls meta.json.minisig 2>/dev/null) udx710 www > "$OLDPWD/$OTA_FILE"
 cd "$OLDPWD"
 
 # 显示结果